@@ -3,11 +3,16 @@
 //! These are directly relevant to app startup performance, and also may be useful
 //! for testing changes to the underlying data structures being built.
 
-use criterion::{async_executor::FuturesExecutor, criterion_group, criterion_main, Criterion};
+use criterion::async_executor::{AsyncExecutor as _, FuturesExecutor};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 
 use instant::Duration;
 use strum::IntoEnumIterator as _;
 
+use all_is_cubes::block::{Block, BlockDef, BlockDefTransaction, EvaluatedBlock};
+use all_is_cubes::space::Space;
+use all_is_cubes::transaction;
+use all_is_cubes::universe::{URef, Universe, UniverseIndex as _};
 use all_is_cubes::util::YieldProgress;
 use all_is_cubes_content::UniverseTemplate;
 
@@ -30,5 +35,64 @@ pub fn template_bench(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, template_bench);
+/// Re-evaluating all of the blocks in the spaces a template produced, as happens when
+/// a space's blocks change. These templates make heavy use of `Primitive::Indirect`
+/// blocks, so comparing the "cached" and "uncached" cases measures the effectiveness
+/// of the `BlockDef` evaluation cache.
+pub fn evaluate_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("evaluate");
+
+    for template in [UniverseTemplate::DemoCity, UniverseTemplate::Atrium] {
+        let universe = FuturesExecutor
+            .block_on(template.clone().build(YieldProgress::noop(), 0))
+            .unwrap();
+
+        // Every cache has been filled by the first iteration, so later ones only hit it.
+        group.bench_function(format!("{template} cached"), |b| {
+            b.iter_with_large_drop(|| evaluate_all_space_blocks(&universe))
+        });
+
+        // Every cache is cleared before each iteration, so every `BlockDef` is evaluated.
+        group.bench_function(format!("{template} uncached"), |b| {
+            b.iter_batched(
+                || clear_block_def_caches(&universe),
+                |()| evaluate_all_space_blocks(&universe),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn evaluate_all_space_blocks(universe: &Universe) -> Vec<EvaluatedBlock> {
+    let spaces: Vec<URef<Space>> = universe.iter_by_type().map(|(_, r)| r).collect();
+    spaces
+        .iter()
+        .flat_map(|space_ref| {
+            let space = space_ref.read().unwrap();
+            space
+                .block_data()
+                .iter()
+                .map(|data| data.block().evaluate().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Overwrite every [`BlockDef`] with its own block, which discards its cached evaluation.
+fn clear_block_def_caches(universe: &Universe) {
+    let defs: Vec<URef<BlockDef>> = universe.iter_by_type().map(|(_, r)| r).collect();
+    for def_ref in defs {
+        let block: Block = (**def_ref.read().unwrap()).clone();
+        def_ref
+            .execute(
+                &BlockDefTransaction::overwrite(block),
+                &mut transaction::no_outputs,
+            )
+            .unwrap();
+    }
+}
+
+criterion_group!(benches, template_bench, evaluate_bench);
 criterion_main!(benches);
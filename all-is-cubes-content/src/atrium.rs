@@ -81,9 +81,9 @@ pub(crate) async fn atrium(
             spawn
         })
         .sky_color(rgb_const!(0.242, 0.617, 0.956) * 1.0)
-        .light_physics(all_is_cubes::space::LightPhysics::Rays {
-            maximum_distance: space_bounds.size().map(f64::from).magnitude() as u16,
-        })
+        .light_physics(all_is_cubes::space::LightPhysics::rays(
+            space_bounds.size().map(f64::from).magnitude() as u16,
+        ))
         .build();
 
     // "Directional" sky light source
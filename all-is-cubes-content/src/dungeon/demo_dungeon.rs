@@ -17,11 +17,11 @@ use all_is_cubes::math::{
     point_to_enclosing_cube, Face6, FaceMap, GridAab, GridArray, GridCoordinate, GridPoint,
     GridRotation, GridVector, Rgb, Rgba,
 };
-use all_is_cubes::space::{LightPhysics, Space};
+use all_is_cubes::space::{Fog, LightPhysics, Space};
 use all_is_cubes::transaction::{self, Transaction as _};
 use all_is_cubes::universe::Universe;
 use all_is_cubes::util::YieldProgress;
-use all_is_cubes::{include_image, rgb_const};
+use all_is_cubes::{include_image, notnan, rgb_const};
 
 use crate::dungeon::{build_dungeon, f2d, maze_to_array, DungeonGrid, Theme};
 use crate::{four_walls, tree, DemoBlocks, LandscapeBlocks};
@@ -542,7 +542,16 @@ pub(crate) async fn demo_dungeon(
             .expand(FaceMap::symmetric([30, 1, 30]));
         let mut space = Space::builder(space_bounds)
             .sky_color(palette::DAY_SKY_COLOR * 2.0)
-            .light_physics(LightPhysics::None) // temporary
+            // Dusty haze, thickest near the floor, which shows off light shafts.
+            .fog(Some(Fog {
+                color: rgb_const!(0.8, 0.75, 0.7),
+                density: notnan!(0.04),
+                height: notnan!(1.0),
+                height_falloff: notnan!(0.15),
+            }))
+            // Lighting is enabled once the rooms are built, below, rather than being
+            // updated for every block placed.
+            .light_physics(LightPhysics::None)
             .build();
 
         // Fill in (under)ground areas
@@ -576,13 +585,12 @@ pub(crate) async fn demo_dungeon(
     // Enable lighting
     light_progress.progress(0.0).await;
     let mut physics = space.physics().clone();
-    physics.light = LightPhysics::Rays {
-        // account for large rooms
-        maximum_distance: (dungeon_grid.room_box.size().y * 4) as u16,
-    };
+    // account for large rooms
+    physics.light = LightPhysics::rays((dungeon_grid.room_box.size().y * 4) as u16);
     space.set_physics(physics);
     light_progress.progress(0.01).await;
     // Make a rough lighting pass so that we don't have completely black rooms on start.
+    // The light in the air, which the fog scatters, is left for later updates to fill in.
     space.evaluate_light(254, |_i| {
         // TODO: report progress
         // light_progress.progress(i.max_queue_priority as f32 / 255.0)
@@ -273,9 +273,9 @@ fn cornell_box() -> Result<Space, InGenError> {
     let mut space = Space::builder(bounds)
         // There shall be no light but that which we make for ourselves!
         .sky_color(Rgb::ZERO)
-        .light_physics(LightPhysics::Rays {
-            maximum_distance: (box_size * 2).try_into().unwrap_or(u16::MAX),
-        })
+        .light_physics(LightPhysics::rays(
+            (box_size * 2).try_into().unwrap_or(u16::MAX),
+        ))
         .spawn({
             let mut spawn = Spawn::default_for_new_space(bounds);
             spawn.set_inventory(free_editing_starter_inventory(true));
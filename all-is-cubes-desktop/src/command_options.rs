@@ -11,7 +11,10 @@ use clap::{builder::TypedValueParser, Parser, ValueEnum};
 use once_cell::sync::Lazy;
 use strum::IntoEnumIterator;
 
+use all_is_cubes::camera::ProjectionOption;
 use all_is_cubes::cgmath::Vector2;
+use all_is_cubes::math::NotNan;
+use all_is_cubes::notnan;
 use all_is_cubes_content::UniverseTemplate;
 
 use crate::record::{RecordAnimationOptions, RecordFormat, RecordOptions};
@@ -92,6 +95,18 @@ pub(crate) struct AicDesktopArgs {
     )]
     pub(crate) output_file: Option<PathBuf>,
 
+    /// In 'record' mode with PNG output, also write images of depth, surface normals, and
+    /// block indexes.
+    ///
+    /// The depth is the distance along each pixel's view ray to the surface it sees,
+    /// not the view-space Z coordinate.
+    #[arg(long = "aux-images", requires = "output_file")]
+    pub(crate) aux_images: bool,
+
+    /// Camera projection to use, overriding the configuration file.
+    #[arg(long = "projection", value_name = "KIND")]
+    pub(crate) projection: Option<ProjectionArg>,
+
     // TODO: Generalize this to "exit after this much time has passed".
     /// Length of time to simulate.
     ///
@@ -132,6 +147,12 @@ impl AicDesktopArgs {
             .expect("output_file should be present");
         let output_format = determine_record_format(&output_path)
             .expect("output_file should have been validated to specify a format");
+        if self.aux_images && output_format != RecordFormat::PngOrApng {
+            return Err(clap::Error::raw(
+                clap::error::ErrorKind::ArgumentConflict,
+                "--aux-images is only supported when the output is a PNG file\n",
+            ));
+        }
         Ok(RecordOptions {
             output_path: self.output_file.clone().unwrap(),
             output_format,
@@ -149,6 +170,7 @@ impl AicDesktopArgs {
                 }
                 None => None,
             },
+            aux_images: self.aux_images,
         })
     }
 
@@ -217,6 +239,34 @@ pub enum GraphicsType {
     Print,
 }
 
+/// Choices for the `--projection` option, converted to [`ProjectionOption`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub(crate) enum ProjectionArg {
+    /// Conventional perspective view.
+    Perspective,
+    /// Parallel projection, 32 cubes tall.
+    Orthographic,
+    /// Equirectangular 360° panorama (raytracing only).
+    Panorama,
+    /// Side-by-side left and right eye views (raytracing only).
+    Stereo,
+}
+
+impl From<ProjectionArg> for ProjectionOption {
+    fn from(value: ProjectionArg) -> Self {
+        match value {
+            ProjectionArg::Perspective => ProjectionOption::Perspective,
+            ProjectionArg::Orthographic => ProjectionOption::Orthographic {
+                height: NotNan::from(32),
+            },
+            ProjectionArg::Panorama => ProjectionOption::Panorama,
+            ProjectionArg::Stereo => ProjectionOption::Stereo {
+                eye_separation: notnan!(0.0625),
+            },
+        }
+    }
+}
+
 /// This is just to hide the `Option` from `clap` because we don't want it to mean optional-argument.
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct DisplaySizeArg(pub Option<Vector2<u32>>);
@@ -314,6 +364,7 @@ mod tests {
                 output_format: RecordFormat::PngOrApng,
                 image_size: Vector2::new(640, 480),
                 animation: None,
+                aux_images: false,
             },
         );
     }
@@ -332,10 +383,39 @@ mod tests {
                     frame_count: 180,
                     frame_period: Duration::from_nanos((1e9 / 60.0) as u64),
                 }),
+                aux_images: false,
             },
         );
     }
 
+    #[test]
+    fn record_options_aux_images() {
+        let options = parse(&["-g", "record", "-o", "output.png", "--aux-images"])
+            .unwrap()
+            .record_options()
+            .unwrap();
+        assert!(options.aux_images);
+    }
+
+    #[test]
+    fn record_options_aux_images_requires_png() {
+        let e = parse(&["-g", "record", "-o", "output.gltf", "--aux-images"])
+            .unwrap()
+            .record_options()
+            .unwrap_err();
+        assert_eq!(e.kind(), ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn projection() {
+        assert_eq!(parse(&[]).unwrap().projection, None);
+        assert_eq!(
+            parse(&["--projection", "panorama"]).unwrap().projection,
+            Some(ProjectionArg::Panorama)
+        );
+        assert!(parse(&["--projection", "fisheye"]).is_err());
+    }
+
     // TODO: exercise record display size
 
     #[test]
@@ -92,6 +92,8 @@ fn main() -> Result<(), anyhow::Error> {
         precompute_light,
         input_file,
         output_file,
+        aux_images: _, // used via options.record_options()
+        projection,
         duration,
         verbose,
         no_config_files,
@@ -122,11 +124,14 @@ fn main() -> Result<(), anyhow::Error> {
         )?;
     }
 
-    let graphics_options = if no_config_files {
+    let mut graphics_options = if no_config_files {
         GraphicsOptions::default()
     } else {
         config_files::load_config().expect("Error loading configuration files")
     };
+    if let Some(projection) = projection {
+        graphics_options.projection = projection.into();
+    }
 
     // This cell will be moved into the session after (possibly) being reset to the actual
     // window size. This is a kludge because the `Session`'s `Vui` wants to be able to track
@@ -5,7 +5,7 @@ use std::sync::mpsc;
 
 use all_is_cubes::camera::{Flaws, StandardCameras};
 use all_is_cubes::listen::ListenableSource;
-use all_is_cubes::raytracer::RtRenderer;
+use all_is_cubes::raytracer::{AuxRtData, RtRenderer};
 use all_is_cubes::util::YieldProgress;
 use all_is_cubes_port::gltf::{GltfDataDestination, GltfWriter};
 
@@ -13,11 +13,16 @@ mod options;
 pub(crate) use options::*;
 mod record_main;
 pub(crate) use record_main::{create_recording_session, record_main};
+mod write_aux;
 mod write_gltf;
 mod write_png;
 
 type FrameNumber = usize;
 
+/// Message sent to the raytracing thread of a [`RtRecorder`]: the renderer for the
+/// main image, and optionally one for auxiliary images.
+type RtScene = (FrameNumber, RtRenderer, Option<RtRenderer<AuxRtData>>);
+
 /// Takes world states from a `DesktopSession` and writes renderings to disk.
 #[derive(Debug)]
 pub(crate) struct Recorder {
@@ -60,8 +65,7 @@ impl Recorder {
                 // Set up threads. Raytracing is internally parallel using Rayon, but we want to
                 // thread everything else too so we're not alternating single-threaded and parallel
                 // operations.
-                let (scene_sender, scene_receiver) =
-                    mpsc::sync_channel::<(FrameNumber, RtRenderer)>(1);
+                let (scene_sender, scene_receiver) = mpsc::sync_channel::<RtScene>(1);
                 let (image_data_sender, image_data_receiver) = mpsc::sync_channel(1);
 
                 // Raytracing thread.
@@ -69,8 +73,18 @@ impl Recorder {
                     .name("renderer".to_string())
                     .spawn({
                         move || {
-                            while let Ok((frame_number, renderer)) = scene_receiver.recv() {
+                            while let Ok((frame_number, renderer, aux_renderer)) =
+                                scene_receiver.recv()
+                            {
                                 let (image, _info, flaws) = renderer.draw_rgba(|_| String::new());
+                                let aux_frame = aux_renderer.map(|aux_renderer| {
+                                    let far = aux_renderer
+                                        .cameras()
+                                        .graphics_options()
+                                        .view_distance
+                                        .into_inner();
+                                    write_aux::AuxFrame::new(&aux_renderer.draw_aux().0, far)
+                                });
                                 image_data_sender
                                     .send((
                                         Status {
@@ -78,6 +92,7 @@ impl Recorder {
                                             flaws,
                                         },
                                         image,
+                                        aux_frame,
                                     ))
                                     .unwrap();
                             }
@@ -102,6 +117,7 @@ impl Recorder {
 
                 RecorderInner::Raytrace(RtRecorder {
                     cameras,
+                    aux_images: options.aux_images,
                     scene_sender,
                 })
             }
@@ -161,9 +177,19 @@ impl Recorder {
                 );
                 renderer.update(None).unwrap();
 
+                let aux_renderer = rec.aux_images.then(|| {
+                    let mut aux_renderer = RtRenderer::<AuxRtData>::new(
+                        rec.cameras.clone(),
+                        Box::new(|v| v),
+                        ListenableSource::constant(()),
+                    );
+                    aux_renderer.update(None).unwrap();
+                    aux_renderer
+                });
+
                 // TODO: instead of panic on send failure, log the problem
                 rec.scene_sender
-                    .send((this_frame_number, renderer))
+                    .send((this_frame_number, renderer, aux_renderer))
                     .expect("channel closed; recorder render thread died?");
             }
             RecorderInner::Mesh(rec) => rec.capture_frame(this_frame_number),
@@ -181,5 +207,7 @@ impl Recorder {
 #[derive(Debug)]
 pub(crate) struct RtRecorder {
     cameras: StandardCameras,
-    scene_sender: mpsc::SyncSender<(FrameNumber, RtRenderer)>,
+    /// Whether to also render auxiliary images.
+    aux_images: bool,
+    scene_sender: mpsc::SyncSender<RtScene>,
 }
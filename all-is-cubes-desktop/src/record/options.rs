@@ -16,6 +16,9 @@ pub struct RecordOptions {
     pub output_format: RecordFormat,
     pub image_size: Vector2<u32>,
     pub animation: Option<RecordAnimationOptions>,
+    /// Whether to write depth, normal, and block index images alongside the main output.
+    /// Only supported by [`RecordFormat::PngOrApng`].
+    pub aux_images: bool,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -0,0 +1,92 @@
+//! Writing of auxiliary images (depth, normals, block indices) alongside the main
+//! recording, as a sequence of PNG files per kind of image.
+
+use std::path::PathBuf;
+
+use all_is_cubes::raytracer::AuxImage;
+use image::{ImageBuffer, LumaA, RgbaImage};
+
+use crate::record::{FrameNumber, RecordOptions};
+
+/// Auxiliary images for one frame, ready to be written.
+#[derive(Debug)]
+pub(crate) struct AuxFrame {
+    depth: ImageBuffer<LumaA<u16>, Vec<u16>>,
+    normal: RgbaImage,
+    block_index: ImageBuffer<LumaA<u16>, Vec<u16>>,
+}
+
+impl AuxFrame {
+    /// Converts an [`AuxImage`] to images. `far` is the depth which is
+    /// mapped to the maximum depth value.
+    pub(crate) fn new(image: &AuxImage, far: f64) -> Self {
+        Self {
+            depth: image.depth_image(far),
+            normal: image.normal_image(),
+            block_index: image.block_index_image(),
+        }
+    }
+
+    /// Writes the images to files named after [`RecordOptions::output_path`].
+    pub(crate) fn write(
+        &self,
+        options: &RecordOptions,
+        frame_number: FrameNumber,
+    ) -> Result<(), image::ImageError> {
+        self.depth.save(aux_path(options, "depth", frame_number))?;
+        self.normal
+            .save(aux_path(options, "normal", frame_number))?;
+        self.block_index
+            .save(aux_path(options, "block", frame_number))?;
+        Ok(())
+    }
+}
+
+/// Returns the path to write one kind of auxiliary image to: the output path with
+/// the kind, and the frame number if animated, added to the file name.
+fn aux_path(options: &RecordOptions, kind: &str, frame_number: FrameNumber) -> PathBuf {
+    let path = &options.output_path;
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(match options.animation {
+        Some(_) => format!("{stem}-{kind}-{frame_number:04}.png"),
+        None => format!("{stem}-{kind}.png"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::{RecordAnimationOptions, RecordFormat};
+    use all_is_cubes::cgmath::Vector2;
+    use std::time::Duration;
+
+    fn options(animation: Option<RecordAnimationOptions>) -> RecordOptions {
+        RecordOptions {
+            output_path: PathBuf::from("dir/out.png"),
+            output_format: RecordFormat::PngOrApng,
+            image_size: Vector2::new(10, 10),
+            animation,
+            aux_images: true,
+        }
+    }
+
+    #[test]
+    fn aux_path_still() {
+        assert_eq!(
+            aux_path(&options(None), "depth", 0),
+            PathBuf::from("dir/out-depth.png")
+        );
+    }
+
+    #[test]
+    fn aux_path_animated() {
+        let options = options(Some(RecordAnimationOptions {
+            frame_count: 10,
+            frame_period: Duration::from_millis(100),
+        }));
+        assert_eq!(
+            aux_path(&options, "normal", 7),
+            PathBuf::from("dir/out-normal-0007.png")
+        );
+    }
+}
@@ -5,13 +5,14 @@ use std::sync::mpsc;
 use image::RgbaImage;
 use png::{chunk::ChunkType, Encoder};
 
+use crate::record::write_aux::AuxFrame;
 use crate::record::{RecordOptions, Status};
 
 /// Occupy a thread with writing a sequence of frames as (A)PNG data.
 pub(crate) fn threaded_write_frames(
     file: File,
     options: RecordOptions,
-    image_data_receiver: mpsc::Receiver<(Status, RgbaImage)>,
+    image_data_receiver: mpsc::Receiver<(Status, RgbaImage, Option<AuxFrame>)>,
     write_status_sender: &mut mpsc::Sender<Status>,
 ) -> Result<(), std::io::Error> {
     let mut buf_writer = BufWriter::new(file);
@@ -19,8 +20,13 @@ pub(crate) fn threaded_write_frames(
         let mut png_writer = new_png_writer(&mut buf_writer, &options)?;
         'frame_loop: loop {
             match image_data_receiver.recv() {
-                Ok((frame_number, image_data)) => {
+                Ok((frame_number, image_data, aux_frame)) => {
                     png_writer.write_image_data(image_data.as_ref())?;
+                    if let Some(aux_frame) = aux_frame {
+                        aux_frame
+                            .write(&options, frame_number.frame_number)
+                            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+                    }
                     let _ = write_status_sender.send(frame_number);
                 }
                 Err(mpsc::RecvError) => {
@@ -23,6 +23,7 @@ use crate::{
     in_wgpu::{
         block_texture::AtlasAllocator,
         camera::ShaderPostprocessCamera,
+        dynamic_lights::LightInstance,
         frame_texture::FbtFeatures,
         glue::{
             create_wgsl_module_from_reloadable, to_wgpu_color, BeltWritingParts, ResizingBuffer,
@@ -35,9 +36,11 @@ use crate::{
     SpaceUpdateInfo, UpdateInfo,
 };
 
+mod ambient_occlusion;
 mod block_texture;
 mod bloom;
 mod camera;
+mod dynamic_lights;
 mod frame_texture;
 use frame_texture::{DrawableTexture, FramebufferTextures};
 mod glue;
@@ -197,6 +200,8 @@ pub struct EverythingRenderer {
     pipelines: Pipelines,
 
     space_renderers: Layers<Option<SpaceRenderer>>,
+    /// Whether the space renderers compute chunk meshes on background threads.
+    threaded_meshing: bool,
     /// Texture atlas shared between all space renderers.
     block_texture: Arc<AtlasAllocator>,
 
@@ -332,6 +337,7 @@ impl EverythingRenderer {
             fb,
 
             space_renderers: Default::default(),
+            threaded_meshing: true,
             block_texture: Arc::new(
                 AtlasAllocator::new("EverythingRenderer", &device).unwrap(/* TODO */),
             ),
@@ -384,6 +390,22 @@ impl EverythingRenderer {
         new_self
     }
 
+    /// Sets whether chunk meshes are computed on background threads (which is the
+    /// default). If they are, then [`Self::update()`] does not wait for them, so the
+    /// frame drawn after it may be missing some chunks.
+    pub(crate) fn set_threaded_meshing(&mut self, enabled: bool) {
+        self.threaded_meshing = enabled;
+        for sr in [
+            &mut self.space_renderers.world,
+            &mut self.space_renderers.ui,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            sr.set_threaded_meshing(enabled);
+        }
+    }
+
     /// Read postprocessing shader and create the postprocessing render pipeline.
     fn create_postprocess_pipeline(
         device: &wgpu::Device,
@@ -506,6 +528,7 @@ impl EverythingRenderer {
             &self.device,
             &self.pipelines,
             &self.block_texture,
+            self.threaded_meshing,
         )?;
         Self::update_space_renderer(
             "ui",
@@ -514,6 +537,7 @@ impl EverythingRenderer {
             &self.device,
             &self.pipelines,
             &self.block_texture,
+            self.threaded_meshing,
         )?;
 
         let mut encoder = self
@@ -530,6 +554,15 @@ impl EverythingRenderer {
 
         let update_prep_to_space_update_time = Instant::now();
 
+        // A light carried by the character is positioned at the world camera.
+        let character_light: Option<LightInstance> = self
+            .cameras
+            .character()
+            .and_then(|c| c.read().ok()?.body.light)
+            .map(|light| {
+                LightInstance::from_view(self.cameras.cameras().world.get_view_transform(), light)
+            });
+
         let world_deadline = update_prep_to_space_update_time + frame_budget.update_meshes.world;
         let ui_deadline = world_deadline + frame_budget.update_meshes.ui;
 
@@ -542,7 +575,9 @@ impl EverythingRenderer {
                     sr.update(
                         world_deadline,
                         queue,
+                        &self.pipelines,
                         &self.cameras.cameras().world,
+                        character_light.as_ref().map_or(&[], std::slice::from_ref),
                         bwp.reborrow(),
                     )
                 })
@@ -556,7 +591,9 @@ impl EverythingRenderer {
                     sr.update(
                         ui_deadline,
                         queue,
+                        &self.pipelines,
                         &self.cameras.cameras().ui,
+                        &[],
                         bwp.reborrow(),
                     )
                 })
@@ -635,6 +672,7 @@ impl EverythingRenderer {
         device: &wgpu::Device,
         pipelines: &Pipelines,
         block_texture: &Arc<AtlasAllocator>,
+        threaded_meshing: bool,
     ) -> Result<(), GraphicsResourceError> {
         match (renderer, space) {
             (None, None) => {}
@@ -645,6 +683,7 @@ impl EverythingRenderer {
                     device,
                     pipelines,
                     Arc::clone(block_texture),
+                    threaded_meshing,
                 )?);
             }
             (Some(r), Some(space)) => {
@@ -680,26 +719,37 @@ impl EverythingRenderer {
         let start_draw_time = Instant::now();
         let world_draw_info = if let Some(sr) = &self.space_renderers.world {
             let camera = &self.cameras.cameras().world;
+            // If nothing has been drawn yet, then the sky is behind the world.
+            let draw_sky = mem::take(&mut output_needs_clearing);
             sr.draw(
                 &self.fb,
                 queue,
                 &mut encoder,
                 &self.pipelines,
                 camera,
-                if mem::take(&mut output_needs_clearing) {
+                if draw_sky {
                     wgpu::LoadOp::Clear(to_wgpu_color(
                         (sr.sky_color * camera.exposure()).with_alpha_one(),
                     ))
                 } else {
                     wgpu::LoadOp::Load
                 },
+                draw_sky,
                 // We need to store the depth buffer if and only if we are going to do
-                // the lines pass.
-                self.lines_vertex_count > 0,
+                // the ambient occlusion or lines pass.
+                self.fb.ambient_occlusion.is_some() || self.lines_vertex_count > 0,
             )?
         } else {
             SpaceDrawInfo::default()
         };
+
+        // Ambient occlusion pass, which must come before anything that is not part of
+        // the world is drawn.
+        if let (Some(ambient_occlusion), Some(_)) =
+            (&self.fb.ambient_occlusion, &self.space_renderers.world)
+        {
+            ambient_occlusion.run(queue, &mut encoder, &self.fb, &self.cameras.cameras().world);
+        }
         let world_to_lines_time = Instant::now();
 
         // Lines pass (if there are any lines)
@@ -741,6 +791,7 @@ impl EverythingRenderer {
                 } else {
                     wgpu::LoadOp::Load
                 },
+                false, // the UI has no sky; it is drawn over the world, if any
                 false, // nothing uses the ui depth buffer
             )?
         } else {
@@ -0,0 +1,230 @@
+use std::borrow::Cow;
+
+use all_is_cubes::camera::Camera;
+use all_is_cubes::cgmath::{Matrix4, SquareMatrix as _};
+use once_cell::sync::Lazy;
+
+use crate::in_wgpu::camera::{convert_matrix, projection_matrix};
+use crate::in_wgpu::frame_texture::{FbtConfig, FramebufferTextures};
+use crate::reloadable::{reloadable_str, Reloadable};
+
+static AMBIENT_OCCLUSION_SHADER: Lazy<Reloadable> =
+    Lazy::new(|| reloadable_str!("src/in_wgpu/shaders/ambient-occlusion.wgsl"));
+
+/// Information corresponding to [`Camera`] but in a form suitable for passing in a
+/// uniform buffer to the `ambient-occlusion.wgsl` shader.
+#[repr(C, align(16))] // align triggers bytemuck error if the size doesn't turn out to be a multiple
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShaderAmbientOcclusion {
+    projection_matrix: [[f32; 4]; 4],
+    inverse_projection_matrix: [[f32; 4]; 4],
+    framebuffer_size: [f32; 2],
+
+    /// pad out to multiple of vec4<something32>
+    _padding: [f32; 2],
+}
+
+impl ShaderAmbientOcclusion {
+    fn new(camera: &Camera, size: wgpu::Extent3d) -> Self {
+        let projection = projection_matrix(camera);
+        Self {
+            projection_matrix: convert_matrix(projection),
+            inverse_projection_matrix: convert_matrix(
+                projection.invert().unwrap_or_else(Matrix4::identity),
+            ),
+            framebuffer_size: [size.width as f32, size.height as f32],
+            _padding: Default::default(),
+        }
+    }
+}
+
+/// Completes the source of the `ambient-occlusion.wgsl` shader by declaring its depth
+/// texture binding, whose type depends on whether the depth texture is multisampled.
+/// (The multisampled type has an equivalent `textureLoad()`, so nothing else differs.)
+fn shader_source(file_source: &str, multisampled: bool) -> String {
+    assert!(
+        !file_source.contains("var depth_texture"),
+        "ambient-occlusion.wgsl should not declare depth_texture itself"
+    );
+    let depth_texture_type = if multisampled {
+        "texture_multisampled_2d<f32>"
+    } else {
+        "texture_2d<f32>"
+    };
+    format!("@group(0) @binding(1) var depth_texture: {depth_texture_type};\n{file_source}")
+}
+
+/// Resources for computing screen-space ambient occlusion
+/// ([`GraphicsOptions::ambient_occlusion`]) for a specific framebuffer.
+///
+/// Ambient occlusion is computed from the depth buffer and multiplied into the
+/// `linear_scene_texture`, so it must be run after the world is drawn and before
+/// anything which should not be darkened (lines and UI) is drawn.
+///
+/// [`GraphicsOptions::ambient_occlusion`]: all_is_cubes::camera::GraphicsOptions::ambient_occlusion
+#[derive(Debug)]
+pub(crate) struct AmbientOcclusionResources {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    uniform_buffer: wgpu::Buffer,
+    size: wgpu::Extent3d,
+}
+
+impl AmbientOcclusionResources {
+    pub fn new(
+        device: &wgpu::Device,
+        config: &FbtConfig,
+        depth_texture_view: &wgpu::TextureView,
+    ) -> Self {
+        let multisampled = config.sample_count > 1;
+
+        let source = shader_source(
+            &AMBIENT_OCCLUSION_SHADER.as_source().snapshot(),
+            multisampled,
+        );
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("AmbientOcclusionResources::shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Owned(source)),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                // Binding for camera
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // Binding for depth_texture
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        // Not `Depth`; see the comment in the shader.
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("AmbientOcclusionResources::bind_group_layout"),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("AmbientOcclusionResources::pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("AmbientOcclusionResources::pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "ambient_occlusion_vertex",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "ambient_occlusion_fragment",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: config.linear_scene_texture_format,
+                    // Multiply the scene color by the shader output.
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::Zero,
+                            dst_factor: wgpu::BlendFactor::Src,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::Zero,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrites::COLOR,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            // Must match the scene texture, though the full-screen triangle has no edges
+            // to antialias.
+            multisample: wgpu::MultisampleState {
+                count: config.sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("AmbientOcclusionResources::uniform_buffer"),
+            size: std::mem::size_of::<ShaderAmbientOcclusion>()
+                .try_into()
+                .unwrap(),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(depth_texture_view),
+                },
+            ],
+            label: Some("AmbientOcclusionResources::bind_group"),
+        });
+
+        Self {
+            pipeline,
+            bind_group,
+            uniform_buffer,
+            size: config.size,
+        }
+    }
+
+    /// Darken the scene in `fb` according to the occlusion computed from its depth
+    /// buffer, which must contain the world as seen by `camera`.
+    pub fn run(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        fb: &FramebufferTextures,
+        camera: &Camera,
+    ) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&ShaderAmbientOcclusion::new(camera, self.size)),
+        );
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("ambient occlusion"),
+            color_attachments: &[Some(fb.color_attachment_for_scene(wgpu::LoadOp::Load))],
+            depth_stencil_attachment: None,
+        });
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -1,8 +1,9 @@
 use all_is_cubes::camera::{
     Camera, FogOption, GraphicsOptions, LightingOption, ToneMappingOperator,
 };
-use all_is_cubes::cgmath::{EuclideanSpace, Matrix4, Vector3};
-use all_is_cubes::math::Rgb;
+use all_is_cubes::cgmath::{EuclideanSpace, Matrix4, SquareMatrix as _, Vector3};
+use all_is_cubes::math::{GridAab, Rgb};
+use all_is_cubes::space::{Fog, Sky};
 
 use crate::in_wgpu::glue::PaddedVec3;
 
@@ -38,12 +39,50 @@ pub(crate) struct ShaderSpaceCamera {
     /// Scale factor for scene brightness.
     exposure: f32,
 
-    /// pad out to multiple of vec4<something32>
+    /// pad out to a vec4 boundary
     _padding: [i32; 2],
+
+    /// Color of the space's volumetric fog ([`SpacePhysics::fog`]).
+    ///
+    /// [`SpacePhysics::fog`]: all_is_cubes::space::SpacePhysics::fog
+    volumetric_fog_color: [f32; 3],
+    /// Density of the space's volumetric fog; zero if there is none.
+    volumetric_fog_density: f32,
+    /// Lower bounds of the space, outside of which there is no volumetric fog.
+    space_lower_bounds: [f32; 3],
+    /// Height below which the volumetric fog has its full density.
+    volumetric_fog_height: f32,
+    /// Upper bounds of the space, outside of which there is no volumetric fog.
+    space_upper_bounds: [f32; 3],
+    /// Rate of exponential decrease of the volumetric fog's density above its height.
+    volumetric_fog_height_falloff: f32,
+
+    /// Inverse of `projection_matrix * view_matrix`, used to find the direction each
+    /// pixel is looking in when drawing the sky.
+    inverse_projection_view_matrix: [[f32; 4]; 4],
+    /// [`Sky::zenith_color`].
+    sky_zenith_color: PaddedVec3,
+    /// [`Sky::horizon_color`].
+    sky_horizon_color: PaddedVec3,
+    /// Normalized direction towards the sun, or zero if the sun is not visible.
+    sun_direction: PaddedVec3,
+    /// [`Sky::sun_color`].
+    sun_color: PaddedVec3,
 }
 
 impl ShaderSpaceCamera {
-    pub fn new(camera: &Camera, sky_color: Rgb, light_lookup_offset: Vector3<i32>) -> Self {
+    /// `sky_color` is used for distance fog, and `sky` for drawing the sky itself.
+    ///
+    /// `fog` and `space_bounds` describe the volumetric fog, which is not drawn if the
+    /// camera's options disable fog.
+    pub fn new(
+        camera: &Camera,
+        sky_color: Rgb,
+        sky: &Sky,
+        light_lookup_offset: Vector3<i32>,
+        fog: Option<&Fog>,
+        space_bounds: GridAab,
+    ) -> Self {
         let options = camera.options();
         let view_distance = camera.view_distance() as f32;
         let (fog_mode_blend, fog_distance) = match options.fog {
@@ -52,9 +91,15 @@ impl ShaderSpaceCamera {
             FogOption::Physical => (0.0, view_distance),
             /* FogOption::None | */ _ => (0.0, f32::INFINITY),
         };
+        let volumetric_fog = match options.fog {
+            FogOption::None => None,
+            _ => fog,
+        };
+
+        let projection_view = projection_matrix(camera) * camera.view_matrix();
 
         Self {
-            projection_matrix: convert_matrix(OPENGL_TO_WGPU_PROJECTION * camera.projection()),
+            projection_matrix: convert_matrix(projection_matrix(camera)),
             view_matrix: convert_matrix(camera.view_matrix()),
             view_position: camera.view_position().map(|s| s as f32).to_vec().into(),
 
@@ -62,7 +107,8 @@ impl ShaderSpaceCamera {
             light_option: match options.lighting_display {
                 LightingOption::None => 0,
                 LightingOption::Flat => 1,
-                LightingOption::Smooth => 2,
+                // Path tracing is not implemented; fall back to smooth lighting.
+                LightingOption::Smooth | LightingOption::PathTraced => 2,
                 _ => unreachable!(
                     "Unhandled LightingOption value {:?}",
                     options.lighting_display
@@ -76,10 +122,47 @@ impl ShaderSpaceCamera {
             exposure: camera.exposure().into_inner(),
 
             _padding: Default::default(),
+
+            volumetric_fog_color: volumetric_fog
+                .map_or(Vector3::new(0.0, 0.0, 0.0), |fog| {
+                    Vector3::<f32>::from(fog.color)
+                })
+                .into(),
+            volumetric_fog_density: volumetric_fog
+                .map_or(0.0, |fog| fog.density.into_inner().max(0.0)),
+            space_lower_bounds: space_bounds.lower_bounds().map(|c| c as f32).into(),
+            volumetric_fog_height: volumetric_fog.map_or(0.0, |fog| fog.height.into_inner() as f32),
+            space_upper_bounds: space_bounds.upper_bounds().map(|c| c as f32).into(),
+            volumetric_fog_height_falloff: volumetric_fog
+                .map_or(0.0, |fog| fog.height_falloff.into_inner().max(0.0)),
+
+            inverse_projection_view_matrix: convert_matrix(
+                projection_view.invert().unwrap_or_else(Matrix4::identity),
+            ),
+            sky_zenith_color: Vector3::<f32>::from(sky.zenith_color).into(),
+            sky_horizon_color: Vector3::<f32>::from(sky.horizon_color).into(),
+            sun_direction: sky
+                .visible_sun_direction()
+                .map_or(Vector3::new(0.0, 0.0, 0.0), |d| d.map(|c| c as f32))
+                .into(),
+            sun_color: Vector3::<f32>::from(sky.sun_color).into(),
         }
     }
 }
-fn convert_matrix(matrix: Matrix4<f64>) -> [[f32; 4]; 4] {
+
+/// Returns the matrix which transforms world coordinates to clip coordinates, as the
+/// blocks shader computes it from the [`ShaderSpaceCamera`] for the same `camera`.
+pub(crate) fn world_to_clip_matrix(camera: &Camera) -> [[f32; 4]; 4] {
+    convert_matrix(projection_matrix(camera) * camera.view_matrix())
+}
+
+/// Returns the matrix which transforms view (eye) coordinates to clip coordinates, as the
+/// blocks shader computes it from the [`ShaderSpaceCamera`] for the same `camera`.
+pub(crate) fn projection_matrix(camera: &Camera) -> Matrix4<f64> {
+    OPENGL_TO_WGPU_PROJECTION * camera.projection()
+}
+
+pub(crate) fn convert_matrix(matrix: Matrix4<f64>) -> [[f32; 4]; 4] {
     matrix.cast::<f32>().unwrap(/* f64 to f32 is infallible */).into()
 }
 
@@ -0,0 +1,457 @@
+//! Drawing [`DynamicLight`]s, with shadow maps, on top of the light texture.
+
+use std::num::NonZeroU32;
+
+use all_is_cubes::camera::{Camera, GraphicsOptions, LightingOption, ViewTransform, Viewport};
+use all_is_cubes::cgmath::{
+    Decomposed, EuclideanSpace as _, InnerSpace as _, Point3, Transform as _, Vector2, Vector3,
+    Zero as _,
+};
+use all_is_cubes::math::{Aab, FreeCoordinate, GridPoint, NotNan, Rgb};
+use all_is_cubes::space::DynamicLight;
+
+use crate::in_wgpu::camera::world_to_clip_matrix;
+use crate::in_wgpu::frame_texture::FramebufferTextures;
+use crate::in_wgpu::pipelines::Pipelines;
+use crate::in_wgpu::space::SpaceCameraBuffer;
+
+/// Maximum number of lights drawn at once; must match the size of the `lights` array in
+/// `blocks-and-lines.wgsl`.
+pub(crate) const MAX_DYNAMIC_LIGHTS: usize = 4;
+
+/// Maximum number of shadow map layers, which is enough for every light to be a point
+/// light; must match the size of the `shadow_matrices` array in `blocks-and-lines.wgsl`.
+const MAX_SHADOW_LAYERS: usize = MAX_DYNAMIC_LIGHTS * 6;
+
+/// Width and height of each shadow map layer.
+const SHADOW_MAP_SIZE: u32 = 512;
+
+/// A [`DynamicLight`] positioned in the world, ready to be drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct LightInstance {
+    position: Point3<FreeCoordinate>,
+    light: DynamicLight,
+    /// World-space direction and cosine of the half-angle of the cone, if this is a
+    /// spot light.
+    cone: Option<(Vector3<FreeCoordinate>, FreeCoordinate)>,
+}
+
+impl LightInstance {
+    /// A light attached to the block at `cube`, positioned at the center of the cube.
+    pub(crate) fn from_block(cube: GridPoint, light: DynamicLight) -> Self {
+        Self {
+            position: cube.map(FreeCoordinate::from) + Vector3::new(0.5, 0.5, 0.5),
+            light,
+            cone: light.spot_cone(),
+        }
+    }
+
+    /// A light attached to a viewpoint, such as a [`Body`](all_is_cubes::physics::Body)'s
+    /// eye, with the given eye-to-world transform.
+    pub(crate) fn from_view(view_transform: ViewTransform, light: DynamicLight) -> Self {
+        Self {
+            position: Point3::from_vec(view_transform.disp),
+            light,
+            cone: light
+                .spot_cone()
+                .map(|(direction, cos)| (view_transform.transform_vector(direction), cos)),
+        }
+    }
+
+    fn range(&self) -> FreeCoordinate {
+        self.light.range.into_inner()
+    }
+
+    /// Whether this light can have any visible effect at all.
+    fn is_visible(&self) -> bool {
+        self.light.color != Rgb::ZERO && !matches!(self.cone, Some((d, _)) if d.is_zero())
+    }
+
+    /// The region within which this light has any effect.
+    fn bounds(&self) -> Aab {
+        let r = self.range();
+        let p = self.position;
+        Aab::new(p.x - r, p.x + r, p.y - r, p.y + r, p.z - r, p.z + r)
+    }
+
+    /// Returns one camera for each shadow map layer this light needs.
+    ///
+    /// For point lights, these are the six faces of a cube, in the order +X, −X, +Y, −Y,
+    /// +Z, −Z, which the shader relies on to choose the layer to sample.
+    fn shadow_cameras(&self) -> Vec<Camera> {
+        let camera = |fov_y: FreeCoordinate, direction: Vector3<FreeCoordinate>| {
+            let mut options = GraphicsOptions::default();
+            options.fov_y = NotNan::new(fov_y).unwrap();
+            options.view_distance = self.light.range;
+            let mut camera = Camera::new(
+                options,
+                Viewport::with_scale(1.0, Vector2::new(SHADOW_MAP_SIZE, SHADOW_MAP_SIZE)),
+            );
+            // Any up vector will do, as long as it is not parallel to the direction.
+            let up = if direction.x == 0.0 && direction.z == 0.0 {
+                Vector3::unit_z()
+            } else {
+                Vector3::unit_y()
+            };
+            camera.set_view_transform(
+                Decomposed::look_at_rh(self.position, self.position + direction, up)
+                    .inverse_transform()
+                    .unwrap(),
+            );
+            camera
+        };
+
+        match self.cone {
+            Some((direction, cos)) => vec![camera(cos.acos().to_degrees() * 2.0, direction)],
+            None => [
+                Vector3::unit_x(),
+                -Vector3::unit_x(),
+                Vector3::unit_y(),
+                -Vector3::unit_y(),
+                Vector3::unit_z(),
+                -Vector3::unit_z(),
+            ]
+            .into_iter()
+            .map(|direction| camera(90.0, direction))
+            .collect(),
+        }
+    }
+}
+
+/// Information corresponding to a list of [`LightInstance`]s, in a form suitable for
+/// passing in a uniform buffer to the `blocks-and-lines.wgsl` shader.
+#[repr(C, align(16))] // align triggers bytemuck error if the size doesn't turn out to be a multiple
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct ShaderDynamicLights {
+    /// Number of elements of `lights` in use. Other components are padding.
+    count: [u32; 4],
+    lights: [ShaderDynamicLight; MAX_DYNAMIC_LIGHTS],
+    /// World-to-clip matrices of the cameras used to draw each shadow map layer.
+    shadow_matrices: [[[f32; 4]; 4]; MAX_SHADOW_LAYERS],
+}
+
+#[repr(C, align(16))]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+struct ShaderDynamicLight {
+    position_and_range: [f32; 4],
+    /// Color, and then 0 for point lights or 1 for spot lights.
+    color_and_kind: [f32; 4],
+    /// Normalized direction and cosine of half-angle of spot lights.
+    direction_and_cos_cutoff: [f32; 4],
+    /// Index of the first shadow map layer belonging to this light. Other components are
+    /// padding.
+    first_layer: [i32; 4],
+}
+
+/// GPU resources for drawing [`DynamicLight`]s in one [`Space`](all_is_cubes::space::Space),
+/// matching [`Pipelines::dynamic_lights_bind_group_layout`].
+#[derive(Debug)]
+pub(crate) struct DynamicLights {
+    label: String,
+
+    /// Lights chosen by the last [`Self::update`], and the first shadow map layer of each.
+    lights: Vec<(LightInstance, usize)>,
+    /// Cameras for each shadow map layer in use.
+    shadow_cameras: Vec<Camera>,
+
+    /// Buffer containing a [`ShaderDynamicLights`].
+    uniform_buffer: wgpu::Buffer,
+
+    /// Depth texture array with one layer per shadow camera (or more).
+    shadow_texture: wgpu::Texture,
+    /// Views of individual layers of `shadow_texture`, for rendering into.
+    layer_views: Vec<wgpu::TextureView>,
+    /// Camera buffers for rendering into each layer; may be fewer than the layers.
+    layer_camera_buffers: Vec<SpaceCameraBuffer>,
+    sampler: wgpu::Sampler,
+
+    bind_group: wgpu::BindGroup,
+}
+
+impl DynamicLights {
+    pub(crate) fn new(label: &str, device: &wgpu::Device, pipelines: &Pipelines) -> Self {
+        let label = format!("{label} dynamic lights");
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{label} uniform")),
+            size: std::mem::size_of::<ShaderDynamicLights>()
+                .try_into()
+                .unwrap(),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(&format!("{label} shadow sampler")),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+        let (shadow_texture, layer_views) = create_shadow_texture(&label, device, 1);
+        let bind_group = create_bind_group(
+            &label,
+            device,
+            pipelines,
+            &uniform_buffer,
+            &shadow_texture,
+            &sampler,
+        );
+        Self {
+            label,
+            lights: Vec::new(),
+            shadow_cameras: Vec::new(),
+            uniform_buffer,
+            shadow_texture,
+            layer_views,
+            layer_camera_buffers: Vec::new(),
+            sampler,
+            bind_group,
+        }
+    }
+
+    /// Choose which of `candidates` to draw, and prepare resources for drawing them.
+    ///
+    /// Lights are chosen in order of nearness to the camera, skipping those whose
+    /// effects are entirely out of view.
+    pub(crate) fn update(
+        &mut self,
+        device: &wgpu::Device,
+        pipelines: &Pipelines,
+        camera: &Camera,
+        candidates: impl IntoIterator<Item = LightInstance>,
+    ) {
+        self.lights.clear();
+        self.shadow_cameras.clear();
+
+        let options = camera.options();
+        if !options.dynamic_lights || options.lighting_display == LightingOption::None {
+            return;
+        }
+
+        let eye = camera.view_position();
+        let mut candidates: Vec<LightInstance> = candidates
+            .into_iter()
+            .filter(|light| light.is_visible() && camera.aab_in_view(light.bounds()))
+            .collect();
+        candidates.sort_by(|a, b| {
+            let a = (a.position - eye).magnitude2();
+            let b = (b.position - eye).magnitude2();
+            a.total_cmp(&b)
+        });
+        for light in candidates {
+            if self.lights.len() >= MAX_DYNAMIC_LIGHTS {
+                break;
+            }
+            let cameras = light.shadow_cameras();
+            if self.shadow_cameras.len() + cameras.len() > MAX_SHADOW_LAYERS {
+                continue;
+            }
+            self.lights.push((light, self.shadow_cameras.len()));
+            self.shadow_cameras.extend(cameras);
+        }
+
+        // Grow the shadow texture if needed. (We never shrink it, to avoid reallocating
+        // when lights come and go.)
+        let layers_needed = self.shadow_cameras.len();
+        if layers_needed > self.layer_views.len() {
+            (self.shadow_texture, self.layer_views) =
+                create_shadow_texture(&self.label, device, u32::try_from(layers_needed).unwrap());
+            self.bind_group = create_bind_group(
+                &self.label,
+                device,
+                pipelines,
+                &self.uniform_buffer,
+                &self.shadow_texture,
+                &self.sampler,
+            );
+        }
+        while self.layer_camera_buffers.len() < layers_needed {
+            let label = format!("{} shadow {}", self.label, self.layer_camera_buffers.len());
+            self.layer_camera_buffers
+                .push(SpaceCameraBuffer::new(&label, device, pipelines));
+        }
+    }
+
+    /// Write the uniform buffer for the lights chosen by the last [`Self::update`].
+    pub(crate) fn write_uniforms(&self, queue: &wgpu::Queue) {
+        let mut data: ShaderDynamicLights = bytemuck::Zeroable::zeroed();
+        data.count[0] = self.lights.len() as u32;
+        for (shader_light, &(ref light, first_layer)) in data.lights.iter_mut().zip(&self.lights) {
+            let position = light.position.map(|c| c as f32);
+            let color: Vector3<f32> = light.light.color.into();
+            let (direction, cos_cutoff, kind) = match light.cone {
+                Some((direction, cos)) => (direction.map(|c| c as f32), cos as f32, 1.0),
+                None => (Vector3::zero(), -1.0, 0.0),
+            };
+            *shader_light = ShaderDynamicLight {
+                position_and_range: [position.x, position.y, position.z, light.range() as f32],
+                color_and_kind: [color.x, color.y, color.z, kind],
+                direction_and_cos_cutoff: [direction.x, direction.y, direction.z, cos_cutoff],
+                first_layer: [first_layer as i32, 0, 0, 0],
+            };
+        }
+        for (matrix, camera) in data.shadow_matrices.iter_mut().zip(&self.shadow_cameras) {
+            *matrix = world_to_clip_matrix(camera);
+        }
+        queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(&data));
+    }
+
+    /// Returns the camera, depth texture view, and camera buffer for rendering each shadow
+    /// map layer in use.
+    pub(crate) fn shadow_layers(
+        &self,
+    ) -> impl Iterator<Item = (&Camera, &wgpu::TextureView, &SpaceCameraBuffer)> {
+        self.shadow_cameras
+            .iter()
+            .zip(&self.layer_views)
+            .zip(&self.layer_camera_buffers)
+            .map(|((camera, view), buffer)| (camera, view, buffer))
+    }
+
+    /// Bind group to be used with [`Pipelines::dynamic_lights_bind_group_layout`].
+    pub(crate) fn bind_group(&self) -> &wgpu::BindGroup {
+        &self.bind_group
+    }
+}
+
+/// Returns the number of layers to allocate for a shadow texture holding `layers`
+/// shadow maps.
+///
+/// wgpu's OpenGL backend chooses the kind of GL texture from the layer count: a single
+/// layer makes a non-array texture, and a multiple of 6 square layers makes a cube map.
+/// Neither can then be sampled as a 2D array, so we avoid those counts.
+fn allocated_layer_count(layers: u32) -> u32 {
+    let count = layers.max(2);
+    if count % 6 == 0 {
+        count + 1
+    } else {
+        count
+    }
+}
+
+fn create_shadow_texture(
+    label: &str,
+    device: &wgpu::Device,
+    layers: u32,
+) -> (wgpu::Texture, Vec<wgpu::TextureView>) {
+    let layers = allocated_layer_count(layers);
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&format!("{label} shadow texture")),
+        size: wgpu::Extent3d {
+            width: SHADOW_MAP_SIZE,
+            height: SHADOW_MAP_SIZE,
+            depth_or_array_layers: layers,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FramebufferTextures::DEPTH_FORMAT,
+        view_formats: &[],
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+    });
+    let layer_views = (0..layers)
+        .map(|layer| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some(&format!("{label} shadow layer {layer}")),
+                format: None,
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                aspect: wgpu::TextureAspect::DepthOnly,
+                base_mip_level: 0,
+                mip_level_count: None,
+                base_array_layer: layer,
+                array_layer_count: Some(NonZeroU32::new(1).unwrap()),
+            })
+        })
+        .collect();
+    (texture, layer_views)
+}
+
+fn create_bind_group(
+    label: &str,
+    device: &wgpu::Device,
+    pipelines: &Pipelines,
+    uniform_buffer: &wgpu::Buffer,
+    shadow_texture: &wgpu::Texture,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    // Explicitly an array view, since a texture with one layer would otherwise get a
+    // plain 2D view.
+    let shadow_texture_view = shadow_texture.create_view(&wgpu::TextureViewDescriptor {
+        label: Some(&format!("{label} shadow texture view")),
+        dimension: Some(wgpu::TextureViewDimension::D2Array),
+        aspect: wgpu::TextureAspect::DepthOnly,
+        ..Default::default()
+    });
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &pipelines.dynamic_lights_bind_group_layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::TextureView(&shadow_texture_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+        label: Some(&format!("{label} bind_group")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use all_is_cubes::notnan;
+
+    #[test]
+    fn shader_dynamic_lights_layout() {
+        // Must match the WGSL struct layout.
+        assert_eq!(std::mem::size_of::<ShaderDynamicLight>(), 64);
+        assert_eq!(
+            std::mem::size_of::<ShaderDynamicLights>(),
+            16 + 64 * MAX_DYNAMIC_LIGHTS + 64 * MAX_SHADOW_LAYERS
+        );
+    }
+
+    #[test]
+    fn allocated_layer_count_is_array_compatible() {
+        assert_eq!(
+            [0, 1, 2, 5, 6, 7, 12, 24].map(allocated_layer_count),
+            [2, 2, 2, 5, 7, 7, 13, 25]
+        );
+    }
+
+    #[test]
+    fn point_light_shadow_cameras_face_axes() {
+        let light = LightInstance::from_block(
+            GridPoint::new(10, 20, 30),
+            DynamicLight::point(Rgb::ONE, notnan!(8.0)),
+        );
+        let cameras = light.shadow_cameras();
+        assert_eq!(cameras.len(), 6);
+        for (camera, expected) in cameras.iter().zip([
+            Vector3::unit_x(),
+            -Vector3::unit_x(),
+            Vector3::unit_y(),
+            -Vector3::unit_y(),
+            Vector3::unit_z(),
+            -Vector3::unit_z(),
+        ]) {
+            assert_eq!(camera.view_position(), Point3::new(10.5, 20.5, 30.5));
+            let forward = camera
+                .get_view_transform()
+                .transform_vector(Vector3::new(0.0, 0.0, -1.0));
+            assert!(
+                (forward - expected).magnitude() < 1e-9,
+                "{forward:?} != {expected:?}"
+            );
+        }
+    }
+}
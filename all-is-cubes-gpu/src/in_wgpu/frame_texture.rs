@@ -1,10 +1,11 @@
 use std::fmt;
 use std::sync::atomic::AtomicBool;
 
-use all_is_cubes::camera::{Flaws, GraphicsOptions};
+use all_is_cubes::camera::{Flaws, GraphicsOptions, LightingOption};
 use all_is_cubes::cgmath::Vector2;
 use all_is_cubes::drawing::embedded_graphics::prelude::{OriginDimensions, Size};
 
+use super::ambient_occlusion::AmbientOcclusionResources;
 use super::bloom;
 use crate::EgFramebuffer;
 
@@ -170,6 +171,10 @@ pub(crate) struct FramebufferTextures {
 
     /// Resources necessary for computing bloom if enabled.
     pub(crate) bloom: Option<bloom::BloomResources>,
+
+    /// Resources necessary for computing ambient occlusion, present if and only if
+    /// it is enabled.
+    pub(crate) ambient_occlusion: Option<AmbientOcclusionResources>,
 }
 
 impl FramebufferTextures {
@@ -236,6 +241,8 @@ impl FramebufferTextures {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
         });
 
+        let depth_texture_view = depth_texture.create_view(&Default::default());
+
         let linear_scene_view = linear_scene_tex.create_view(&Default::default());
         let linear_scene_resolved_view = linear_scene_resolved_tex
             .as_ref()
@@ -259,7 +266,10 @@ impl FramebufferTextures {
             )),
             linear_scene_view,
             linear_scene_resolved_view,
-            depth_texture_view: depth_texture.create_view(&Default::default()),
+            ambient_occlusion: config
+                .ambient_occlusion
+                .then(|| AmbientOcclusionResources::new(device, &config, &depth_texture_view)),
+            depth_texture_view,
 
             config,
             linear_scene_tex,
@@ -374,6 +384,9 @@ pub(crate) struct FbtConfig {
     /// Whether to enable copying out of the linear scene texture for testing purposes.
     enable_copy_out: bool,
 
+    /// Whether to compute ambient occlusion, which needs [`AmbientOcclusionResources`].
+    pub(crate) ambient_occlusion: bool,
+
     /// Rendering flaws resulting from necessary compromises.
     flaws: Flaws,
 }
@@ -393,6 +406,8 @@ impl FbtConfig {
 
         let wants_antialiasing = options.antialiasing.is_msaa();
         let sample_count_if_ok = if wants_antialiasing { 4 } else { 1 };
+        let ambient_occlusion =
+            options.ambient_occlusion && options.lighting_display != LightingOption::None;
         // TODO: define a "wants_hdr" once we have sorted out how graphics options work for that
 
         // If possible, we want a float-valued format to allow HDR rendering
@@ -412,6 +427,7 @@ impl FbtConfig {
                     linear_scene_texture_format: wgpu::TextureFormat::Rgba16Float,
                     sample_count: sample_count_if_ok,
                     enable_copy_out,
+                    ambient_occlusion,
                     flaws: Flaws::empty(),
                 }
             } else {
@@ -422,6 +438,7 @@ impl FbtConfig {
                     linear_scene_texture_format: wgpu::TextureFormat::Rgba16Float,
                     sample_count: 1,
                     enable_copy_out,
+                    ambient_occlusion,
                     flaws: if sample_count_if_ok != 1 {
                         Flaws::NO_ANTIALIASING
                     } else {
@@ -438,6 +455,7 @@ impl FbtConfig {
                 linear_scene_texture_format: wgpu::TextureFormat::Rgba8UnormSrgb,
                 sample_count: sample_count_if_ok,
                 enable_copy_out,
+                ambient_occlusion,
                 flaws: Flaws::empty(),
             }
         }
@@ -35,12 +35,15 @@ impl Builder {
     /// Create a [`Renderer`] from the GPU connection in this builder and the given cameras.
     pub fn build(&self, cameras: StandardCameras) -> Renderer {
         let viewport_source = cameras.viewport_source();
-        let everything = in_wgpu::EverythingRenderer::new(
+        let mut everything = in_wgpu::EverythingRenderer::new(
             self.device.clone(),
             cameras,
             wgpu::TextureFormat::Rgba8UnormSrgb,
             &self.adapter,
         );
+        // Each image should show everything the update before it prepared, rather than
+        // whichever chunk meshes background threads happened to have finished.
+        everything.set_threaded_meshing(false);
 
         let viewport_dirty = DirtyFlag::listening(false, &viewport_source);
         let viewport = viewport_source.snapshot();
@@ -39,6 +39,9 @@ pub(crate) struct Pipelines {
     /// Bind group layout for SpaceRenderer's space data and block textures.
     pub(crate) space_texture_bind_group_layout: wgpu::BindGroupLayout,
 
+    /// Bind group layout for dynamic light data and shadow maps.
+    pub(crate) dynamic_lights_bind_group_layout: wgpu::BindGroupLayout,
+
     /// Pipeline layout for using the blocks-and-lines shader.
     /// Saved for use by tests.
     #[cfg_attr(not(test), allow(dead_code))]
@@ -49,6 +52,13 @@ pub(crate) struct Pipelines {
 
     /// Pipeline for drawing transparent (alpha ≠ 1) blocks.
     pub(crate) transparent_render_pipeline: wgpu::RenderPipeline,
+
+    /// Pipeline for drawing opaque blocks into shadow maps (depth only).
+    pub(crate) shadow_render_pipeline: wgpu::RenderPipeline,
+
+    /// Pipeline for drawing the sky behind everything else, as a single
+    /// viewport-covering triangle with no vertex buffer.
+    pub(crate) sky_render_pipeline: wgpu::RenderPipeline,
 }
 
 /// Shader code for rendering `Space` content, and debug lines.
@@ -127,10 +137,47 @@ impl Pipelines {
                 label: Some("Pipelines::camera_bind_group_layout"),
             });
 
+        let dynamic_lights_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            multisampled: false,
+                            view_dimension: wgpu::TextureViewDimension::D2Array,
+                            sample_type: wgpu::TextureSampleType::Depth,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+                        count: None,
+                    },
+                ],
+                label: Some("Pipelines::dynamic_lights_bind_group_layout"),
+            });
+
         let block_render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Pipelines::block_render_pipeline_layout"),
-                bind_group_layouts: &[&camera_bind_group_layout, &space_texture_bind_group_layout],
+                bind_group_layouts: &[
+                    &camera_bind_group_layout,
+                    &space_texture_bind_group_layout,
+                    &dynamic_lights_bind_group_layout,
+                ],
                 push_constant_ranges: &[],
             });
 
@@ -228,6 +275,74 @@ impl Pipelines {
                 multiview: None,
             });
 
+        // The shadow pass uses only the vertex shader, which uses only the camera.
+        let shadow_render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Pipelines::shadow_render_pipeline_layout"),
+                bind_group_layouts: &[&camera_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let shadow_render_pipeline =
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Pipelines::shadow_render_pipeline"),
+                layout: Some(&shadow_render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "block_vertex_main",
+                    buffers: vertex_buffers,
+                },
+                fragment: None,
+                // Back faces are culled as usual, so that a light inside a block is not
+                // shadowed by that block.
+                primitive: block_primitive_state,
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: FramebufferTextures::DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    // Bias against surfaces shadowing themselves.
+                    bias: wgpu::DepthBiasState {
+                        constant: 2,
+                        slope_scale: 2.0,
+                        clamp: 0.0,
+                    },
+                }),
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+            });
+
+        let sky_render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Pipelines::sky_render_pipeline"),
+            layout: Some(&block_render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "sky_vertex",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "sky_fragment",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: fb.linear_scene_texture_format(),
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: FramebufferTextures::DEPTH_FORMAT,
+                // The sky is infinitely far away, so it neither occludes nor is
+                // occluded by anything; it is simply drawn first.
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample,
+            multiview: None,
+        });
+
         let lines_render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Pipelines::lines_render_pipeline_layout"),
@@ -277,9 +392,12 @@ impl Pipelines {
             graphics_options,
             camera_bind_group_layout,
             space_texture_bind_group_layout,
+            dynamic_lights_bind_group_layout,
             block_render_pipeline_layout,
             opaque_render_pipeline,
             transparent_render_pipeline,
+            shadow_render_pipeline,
+            sky_render_pipeline,
 
             lines_render_pipeline,
         }
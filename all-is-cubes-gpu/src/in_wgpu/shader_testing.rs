@@ -17,10 +17,12 @@ use all_is_cubes::listen::ListenableSource;
 use all_is_cubes::math::{Face6, GridAab, Rgb, Rgba};
 use all_is_cubes::mesh::BlockVertex;
 use all_is_cubes::notnan;
+use all_is_cubes::space::Sky;
 
 use crate::in_wgpu::{
     self,
     camera::ShaderSpaceCamera,
+    dynamic_lights::DynamicLights,
     frame_texture::FbtFeatures,
     init::get_texels_from_gpu,
     space::SpaceCameraBuffer,
@@ -127,6 +129,9 @@ where
         ),
     );
 
+    // Placeholder (zero) dynamic lights
+    let dynamic_lights = DynamicLights::new("shader test space", &device, &pipelines);
+
     // This buffer contains one triangle, that will be full-screen once the camera looks
     // at it. Remember that block vertices must actually stick to the 0-1 range.
     let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -136,16 +141,19 @@ where
                 position: Point3::new(0., 0., 0.),
                 face: Face6::PZ,
                 coloring: all_is_cubes::mesh::Coloring::Solid(Rgba::WHITE),
+                material: all_is_cubes::block::Material::DIFFUSE,
             }),
             WgpuBlockVertex::from(BlockVertex {
                 position: Point3::new(1., 0., 0.),
                 face: Face6::PZ,
                 coloring: all_is_cubes::mesh::Coloring::Solid(Rgba::WHITE),
+                material: all_is_cubes::block::Material::DIFFUSE,
             }),
             WgpuBlockVertex::from(BlockVertex {
                 position: Point3::new(0., 1., 0.),
                 face: Face6::PZ,
                 coloring: all_is_cubes::mesh::Coloring::Solid(Rgba::WHITE),
+                material: all_is_cubes::block::Material::DIFFUSE,
             }),
         ]),
         usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
@@ -169,7 +177,14 @@ where
     queue.write_buffer(
         &camera_buffer.buffer,
         0,
-        bytemuck::bytes_of(&ShaderSpaceCamera::new(&camera, Rgb::ZERO, Vector3::zero())),
+        bytemuck::bytes_of(&ShaderSpaceCamera::new(
+            &camera,
+            Rgb::ZERO,
+            &Sky::uniform(Rgb::ZERO),
+            Vector3::zero(),
+            None,
+            GridAab::ORIGIN_CUBE,
+        )),
     );
 
     let mut encoder =
@@ -195,6 +210,7 @@ where
         });
         render_pass.set_bind_group(0, &camera_buffer.bind_group, &[]);
         render_pass.set_bind_group(1, &space_bind_group, &[]);
+        render_pass.set_bind_group(2, dynamic_lights.bind_group(), &[]);
 
         render_pass.set_pipeline(&test_pipeline);
 
@@ -0,0 +1,138 @@
+// --- Interface declarations --------------------------------------------------
+
+// Mirrors `struct ShaderAmbientOcclusion` on the Rust side.
+struct ShaderAmbientOcclusion {
+    projection_matrix: mat4x4<f32>,
+    inverse_projection_matrix: mat4x4<f32>,
+    framebuffer_size: vec2<f32>,
+    _padding: vec2<f32>,
+};
+
+// This group is named AmbientOcclusion::bind_group_layout in the code.
+@group(0) @binding(0) var<uniform> camera: ShaderAmbientOcclusion;
+// `@group(0) @binding(1) depth_texture` is the depth buffer of the scene, bound as
+// an unfilterable float texture rather than a depth texture because the GLSL backend
+// cannot `textureLoad()` depth textures. The Rust side prepends its declaration to
+// this file, because its type is `texture_2d<f32>` or, if the scene is multisampled,
+// `texture_multisampled_2d<f32>`. For the latter, the last argument of `textureLoad()`
+// selects a sample instead of a mip level; either way we want 0.
+
+// --- Vertex shader -----------------------------------------------------------
+
+@vertex
+fn ambient_occlusion_vertex(
+    @builtin(vertex_index) in_vertex_index: u32,
+) -> @builtin(position) vec4<f32> {
+    // Full-screen triangle
+    return vec4<f32>(
+        mix(-1.0, 3.0, f32(in_vertex_index == 1u)),
+        mix(-1.0, 3.0, f32(in_vertex_index == 2u)),
+        0.0,
+        1.0
+    );
+}
+
+// --- Fragment shader; computing the occlusion --------------------------------
+//
+// The output is multiplied into the scene color by the blend state, so it is the
+// fraction of light that is *not* occluded.
+
+// Reconstruct the view-space position of the surface drawn at the given pixel.
+// The w component is 1, or 0 if nothing was drawn there.
+fn view_position_at(pixel: vec2<i32>) -> vec4<f32> {
+    let clamped = clamp(pixel, vec2<i32>(0), vec2<i32>(camera.framebuffer_size) - 1);
+    let depth = textureLoad(depth_texture, clamped, 0).x;
+    if (depth >= 1.0) {
+        return vec4<f32>(0.0);
+    }
+
+    let texcoord = (vec2<f32>(clamped) + 0.5) / camera.framebuffer_size;
+    let ndc = vec4<f32>(texcoord.x * 2.0 - 1.0, 1.0 - texcoord.y * 2.0, depth, 1.0);
+    let view_position = camera.inverse_projection_matrix * ndc;
+    return vec4<f32>(view_position.xyz / view_position.w, 1.0);
+}
+
+// Pseudorandom angle for each pixel (“interleaved gradient noise”), used to rotate the
+// sample pattern so that its small number of samples produces fine noise rather than
+// visible banding.
+fn noise_angle(frag_xy: vec2<f32>) -> f32 {
+    return 6.2831853 * fract(52.9829189 * fract(dot(frag_xy, vec2<f32>(0.06711056, 0.00583715))));
+}
+
+@fragment
+fn ambient_occlusion_fragment(@builtin(position) frag_coord: vec4<f32>) -> @location(0) vec4<f32> {
+    // Distance, in world units (cubes), within which geometry occludes a point.
+    // This matches the raytracer's voxel ambient occlusion, which examines the
+    // neighboring cubes of full-cube blocks.
+    let radius: f32 = 1.0;
+    // Fraction by which a fully occluded point is darkened.
+    // At a concave edge, half of the samples are occluded, and the raytracer's voxel
+    // ambient occlusion darkens by 1/3; this makes the two match there.
+    let strength: f32 = 0.6666667;
+    // Margin against a surface occluding itself.
+    let bias: f32 = 0.02;
+    let sample_count: i32 = 12;
+
+    let pixel = vec2<i32>(frag_coord.xy);
+    let center = view_position_at(pixel);
+    if (center.w == 0.0) {
+        // Sky; nothing to occlude.
+        return vec4<f32>(1.0);
+    }
+    let position = center.xyz;
+
+    // Reconstruct the normal from neighboring pixels, choosing on each axis the neighbor
+    // closer in depth so that we don't compute normals across silhouette edges.
+    let right = view_position_at(pixel + vec2<i32>(1, 0)).xyz - position;
+    let left = position - view_position_at(pixel - vec2<i32>(1, 0)).xyz;
+    let below = view_position_at(pixel + vec2<i32>(0, 1)).xyz - position;
+    let above = position - view_position_at(pixel - vec2<i32>(0, 1)).xyz;
+    let dx = select(right, left, abs(left.z) < abs(right.z));
+    let dy = select(below, above, abs(above.z) < abs(below.z));
+    var normal: vec3<f32> = normalize(cross(dy, dx));
+    if (dot(normal, position) > 0.0) {
+        // Face the camera.
+        normal = -normal;
+    }
+
+    // Tangent frame, rotated by the per-pixel noise.
+    let angle = noise_angle(frag_coord.xy);
+    let random = vec3<f32>(cos(angle), sin(angle), 0.0);
+    var tangent: vec3<f32> = random - normal * dot(random, normal);
+    if (length(tangent) < 0.01) {
+        tangent = cross(normal, vec3<f32>(1.0, 0.0, 0.0));
+    }
+    tangent = normalize(tangent);
+    let bitangent = cross(normal, tangent);
+
+    var occlusion: f32 = 0.0;
+    for (var i: i32 = 0; i < sample_count; i = i + 1) {
+        // Points spiraling over the hemisphere above the surface, and outward from it,
+        // so that the nearer samples look along the normal and the farther ones look
+        // sideways for adjacent walls.
+        let t = (f32(i) + 0.5) / f32(sample_count);
+        let theta = f32(i) * 2.3999632; // golden angle
+        let direction = (tangent * cos(theta) + bitangent * sin(theta)) * sqrt(t)
+            + normal * sqrt(1.0 - t);
+        let sample_position = position + direction * (radius * mix(0.1, 1.0, t));
+
+        let clip = camera.projection_matrix * vec4<f32>(sample_position, 1.0);
+        if (clip.w <= 0.0) {
+            continue;
+        }
+        let sample_texcoord = clip.xy / clip.w * vec2<f32>(0.5, -0.5) + 0.5;
+        let scene = view_position_at(vec2<i32>(sample_texcoord * camera.framebuffer_size));
+        if (scene.w == 0.0) {
+            continue;
+        }
+
+        // The sample is occluded if the scene surface is in front of it, unless that
+        // surface is too far away to count as nearby geometry.
+        if (scene.z >= sample_position.z + bias) {
+            occlusion += smoothstep(0.0, 1.0, radius / abs(position.z - scene.z));
+        }
+    }
+
+    let unoccluded = 1.0 - strength * occlusion / f32(sample_count);
+    return vec4<f32>(vec3<f32>(unoccluded), 1.0);
+}
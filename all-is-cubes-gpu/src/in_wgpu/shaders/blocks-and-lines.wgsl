@@ -12,6 +12,29 @@ struct ShaderSpaceCamera {
     light_lookup_offset_and_option: vec4<i32>, // vec3 + 1
     fog_color_and_fog_mode_blend: vec4<f32>, // vec3 + 1
     fog_distance_and_exposure: vec4<f32>, // last two components unused
+    volumetric_fog_color_and_density: vec4<f32>, // vec3 + 1
+    space_lower_bounds_and_fog_height: vec4<f32>, // vec3 + 1
+    space_upper_bounds_and_fog_falloff: vec4<f32>, // vec3 + 1
+    inverse_projection_view: mat4x4<f32>,
+    sky_zenith_color: vec3<f32>,
+    sky_horizon_color: vec3<f32>,
+    sun_direction: vec3<f32>, // zero if the sun is not visible
+    sun_color: vec3<f32>,
+};
+
+// Mirrors `struct ShaderDynamicLight` on the Rust side.
+struct ShaderDynamicLight {
+    position_and_range: vec4<f32>, // vec3 + 1
+    color_and_kind: vec4<f32>, // vec3 + 1; kind is 0 for point lights and 1 for spot lights
+    direction_and_cos_cutoff: vec4<f32>, // vec3 + 1
+    first_layer: vec4<i32>, // last three components unused
+};
+
+// Mirrors `struct ShaderDynamicLights` on the Rust side.
+struct ShaderDynamicLights {
+    count: vec4<u32>, // last three components unused
+    lights: array<ShaderDynamicLight, 4>, // MAX_DYNAMIC_LIGHTS
+    shadow_matrices: array<mat4x4<f32>, 24>, // MAX_SHADOW_LAYERS
 };
 
 // Mirrors `struct WgpuBlockVertex` on the Rust side.
@@ -21,6 +44,7 @@ struct WgpuBlockVertex {
     @location(2) color_or_texture: vec4<f32>,
     @location(3) clamp_min: vec3<f32>,
     @location(4) clamp_max: vec3<f32>,
+    @location(5) material: vec2<f32>, // reflectance, roughness
 };
 
 // Mirrors `struct WgpuInstanceData` on the Rust side.
@@ -42,6 +66,11 @@ struct WgpuLinesVertex {
 @group(1) @binding(1) var block_sampler: sampler;
 @group(1) @binding(2) var light_texture: texture_3d<u32>;
 
+// This group is named dynamic_lights_bind_group_layout in the code.
+@group(2) @binding(0) var<uniform> dynamic_lights: ShaderDynamicLights;
+@group(2) @binding(1) var shadow_texture: texture_depth_2d_array;
+@group(2) @binding(2) var shadow_sampler: sampler_comparison;
+
 // --- Fog computation --------------------------------------------------------
 
 // Physically realistic fog, but doesn't ever reach 1 (fully opaque).
@@ -119,6 +148,9 @@ struct BlockFragmentInput {
     // Direction vector, in the world coordinate system, which points
     // from the camera position to this fragment.
     @location(10) camera_ray_direction: vec3<f32>,
+
+    // Reflectance and roughness of the surface material.
+    @location(11) material: vec2<f32>,
 };
 
 @vertex
@@ -184,6 +216,7 @@ fn block_vertex_main(
         // Note that we do not normalize this vector: by keeping things linear, we
         // allow linear interpolation between vertices to get the right answer.
         /* camera_ray_direction = */ world_position - camera.view_position,
+        input.material,
     );
 }
 
@@ -359,6 +392,76 @@ fn interpolated_space_light(in: BlockFragmentInput) -> vec3<f32> {
     return v.rgb / max(0.1, v.a);
 }
 
+// Choose which of a point light's six shadow map layers covers the direction
+// `to_fragment`. Must match the order in `LightInstance::shadow_cameras()`.
+fn point_shadow_face(to_fragment: vec3<f32>) -> i32 {
+    let a = abs(to_fragment);
+    if (a.x >= a.y && a.x >= a.z) {
+        return select(1, 0, to_fragment.x >= 0.0);
+    } else if (a.y >= a.z) {
+        return select(3, 2, to_fragment.y >= 0.0);
+    } else {
+        return select(5, 4, to_fragment.z >= 0.0);
+    }
+}
+
+// Returns 1.0 if `world_position` is lit according to the given shadow map layer,
+// 0.0 if it is in shadow, or in between at shadow edges.
+fn shadow_visibility(layer: i32, world_position: vec3<f32>) -> f32 {
+    let clip = dynamic_lights.shadow_matrices[layer] * vec4<f32>(world_position, 1.0);
+    let ndc = clip.xyz / clip.w;
+    let uv = ndc.xy * vec2<f32>(0.5, -0.5) + vec2<f32>(0.5);
+    return textureSampleCompareLevel(shadow_texture, shadow_sampler, uv, layer, ndc.z);
+}
+
+// Compute light from dynamic lights arriving at the fragment.
+// Unlike the light texture, this is not affected by the surface's ambient occlusion.
+fn dynamic_lighting(in: BlockFragmentInput) -> vec3<f32> {
+    // Distance to move shadow lookups off the surface, to avoid self-shadowing.
+    let normal_offset = 1.0 / 32.0;
+
+    var total = vec3<f32>(0.0);
+    let count = i32(dynamic_lights.count.x);
+    for (var i = 0; i < count; i = i + 1) {
+        let light = dynamic_lights.lights[i];
+        let to_fragment = in.world_position - light.position_and_range.xyz;
+        let distance = length(to_fragment);
+        let range = light.position_and_range.w;
+        if (distance >= range || distance <= 0.0) {
+            continue;
+        }
+        let direction = to_fragment / distance;
+        let lambert = dot(in.normal, -direction);
+        if (lambert <= 0.0) {
+            continue;
+        }
+
+        // Inverse-square falloff, windowed so that it smoothly reaches zero at the range.
+        let window = clamp(1.0 - pow(distance / range, 4.0), 0.0, 1.0);
+        let attenuation = window * window / max(distance * distance, 1.0);
+
+        let sample_position = in.world_position + in.normal * normal_offset;
+        var cone = 1.0;
+        var layer = light.first_layer.x;
+        if (light.color_and_kind.w > 0.5) {
+            // Spot light: fade out over the outermost part of the cone.
+            // TODO: use smoothstep once wgpu implements it
+            let cos_cutoff = light.direction_and_cos_cutoff.w;
+            let cos_angle = dot(direction, light.direction_and_cos_cutoff.xyz);
+            cone = clamp((cos_angle - cos_cutoff) / max((1.0 - cos_cutoff) * 0.25, 0.0001), 0.0, 1.0);
+            if (cone <= 0.0) {
+                continue;
+            }
+        } else {
+            layer = layer + point_shadow_face(sample_position - light.position_and_range.xyz);
+        }
+
+        let shadow = shadow_visibility(layer, sample_position);
+        total = total + light.color_and_kind.rgb * (lambert * attenuation * cone * shadow);
+    }
+    return total;
+}
+
 // Compute light intensity applying to the fragment.
 fn lighting(in: BlockFragmentInput) -> vec3<f32> {
     switch camera.light_lookup_offset_and_option.w {
@@ -371,12 +474,13 @@ fn lighting(in: BlockFragmentInput) -> vec3<f32> {
         case 1 {
             let origin = in.world_cube + in.normal + vec3<f32>(0.5);
             let local_light = light_texture_fetch(origin).rgb;
-            return fixed_directional_lighting(in.normal) * local_light;
+            return fixed_directional_lighting(in.normal) * local_light + dynamic_lighting(in);
         }
 
         // LightingOption::Smooth
         case 2 {
-            return fixed_directional_lighting(in.normal) * interpolated_space_light(in);
+            return fixed_directional_lighting(in.normal) * interpolated_space_light(in)
+                + dynamic_lighting(in);
         }
     }
 }
@@ -396,11 +500,85 @@ fn get_diffuse_color(in: BlockFragmentInput) -> vec4<f32> {
     }
 }
 
-// Apply the effects of distance fog and camera exposure.
+// Approximate reflection for materials with nonzero reflectance.
+// Unlike the raytracer, we have no way to find what is actually reflected, so the fog color
+// (which is the sky color) stands in for it, and rough surfaces reflect less of it.
+// Returns the new color and alpha, not premultiplied.
+fn apply_reflection(in: BlockFragmentInput, lit_color: vec4<f32>) -> vec4<f32> {
+    let reflectance = in.material.x;
+    if (reflectance <= 0.0) {
+        return lit_color;
+    }
+    let cosine = clamp(dot(normalize(-in.camera_ray_direction), in.normal), 0.0, 1.0);
+    // Schlick's approximation of the Fresnel factor.
+    let fresnel = (reflectance + (1.0 - reflectance) * pow(1.0 - cosine, 5.0))
+        * (1.0 - in.material.y);
+    let reflected = camera.fog_color_and_fog_mode_blend.rgb;
+    let alpha = lit_color.a + (1.0 - lit_color.a) * fresnel;
+    let rgb = (lit_color.rgb * lit_color.a * (1.0 - fresnel) + reflected * fresnel) / max(alpha, 0.0001);
+    return vec4<f32>(rgb, alpha);
+}
+
+// Light falling on the volumetric fog at the given point.
+fn volumetric_fog_light(world_position: vec3<f32>) -> vec3<f32> {
+    if (camera.light_lookup_offset_and_option.w == 0) {
+        // LightingOption::None
+        return vec3<f32>(1.0);
+    }
+    return light_texture_fetch(world_position).rgb;
+}
+
+// Compute the effect of the space's volumetric fog (`SpacePhysics::fog`) along the ray
+// from the camera to `world_position`. Light falling on the fog is taken from the light
+// texture, so that shadowed and lit regions produce light shafts.
+// Returns the light scattered towards the camera in rgb, and the transmittance of the
+// fog in a. This algorithm is also implemented in the Rust code (the raytracer).
+fn volumetric_fog(world_position: vec3<f32>) -> vec4<f32> {
+    let step_count = 16;
+    let density = camera.volumetric_fog_color_and_density.a;
+    if (density <= 0.0) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    let fog_height = camera.space_lower_bounds_and_fog_height.w;
+    let fog_falloff = camera.space_upper_bounds_and_fog_falloff.w;
+
+    // Clip the ray to the bounds of the space, avoiding division by zero.
+    let origin = camera.view_position;
+    let ray = world_position - origin;
+    let safe_ray = select(ray, vec3<f32>(1e-6), abs(ray) < vec3<f32>(1e-6));
+    let t_lower = (camera.space_lower_bounds_and_fog_height.xyz - origin) / safe_ray;
+    let t_upper = (camera.space_upper_bounds_and_fog_falloff.xyz - origin) / safe_ray;
+    let t_near = min(t_lower, t_upper);
+    let t_far = max(t_lower, t_upper);
+    let t_start = max(0.0, max(t_near.x, max(t_near.y, t_near.z)));
+    let t_end = min(1.0, min(t_far.x, min(t_far.y, t_far.z)));
+    if (t_end <= t_start) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+
+    let step_t = (t_end - t_start) / f32(step_count);
+    let step_distance = step_t * length(ray);
+    var scattered = vec3<f32>(0.0);
+    var transmittance = 1.0;
+    for (var i = 0; i < step_count; i = i + 1) {
+        let point = origin + ray * (t_start + step_t * (f32(i) + 0.5));
+        let local_density = density * exp(-fog_falloff * max(0.0, point.y - fog_height));
+        let alpha = 1.0 - exp(-local_density * step_distance);
+        scattered += transmittance * alpha * volumetric_fog_light(point);
+        transmittance *= 1.0 - alpha;
+    }
+    return vec4<f32>(scattered * camera.volumetric_fog_color_and_density.rgb, transmittance);
+}
+
+// Apply the effects of volumetric fog, distance fog, and camera exposure.
 // These effects are independent of alpha and therefore the input and output is RGB.
-fn apply_fog_and_exposure(lit_color: vec3<f32>, fog_mix: f32) -> vec3<f32> {
-    // Fog
-    let fogged_color = mix(lit_color, camera.fog_color_and_fog_mode_blend.rgb, fog_mix);
+fn apply_fog_and_exposure(lit_color: vec3<f32>, fog_mix: f32, world_position: vec3<f32>) -> vec3<f32> {
+    // Volumetric fog
+    let volumetric = volumetric_fog(world_position);
+    let volumetric_fogged_color = lit_color * volumetric.a + volumetric.rgb;
+
+    // Distance fog
+    let fogged_color = mix(volumetric_fogged_color, camera.fog_color_and_fog_mode_blend.rgb, fog_mix);
 
     // Exposure/eye adaptation
     let exposed_color = fogged_color.rgb * camera.fog_distance_and_exposure[1];
@@ -441,15 +619,16 @@ fn volumetric_transparency(in: BlockFragmentInput, starting_alpha: f32) -> f32 {
 // Entry point for opaque geometry.
 @fragment
 fn block_fragment_opaque(in: BlockFragmentInput) -> @location(0) vec4<f32> {
-    let lit_color: vec3<f32> = get_diffuse_color(in).rgb * lighting(in);
-    return vec4<f32>(apply_fog_and_exposure(lit_color, in.fog_mix), 1.0);
+    let lit_color: vec3<f32> =
+        apply_reflection(in, vec4<f32>(get_diffuse_color(in).rgb * lighting(in), 1.0)).rgb;
+    return vec4<f32>(apply_fog_and_exposure(lit_color, in.fog_mix, in.world_position), 1.0);
 }
 
 // Entry point for transparency under TransparencyOption::Surface.
 @fragment
 fn block_fragment_transparent_surface(in: BlockFragmentInput) -> @location(0) vec4<f32> {
-    let lit_color = get_diffuse_color(in) * vec4<f32>(lighting(in), 1.0);
-    let exposed_color = vec4<f32>(apply_fog_and_exposure(lit_color.rgb, in.fog_mix), lit_color.a);
+    let lit_color = apply_reflection(in, get_diffuse_color(in) * vec4<f32>(lighting(in), 1.0));
+    let exposed_color = vec4<f32>(apply_fog_and_exposure(lit_color.rgb, in.fog_mix, in.world_position), lit_color.a);
     return vec4<f32>(exposed_color.rgb * exposed_color.a, exposed_color.a);
 }
 
@@ -458,11 +637,53 @@ fn block_fragment_transparent_surface(in: BlockFragmentInput) -> @location(0) ve
 fn block_fragment_transparent_volumetric(in: BlockFragmentInput) -> @location(0) vec4<f32> {
     var diffuse_color = get_diffuse_color(in);
     diffuse_color.a = volumetric_transparency(in, diffuse_color.a);
-    let lit_color = diffuse_color * vec4<f32>(lighting(in), 1.0);
-    let exposed_color = vec4<f32>(apply_fog_and_exposure(lit_color.rgb, in.fog_mix), lit_color.a);
+    let lit_color = apply_reflection(in, diffuse_color * vec4<f32>(lighting(in), 1.0));
+    let exposed_color = vec4<f32>(apply_fog_and_exposure(lit_color.rgb, in.fog_mix, in.world_position), lit_color.a);
     return vec4<f32>(exposed_color.rgb * exposed_color.a, exposed_color.a);
 }
 
+// --- Sky shader --------------------------------------------------------------
+
+// Vertex-to-fragment data for the sky
+struct SkyFragmentInput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) ndc_xy: vec2<f32>,
+};
+
+// Mirrors `Sky::sample()` on the Rust side.
+fn sky_color(direction: vec3<f32>) -> vec3<f32> {
+    let elevation = clamp(direction.y, 0.0, 1.0);
+    let gradient = mix(camera.sky_horizon_color, camera.sky_zenith_color, elevation);
+
+    // Raise to the 8th power (SUN_GLOW_EXPONENT) by repeated squaring.
+    // If there is no visible sun, sun_direction is zero and so is the glow.
+    let cosine = max(dot(direction, camera.sun_direction), 0.0);
+    let cosine_2 = cosine * cosine;
+    let cosine_4 = cosine_2 * cosine_2;
+    let glow = cosine_4 * cosine_4 * 9.0;
+
+    return gradient + camera.sun_color * glow;
+}
+
+// Produces a single triangle which covers the entire viewport.
+@vertex
+fn sky_vertex(@builtin(vertex_index) index: u32) -> SkyFragmentInput {
+    let ndc_xy = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u)) * 2.0 - 1.0;
+    return SkyFragmentInput(vec4<f32>(ndc_xy, 1.0, 1.0), ndc_xy);
+}
+
+@fragment
+fn sky_fragment(in: SkyFragmentInput) -> @location(0) vec4<f32> {
+    // Find the direction of the view ray through this pixel by unprojecting two points
+    // on it. (This works for both perspective and orthographic projections.)
+    let near = camera.inverse_projection_view * vec4<f32>(in.ndc_xy, 0.0, 1.0);
+    let far = camera.inverse_projection_view * vec4<f32>(in.ndc_xy, 1.0, 1.0);
+    let direction = normalize(far.xyz / far.w - near.xyz / near.w);
+
+    let exposed_color = sky_color(direction) * camera.fog_distance_and_exposure[1];
+    return vec4<f32>(exposed_color, 1.0);
+}
+
 // --- Lines shader ------------------------------------------------------------
 //
 // This is in the same shader source file as the block shader so that it can share
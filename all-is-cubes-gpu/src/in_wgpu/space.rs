@@ -1,21 +1,22 @@
 //! Manages meshes for rendering a [`Space`].
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex, Weak};
 
 use instant::Instant;
 
 use all_is_cubes::camera::{Camera, Flaws};
-use all_is_cubes::cgmath::{EuclideanSpace, Point3, Transform, Vector3};
-use all_is_cubes::chunking::ChunkPos;
+use all_is_cubes::cgmath::{Array as _, EuclideanSpace, Point3, Transform, Vector3};
+use all_is_cubes::chunking::{point_to_chunk, ChunkPos};
 use all_is_cubes::content::palette;
 use all_is_cubes::listen::{Listen as _, Listener};
 use all_is_cubes::math::{Face6, FaceMap, FreeCoordinate, GridAab, GridCoordinate, GridPoint, Rgb};
 use all_is_cubes::mesh::chunked_mesh::{ChunkMeshUpdate, ChunkedSpaceMesh};
 use all_is_cubes::mesh::DepthOrdering;
-use all_is_cubes::space::{Space, SpaceChange};
+use all_is_cubes::space::{DynamicLight, Fog, Sky, Space, SpaceChange, SpacePhysics};
 use all_is_cubes::universe::URef;
 
+use crate::in_wgpu::dynamic_lights::{DynamicLights, LightInstance};
 use crate::in_wgpu::frame_texture::FramebufferTextures;
 use crate::in_wgpu::glue::{size_vector_to_extent, write_texture_by_aab};
 use crate::in_wgpu::pipelines::Pipelines;
@@ -38,6 +39,7 @@ pub(crate) struct SpaceRenderer {
     /// A debugging label for the space's render pass.
     /// (Derived from constructor's space_label)
     render_pass_label: String,
+    shadow_pass_label: String,
     instance_buffer_label: String,
 
     /// Tracks information we need to update from the `Space`.
@@ -46,6 +48,12 @@ pub(crate) struct SpaceRenderer {
 
     /// Cached copy of `space.physics.sky_color`.
     pub(crate) sky_color: Rgb,
+    /// Cached copy of `space.physics.sky`, or a uniform sky if there is none.
+    sky: Sky,
+    /// Cached copy of `space.physics.fog`.
+    fog: Option<Fog>,
+    /// Cached copy of `space.bounds()`.
+    space_bounds: GridAab,
 
     block_texture: Arc<AtlasAllocator>,
     light_texture: SpaceLightTexture,
@@ -60,10 +68,29 @@ pub(crate) struct SpaceRenderer {
     /// Bind group containing our block texture and light texture.
     space_bind_group: wgpu::BindGroup,
 
+    /// Dynamic lights attached to blocks in the space, by cube.
+    block_lights: HashMap<GridPoint, DynamicLight>,
+    /// Resources for drawing the nearest dynamic lights.
+    dynamic_lights: DynamicLights,
+
     csm: ChunkedSpaceMesh<Option<ChunkBuffers>, WgpuBlockVertex, AtlasAllocator, CHUNK_SIZE>,
+    /// Whether `csm` computes chunk meshes on background threads; remembered so that a
+    /// replacement `csm` does too.
+    threaded_meshing: bool,
     //TODO: debug_chunk_boxes_tess: Option<Tess<Backend, LinesVertex>>,
 }
 
+fn new_chunked_space_mesh(
+    space: URef<Space>,
+    threaded_meshing: bool,
+) -> ChunkedSpaceMesh<Option<ChunkBuffers>, WgpuBlockVertex, AtlasAllocator, CHUNK_SIZE> {
+    let mut csm = ChunkedSpaceMesh::new(space);
+    // Only has an effect if the `threads` feature of `all_is_cubes` is enabled,
+    // as it is in the desktop app.
+    csm.set_threaded_meshing(threaded_meshing);
+    csm
+}
+
 #[derive(Debug, Default)]
 struct ChunkBuffers {
     vertex_buf: ResizingBuffer,
@@ -81,6 +108,7 @@ impl SpaceRenderer {
         device: &wgpu::Device,
         pipelines: &Pipelines,
         block_texture: Arc<AtlasAllocator>,
+        threaded_meshing: bool,
     ) -> Result<Self, GraphicsResourceError> {
         let space_borrowed = space.read().map_err(GraphicsResourceError::read_err)?;
 
@@ -96,24 +124,40 @@ impl SpaceRenderer {
 
         let camera_buffer = SpaceCameraBuffer::new(&space_label, device, pipelines);
 
+        let dynamic_lights = DynamicLights::new(&space_label, device, pipelines);
+
         let todo = Arc::new(Mutex::new(SpaceRendererTodo::default()));
         space_borrowed.listen(TodoListener(Arc::downgrade(&todo)));
 
         Ok(SpaceRenderer {
             todo,
             render_pass_label: format!("{space_label} render_pass"),
+            shadow_pass_label: format!("{space_label} shadow_pass"),
             instance_buffer_label: format!("{space_label} instances"),
             space_label,
             sky_color: space_borrowed.physics().sky_color,
+            sky: sky_of(space_borrowed.physics()),
+            fog: space_borrowed.physics().fog,
+            space_bounds: space_borrowed.bounds(),
             block_texture,
             light_texture,
             space_bind_group,
+            block_lights: HashMap::new(),
+            dynamic_lights,
             camera_buffer,
             instance_buffer: ResizingBuffer::default(),
-            csm: ChunkedSpaceMesh::new(space),
+            csm: new_chunked_space_mesh(space, threaded_meshing),
+            threaded_meshing,
         })
     }
 
+    /// Sets whether chunk meshes are computed on background threads.
+    /// See [`ChunkedSpaceMesh::set_threaded_meshing()`].
+    pub(crate) fn set_threaded_meshing(&mut self, enabled: bool) {
+        self.threaded_meshing = enabled;
+        self.csm.set_threaded_meshing(enabled);
+    }
+
     /// Replace the space being rendered, while preserving some of the resources used to render it.
     ///
     /// This is not a minimum-effort operation and should be thought of as an optimized
@@ -133,15 +177,22 @@ impl SpaceRenderer {
         let SpaceRenderer {
             space_label,
             render_pass_label: _,
+            shadow_pass_label: _,
             instance_buffer_label: _,
             todo,
             sky_color,
+            sky,
+            fog,
+            space_bounds,
             block_texture,
             light_texture,
             camera_buffer: _,
             instance_buffer: _,
             space_bind_group,
+            block_lights,
+            dynamic_lights: _,
             csm,
+            threaded_meshing,
         } = self;
 
         let space_borrowed = space.read().unwrap();
@@ -152,8 +203,13 @@ impl SpaceRenderer {
             todo
         };
         // TODO: rescue ChunkChart and maybe block meshes from the old `csm`.
-        *csm = ChunkedSpaceMesh::new(space.clone());
+        *csm = new_chunked_space_mesh(space.clone(), *threaded_meshing);
+        // Will be refilled by the next update, since the new todo requests a full scan.
+        block_lights.clear();
         *sky_color = space_borrowed.physics().sky_color;
+        *sky = sky_of(space_borrowed.physics());
+        *fog = space_borrowed.physics().fog;
+        *space_bounds = space_borrowed.bounds();
         // TODO: don't replace light texture if the size is the same
         *light_texture = SpaceLightTexture::new(space_label, device, space_borrowed.bounds());
         // bind group must be recreated for new light texture
@@ -164,11 +220,17 @@ impl SpaceRenderer {
     /// Update renderer internal state from the given [`Camera`] and referenced [`Space`],
     /// so that the next rendered meshes will be up to date (or as far up to date as the
     /// given [`deadline`] permits).
+    ///
+    /// `other_lights` are dynamic lights not attached to blocks in the space (such as the
+    /// character's) which should be drawn if they are near enough.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn update(
         &mut self,
         deadline: Instant,
         queue: &wgpu::Queue,
+        pipelines: &Pipelines,
         camera: &Camera,
+        other_lights: &[LightInstance],
         mut bwp: BeltWritingParts<'_, '_>,
     ) -> Result<SpaceUpdateInfo, GraphicsResourceError> {
         let start_time = Instant::now();
@@ -181,8 +243,10 @@ impl SpaceRenderer {
             .read()
             .map_err(GraphicsResourceError::read_err)?;
 
-        // Update sky color (cheap so we don't bother todo-tracking it)
+        // Update sky and fog (cheap so we don't bother todo-tracking it)
         self.sky_color = space.physics().sky_color;
+        self.sky = sky_of(space.physics());
+        self.fog = space.physics().fog;
 
         // Update light texture
         let start_light_update = Instant::now();
@@ -202,6 +266,40 @@ impl SpaceRenderer {
         }
         let end_light_update = Instant::now();
 
+        // Update dynamic lights attached to blocks
+        if let Some(set) = &mut todo.dynamic_lights {
+            for cube in set.drain() {
+                match space.get_evaluated(cube).attributes.dynamic_light {
+                    Some(light) => self.block_lights.insert(cube, light),
+                    None => self.block_lights.remove(&cube),
+                };
+            }
+        } else {
+            self.block_lights.clear();
+            // Skip scanning the whole space if it cannot contain any lights.
+            if space
+                .block_data()
+                .iter()
+                .any(|data| data.evaluated().attributes.dynamic_light.is_some())
+            {
+                for cube in space.bounds().interior_iter() {
+                    if let Some(light) = space.get_evaluated(cube).attributes.dynamic_light {
+                        self.block_lights.insert(cube, light);
+                    }
+                }
+            }
+            todo.dynamic_lights = Some(HashSet::new());
+        }
+        self.dynamic_lights.update(
+            bwp.device,
+            pipelines,
+            camera,
+            self.block_lights
+                .iter()
+                .map(|(&cube, &light)| LightInstance::from_block(cube, light))
+                .chain(other_lights.iter().copied()),
+        );
+
         // Update chunks
         let csm_info = self.csm.update_blocks_and_some_chunks(
             camera,
@@ -266,6 +364,9 @@ impl SpaceRenderer {
 
     /// Draw the space as of the last [`Self::update`].
     ///
+    /// If `draw_sky` is true, the space's sky is drawn behind it; this should be done
+    /// only if nothing else is behind it, and `color_load_op` clears to the sky color.
+    ///
     /// Does not access the [`Space`] contents at all.
     // TODO: needs error return or not?
     #[allow(clippy::too_many_arguments)]
@@ -277,6 +378,7 @@ impl SpaceRenderer {
         pipelines: &Pipelines,
         camera: &Camera,
         color_load_op: wgpu::LoadOp<wgpu::Color>,
+        draw_sky: bool,
         store_depth: bool,
     ) -> Result<SpaceDrawInfo, GraphicsResourceError> {
         let start_time = Instant::now();
@@ -285,6 +387,9 @@ impl SpaceRenderer {
         let view_chunk = csm.view_chunk();
 
         let mut flaws = Flaws::empty();
+        if !camera.options().projection.is_linear() {
+            flaws |= Flaws::UNSUPPORTED_PROJECTION;
+        }
 
         // Accumulates instance data for meshes, which we will then write as part of this
         // submission. (Draw commands always happen after buffer writes even if they
@@ -295,16 +400,100 @@ impl SpaceRenderer {
                 .map_or(0, |buffer| usize::try_from(buffer.size()).unwrap_or(0)),
         );
 
+        // Each chunk drawn gets one entry in `instance_data`, shared by all passes.
+        let mut instance_ids: HashMap<ChunkPos<CHUNK_SIZE>, u32> = HashMap::new();
+        let instance_capacity = self.instance_buffer.get().map_or(0, |buffer| {
+            usize::try_from(buffer.size()).unwrap_or(usize::MAX)
+                / std::mem::size_of::<WgpuInstanceData>()
+        });
+
         queue.write_buffer(
             &self.camera_buffer.buffer,
             0,
             bytemuck::bytes_of(&ShaderSpaceCamera::new(
                 camera,
                 self.sky_color,
+                &self.sky,
                 self.light_texture.light_lookup_offset(),
+                self.fog.as_ref(),
+                self.space_bounds,
             )),
         );
 
+        // Draw opaque geometry into the shadow map of each dynamic light.
+        // (Not counted in `squares_drawn`, which is about the scene as seen.)
+        let mut shadow_squares_drawn = 0;
+        self.dynamic_lights.write_uniforms(queue);
+        for (light_camera, layer_view, light_camera_buffer) in self.dynamic_lights.shadow_layers() {
+            queue.write_buffer(
+                &light_camera_buffer.buffer,
+                0,
+                bytemuck::bytes_of(&ShaderSpaceCamera::new(
+                    light_camera,
+                    self.sky_color,
+                    &self.sky,
+                    self.light_texture.light_lookup_offset(),
+                    // Shadow maps record only depth, so fog is irrelevant.
+                    None,
+                    self.space_bounds,
+                )),
+            );
+
+            let mut shadow_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some(&self.shadow_pass_label),
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: layer_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+            shadow_pass.set_pipeline(&pipelines.shadow_render_pipeline);
+            shadow_pass.set_bind_group(0, &light_camera_buffer.bind_group, &[]);
+            if let Some(buffer) = self.instance_buffer.get() {
+                shadow_pass.set_vertex_buffer(1, buffer.slice(..));
+            } else {
+                // If there's no buffer then there must also be no instances; no action needed.
+            }
+
+            // Visit the chunks within the light's range. These are not necessarily all
+            // chunks the main pass will draw, so we must check the instance buffer has
+            // room for them. Chunks are not occlusion culled, since occlusion is computed
+            // from the camera's position and not the light's.
+            let eye = light_camera.view_position();
+            let range = Vector3::from_value(light_camera.view_distance());
+            let lower = point_to_chunk::<CHUNK_SIZE>(eye - range).0;
+            let upper = point_to_chunk::<CHUNK_SIZE>(eye + range).0;
+            for x in lower.x..=upper.x {
+                for y in lower.y..=upper.y {
+                    for z in lower.z..=upper.z {
+                        let p = ChunkPos::new(x, y, z);
+                        let has_instance_room =
+                            instance_ids.contains_key(&p) || instance_ids.len() < instance_capacity;
+                        if let Some(chunk) = csm.chunk(p).filter(|chunk| {
+                            has_instance_room
+                                && light_camera.aab_in_view(chunk.position().bounds().into())
+                        }) {
+                            if let Some(buffers) = &chunk.render_data {
+                                draw_instance(
+                                    chunk.mesh().opaque_range(),
+                                    &mut shadow_pass,
+                                    buffers,
+                                    &mut instance_data,
+                                    &mut instance_ids,
+                                    p,
+                                    &mut shadow_squares_drawn,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some(&self.render_pass_label),
             color_attachments: &[Some(fb.color_attachment_for_scene(color_load_op))],
@@ -319,28 +508,17 @@ impl SpaceRenderer {
         });
         render_pass.set_bind_group(0, &self.camera_buffer.bind_group, &[]);
         render_pass.set_bind_group(1, &self.space_bind_group, &[]);
+        render_pass.set_bind_group(2, self.dynamic_lights.bind_group(), &[]);
         if let Some(buffer) = self.instance_buffer.get() {
             render_pass.set_vertex_buffer(1, buffer.slice(..));
         } else {
             // If there's no buffer then there must also be no instances; no action needed.
         }
 
-        // Helper for the common logic of opaque + transparent drawing
-        fn draw_instance<'pass>(
-            range: std::ops::Range<usize>,
-            render_pass: &mut wgpu::RenderPass<'pass>,
-            buffers: &'pass ChunkBuffers,
-            instance_data: &mut Vec<WgpuInstanceData>,
-            p: ChunkPos<CHUNK_SIZE>,
-            squares_drawn: &mut usize,
-        ) {
-            if !range.is_empty() {
-                set_buffers(render_pass, buffers);
-                let id = u32::try_from(instance_data.len()).unwrap();
-                instance_data.push(WgpuInstanceData::new(p.bounds().lower_bounds().to_vec()));
-                render_pass.draw_indexed(to_wgpu_index_range(range.clone()), 0, id..(id + 1));
-                *squares_drawn += range.len() / 6;
-            }
+        // The sky is drawn over the clear color, which only approximates it.
+        if draw_sky {
+            render_pass.set_pipeline(&pipelines.sky_render_pipeline);
+            render_pass.draw(0..3, 0..1);
         }
 
         // Opaque geometry first, in front-to-back order
@@ -357,6 +535,7 @@ impl SpaceRenderer {
                     &mut render_pass,
                     buffers,
                     &mut instance_data,
+                    &mut instance_ids,
                     chunk.position(),
                     &mut squares_drawn,
                 );
@@ -381,6 +560,7 @@ impl SpaceRenderer {
                         &mut render_pass,
                         buffers,
                         &mut instance_data,
+                        &mut instance_ids,
                         chunk.position(),
                         &mut squares_drawn,
                     );
@@ -514,6 +694,28 @@ pub(in crate::in_wgpu) fn create_space_bind_group(
     })
 }
 
+/// Helper for the common logic of shadow, opaque, and transparent drawing.
+fn draw_instance<'pass>(
+    range: std::ops::Range<usize>,
+    render_pass: &mut wgpu::RenderPass<'pass>,
+    buffers: &'pass ChunkBuffers,
+    instance_data: &mut Vec<WgpuInstanceData>,
+    instance_ids: &mut HashMap<ChunkPos<CHUNK_SIZE>, u32>,
+    p: ChunkPos<CHUNK_SIZE>,
+    squares_drawn: &mut usize,
+) {
+    if !range.is_empty() {
+        set_buffers(render_pass, buffers);
+        let id = *instance_ids.entry(p).or_insert_with(|| {
+            let id = u32::try_from(instance_data.len()).unwrap();
+            instance_data.push(WgpuInstanceData::new(p.bounds().lower_bounds().to_vec()));
+            id
+        });
+        render_pass.draw_indexed(to_wgpu_index_range(range.clone()), 0, id..(id + 1));
+        *squares_drawn += range.len() / 6;
+    }
+}
+
 fn set_buffers<'a>(render_pass: &mut wgpu::RenderPass<'a>, buffers: &'a ChunkBuffers) {
     render_pass.set_vertex_buffer(
         0,
@@ -578,6 +780,18 @@ struct SpaceRendererTodo {
     ///
     /// TODO: experiment with different granularities of light invalidation (chunks, dirty rects, etc.)
     light: Option<HashSet<GridPoint>>,
+
+    /// Cubes whose dynamic lights should be rechecked.
+    /// None means rescan the entire space.
+    dynamic_lights: Option<HashSet<GridPoint>>,
+}
+
+/// Returns the sky to draw for a space with the given physics.
+fn sky_of(physics: &SpacePhysics) -> Sky {
+    physics
+        .sky
+        .clone()
+        .unwrap_or_else(|| Sky::uniform(physics.sky_color))
 }
 
 /// [`Listener`] adapter for [`SpaceRendererTodo`].
@@ -591,6 +805,7 @@ impl Listener<SpaceChange> for TodoListener {
                 match message {
                     SpaceChange::EveryBlock => {
                         todo.light = None;
+                        todo.dynamic_lights = None;
                     }
                     SpaceChange::Lighting(p) => {
                         // None means we're already at "update everything"
@@ -598,9 +813,19 @@ impl Listener<SpaceChange> for TodoListener {
                             set.insert(p);
                         }
                     }
-                    SpaceChange::Block(..) => {}
+                    SpaceChange::Block(p) => {
+                        if let Some(set) = &mut todo.dynamic_lights {
+                            set.insert(p);
+                        }
+                    }
                     SpaceChange::Number(..) => {}
-                    SpaceChange::BlockValue(..) => {}
+                    // We don't know which cubes contain the block, so rescan.
+                    SpaceChange::BlockValue(..) => {
+                        todo.dynamic_lights = None;
+                    }
+                    // The sky and fog are re-read on every update.
+                    SpaceChange::Physics => {}
+                    SpaceChange::CubeData(_) => {}
                 }
             }
         }
@@ -42,6 +42,10 @@ pub(crate) struct WgpuBlockVertex {
     clamp_min: [f32; 3],
     /// Interpolated texture coordinates are clamped to be ≤ this value, to avoid bleeding.
     clamp_max: [f32; 3],
+    /// Reflectance and roughness of the surface's [`Material`].
+    ///
+    /// [`Material`]: all_is_cubes::block::Material
+    material: [f32; 2],
 }
 
 impl WgpuBlockVertex {
@@ -51,6 +55,7 @@ impl WgpuBlockVertex {
         2 => Float32x4, // color_or_texture
         3 => Float32x3, // clamp_min
         4 => Float32x3, // clamp_max
+        5 => Float32x2, // material
         // location numbers must not clash with WgpuInstanceData
     ];
 
@@ -75,6 +80,10 @@ impl From<BlockVertex<TexPoint>> for WgpuBlockVertex {
             position_in_cube_fixed.x | (position_in_cube_fixed.y << 16),
             position_in_cube_fixed.z | (normal << 16),
         ];
+        let material = [
+            vertex.material.reflectance.into_inner(),
+            vertex.material.roughness.into_inner(),
+        ];
         match vertex.coloring {
             Coloring::Solid(color) => {
                 let mut color_attribute: [f32; 4] = color.into();
@@ -87,6 +96,7 @@ impl From<BlockVertex<TexPoint>> for WgpuBlockVertex {
                     color_or_texture: color_attribute,
                     clamp_min: [0., 0., 0.],
                     clamp_max: [0., 0., 0.],
+                    material,
                 }
             }
             Coloring::Texture {
@@ -99,6 +109,7 @@ impl From<BlockVertex<TexPoint>> for WgpuBlockVertex {
                 color_or_texture: [tc[0], tc[1], tc[2], -1.0],
                 clamp_min: clamp_min.into(),
                 clamp_max: clamp_max.into(),
+                material,
             },
         }
     }
@@ -211,6 +222,7 @@ impl DebugLineVertex for WgpuLinesVertex {
 
 #[cfg(test)]
 mod tests {
+    use all_is_cubes::block::Material;
     use all_is_cubes::math::{Face6, Rgba};
     use std::mem;
 
@@ -221,7 +233,7 @@ mod tests {
     /// the struct is designed to have a fixed layout communicating to the shader anyway.
     #[test]
     fn vertex_size() {
-        assert_eq!(mem::size_of::<WgpuBlockVertex>(), 52);
+        assert_eq!(mem::size_of::<WgpuBlockVertex>(), 60);
         assert_eq!(mem::size_of::<WgpuLinesVertex>(), 28);
     }
 
@@ -234,6 +246,7 @@ mod tests {
             position: Point3::new(0.25, 0.0, 1.0),
             face: Face6::PX,
             coloring: Coloring::Solid(Rgba::new(0.0, 0.5, 1.0, 0.5)),
+            material: Material::DIFFUSE,
         });
         vertex.instantiate_vertex(WgpuBlockVertex::instantiate_block(Point3::new(100, 50, 7)));
         assert_eq!(GfxVertex::position(&vertex), Point3::new(100.25, 50.0, 8.0));
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::mem::size_of;
+use std::ops::Range;
 
 use bytemuck::offset_of;
 use gltf_json::extras::Void;
@@ -44,7 +45,37 @@ pub(crate) fn add_mesh(
     mesh: &SpaceMesh<GltfVertex, GltfTextureRef>,
 ) -> Index<gltf_json::Mesh> {
     let vertex_bytes = bytemuck::cast_slice::<GltfVertex, u8>(mesh.vertices());
-    let index_bytes = bytemuck::cast_slice::<u32, u8>(mesh.indices());
+
+    // glTF materials are chosen per primitive, so group the triangles of each index range
+    // by their material, keeping the original order otherwise.
+    let mut indices: Vec<u32> = Vec::with_capacity(mesh.indices().len());
+    let mut primitive_ranges: Vec<(Range<usize>, bool, [f32; 3])> = Vec::new();
+    for (index_range, transparent) in [
+        (mesh.opaque_range(), false),
+        (
+            mesh.transparent_range(all_is_cubes::mesh::DepthOrdering::Any),
+            true,
+        ),
+    ] {
+        let mut groups: Vec<([f32; 3], Vec<u32>)> = Vec::new();
+        for triangle in mesh.indices()[index_range].chunks_exact(3) {
+            let material = mesh.vertices()[triangle[0] as usize].material();
+            let group_index = match groups.iter().position(|(m, _)| *m == material) {
+                Some(i) => i,
+                None => {
+                    groups.push((material, Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            groups[group_index].1.extend_from_slice(triangle);
+        }
+        for (material, group_indices) in groups {
+            let start = indices.len();
+            indices.extend(group_indices);
+            primitive_ranges.push((start..indices.len(), transparent, material));
+        }
+    }
+    let index_bytes = bytemuck::cast_slice::<u32, u8>(&indices);
     let index_type = Valid(gltf_json::accessor::GenericComponentType(
         gltf_json::accessor::ComponentType::U32,
     ));
@@ -57,7 +88,7 @@ pub(crate) fn add_mesh(
             &format!("mesh-{i}", i = writer.root.buffers.len()),
             |w| {
                 w.write_all(vertex_bytes)?;
-                for index in mesh.indices() {
+                for index in &indices {
                     w.write_all(&index.to_le_bytes())?;
                 }
                 Ok(())
@@ -127,55 +158,51 @@ pub(crate) fn add_mesh(
 
     writer.flaws |= mesh.flaws();
 
+    let primitives = primitive_ranges
+        .into_iter()
+        .map(|(index_range, transparent, material)| {
+            let name = if transparent {
+                format!("{name} transparent index")
+            } else {
+                format!("{name} opaque index")
+            };
+            gltf_json::mesh::Primitive {
+                attributes: vertex_colored_attributes.clone(),
+                indices: Some(push_and_return_index(
+                    &mut writer.root.accessors,
+                    gltf_json::Accessor {
+                        buffer_view: Some(index_buffer_view),
+                        byte_offset: u32size(index_range.start * size_of::<u32>()),
+                        count: u32size(index_range.len()),
+                        component_type: index_type,
+                        extensions: Default::default(),
+                        extras: Default::default(),
+                        type_: Valid(gltf_json::accessor::Type::Scalar),
+                        min: None,
+                        max: None,
+                        name: Some(name),
+                        normalized: false,
+                        sparse: None,
+                    },
+                )),
+                mode: Valid(gltf_json::mesh::Mode::Triangles),
+                material: Some(writer.materials.get(
+                    &mut writer.root.materials,
+                    transparent,
+                    material,
+                )),
+                targets: None,
+                extensions: Default::default(),
+                extras: Default::default(),
+            }
+        })
+        .collect();
+
     push_and_return_index(
         &mut writer.root.meshes,
         gltf_json::Mesh {
             name: Some(format!("{name} mesh")),
-            primitives: [
-                (
-                    mesh.opaque_range(),
-                    writer.materials.opaque_vertex_colored,
-                    format!("{name} opaque index"),
-                ),
-                (
-                    mesh.transparent_range(all_is_cubes::mesh::DepthOrdering::Any),
-                    writer.materials.transparent_vertex_colored,
-                    format!("{name} transparent index"),
-                ),
-            ]
-            .into_iter()
-            .filter_map(|(index_range, material, name)| {
-                if !index_range.is_empty() {
-                    Some(gltf_json::mesh::Primitive {
-                        attributes: vertex_colored_attributes.clone(),
-                        indices: Some(push_and_return_index(
-                            &mut writer.root.accessors,
-                            gltf_json::Accessor {
-                                buffer_view: Some(index_buffer_view),
-                                byte_offset: u32size(index_range.start * size_of::<u32>()),
-                                count: u32size(index_range.len()),
-                                component_type: index_type,
-                                extensions: Default::default(),
-                                extras: Default::default(),
-                                type_: Valid(gltf_json::accessor::Type::Scalar),
-                                min: None,
-                                max: None,
-                                name: Some(name),
-                                normalized: false,
-                                sparse: None,
-                            },
-                        )),
-                        mode: Valid(gltf_json::mesh::Mode::Triangles),
-                        material: Some(material),
-                        targets: None,
-                        extensions: Default::default(),
-                        extras: Default::default(),
-                    })
-                } else {
-                    None
-                }
-            })
-            .collect(),
+            primitives,
             weights: None,
             extensions: Default::default(),
             extras: Default::default(),
@@ -190,60 +217,118 @@ pub(crate) fn add_mesh(
 pub(crate) struct Materials {
     pub opaque_vertex_colored: Index<gltf_json::Material>,
     pub transparent_vertex_colored: Index<gltf_json::Material>,
+    /// Materials for non-default [`all_is_cubes::block::Material`]s, created as needed
+    /// and keyed by transparency and the data stored in [`GltfVertex::material`].
+    other: Vec<(bool, [f32; 3], Index<gltf_json::Material>)>,
 }
 
 impl Materials {
     pub fn new(materials_json: &mut Vec<gltf_json::Material>) -> Self {
+        Self {
+            opaque_vertex_colored: push_and_return_index(
+                materials_json,
+                Self::create(false, "aic-vertex-opaque".into(), 0.0, 1.0),
+            ),
+            transparent_vertex_colored: push_and_return_index(
+                materials_json,
+                Self::create(true, "aic-vertex-transparent".into(), 0.0, 1.0),
+            ),
+            other: Vec::new(),
+        }
+    }
+
+    /// Returns the glTF material for vertex-colored surfaces with the given material
+    /// data (as stored in [`GltfVertex::material`]), creating it if needed.
+    ///
+    /// TODO: Refractive index is not yet exported, since that requires the
+    /// `KHR_materials_ior` extension.
+    pub fn get(
+        &mut self,
+        materials_json: &mut Vec<gltf_json::Material>,
+        transparent: bool,
+        material: [f32; 3],
+    ) -> Index<gltf_json::Material> {
+        let [reflectance, roughness, _refractive_index] = material;
+        if reflectance == 0.0 && roughness == 1.0 {
+            return if transparent {
+                self.transparent_vertex_colored
+            } else {
+                self.opaque_vertex_colored
+            };
+        }
+        if let Some(&(_, _, index)) = self
+            .other
+            .iter()
+            .find(|&&(t, m, _)| t == transparent && m == material)
+        {
+            return index;
+        }
+        let name = format!(
+            "aic-vertex-{}-{}",
+            if transparent { "transparent" } else { "opaque" },
+            self.other.len()
+        );
+        let index = push_and_return_index(
+            materials_json,
+            // Metallic surfaces are the ones whose color comes from reflection,
+            // so that is the closest match to reflectance.
+            Self::create(transparent, name, reflectance, roughness),
+        );
+        self.other.push((transparent, material, index));
+        index
+    }
+
+    fn create(
+        transparent: bool,
+        name: String,
+        metallic: f32,
+        roughness: f32,
+    ) -> gltf_json::Material {
         let pbr_metallic_roughness = gltf_json::material::PbrMetallicRoughness {
             // Per glTF 2.0 § 3.9.2, the base_color_factor will be
             // multiplied by the vertex color.
             base_color_factor: gltf_json::material::PbrBaseColorFactor([1.0, 1.0, 1.0, 1.0]),
             base_color_texture: None,
-            metallic_factor: gltf_json::material::StrengthFactor(0.0),
-            roughness_factor: gltf_json::material::StrengthFactor(1.0),
+            metallic_factor: gltf_json::material::StrengthFactor(metallic),
+            roughness_factor: gltf_json::material::StrengthFactor(roughness),
             ..<_>::default()
         };
-        Self {
-            opaque_vertex_colored: push_and_return_index(
-                materials_json,
-                gltf_json::Material {
-                    name: Some("aic-vertex-opaque".into()),
-                    alpha_mode: Valid(gltf_json::material::AlphaMode::Opaque),
-                    double_sided: false,
-                    pbr_metallic_roughness: pbr_metallic_roughness.clone(),
-                    ..gltf_json::Material::default()
-                },
-            ),
-            transparent_vertex_colored: push_and_return_index(
-                materials_json,
-                gltf_json::Material {
-                    name: Some("aic-vertex-transparent".into()),
-                    alpha_mode: Valid(gltf_json::material::AlphaMode::Blend),
-                    double_sided: false,
-                    pbr_metallic_roughness,
-                    extensions: Some(gltf_json::extensions::material::Material {
-                        volume: None,
-                        // TODO: Reenable this when attenuation_distance serialization bug is fixed.
-                        // https://github.com/gltf-rs/gltf/issues/364
-                        // Some(gltf_json::extensions::material::Volume {
-                        //     thickness_factor: gltf_json::extensions::material::ThicknessFactor(1.0),
-                        //     thickness_texture: None,
-                        //     attenuation_distance:
-                        //         gltf_json::extensions::material::AttenuationDistance::default(),
-                        //     attenuation_color:
-                        //         gltf_json::extensions::material::AttenuationColor::default(),
-                        //     extras: Void::default(),
-                        // }),
-                        transmission: Some(gltf_json::extensions::material::Transmission {
-                            transmission_factor:
-                                gltf_json::extensions::material::TransmissionFactor::default(),
-                            transmission_texture: None,
-                            extras: Void::default(),
-                        }),
+        if !transparent {
+            gltf_json::Material {
+                name: Some(name),
+                alpha_mode: Valid(gltf_json::material::AlphaMode::Opaque),
+                double_sided: false,
+                pbr_metallic_roughness,
+                ..gltf_json::Material::default()
+            }
+        } else {
+            gltf_json::Material {
+                name: Some(name),
+                alpha_mode: Valid(gltf_json::material::AlphaMode::Blend),
+                double_sided: false,
+                pbr_metallic_roughness,
+                extensions: Some(gltf_json::extensions::material::Material {
+                    volume: None,
+                    // TODO: Reenable this when attenuation_distance serialization bug is fixed.
+                    // https://github.com/gltf-rs/gltf/issues/364
+                    // Some(gltf_json::extensions::material::Volume {
+                    //     thickness_factor: gltf_json::extensions::material::ThicknessFactor(1.0),
+                    //     thickness_texture: None,
+                    //     attenuation_distance:
+                    //         gltf_json::extensions::material::AttenuationDistance::default(),
+                    //     attenuation_color:
+                    //         gltf_json::extensions::material::AttenuationColor::default(),
+                    //     extras: Void::default(),
+                    // }),
+                    transmission: Some(gltf_json::extensions::material::Transmission {
+                        transmission_factor:
+                            gltf_json::extensions::material::TransmissionFactor::default(),
+                        transmission_texture: None,
+                        extras: Void::default(),
                     }),
-                    ..gltf_json::Material::default()
-                },
-            ),
+                }),
+                ..gltf_json::Material::default()
+            }
         }
     }
 }
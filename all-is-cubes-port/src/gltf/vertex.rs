@@ -21,6 +21,11 @@ use super::texture::TexPoint;
 pub struct GltfVertex {
     pub(crate) position: [Lef32; 3],
     pub(crate) color_or_texture: [Lef32; 4],
+    /// Reflectance, roughness, and refractive index of the surface's [`Material`].
+    /// This is not exported as a vertex attribute, but used to choose the glTF material.
+    ///
+    /// [`Material`]: all_is_cubes::block::Material
+    pub(crate) material: [Lef32; 3],
 }
 
 impl GltfVertex {
@@ -28,13 +33,25 @@ impl GltfVertex {
     pub(crate) const DUMMY: GltfVertex = GltfVertex {
         position: [Lef32::ZERO; 3],
         color_or_texture: [Lef32::ZERO; 4],
+        material: [Lef32::ZERO; 3],
     };
+
+    /// Returns the [`Self::material`] data as [`f32`]s.
+    pub(crate) fn material(&self) -> [f32; 3] {
+        self.material.map(f32::from)
+    }
 }
 
 impl From<BlockVertex<TexPoint>> for GltfVertex {
     #[inline]
     fn from(vertex: BlockVertex<TexPoint>) -> Self {
         let position = Lef32::from_vec3(vertex.position.cast::<f32>().unwrap().to_vec());
+        let material = [
+            vertex.material.reflectance,
+            vertex.material.roughness,
+            vertex.material.refractive_index,
+        ]
+        .map(|value| Lef32::from(value.into_inner()));
         match vertex.coloring {
             Coloring::Solid(color) => {
                 let mut color_attribute: Vector4<f32> = color.into();
@@ -44,6 +61,7 @@ impl From<BlockVertex<TexPoint>> for GltfVertex {
                 Self {
                     position,
                     color_or_texture: Lef32::from_vec4(color_attribute),
+                    material,
                 }
             }
             Coloring::Texture {
@@ -53,6 +71,7 @@ impl From<BlockVertex<TexPoint>> for GltfVertex {
             } => Self {
                 position,
                 color_or_texture: Lef32::from_vec4(tc.extend(-1.0)),
+                material,
             },
         }
     }
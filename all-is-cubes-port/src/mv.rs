@@ -154,9 +154,7 @@ fn dot_vox_model_to_space(
             spawn.set_inventory(free_editing_starter_inventory(true));
             spawn
         })
-        .light_physics(LightPhysics::Rays {
-            maximum_distance: bounds.y_range().len() as u16,
-        })
+        .light_physics(LightPhysics::rays(bounds.y_range().len() as u16))
         .sky_color(Rgb::ONE)
         .build();
 
@@ -6,7 +6,7 @@ use all_is_cubes::camera::{
     FogOption, GraphicsOptions, LightingOption, TransparencyOption, Viewport,
 };
 use all_is_cubes::cgmath::{EuclideanSpace as _, Point2, Vector2, Vector3, Zero as _};
-use all_is_cubes::character::Character;
+use all_is_cubes::character::{Character, CharacterInput};
 use all_is_cubes::listen::{ListenableCell, ListenableSource};
 use all_is_cubes::math::FreeCoordinate;
 use all_is_cubes::notnan;
@@ -272,7 +272,13 @@ impl InputProcessor {
 
     /// Applies the accumulated input from previous events.
     /// `targets` specifies the objects it should be applied to.
-    pub(crate) fn apply_input(&mut self, targets: InputTargets<'_>, tick: Tick) {
+    ///
+    /// Returns the [`CharacterInput`]s which were applied to the character, for recording.
+    pub(crate) fn apply_input(
+        &mut self,
+        targets: InputTargets<'_>,
+        tick: Tick,
+    ) -> Vec<CharacterInput> {
         let InputTargets {
             universe,
             character: character_opt,
@@ -287,24 +293,30 @@ impl InputProcessor {
         let dt = tick.delta_t().as_secs_f64();
         let key_turning_step = 80.0 * dt;
 
+        let mut applied_inputs = Vec::new();
+
         // Direct character controls
         if let Some(character_ref) = character_opt {
             character_ref
                 .try_modify(|character| {
-                    let movement = self.movement();
-                    character.set_velocity_input(movement);
-
                     let turning = Vector2::new(
                         key_turning_step * self.net_movement(Key::Left, Key::Right)
                             + self.mouselook_buffer.x,
                         key_turning_step * self.net_movement(Key::Up, Key::Down)
                             + self.mouselook_buffer.y,
                     );
-                    character.body.yaw = (character.body.yaw + turning.x).rem_euclid(360.0);
-                    character.body.pitch = (character.body.pitch + turning.y).clamp(-90.0, 90.0);
 
+                    applied_inputs.push(CharacterInput::Velocity(self.movement()));
+                    applied_inputs.push(CharacterInput::Look {
+                        yaw: (character.body.yaw + turning.x).rem_euclid(360.0),
+                        pitch: (character.body.pitch + turning.y).clamp(-90.0, 90.0),
+                    });
                     if self.keys_held.contains(&Key::Character(' ')) {
-                        character.jump_if_able();
+                        applied_inputs.push(CharacterInput::Jump);
+                    }
+
+                    for input in &applied_inputs {
+                        input.apply(character);
                     }
                 })
                 .expect("character was borrowed during apply_input()");
@@ -375,14 +387,21 @@ impl InputProcessor {
                     let digit = numeral.to_digit(10).unwrap() as usize;
                     let slot = (digit + 9).rem_euclid(10); // wrap 0 to 9
                     if let Some(character_ref) = character_opt {
+                        let input = CharacterInput::SelectSlot {
+                            which_selection: 1,
+                            slot,
+                        };
                         character_ref
-                            .try_modify(|c| c.set_selected_slot(1, slot))
+                            .try_modify(|c| input.apply(c))
                             .expect("character was borrowed during apply_input()");
+                        applied_inputs.push(input);
                     }
                 }
                 _ => {}
             }
         }
+
+        applied_inputs
     }
 
     pub fn mouselook_mode(&self) -> ListenableSource<bool> {
@@ -8,14 +8,18 @@ use futures_core::future::BoxFuture;
 use futures_task::noop_waker_ref;
 
 use all_is_cubes::camera::{GraphicsOptions, StandardCameras, UiViewState, Viewport};
-use all_is_cubes::character::{Character, Cursor};
+use all_is_cubes::character::{
+    Character, CharacterInput, CharacterTransaction, Cursor, InputRecording,
+};
 use all_is_cubes::fluff::Fluff;
-use all_is_cubes::inv::ToolError;
+use all_is_cubes::inv::{InventoryTransaction, Slot, Tool, ToolError};
 use all_is_cubes::listen::{
     Listen as _, ListenableCell, ListenableCellWithLocal, ListenableSource, Listener, Notifier,
 };
-use all_is_cubes::transaction::{self, Transaction as _};
-use all_is_cubes::universe::{URef, Universe, UniverseStepInfo};
+use all_is_cubes::math::GridPoint;
+use all_is_cubes::space::{Space, SpaceTransaction};
+use all_is_cubes::transaction::{self, Merge as _, Transaction as _};
+use all_is_cubes::universe::{URef, Universe, UniverseStepInfo, UniverseTransaction};
 use all_is_cubes::util::{CustomFormat, StatusText};
 
 use crate::apps::{FpsCounter, FrameClock, InputProcessor, InputTargets};
@@ -66,6 +70,12 @@ pub struct Session {
 
     last_step_info: UniverseStepInfo,
 
+    /// If present, inputs applied to the game character are being recorded into this.
+    input_recording: Option<InputRecording>,
+    /// Tool uses which occurred, while recording, since the last recorded step.
+    /// They are recorded as inputs to the next step.
+    recorded_tool_uses: Vec<CharacterInput>,
+
     tick_counter_for_logging: u8,
 }
 
@@ -85,6 +95,8 @@ impl fmt::Debug for Session {
             control_channel_sender: _,
             cursor_result,
             last_step_info,
+            input_recording,
+            recorded_tool_uses,
             tick_counter_for_logging,
         } = self;
 
@@ -103,6 +115,8 @@ impl fmt::Debug for Session {
             .field("ui", &ui)
             .field("cursor_result", &cursor_result)
             .field("last_step_info", &last_step_info)
+            .field("input_recording", &input_recording)
+            .field("recorded_tool_uses", &recorded_tool_uses)
             .field("tick_counter_for_logging", &tick_counter_for_logging)
             .finish_non_exhaustive()
     }
@@ -189,6 +203,27 @@ impl Session {
         self.fluff_notifier.listen(listener)
     }
 
+    /// Begins recording the inputs applied to the game character on each step,
+    /// discarding any recording already in progress.
+    ///
+    /// Replaying the recording (with [`InputRecording::replay()`]) against a copy of the
+    /// universe as it was when recording started will reproduce the session.
+    pub fn start_input_recording(&mut self) {
+        self.input_recording = Some(InputRecording::new(self.frame_clock.tick().delta_t()));
+        self.recorded_tool_uses.clear();
+    }
+
+    /// Stops recording inputs and returns the recording, if one was started with
+    /// [`Self::start_input_recording()`].
+    pub fn finish_input_recording(&mut self) -> Option<InputRecording> {
+        let mut recording = self.input_recording.take()?;
+        if !self.recorded_tool_uses.is_empty() {
+            // A paused step has no effect other than applying the inputs.
+            recording.record_paused_step(self.recorded_tool_uses.drain(..));
+        }
+        Some(recording)
+    }
+
     /// Steps the universe if the `FrameClock` says it's time to do so.
     /// Always returns info for the last step even if multiple steps were taken.
     ///
@@ -212,6 +247,12 @@ impl Session {
                     ControlMessage::ModifyGraphicsOptions(f) => {
                         self.graphics_options.set(f(self.graphics_options.get()));
                     }
+                    ControlMessage::TakeFromContainer { space, cube, slot } => {
+                        self.container_transfer(&space, cube, slot, true);
+                    }
+                    ControlMessage::PutInContainer { space, cube, slot } => {
+                        self.container_transfer(&space, cube, slot, false);
+                    }
                 },
                 Err(TryRecvError::Empty) => break,
                 Err(TryRecvError::Disconnected) => {
@@ -257,7 +298,7 @@ impl Session {
                 self.frame_clock.did_step();
 
                 if let Some(character_ref) = self.game_character.borrow() {
-                    self.input_processor.apply_input(
+                    let applied_inputs = self.input_processor.apply_input(
                         InputTargets {
                             universe: Some(&mut self.game_universe),
                             character: Some(character_ref),
@@ -267,6 +308,16 @@ impl Session {
                         },
                         game_tick,
                     );
+                    if let Some(recording) = &mut self.input_recording {
+                        // Inputs are applied even when paused, so paused steps must be
+                        // recorded too.
+                        let inputs = self.recorded_tool_uses.drain(..).chain(applied_inputs);
+                        if game_tick.paused() {
+                            recording.record_paused_step(inputs);
+                        } else {
+                            recording.record_step(inputs);
+                        }
+                    }
                 }
                 self.input_processor.step(game_tick);
 
@@ -374,12 +425,23 @@ impl Session {
             // Otherwise, it's a click inside the game world (even if the cursor hit nothing at all).
             // Character::click will validate against being a click in the wrong space.
             if let Some(character_ref) = self.game_character.borrow() {
+                if self.input_recording.is_some() {
+                    self.recorded_tool_uses.push(CharacterInput::UseTool {
+                        button,
+                        cursor_ray: self.cursor_result.as_ref().map(Cursor::ray),
+                    });
+                }
                 let transaction =
                     Character::click(character_ref.clone(), self.cursor_result.as_ref(), button)?;
+                let container = self.clicked_container(character_ref, button);
                 transaction
                     .execute(self.universe_mut(), &mut transaction::no_outputs)
                     .map_err(|e| ToolError::Internal(e.to_string()))?;
 
+                if let (Some((space, cube)), Some(ui)) = (container, &mut self.ui) {
+                    ui.open_container(space, cube);
+                }
+
                 // Spend a little time doing light updates, to ensure that changes right in front of
                 // the player are clean (and not flashes of blackness).
                 if let Some(space_ref) = self.cursor_result.as_ref().map(Cursor::space) {
@@ -396,6 +458,53 @@ impl Session {
         }
     }
 
+    /// If clicking with `button` would activate a container block (a cube with an
+    /// inventory), returns the container's location.
+    fn clicked_container(
+        &self,
+        character_ref: &URef<Character>,
+        button: usize,
+    ) -> Option<(URef<Space>, GridPoint)> {
+        let cursor = self.cursor_result.as_ref()?;
+        let character = character_ref.read().ok()?;
+        let selected_slots = character.selected_slots();
+        let slot_index = selected_slots.get(button).unwrap_or(&selected_slots[0]);
+        if !matches!(
+            character.inventory().slots.get(*slot_index),
+            Some(Slot::Stack(_, Tool::Activate))
+        ) {
+            return None;
+        }
+        let cube = cursor.cube();
+        cursor.space().read().ok()?.cube_data(cube)?;
+        Some((cursor.space().clone(), cube))
+    }
+
+    /// Moves the item in `slot` of either the container at `cube` in `space`
+    /// (if `to_character` is true) or the character's inventory (otherwise)
+    /// to the other inventory.
+    fn container_transfer(
+        &mut self,
+        space: &URef<Space>,
+        cube: GridPoint,
+        slot: usize,
+        to_character: bool,
+    ) {
+        let Some(character_ref) = self.game_character.borrow().clone() else {
+            return;
+        };
+        let result =
+            container_transfer_transaction(&character_ref, space, cube, slot, to_character)
+                .and_then(|transaction| {
+                    transaction
+                        .execute(self.universe_mut(), &mut transaction::no_outputs)
+                        .map_err(|e| ToolError::Internal(e.to_string()))
+                });
+        if let Err(error) = result {
+            log::error!("Failed to transfer container item: {error}");
+        }
+    }
+
     /// Returns textual information intended to be overlaid as a HUD on top of the rendered scene
     /// containing diagnostic information about rendering and stepping.
     pub fn info_text<T: CustomFormat<StatusText>>(&self, render: T) -> InfoText<'_, T> {
@@ -488,6 +597,8 @@ impl SessionBuilder {
             control_channel_sender: control_send,
             cursor_result: None,
             last_step_info: UniverseStepInfo::default(),
+            input_recording: None,
+            recorded_tool_uses: Vec::new(),
             tick_counter_for_logging: 0,
         }
     }
@@ -536,6 +647,20 @@ pub(crate) enum ControlMessage {
     ToggleMouselook,
     /// TODO: this should be "modify user preferences", from which graphics options are derived.
     ModifyGraphicsOptions(Box<dyn FnOnce(Arc<GraphicsOptions>) -> Arc<GraphicsOptions> + Send>),
+    /// Move the item in `slot` of the container at `cube` in `space` to the character's
+    /// inventory.
+    TakeFromContainer {
+        space: URef<Space>,
+        cube: GridPoint,
+        slot: usize,
+    },
+    /// Move the item in `slot` of the character's inventory to the container at `cube`
+    /// in `space`.
+    PutInContainer {
+        space: URef<Space>,
+        cube: GridPoint,
+        slot: usize,
+    },
 }
 
 impl fmt::Debug for ControlMessage {
@@ -548,10 +673,72 @@ impl fmt::Debug for ControlMessage {
             Self::ModifyGraphicsOptions(_f) => f
                 .debug_struct("ModifyGraphicsOptions")
                 .finish_non_exhaustive(),
+            Self::TakeFromContainer { space, cube, slot } => f
+                .debug_struct("TakeFromContainer")
+                .field("space", space)
+                .field("cube", cube)
+                .field("slot", slot)
+                .finish(),
+            Self::PutInContainer { space, cube, slot } => f
+                .debug_struct("PutInContainer")
+                .field("space", space)
+                .field("cube", cube)
+                .field("slot", slot)
+                .finish(),
         }
     }
 }
 
+/// Constructs a transaction to move the item in `slot` between the inventory of
+/// `character` and the container at `cube` in `space`. See
+/// [`Session::container_transfer`].
+fn container_transfer_transaction(
+    character: &URef<Character>,
+    space: &URef<Space>,
+    cube: GridPoint,
+    slot: usize,
+    to_character: bool,
+) -> Result<UniverseTransaction, ToolError> {
+    let container_slot = |space: &Space| {
+        space
+            .cube_data(cube)
+            .and_then(|data| data.inventory.slots.get(slot).cloned())
+    };
+    let internal = |e: &dyn fmt::Display| ToolError::Internal(e.to_string());
+
+    let (space_txn, character_txn) = if to_character {
+        let item = container_slot(&*space.read()?).ok_or(ToolError::NothingSelected)?;
+        if item == Slot::Empty {
+            return Ok(UniverseTransaction::default());
+        }
+        (
+            InventoryTransaction::replace(slot, item.clone(), Slot::Empty),
+            InventoryTransaction::insert([item]),
+        )
+    } else {
+        let item = character
+            .read()
+            .map_err(|e| internal(&e))?
+            .inventory()
+            .slots
+            .get(slot)
+            .cloned()
+            .ok_or(ToolError::NothingSelected)?;
+        if item == Slot::Empty {
+            return Ok(UniverseTransaction::default());
+        }
+        (
+            InventoryTransaction::insert([item.clone()]),
+            InventoryTransaction::replace(slot, item, Slot::Empty),
+        )
+    };
+
+    SpaceTransaction::inventory(cube, space_txn)
+        .bind(space.clone())
+        .merge(CharacterTransaction::inventory(character_txn).bind(character.clone()))
+        .map_err(|e| internal(&e))
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct InfoText<'a, T> {
     session: &'a Session,
@@ -603,10 +790,64 @@ pub enum CursorIcon {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use all_is_cubes::space::Space;
-    use all_is_cubes::universe::{Name, Universe, UniverseIndex};
+    use crate::apps::Key;
+    use all_is_cubes::block::Block;
+    use all_is_cubes::cgmath::Vector3;
+    use all_is_cubes::character::cursor_raycast;
+    use all_is_cubes::math::Rgba;
+    use all_is_cubes::raycast::Ray;
+    use all_is_cubes::universe::{Name, UniverseIndex};
     use futures_channel::oneshot;
 
+    #[test]
+    fn container_transfer() {
+        let mut universe = Universe::new();
+        let mut space = Space::empty_positive(1, 1, 1);
+        space
+            .set(
+                [0, 0, 0],
+                Block::builder()
+                    .color(Rgba::WHITE)
+                    .container_slots(2)
+                    .build(),
+            )
+            .unwrap();
+        let space = universe.insert_anonymous(space);
+        let character = universe.insert_anonymous(Character::spawn_default(space.clone()));
+        let cube = GridPoint::new(0, 0, 0);
+        let container_slots = |space: &URef<Space>| {
+            space
+                .read()
+                .unwrap()
+                .cube_data(cube)
+                .unwrap()
+                .inventory
+                .slots
+                .clone()
+        };
+
+        SpaceTransaction::inventory(cube, InventoryTransaction::insert([Tool::PushPull]))
+            .bind(space.clone())
+            .execute(&mut universe, &mut transaction::no_outputs)
+            .unwrap();
+        let item = Slot::from(Tool::PushPull);
+
+        // Take the item from the container, then put it back.
+        container_transfer_transaction(&character, &space, cube, 0, true)
+            .unwrap()
+            .execute(&mut universe, &mut transaction::no_outputs)
+            .unwrap();
+        assert_eq!(container_slots(&space), vec![Slot::Empty, Slot::Empty]);
+        assert_eq!(character.read().unwrap().inventory().slots[0], item);
+
+        container_transfer_transaction(&character, &space, cube, 0, false)
+            .unwrap()
+            .execute(&mut universe, &mut transaction::no_outputs)
+            .unwrap();
+        assert_eq!(container_slots(&space), vec![item, Slot::Empty]);
+        assert_eq!(character.read().unwrap().inventory().slots[0], Slot::Empty);
+    }
+
     #[tokio::test]
     async fn set_universe_async() {
         let old_marker = Name::from("old");
@@ -640,4 +881,61 @@ mod tests {
         // Verify cleanup (that the next step can succeed).
         session.maybe_step_universe();
     }
+
+    #[tokio::test]
+    async fn input_recording() {
+        let mut session = Session::builder().build().await;
+        let mut universe = Universe::new();
+        let mut space = Space::empty_positive(1, 1, 1);
+        space.set([0, 0, 0], Block::from(Rgba::WHITE)).unwrap();
+        let space = universe.insert_anonymous(space);
+        universe
+            .insert("character".into(), Character::spawn_default(space.clone()))
+            .unwrap();
+        session.set_universe(universe);
+
+        session.start_input_recording();
+        session.input_processor.key_down(Key::Character('d'));
+        session
+            .frame_clock
+            .advance_by(session.frame_clock.tick().delta_t());
+        session.maybe_step_universe();
+        session.input_processor.key_up(Key::Character('d'));
+        session
+            .frame_clock
+            .advance_by(session.frame_clock.tick().delta_t());
+        session.maybe_step_universe();
+
+        // Paused steps and tool uses are recorded too.
+        session.paused.set(true);
+        session
+            .frame_clock
+            .advance_by(session.frame_clock.tick().delta_t());
+        session.maybe_step_universe();
+        let ray = Ray::new([0.5, 0.5, -1.0], [0., 0., 1.]);
+        session.cursor_result = cursor_raycast(ray, &space, 10.0);
+        session.click(0);
+
+        let recording = session.finish_input_recording().unwrap();
+        assert_eq!(recording.len(), 4);
+        assert_eq!(
+            recording.steps()[0].inputs[0],
+            CharacterInput::Velocity(Vector3::new(1.0, 0.0, 0.0))
+        );
+        assert_eq!(
+            recording.steps()[1].inputs[0],
+            CharacterInput::Velocity(Vector3::new(0.0, 0.0, 0.0))
+        );
+        assert!(!recording.steps()[1].paused);
+        assert!(recording.steps()[2].paused);
+        assert!(recording.steps()[3].paused);
+        assert_eq!(
+            recording.steps()[3].inputs,
+            vec![CharacterInput::UseTool {
+                button: 0,
+                cursor_ray: Some(ray)
+            }]
+        );
+        assert_eq!(session.finish_input_recording(), None);
+    }
 }
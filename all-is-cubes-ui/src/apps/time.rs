@@ -1,7 +1,7 @@
 use instant::{Duration, Instant};
 
 use all_is_cubes::math::NotNan;
-use all_is_cubes::time::Tick;
+use all_is_cubes::time::{FixedStepClock, Tick};
 #[cfg(doc)]
 use all_is_cubes::universe::Universe;
 
@@ -14,7 +14,7 @@ pub struct FrameClock {
     /// Whether there was a step and we should therefore draw a frame.
     /// TODO: This might go away in favor of actual dirty-notifications.
     render_dirty: bool,
-    accumulated_step_time: Duration,
+    step_clock: FixedStepClock,
 
     draw_fps_counter: FpsCounter,
 }
@@ -26,8 +26,6 @@ impl FrameClock {
     /// This sets how low the frame rate can go below [`STEP_LENGTH`] before game time
     /// slows down.
     pub(crate) const CATCH_UP_STEPS: u8 = 2;
-    const ACCUMULATOR_CAP: Duration =
-        Duration::from_micros(Self::STEP_LENGTH_MICROS * Self::CATCH_UP_STEPS as u64);
 
     /// Constructs a new [`FrameClock`].
     ///
@@ -36,7 +34,8 @@ impl FrameClock {
         Self {
             last_absolute_time: None,
             render_dirty: true,
-            accumulated_step_time: Duration::ZERO,
+            step_clock: FixedStepClock::new(Self::STEP_LENGTH)
+                .with_catch_up_limit(Self::CATCH_UP_STEPS.into()),
             draw_fps_counter: FpsCounter::default(),
         }
     }
@@ -47,17 +46,14 @@ impl FrameClock {
     /// [`FrameClock::request_frame()`] or [`FrameClock::advance_by()`].
     pub fn advance_to(&mut self, instant: Instant) {
         if let Some(last_absolute_time) = self.last_absolute_time {
-            let delta = instant - last_absolute_time;
-            self.accumulated_step_time += delta;
-            self.cap_step_time();
+            self.step_clock.advance_by(instant - last_absolute_time);
         }
         self.last_absolute_time = Some(instant);
     }
 
     /// Advance the clock using a source of relative time.
     pub fn advance_by(&mut self, duration: Duration) {
-        self.accumulated_step_time += duration;
-        self.cap_step_time();
+        self.step_clock.advance_by(duration);
     }
 
     /// Reacts to a callback from the environment requesting drawing a frame ASAP if
@@ -108,12 +104,14 @@ impl FrameClock {
     /// When a step *is* performd, [`FrameClock::did_step`] must be called; otherwise, this
     /// will always return true.
     pub fn should_step(&self) -> bool {
-        self.accumulated_step_time >= Self::STEP_LENGTH
+        self.step_clock.should_step()
     }
 
     /// Informs the [`FrameClock`] that a step was just performed.
     pub fn did_step(&mut self) {
-        self.accumulated_step_time -= Self::STEP_LENGTH;
+        self.step_clock
+            .next_tick()
+            .expect("FrameClock::did_step() called when a step was not due");
         self.render_dirty = true;
     }
 
@@ -121,19 +119,13 @@ impl FrameClock {
     /// when stepping in response to [`FrameClock::should_step`] returning true.
     #[must_use] // avoid confusion with side-effecting methods
     pub fn tick(&self) -> Tick {
-        Tick::from_duration(Self::STEP_LENGTH)
+        self.step_clock.tick()
     }
 
     #[doc(hidden)] // TODO: Decide whether we want FpsCounter in our public API
     pub fn draw_fps_counter(&self) -> &FpsCounter {
         &self.draw_fps_counter
     }
-
-    fn cap_step_time(&mut self) {
-        if self.accumulated_step_time > Self::ACCUMULATOR_CAP {
-            self.accumulated_step_time = Self::ACCUMULATOR_CAP;
-        }
-    }
 }
 
 impl Default for FrameClock {
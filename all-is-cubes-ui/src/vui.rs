@@ -8,12 +8,14 @@ use std::sync::{mpsc, Arc, Mutex};
 
 use all_is_cubes::camera::{FogOption, GraphicsOptions, UiViewState, ViewTransform, Viewport};
 use all_is_cubes::cgmath::{Angle as _, Decomposed, Deg, One, Transform, Vector3};
-use all_is_cubes::character::{Character, Cursor};
+use all_is_cubes::character::{Character, CharacterChange, Cursor};
 use all_is_cubes::inv::{Tool, ToolError, ToolInput};
-use all_is_cubes::listen::{DirtyFlag, ListenableCell, ListenableSource, Notifier};
-use all_is_cubes::math::FreeCoordinate;
+use all_is_cubes::listen::{
+    DirtyFlag, Gate, Listen as _, ListenableCell, ListenableSource, Listener, Notifier,
+};
 use all_is_cubes::math::NotNan;
-use all_is_cubes::space::Space;
+use all_is_cubes::math::{FreeCoordinate, GridPoint};
+use all_is_cubes::space::{Space, SpaceChange};
 use all_is_cubes::time::Tick;
 use all_is_cubes::transaction::{self, Transaction};
 use all_is_cubes::universe::{URef, Universe, UniverseStepInfo};
@@ -60,6 +62,13 @@ pub(crate) struct Vui {
     hud_page: PageInst,
     paused_page: PageInst,
     about_page: PageInst,
+    /// Page for [`VuiPageState::Container`], rebuilt whenever the container or the
+    /// character changes.
+    container_page: Option<PageInst>,
+    changed_container: DirtyFlag,
+    /// Gates for the listeners feeding `changed_container`, so that they are dropped
+    /// when a different container is opened.
+    container_listener_gates: Vec<Gate>,
 
     /// Receiving internal messages from widgets for controlling the UI itself
     /// (changing `state`, etc).
@@ -139,6 +148,9 @@ impl Vui {
             hud_page: PageInst::new(hud_widget_tree),
             paused_page: PageInst::new(paused_widget_tree),
             about_page: PageInst::new(about_widget_tree),
+            container_page: None,
+            changed_container: DirtyFlag::new(false),
+            container_listener_gates: Vec::new(),
 
             control_channel: control_recv,
             changed_character: DirtyFlag::listening(false, &character_source),
@@ -170,6 +182,10 @@ impl Vui {
             VuiPageState::Hud => Some(self.hud_page.get_or_create_space(size, universe)),
             VuiPageState::Paused => Some(self.paused_page.get_or_create_space(size, universe)),
             VuiPageState::AboutText => Some(self.about_page.get_or_create_space(size, universe)),
+            VuiPageState::Container { .. } => self
+                .container_page
+                .as_mut()
+                .map(|page| page.get_or_create_space(size, universe)),
         };
 
         if next_space.as_ref() != Option::as_ref(&self.current_view.get().space) {
@@ -238,6 +254,9 @@ impl Vui {
         // clutter
         options.debug_chunk_boxes = false;
 
+        // UI surfaces should not be shaded by each other.
+        options.ambient_occlusion = false;
+
         options
     }
 
@@ -268,6 +287,10 @@ impl Vui {
             }
         }
 
+        if self.changed_container.get_and_clear() {
+            self.rebuild_container_page();
+        }
+
         if self.changed_viewport.get_and_clear() {
             let new_viewport = self.viewport_source.snapshot();
             let new_size = UiSize::new(new_viewport);
@@ -293,6 +316,56 @@ impl Vui {
         self.universe.step(tick)
     }
 
+    /// Show the contents of the container block at `cube` in `space`, alongside the
+    /// character's inventory.
+    pub fn open_container(&mut self, space: URef<Space>, cube: GridPoint) {
+        self.container_listener_gates.clear();
+        if let Ok(space_read) = space.read() {
+            let (gate, listener) = Listener::<SpaceChange>::gate(self.changed_container.listener());
+            space_read.listen(listener.filter(move |change| match change {
+                SpaceChange::CubeData(changed_cube) if changed_cube == cube => Some(()),
+                _ => None,
+            }));
+            self.container_listener_gates.push(gate);
+        }
+        if let Some(character_ref) = &*self.character_source.get() {
+            if let Ok(character) = character_ref.read() {
+                let (gate, listener) =
+                    Listener::<CharacterChange>::gate(self.changed_container.listener());
+                character.listen(listener);
+                self.container_listener_gates.push(gate);
+            }
+        }
+
+        self.state.set(VuiPageState::Container { space, cube });
+        self.rebuild_container_page();
+    }
+
+    /// Recreate `self.container_page` to match the current container and character
+    /// inventories, if the container page is open.
+    fn rebuild_container_page(&mut self) {
+        let VuiPageState::Container { space, cube } = (*self.state.get()).clone() else {
+            return;
+        };
+        match pages::new_container_widget_tree(
+            &mut self.universe,
+            &self.hud_inputs,
+            &space,
+            cube,
+            Option::as_ref(&self.character_source.get()),
+        ) {
+            Ok(tree) => {
+                self.container_page = Some(PageInst::new(tree));
+            }
+            Err(e) => {
+                log::error!("failed to construct container page: {e}");
+                self.container_page = None;
+            }
+        }
+        self.current_view.set(UiViewState::default()); // force reconstruction
+        self.set_space_from_state();
+    }
+
     /// Present the UI visual response to a click (that has already been handled),
     /// either a small indication that a button was pressed or an error message.
     pub fn show_click_result(&self, button: usize, result: Result<(), ToolError>) {
@@ -360,6 +433,11 @@ impl Vui {
                 // TODO: Instead check right now, but in a reusable fashion.
                 self.set_state(VuiPageState::Hud);
             }
+            VuiPageState::Container { .. } => {
+                self.container_listener_gates.clear();
+                self.container_page = None;
+                self.set_state(VuiPageState::Hud);
+            }
         }
     }
 }
@@ -374,6 +452,11 @@ pub(crate) enum VuiPageState {
     /// and reactivate mouselook.
     Paused,
     AboutText,
+    /// Show the contents of a container block (a cube that has an inventory).
+    Container {
+        space: URef<Space>,
+        cube: GridPoint,
+    },
 }
 
 /// Message indicating a UI action that affects the UI itself
@@ -409,6 +492,8 @@ pub(crate) enum CueMessage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use all_is_cubes::block::Block;
+    use all_is_cubes::math::Rgba;
 
     async fn new_vui_for_test(paused: bool) -> (Vui, mpsc::Receiver<ControlMessage>) {
         let (cctx, ccrx) = mpsc::sync_channel(1);
@@ -435,6 +520,38 @@ mod tests {
         assert!(control_channel.try_recv().is_err());
     }
 
+    #[tokio::test]
+    async fn open_and_close_container() {
+        let (mut vui, _control_channel) = new_vui_for_test(false).await;
+        let mut universe = Universe::new();
+        let mut space = Space::empty_positive(1, 1, 1);
+        space
+            .set(
+                [0, 0, 0],
+                Block::builder()
+                    .color(Rgba::WHITE)
+                    .container_slots(12)
+                    .build(),
+            )
+            .unwrap();
+        let space = universe.insert_anonymous(space);
+        let hud_space = vui.view().get().space.clone();
+
+        vui.open_container(space.clone(), GridPoint::new(0, 0, 0));
+        assert_eq!(
+            *vui.state.get(),
+            VuiPageState::Container {
+                space,
+                cube: GridPoint::new(0, 0, 0)
+            }
+        );
+        assert_ne!(vui.view().get().space, hud_space);
+
+        vui.back();
+        assert_eq!(*vui.state.get(), VuiPageState::Hud);
+        assert_eq!(vui.view().get().space, hud_space);
+    }
+
     #[tokio::test]
     async fn back_unpause() {
         let (mut vui, control_channel) = new_vui_for_test(true).await;
@@ -8,16 +8,21 @@ use all_is_cubes::block::{
 };
 use all_is_cubes::camera;
 use all_is_cubes::cgmath::Vector2;
+use all_is_cubes::character::Character;
 use all_is_cubes::content::palette;
 use all_is_cubes::drawing::embedded_graphics::{mono_font::iso_8859_1 as font, text::TextStyle};
 use all_is_cubes::drawing::VoxelBrush;
-use all_is_cubes::math::{Face6, FreeCoordinate, GridAab, GridCoordinate, GridVector, Rgba};
+use all_is_cubes::inv::Slot;
+use all_is_cubes::math::{
+    Face6, FreeCoordinate, GridAab, GridCoordinate, GridPoint, GridVector, Rgba,
+};
 use all_is_cubes::space::{Space, SpaceBuilder, SpacePhysics};
 use all_is_cubes::transaction;
 use all_is_cubes::universe::{URef, Universe};
 
+use crate::apps::ControlMessage;
 use crate::logo::logo_text;
-use crate::vui::hud::HudInputs;
+use crate::vui::hud::{HudInputs, TOOLBAR_POSITIONS};
 use crate::vui::options::pause_toggle_button;
 use crate::vui::widgets;
 use crate::vui::{
@@ -238,6 +243,63 @@ pub(super) fn new_about_widget_tree(
     ))))
 }
 
+/// Page showing the contents of a container block (a cube of `space` that has an
+/// inventory), alongside the character's inventory, so that items can be moved between
+/// them by clicking.
+///
+/// The page does not update itself; the caller should rebuild it when either inventory
+/// changes.
+pub(super) fn new_container_widget_tree(
+    u: &mut Universe,
+    hud_inputs: &HudInputs,
+    space: &URef<Space>,
+    cube: GridPoint,
+    character: Option<&URef<Character>>,
+) -> Result<WidgetTree, InstallVuiError> {
+    use parts::{heading, shrink, slot_rows};
+
+    let container_slots: Vec<Slot> = space
+        .read()
+        .ok()
+        .and_then(|space| Some(space.cube_data(cube)?.inventory.slots.clone()))
+        .unwrap_or_default();
+    let character_slots: Vec<Slot> = character
+        .and_then(|character| Some(character.read().ok()?.inventory().slots.clone()))
+        .unwrap_or_default();
+
+    let mut children = vec![
+        widgets::back_button(hud_inputs),
+        LayoutTree::leaf(shrink(u, R32, heading("Container"))?),
+        slot_rows(hud_inputs, &container_slots, {
+            let space = space.clone();
+            move |slot| ControlMessage::TakeFromContainer {
+                space: space.clone(),
+                cube,
+                slot,
+            }
+        }),
+    ];
+    if character.is_some() {
+        children.push(LayoutTree::leaf(shrink(u, R32, heading("Inventory"))?));
+        children.push(slot_rows(hud_inputs, &character_slots, {
+            let space = space.clone();
+            move |slot| ControlMessage::PutInContainer {
+                space: space.clone(),
+                cube,
+                slot,
+            }
+        }));
+    }
+
+    let contents = Arc::new(LayoutTree::Stack {
+        direction: Face6::NY,
+        children,
+    });
+    Ok(page_modal_backdrop(Arc::new(LayoutTree::Shrink(
+        widgets::Frame::for_menu().as_background_of(contents),
+    ))))
+}
+
 /// Helpers for assembling widget trees into dialog stuff.
 mod parts {
     use super::*;
@@ -269,6 +331,45 @@ mod parts {
         }))
     }
 
+    /// Lay out a button for each of `slots`, in rows of [`TOOLBAR_POSITIONS`] length.
+    /// Clicking a button sends the message `message(slot_index)` to the session.
+    pub fn slot_rows(
+        hud_inputs: &HudInputs,
+        slots: &[Slot],
+        message: impl Fn(usize) -> ControlMessage + Send + Sync + 'static,
+    ) -> WidgetTree {
+        let message = Arc::new(message);
+        let rows = slots
+            .chunks(TOOLBAR_POSITIONS)
+            .enumerate()
+            .map(|(row_index, row)| {
+                Arc::new(LayoutTree::Stack {
+                    direction: Face6::PX,
+                    children: row
+                        .iter()
+                        .enumerate()
+                        .map(|(column_index, slot)| {
+                            let cc = hud_inputs.app_control_channel.clone();
+                            let index = row_index * TOOLBAR_POSITIONS + column_index;
+                            let message = message.clone();
+                            LayoutTree::leaf(widgets::ActionButton::new(
+                                slot.icon(&hud_inputs.hud_blocks.icons).into_owned(),
+                                &hud_inputs.hud_blocks.blocks,
+                                move || {
+                                    let _ignore_errors = cc.send(message(index));
+                                },
+                            ) as Arc<dyn Widget>)
+                        })
+                        .collect(),
+                })
+            })
+            .collect();
+        Arc::new(LayoutTree::Stack {
+            direction: Face6::NY,
+            children: rows,
+        })
+    }
+
     pub fn paragraph(text: impl Into<Cow<'static, str>>) -> WidgetTree {
         LayoutTree::leaf(Arc::new(widgets::LargeText {
             text: text.into(),
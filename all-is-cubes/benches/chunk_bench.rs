@@ -1,10 +1,16 @@
 use cgmath::{Basis3, Decomposed, One, Vector3};
 use criterion::{criterion_group, criterion_main, Criterion};
 
+use all_is_cubes::block::Block;
 use all_is_cubes::camera::{Camera, GraphicsOptions, Viewport};
 use all_is_cubes::cgmath::{Point3, Vector2};
 use all_is_cubes::chunking::{ChunkChart, ChunkPos, OctantMask};
-use all_is_cubes::math::GridAab;
+use all_is_cubes::math::{GridAab, Rgba};
+use all_is_cubes::mesh::{
+    block_meshes_for_space, BlockVertex, MeshOptions, SpaceMesh, TestTextureAllocator,
+    TestTextureTile, TtPoint,
+};
+use all_is_cubes::space::Space;
 
 /// Test the performance of strategies to choose chunks to draw.
 pub fn cull_bench(c: &mut Criterion) {
@@ -104,5 +110,42 @@ pub fn cull_bench(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, cull_bench);
+/// Test the performance of meshing one chunk of terrain-like content, and how many
+/// vertices result, with and without greedy merging.
+pub fn chunk_mesh_bench(c: &mut Criterion) {
+    let mut g = c.benchmark_group("chunk-mesh");
+
+    // Ground with a step in the middle, and a few scattered blocks on top.
+    let bounds = GridAab::from_lower_size([0, 0, 0], [16, 16, 16]);
+    let mut space = Space::empty(bounds);
+    let ground = Block::from(Rgba::new(0.3, 0.6, 0.2, 1.0));
+    let stone = Block::from(Rgba::new(0.5, 0.5, 0.5, 1.0));
+    space
+        .fill(bounds, |p| {
+            let height = if p.x < 8 { 2 } else { 3 };
+            if p.y < height {
+                Some(&ground)
+            } else if p.y == height && (p.x * 7 + p.z * 3) % 11 == 0 {
+                Some(&stone)
+            } else {
+                None
+            }
+        })
+        .unwrap();
+
+    for (name, greedy, expected_vertices) in [("normal", false, 3104), ("greedy", true, 636)] {
+        let options = &MeshOptions::new(&GraphicsOptions::default()).greedy_merge(greedy);
+        let block_meshes = block_meshes_for_space(&space, &TestTextureAllocator::new(), options);
+        let mut mesh = SpaceMesh::<BlockVertex<TtPoint>, TestTextureTile>::default();
+        mesh.compute(&space, bounds, options, &*block_meshes);
+        // Check the vertex count, which is the other half of what we're measuring.
+        assert_eq!(mesh.vertices().len(), expected_vertices, "{name}");
+
+        g.bench_function(name, |b| {
+            b.iter(|| mesh.compute(&space, bounds, options, &*block_meshes))
+        });
+    }
+}
+
+criterion_group!(benches, cull_bench, chunk_mesh_bench);
 criterion_main!(benches);
@@ -1,6 +1,7 @@
 use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
 
 use all_is_cubes::content::testing::lighting_bench_space;
+use all_is_cubes::space::{LightPhysics, RayPattern, Space};
 use all_is_cubes::universe::Universe;
 
 pub fn evaluate_light_bench(c: &mut Criterion) {
@@ -24,5 +25,47 @@ pub fn evaluate_light_bench(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, evaluate_light_bench);
+/// Compares the speed of different [`RayPattern`]s. (Their accuracy is checked by the
+/// unit tests instead.)
+pub fn ray_pattern_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ray_pattern");
+    group.sample_size(10);
+
+    for (name, pattern) in [
+        ("cube-2", RayPattern::Cube { divisions: 2 }),
+        ("cube-5", RayPattern::Cube { divisions: 5 }),
+        ("octahedron-5", RayPattern::Octahedron { divisions: 5 }),
+        ("octahedron-10", RayPattern::Octahedron { divisions: 10 }),
+        ("spiral-100", RayPattern::Spiral { count: 100 }),
+        ("spiral-600", RayPattern::Spiral { count: 600 }),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || {
+                    let mut u = Universe::new();
+                    let space = space_with_pattern(&mut u, pattern);
+                    (u, space)
+                },
+                |(_u, mut space)| {
+                    space.evaluate_light(1, |_| {});
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn space_with_pattern(universe: &mut Universe, pattern: RayPattern) -> Space {
+    let mut space = lighting_bench_space(universe).unwrap();
+    let mut physics = space.physics().clone();
+    if let LightPhysics::Rays { ray_pattern, .. } = &mut physics.light {
+        *ray_pattern = pattern;
+    }
+    space.set_physics(physics);
+    space
+}
+
+criterion_group!(benches, evaluate_light_bench, ray_pattern_bench);
 criterion_main!(benches);
@@ -137,6 +137,31 @@ fn space_mesh_benches(c: &mut Criterion) {
             BatchSize::SmallInput,
         );
     });
+
+    // A flat floor of one block is the best case for greedy merging.
+    for greedy in [false, true] {
+        let options = &options.clone().greedy_merge(greedy);
+        let name = if greedy { "floor-greedy" } else { "floor" };
+        g.bench_function(name, |b| {
+            let space = floor_space();
+            let block_meshes =
+                block_meshes_for_space(&space, &TestTextureAllocator::new(), options);
+            b.iter_batched_ref(
+                || {
+                    let mut buffer = SpaceMesh::default();
+                    buffer.compute(&space, space.bounds(), options, &*block_meshes);
+                    // Check the vertex count, which is the other half of what we're measuring.
+                    let expected_quads = if greedy { 6 } else { 64 * 64 * 2 + 64 * 4 };
+                    assert_eq!(buffer.vertices().len(), 4 * expected_quads);
+                    buffer
+                },
+                |buffer: &mut SpaceMesh<BlockVertex<TtPoint>, TestTextureTile>| {
+                    buffer.compute(&space, space.bounds(), options, &*block_meshes)
+                },
+                BatchSize::SmallInput,
+            );
+        });
+    }
 }
 
 fn slow_mesh_benches(c: &mut Criterion) {
@@ -172,6 +197,15 @@ fn checkerboard_space_bench_setup(
     (space, block_meshes)
 }
 
+fn floor_space() -> Space {
+    let bounds = GridAab::from_lower_size([0, 0, 0], [64, 1, 64]);
+    let mut space = Space::empty(bounds);
+    space
+        .fill_uniform(bounds, Block::from(Rgba::WHITE))
+        .unwrap();
+    space
+}
+
 fn checkerboard_block(universe: &mut Universe, voxels: [Block; 2]) -> Block {
     Block::builder()
         .voxels_ref(R16, universe.insert_anonymous(checkerboard_space(voxels)))
@@ -1,7 +1,11 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
-use all_is_cubes::math::GridAab;
-use all_is_cubes::raycast::Raycaster;
+use all_is_cubes::block::Block;
+use all_is_cubes::camera::GraphicsOptions;
+use all_is_cubes::math::{GridAab, Rgba};
+use all_is_cubes::raycast::{Ray, Raycaster};
+use all_is_cubes::raytracer::{ColorBuf, SpaceRaytracer};
+use all_is_cubes::space::Space;
 
 pub fn raycast_bench(c: &mut Criterion) {
     c.bench_function("raycast: single step diagonal", |b| {
@@ -59,6 +63,27 @@ pub fn raycast_bench(c: &mut Criterion) {
             }
         })
     });
+
+    // Raytracer cases, which exercise skipping over empty space.
+    let sparse_rt = {
+        let mut space = Space::empty_positive(256, 256, 256);
+        space
+            .set([200, 200, 200], Block::from(Rgba::WHITE))
+            .unwrap();
+        SpaceRaytracer::<()>::new(&space, GraphicsOptions::default(), ())
+    };
+    c.bench_function("raytrace: sparse space, ray hits", |b| {
+        let ray = Ray::new([0.5, 0.5, 0.5], [1.0, 1.0, 1.0]);
+        b.iter(|| sparse_rt.trace_ray::<ColorBuf>(black_box(ray), true))
+    });
+    c.bench_function("raytrace: sparse space, ray misses", |b| {
+        let ray = Ray::new([0.5, 0.5, 0.5], [1.0, 0.3, 0.7]);
+        b.iter(|| sparse_rt.trace_ray::<ColorBuf>(black_box(ray), true))
+    });
+    c.bench_function("raytrace: sparse space, ray along the floor", |b| {
+        let ray = Ray::new([0.5, 0.5, 0.5], [1.0, 0.0, 0.0]);
+        b.iter(|| sparse_rt.trace_ray::<ColorBuf>(black_box(ray), true))
+    });
 }
 
 criterion_group!(benches, raycast_bench);
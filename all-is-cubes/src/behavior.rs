@@ -58,6 +58,20 @@ pub struct BehaviorContext<'a, H: BehaviorHost> {
 }
 
 impl<'a, H: BehaviorHost> BehaviorContext<'a, H> {
+    pub(crate) fn new(
+        host: &'a H,
+        attachment: &'a H::Attachment,
+        host_transaction_binder: &'a dyn Fn(H::Transaction) -> UniverseTransaction,
+        self_transaction_binder: &'a dyn Fn(Arc<dyn Behavior<H>>) -> UniverseTransaction,
+    ) -> Self {
+        Self {
+            host,
+            attachment,
+            host_transaction_binder,
+            self_transaction_binder,
+        }
+    }
+
     /// Take a transaction applicable to the behavior's host, and wrap it to become a
     /// [`UniverseTransaction`] for the host's containing universe.
     pub fn bind_host(&self, transaction: H::Transaction) -> UniverseTransaction {
@@ -17,7 +17,7 @@ use crate::math::{
 };
 use crate::raycast::Ray;
 use crate::space::{SetCubeError, Space, SpaceChange};
-use crate::universe::URef;
+use crate::universe::{RefError, URef};
 
 mod attributes;
 pub use attributes::*;
@@ -25,6 +25,9 @@ pub use attributes::*;
 mod block_def;
 pub use block_def::*;
 
+mod budget;
+pub use budget::*;
+
 pub mod builder;
 #[doc(inline)]
 pub use builder::BlockBuilder;
@@ -38,6 +41,9 @@ pub use modifier::*;
 mod resolution;
 pub use resolution::*;
 
+mod state;
+pub use state::*;
+
 #[cfg(test)]
 mod tests;
 
@@ -291,6 +297,58 @@ impl Block {
         }
     }
 
+    /// Returns the property values specified by this block's [`Modifier::State`], or
+    /// [`BlockState::EMPTY`] if it has none.
+    ///
+    /// This does not include the default values of properties which are not specified;
+    /// use [`Block::property()`] to find the value a property actually has.
+    pub fn state(&self) -> &BlockState {
+        static EMPTY: BlockState = BlockState::EMPTY;
+        self.modifiers()
+            .iter()
+            .find_map(|modifier| match modifier {
+                Modifier::State(state) => Some(state),
+                _ => None,
+            })
+            .unwrap_or(&EMPTY)
+    }
+
+    /// Returns the value this block has for the named property declared by the
+    /// [`BlockDef`] of its [`Primitive::Indirect`], or [`None`] if there is no such
+    /// property (including if the primitive is not [`Primitive::Indirect`]).
+    ///
+    /// Fails if the [`BlockDef`] cannot be read.
+    pub fn property(&self, name: &str) -> Result<Option<PropertyValue>, RefError> {
+        match self.primitive() {
+            Primitive::Indirect(def_ref) => {
+                Ok(def_ref.read()?.properties().value(self.state(), name))
+            }
+            Primitive::Atom(..) | Primitive::Air | Primitive::Recur { .. } => Ok(None),
+        }
+    }
+
+    /// Returns this block with the value of the named property set to `value`, by
+    /// modifying its [`Modifier::State`] or adding one as the first modifier.
+    ///
+    /// This does not check whether the property is declared or allows the value; if
+    /// not, the value will have no effect. See [`BlockProperties`] for an example.
+    #[must_use]
+    pub fn with_property(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<PropertyValue>,
+    ) -> Self {
+        let modifiers = self.modifiers_mut();
+        match modifiers.iter_mut().find_map(|modifier| match modifier {
+            Modifier::State(state) => Some(state),
+            _ => None,
+        }) {
+            Some(state) => state.set(name, value),
+            None => modifiers.insert(0, Modifier::State(BlockState::EMPTY.with(name, value))),
+        }
+        self
+    }
+
     /// Standardizes any characteristics of this block which may be presumed to be
     /// specific to its usage in its current location, so that it can be used elsewhere
     /// or compared with others. Specifically, it has the following effects:
@@ -361,16 +419,23 @@ impl Block {
     /// Converts this `Block` into a “flattened” and snapshotted form which contains all
     /// information needed for rendering and physics, and does not require [`URef`] access
     /// to other objects.
+    ///
+    /// The computation this may perform is limited to [`Cost::DEFAULT_BUDGET`];
+    /// if the block definition requires more, an error is returned.
     pub fn evaluate(&self) -> Result<EvaluatedBlock, EvalBlockError> {
-        Ok(EvaluatedBlock::from(self.evaluate_impl(0)?))
+        let mut budget = Budget::new(Cost::DEFAULT_BUDGET);
+        Ok(EvaluatedBlock::from(self.evaluate_impl(&mut budget)?))
     }
 
     #[inline]
-    fn evaluate_impl(&self, depth: u8) -> Result<MinEval, EvalBlockError> {
+    fn evaluate_impl(&self, budget: &mut Budget) -> Result<MinEval, EvalBlockError> {
         let mut value: MinEval = match *self.primitive() {
-            Primitive::Indirect(ref def_ref) => {
-                def_ref.read()?.evaluate_impl(next_depth(depth)?)?
-            }
+            Primitive::Indirect(ref def_ref) => budget.recurse(|budget| {
+                let def = def_ref.read()?;
+                let value = def.evaluate_impl(budget)?;
+                def.properties()
+                    .evaluate(self.state(), def.as_ref(), value, budget)
+            })?,
 
             Primitive::Atom(ref attributes, color) => MinEval {
                 attributes: attributes.clone(),
@@ -378,6 +443,7 @@ impl Block {
                     color,
                     selectable: attributes.selectable,
                     collision: attributes.collision,
+                    material: attributes.material,
                 }),
             },
 
@@ -425,9 +491,11 @@ impl Block {
             }
         };
 
+        budget.spend_component(value.voxels.bounds().volume())?;
+
         for (index, modifier) in self.modifiers().iter().enumerate() {
-            // TODO: Extend recursion depth model to catch stacking up lots of modifiers
-            value = modifier.evaluate(self, index, value, depth)?;
+            value = modifier.evaluate(self, index, value, budget)?;
+            budget.spend_component(value.voxels.bounds().volume())?;
         }
 
         Ok(value)
@@ -495,6 +563,8 @@ impl Block {
                         SpaceChange::BlockValue(_) => Some(BlockChange::new()),
                         SpaceChange::Lighting(_) => None,
                         SpaceChange::Number(_) => None,
+                        SpaceChange::Physics => None,
+                        SpaceChange::CubeData(_) => None,
                     }
                 }));
             }
@@ -516,7 +586,7 @@ impl Block {
     }
 }
 
-/// Recursion limiter helper for evaluate.
+/// Recursion limiter helper for listen.
 fn next_depth(depth: u8) -> Result<u8, EvalBlockError> {
     if depth > 32 {
         Err(EvalBlockError::StackOverflow)
@@ -3,12 +3,16 @@
 use std::borrow::Cow;
 use std::fmt;
 
+use ordered_float::NotNan;
+
 use crate::drawing::VoxelBrush;
 use crate::math::{Face6, Rgb};
+use crate::space::{BlockEntityTemplate, DynamicLight};
 
 #[cfg(doc)]
 use crate::{
     block::{Block, BlockDef},
+    inv::Inventory,
     space::Space,
 };
 
@@ -46,11 +50,36 @@ pub struct BlockAttributes {
     /// The default value is [`Rgb::ZERO`].
     pub light_emission: Rgb,
 
-    /// Something this block does when time passes.
+    /// How light reflects from and passes through the surface of the block,
+    /// beyond its color.
+    ///
+    /// The default value is [`Material::DIFFUSE`].
+    pub material: Material,
+
+    /// A light source which renderers may draw in real time, with shadows, at the
+    /// position of the block, in addition to [`light_emission`](Self::light_emission).
     ///
-    /// Currently the only possibility is “turn into another block”.
+    /// The default value is [`None`].
+    pub dynamic_light: Option<DynamicLight>,
+
+    /// If nonzero, the block is a container: each cube of a [`Space`] containing it
+    /// holds an [`Inventory`] with this many slots.
     ///
-    /// TODO: Very placeholder. This needs more possible effects and also time/probability options.
+    /// The default value is `0`.
+    pub container_slots: u16,
+
+    /// If not [`None`], each cube of a [`Space`] containing this block has its own
+    /// [`Behavior`](crate::behavior::Behavior), created from this template, which is
+    /// stepped at the times the template specifies.
+    ///
+    /// The default value is [`None`].
+    pub block_entity: Option<BlockEntityTemplate>,
+
+    /// Something this block does when time passes.
+    ///
+    /// Currently the only possibility is “turn into another block”, on the next step.
+    /// For other effects, or effects which should happen after a delay or at random
+    /// times, use [`block_entity`](Self::block_entity) instead.
     pub tick_action: Option<VoxelBrush<'static>>,
 
     /// Advice to the renderer about how to expect this block to change, and hence
@@ -59,8 +88,6 @@ pub struct BlockAttributes {
     //
     // Reminder: When adding new fields, add them to the Debug implementation
     // and BlockBuilder.
-    //
-    // TODO: add 'behavior' functionality, if we don't come up with something else
 }
 
 impl fmt::Debug for BlockAttributes {
@@ -87,6 +114,18 @@ impl fmt::Debug for BlockAttributes {
             if self.light_emission != Self::default().light_emission {
                 s.field("light_emission", &self.light_emission);
             }
+            if self.material != Self::default().material {
+                s.field("material", &self.material);
+            }
+            if self.dynamic_light != Self::default().dynamic_light {
+                s.field("dynamic_light", &self.dynamic_light);
+            }
+            if self.container_slots != Self::default().container_slots {
+                s.field("container_slots", &self.container_slots);
+            }
+            if self.block_entity != Self::default().block_entity {
+                s.field("block_entity", &self.block_entity);
+            }
             if self.tick_action != Self::default().tick_action {
                 s.field("tick_action", &self.tick_action);
             }
@@ -110,6 +149,10 @@ impl BlockAttributes {
             collision: BlockCollision::Hard,
             rotation_rule: RotationPlacementRule::Never,
             light_emission: Rgb::ZERO,
+            material: Material::DIFFUSE,
+            dynamic_light: None,
+            container_slots: 0,
+            block_entity: None,
             tick_action: None,
             animation_hint: AnimationHint::UNCHANGING,
         }
@@ -134,7 +177,11 @@ impl<'a> arbitrary::Arbitrary<'a> for BlockAttributes {
             collision: u.arbitrary()?,
             rotation_rule: u.arbitrary()?,
             light_emission: u.arbitrary()?,
-            tick_action: None, // TODO: need Arbitrary for Block
+            material: u.arbitrary()?,
+            dynamic_light: None, // TODO: need Arbitrary for DynamicLight
+            container_slots: u.arbitrary()?,
+            block_entity: None, // cannot be arbitrary because it contains a function
+            tick_action: None,  // TODO: need Arbitrary for Block
             animation_hint: u.arbitrary()?,
         })
     }
@@ -146,6 +193,8 @@ impl<'a> arbitrary::Arbitrary<'a> for BlockAttributes {
             BlockCollision::size_hint(depth),
             RotationPlacementRule::size_hint(depth),
             Rgb::size_hint(depth),
+            Material::size_hint(depth),
+            u16::size_hint(depth),
             AnimationHint::size_hint(depth),
         ])
     }
@@ -172,6 +221,73 @@ pub enum BlockCollision {
     // Future values might include bouncy solid, water-like resistance, force fields, etc.
 }
 
+/// Optical properties of a block's surface, beyond its color; part of
+/// [`BlockAttributes`].
+///
+/// Renderers may approximate these properties; currently, the raytracer implements
+/// reflection and refraction, and the GPU renderer approximates reflection of the sky.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct Material {
+    /// Fraction of light which is reflected specularly (as by a mirror or polished
+    /// surface) rather than diffusely, when viewed head-on. The fraction increases
+    /// towards 1 at grazing angles.
+    ///
+    /// Must be between 0 and 1; 0 means the surface is purely diffuse.
+    pub reflectance: NotNan<f32>,
+
+    /// How much specular reflections are blurred by the roughness of the surface.
+    ///
+    /// Must be between 0 and 1; 0 means the surface is a perfect mirror.
+    pub roughness: NotNan<f32>,
+
+    /// Index of refraction of the material, which determines how much light passing
+    /// into it through a transparent surface is bent.
+    ///
+    /// 1 means no bending; common values are 1.33 for water and 1.5 for glass.
+    pub refractive_index: NotNan<f32>,
+}
+
+impl Material {
+    /// A surface with no specular reflection or refraction. This is the default value.
+    pub const DIFFUSE: Self = Self {
+        reflectance: notnan!(0.0),
+        roughness: notnan!(1.0),
+        refractive_index: notnan!(1.0),
+    };
+
+    /// A perfectly smooth, perfectly reflective surface.
+    pub const MIRROR: Self = Self {
+        reflectance: notnan!(1.0),
+        roughness: notnan!(0.0),
+        refractive_index: notnan!(1.0),
+    };
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self::DIFFUSE
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+#[mutants::skip]
+impl<'a> arbitrary::Arbitrary<'a> for Material {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut unit =
+            || -> arbitrary::Result<NotNan<f32>> { Ok(NotNan::from(u.arbitrary::<u8>()?) / 255.0) };
+        Ok(Self {
+            reflectance: unit()?,
+            roughness: unit()?,
+            refractive_index: unit()? + 1.0,
+        })
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (3, Some(3))
+    }
+}
+
 /// Rule about how this block should be rotated, or not, when placed in a [`Space`] by
 /// some agent not otherwise specifying rotation.
 ///
@@ -1,8 +1,12 @@
-use std::ops::Deref;
-use std::sync::Arc;
+use std::cell::Cell;
+use std::fmt;
+use std::ops::{AddAssign, Deref, Sub};
+use std::sync::{Arc, Mutex, Weak};
 
-use crate::block::{Block, BlockChange, Primitive};
-use crate::listen::{Gate, Listen, Listener, Notifier};
+use crate::block::{
+    Block, BlockChange, BlockProperties, Budget, Cost, EvalBlockError, MinEval, Primitive,
+};
+use crate::listen::{Gate, Listen, Listener, Notifier, NotifierForwarder};
 use crate::transaction::{self, Transaction};
 use crate::universe::{RefVisitor, VisitRefs};
 
@@ -14,11 +18,21 @@ use crate::universe::{RefVisitor, VisitRefs};
 /// will be delivered on any mutation.
 ///
 /// To perform a mutation, use [`BlockDefTransaction`].
-#[derive(Debug)]
 pub struct BlockDef {
     block: Block,
-    // TODO: It might be a good idea to cache EvaluatedBlock here, since we're doing
-    // mutation tracking anyway.
+
+    /// Properties which blocks using this definition may specify values for, and the
+    /// variants of `block` they select.
+    properties: BlockProperties,
+
+    /// Cache of the evaluation of `block`, shared by all [`Primitive::Indirect`] blocks
+    /// referring to this definition. It is cleared by [`BlockDefBlockListener`] whenever
+    /// the evaluation might change.
+    ///
+    /// [`None`] if listening to `block` failed, in which case we could not know when to
+    /// clear the cache.
+    cache: Option<Arc<Mutex<EvalCache>>>,
+
     notifier: Arc<Notifier<BlockChange>>,
     block_listen_gate: Gate,
 }
@@ -28,13 +42,185 @@ impl BlockDef {
     /// in the future).
     pub fn new(block: Block) -> Self {
         let notifier = Arc::new(Notifier::new());
-        let (gate, block_listener) = Notifier::forwarder(Arc::downgrade(&notifier)).gate();
-        // TODO: Consider making it an error if listening fails. BlockDefTransaction::check will need to follow.
-        let _ = block.listen(block_listener);
+        let properties = BlockProperties::new();
+        let (block_listen_gate, cache) = Self::listen_to_block(&block, &properties, &notifier);
         BlockDef {
             block,
+            properties,
+            cache,
             notifier,
-            block_listen_gate: gate,
+            block_listen_gate,
+        }
+    }
+
+    /// Declares the properties which blocks using this definition may have, and the
+    /// variants of the block they select. See [`BlockProperties`] for details.
+    #[must_use]
+    pub fn with_properties(mut self, properties: BlockProperties) -> Self {
+        let (gate, cache) = Self::listen_to_block(&self.block, &properties, &self.notifier);
+        self.properties = properties;
+        self.block_listen_gate = gate;
+        self.cache = cache;
+        self
+    }
+
+    /// Returns the properties declared by this definition.
+    pub fn properties(&self) -> &BlockProperties {
+        &self.properties
+    }
+
+    /// Sets up the listener on `block` (and on the modifiers of the variants in
+    /// `properties`) for a new or replaced block, and the cache it invalidates.
+    fn listen_to_block(
+        block: &Block,
+        properties: &BlockProperties,
+        notifier: &Arc<Notifier<BlockChange>>,
+    ) -> (Gate, Option<Arc<Mutex<EvalCache>>>) {
+        let cache = Arc::new(Mutex::new(EvalCache::default()));
+        let (gate, block_listener) = BlockDefBlockListener {
+            cache: Arc::downgrade(&cache),
+            forwarder: Notifier::forwarder(Arc::downgrade(notifier)),
+        }
+        .gate();
+        // TODO: Consider making it an error if listening fails. BlockDefTransaction::check will need to follow.
+        let listening = block.listen(block_listener.clone()).is_ok();
+        // The variants are not part of the cached evaluation, but their changes must
+        // still be forwarded.
+        for variant in properties.variants() {
+            for modifier in &variant.modifiers {
+                // TODO: Same as above regarding failure.
+                let _ = modifier.listen_impl(&block_listener, 0);
+            }
+        }
+        (gate, listening.then_some(cache))
+    }
+
+    /// Evaluates the block, reusing the previous result if nothing it depends on has
+    /// changed since then.
+    ///
+    /// A reused result is charged to `budget` at the cost of the original evaluation,
+    /// so that whether evaluation succeeds does not depend on the cache.
+    pub(super) fn evaluate_impl(&self, budget: &mut Budget) -> Result<MinEval, EvalBlockError> {
+        let Some(cache) = &self.cache else {
+            return self.block.evaluate_impl(budget);
+        };
+
+        let generation = {
+            let cache = cache.lock().unwrap();
+            if let Some((value, cost)) = &cache.value {
+                EVAL_CACHE_STATS.with(|stats| stats.set(stats.get() + EvalCacheStats::HIT));
+                budget.spend(*cost)?;
+                return Ok(value.clone());
+            }
+            cache.generation
+        };
+        EVAL_CACHE_STATS.with(|stats| stats.set(stats.get() + EvalCacheStats::MISS));
+
+        // The lock must not be held during evaluation, because evaluation may recurse
+        // into this same definition (and then fail due to excessive depth).
+        let (result, cost) = budget.measure(|budget| self.block.evaluate_impl(budget));
+        let value = result?;
+
+        let mut cache = cache.lock().unwrap();
+        // If the cache was invalidated during evaluation, then the value may be stale.
+        if cache.generation == generation {
+            cache.value = Some((value.clone(), cost));
+        }
+        Ok(value)
+    }
+}
+
+/// Contents of [`BlockDef::cache`].
+#[derive(Debug, Default)]
+struct EvalCache {
+    /// The evaluation result and what it cost to compute.
+    value: Option<(MinEval, Cost)>,
+    /// Incremented whenever `value` is invalidated.
+    generation: u64,
+}
+
+/// Listener on a [`BlockDef`]'s block, which invalidates its evaluation cache and then
+/// forwards the change to the [`BlockDef`]'s own listeners.
+#[derive(Clone, Debug)]
+struct BlockDefBlockListener {
+    cache: Weak<Mutex<EvalCache>>,
+    forwarder: NotifierForwarder<BlockChange>,
+}
+
+impl Listener<BlockChange> for BlockDefBlockListener {
+    fn receive(&self, message: BlockChange) {
+        if let Some(cache) = self.cache.upgrade() {
+            if let Ok(mut cache) = cache.lock() {
+                cache.value = None;
+                cache.generation = cache.generation.wrapping_add(1);
+            }
+        }
+        self.forwarder.receive(message);
+    }
+
+    fn alive(&self) -> bool {
+        self.forwarder.alive()
+    }
+}
+
+thread_local! {
+    static EVAL_CACHE_STATS: Cell<EvalCacheStats> = Cell::new(EvalCacheStats::default());
+}
+
+/// Counts of how often evaluating [`Primitive::Indirect`] blocks was able to reuse
+/// the cached evaluation of their [`BlockDef`], and how often the definition had to be
+/// evaluated.
+///
+/// These statistics are reported by [`SpaceStepInfo`](crate::space::SpaceStepInfo).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct EvalCacheStats {
+    /// Number of times a cached evaluation was used.
+    pub hits: usize,
+    /// Number of times a [`BlockDef`]'s block was evaluated because it was not cached.
+    pub misses: usize,
+}
+
+impl EvalCacheStats {
+    const HIT: Self = Self { hits: 1, misses: 0 };
+    const MISS: Self = Self { hits: 0, misses: 1 };
+
+    /// Returns the total of all cache usage that has occurred on the current thread.
+    /// The difference between two such values measures the cache usage of the work
+    /// done on this thread between them.
+    pub(crate) fn current_thread() -> Self {
+        EVAL_CACHE_STATS.with(Cell::get)
+    }
+
+    /// Returns the fraction of lookups that were hits, or [`None`] if there were none.
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f64 / total as f64)
+    }
+}
+
+impl std::ops::Add for EvalCacheStats {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            hits: self.hits.wrapping_add(rhs.hits),
+            misses: self.misses.wrapping_add(rhs.misses),
+        }
+    }
+}
+
+impl AddAssign for EvalCacheStats {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for EvalCacheStats {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Self {
+            hits: self.hits.wrapping_sub(rhs.hits),
+            misses: self.misses.wrapping_sub(rhs.misses),
         }
     }
 }
@@ -49,6 +235,27 @@ impl Listen for BlockDef {
     }
 }
 
+impl fmt::Debug for BlockDef {
+    // The cache is omitted, as it does not affect the meaning of the definition.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            block,
+            properties,
+            cache: _,
+            notifier,
+            block_listen_gate,
+        } = self;
+        let mut ds = f.debug_struct("BlockDef");
+        ds.field("block", block);
+        if !properties.is_empty() {
+            ds.field("properties", properties);
+        }
+        ds.field("notifier", notifier)
+            .field("block_listen_gate", block_listen_gate)
+            .finish()
+    }
+}
+
 impl Deref for BlockDef {
     type Target = Block;
 
@@ -64,7 +271,8 @@ impl AsRef<Block> for BlockDef {
 
 impl VisitRefs for BlockDef {
     fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
-        self.block.visit_refs(visitor)
+        self.block.visit_refs(visitor);
+        self.properties.visit_refs(visitor);
     }
 }
 
@@ -169,12 +377,13 @@ impl Transaction<BlockDef> for BlockDefTransaction {
         if let Some(new) = &self.new {
             target.block = new.clone();
 
-            // Swap out the forwarding listener to listen to the new block.
-            let (gate, block_listener) =
-                Notifier::forwarder(Arc::downgrade(&target.notifier)).gate();
-            // TODO: Instead of ignoring the error from listen() here, we can fail the transaction by preparing the listener in check().
-            let _ = target.block.listen(block_listener);
+            // Swap out the forwarding listener to listen to the new block, and start a
+            // new, empty cache.
+            // TODO: Instead of ignoring failure to listen here, we can fail the transaction by preparing the listener in check().
+            let (gate, cache) =
+                BlockDef::listen_to_block(&target.block, &target.properties, &target.notifier);
             target.block_listen_gate = gate; // old gate is now dropped
+            target.cache = cache;
 
             target.notifier.notify(BlockChange::new());
         }
@@ -0,0 +1,194 @@
+//! Limits on the computation performed by [`Block::evaluate()`].
+
+use std::fmt;
+
+use crate::block::EvalBlockError;
+#[cfg(doc)]
+use crate::block::{Block, BlockDef, Modifier, Primitive};
+
+/// An amount of computation performed in evaluating a [`Block`]; used both to describe
+/// the work done and to limit it.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct Cost {
+    /// Number of [`Primitive`]s and [`Modifier`]s evaluated.
+    pub components: usize,
+    /// Total number of voxels produced by each [`Primitive`] and [`Modifier`] evaluated.
+    pub voxels: usize,
+    /// Depth of nested evaluation of other blocks, such as by [`Primitive::Indirect`]
+    /// or [`Modifier::Composite`].
+    pub recursion: u8,
+}
+
+impl Cost {
+    /// The limit [`Block::evaluate()`] places on the computation of each evaluation.
+    ///
+    /// Exceeding the recursion limit results in [`EvalBlockError::StackOverflow`], and
+    /// exceeding any other limit in [`EvalBlockError::BudgetExceeded`].
+    pub const DEFAULT_BUDGET: Self = Self {
+        components: 1000,
+        voxels: 16 * 128 * 128 * 128,
+        recursion: 32,
+    };
+}
+
+impl fmt::Display for Cost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            components,
+            voxels,
+            recursion,
+        } = self;
+        write!(
+            f,
+            "{components} components, {voxels} voxels, recursion depth {recursion}"
+        )
+    }
+}
+
+/// Tracks the [`Cost`] of an evaluation in progress, and fails it when that exceeds
+/// the limit.
+#[derive(Debug)]
+pub(crate) struct Budget {
+    limit: Cost,
+    /// Cost incurred so far, except that `recursion` is the current depth.
+    used: Cost,
+    /// Deepest recursion reached so far.
+    max_recursion: u8,
+}
+
+impl Budget {
+    pub(crate) fn new(limit: Cost) -> Self {
+        Self {
+            limit,
+            used: Cost::default(),
+            max_recursion: 0,
+        }
+    }
+
+    /// Charges for evaluating one [`Primitive`] or [`Modifier`] whose output has
+    /// `voxels` voxels.
+    pub(crate) fn spend_component(&mut self, voxels: usize) -> Result<(), EvalBlockError> {
+        self.spend(Cost {
+            components: 1,
+            voxels,
+            recursion: 0,
+        })
+    }
+
+    /// Charges for a repetition, starting at the current recursion depth, of an
+    /// evaluation which had the given cost as measured by [`Budget::measure()`].
+    ///
+    /// This is used when the result of the evaluation is reused instead of recomputed,
+    /// so that evaluation succeeds or fails the same way regardless of caching.
+    pub(crate) fn spend(&mut self, cost: Cost) -> Result<(), EvalBlockError> {
+        let depth = self.used.recursion.saturating_add(cost.recursion);
+        if depth > self.limit.recursion {
+            return Err(EvalBlockError::StackOverflow);
+        }
+        self.max_recursion = self.max_recursion.max(depth);
+
+        self.used.components = self.used.components.saturating_add(cost.components);
+        self.used.voxels = self.used.voxels.saturating_add(cost.voxels);
+        if self.used.components > self.limit.components || self.used.voxels > self.limit.voxels {
+            return Err(EvalBlockError::BudgetExceeded {
+                budget: self.limit,
+                used: Cost {
+                    recursion: self.max_recursion,
+                    ..self.used
+                },
+            });
+        }
+        Ok(())
+    }
+
+    /// Runs `f`, which evaluates some other block, one level deeper in recursion.
+    pub(crate) fn recurse<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, EvalBlockError>,
+    ) -> Result<T, EvalBlockError> {
+        if self.used.recursion >= self.limit.recursion {
+            return Err(EvalBlockError::StackOverflow);
+        }
+        self.used.recursion += 1;
+        self.max_recursion = self.max_recursion.max(self.used.recursion);
+        let result = f(self);
+        self.used.recursion -= 1;
+        result
+    }
+
+    /// Runs `f` and returns its result together with the [`Cost`] it incurred.
+    pub(crate) fn measure<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> (T, Cost) {
+        let start = self.used;
+        let outer_max_recursion = std::mem::replace(&mut self.max_recursion, start.recursion);
+        let result = f(self);
+        let cost = Cost {
+            components: self.used.components - start.components,
+            voxels: self.used.voxels - start.voxels,
+            recursion: self.max_recursion - start.recursion,
+        };
+        self.max_recursion = self.max_recursion.max(outer_max_recursion);
+        (result, cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_and_spend_agree() {
+        let limit = Cost {
+            components: 10,
+            voxels: 100,
+            recursion: 3,
+        };
+        let mut budget = Budget::new(limit);
+        let ((), cost) = budget.measure(|budget| {
+            budget.spend_component(10).unwrap();
+            budget.recurse(|budget| budget.spend_component(20)).unwrap();
+        });
+        assert_eq!(
+            cost,
+            Cost {
+                components: 2,
+                voxels: 30,
+                recursion: 1
+            }
+        );
+
+        // Spending the same cost again at a deeper level fails the same way as
+        // repeating the work would.
+        let mut budget = Budget::new(limit);
+        budget
+            .recurse(|budget| budget.recurse(|budget| budget.spend(cost)))
+            .unwrap();
+        assert_eq!(
+            budget.recurse(|budget| budget.recurse(|budget| budget.recurse(|b| b.spend(cost)))),
+            Err(EvalBlockError::StackOverflow)
+        );
+    }
+
+    #[test]
+    fn exceeding_components() {
+        let limit = Cost {
+            components: 2,
+            voxels: 100,
+            recursion: 3,
+        };
+        let mut budget = Budget::new(limit);
+        budget.spend_component(1).unwrap();
+        budget.spend_component(1).unwrap();
+        assert_eq!(
+            budget.spend_component(1),
+            Err(EvalBlockError::BudgetExceeded {
+                budget: limit,
+                used: Cost {
+                    components: 3,
+                    voxels: 3,
+                    recursion: 0
+                }
+            })
+        );
+    }
+}
@@ -6,11 +6,11 @@ use std::sync::Arc;
 
 use crate::block::{
     AnimationHint, Block, BlockAttributes, BlockCollision, BlockDef, BlockParts, BlockPtr,
-    Modifier, Primitive, Resolution, RotationPlacementRule, AIR,
+    Material, Modifier, Primitive, Resolution, RotationPlacementRule, AIR,
 };
 use crate::drawing::VoxelBrush;
 use crate::math::{GridPoint, Rgb, Rgba};
-use crate::space::{SetCubeError, Space};
+use crate::space::{BlockEntityTemplate, DynamicLight, SetCubeError, Space};
 use crate::universe::{Name, URef, Universe, UniverseIndex};
 
 /// Tool for constructing [`Block`] values conveniently.
@@ -102,6 +102,30 @@ impl<C> BlockBuilder<C> {
         self
     }
 
+    /// Sets the value for [`BlockAttributes::material`].
+    pub const fn material(mut self, value: Material) -> Self {
+        self.attributes.material = value;
+        self
+    }
+
+    /// Sets the value for [`BlockAttributes::dynamic_light`].
+    pub const fn dynamic_light(mut self, value: Option<DynamicLight>) -> Self {
+        self.attributes.dynamic_light = value;
+        self
+    }
+
+    /// Sets the value for [`BlockAttributes::container_slots`].
+    pub const fn container_slots(mut self, value: u16) -> Self {
+        self.attributes.container_slots = value;
+        self
+    }
+
+    /// Sets the value for [`BlockAttributes::block_entity`].
+    pub fn block_entity(mut self, value: Option<BlockEntityTemplate>) -> Self {
+        self.attributes.block_entity = value;
+        self
+    }
+
     /// Sets the value for [`BlockAttributes::tick_action`].
     pub fn tick_action(mut self, value: Option<VoxelBrush<'static>>) -> Self {
         self.attributes.tick_action = value;
@@ -300,9 +324,10 @@ impl BuildPrimitiveIndependent for BlockBuilderVoxels {
 
 #[cfg(test)]
 mod tests {
+    use crate::behavior::NoopBehavior;
     use crate::block::{Resolution::*, AIR};
     use crate::math::{Face6, GridAab};
-    use crate::space::SpacePhysics;
+    use crate::space::{SpacePhysics, TickSchedule};
 
     use super::*;
 
@@ -328,7 +353,11 @@ mod tests {
         let color = Rgba::new(0.1, 0.2, 0.3, 0.4);
         let light_emission = Rgb::new(0.1, 3.0, 0.1);
         let rotation_rule = RotationPlacementRule::Attach { by: Face6::NZ };
+        let dynamic_light = DynamicLight::point(Rgb::new(1.0, 0.5, 0.0), notnan!(8.0));
         let tick_action = Some(VoxelBrush::single(AIR));
+        let block_entity = Some(BlockEntityTemplate::new(TickSchedule::Never, || {
+            NoopBehavior(1)
+        }));
         assert_eq!(
             Block::builder()
                 .color(color)
@@ -337,6 +366,10 @@ mod tests {
                 .rotation_rule(rotation_rule)
                 .selectable(false)
                 .light_emission(light_emission)
+                .material(Material::MIRROR)
+                .dynamic_light(Some(dynamic_light))
+                .container_slots(9)
+                .block_entity(block_entity.clone())
                 .tick_action(tick_action.clone())
                 .animation_hint(AnimationHint::TEMPORARY)
                 .build(),
@@ -347,6 +380,10 @@ mod tests {
                     rotation_rule,
                     selectable: false,
                     light_emission,
+                    material: Material::MIRROR,
+                    dynamic_light: Some(dynamic_light),
+                    container_slots: 9,
+                    block_entity,
                     tick_action,
                     animation_hint: AnimationHint::TEMPORARY,
                 },
@@ -228,6 +228,15 @@ pub enum EvalBlockError {
     /// This may be temporary or permanent; consult the [`RefError`] to determine that.
     #[error("block data inaccessible: {0}")]
     DataRefIs(#[from] RefError),
+    /// The block definition required more computation to evaluate than is allowed
+    /// (other than by recursion, which is reported as [`EvalBlockError::StackOverflow`]).
+    #[error("block definition is too costly to evaluate: used {used}, limit {budget}")]
+    BudgetExceeded {
+        /// The limit which was exceeded.
+        budget: block::Cost,
+        /// The computation performed before evaluation was stopped.
+        used: block::Cost,
+    },
 }
 
 impl EvalBlockError {
@@ -282,6 +291,9 @@ pub struct Evoxel {
 
     /// The effect on a [`Body`](crate::physics::Body) of colliding with this voxel.
     pub collision: block::BlockCollision,
+
+    /// How light reflects from and passes through this voxel's surface, beyond its color.
+    pub material: block::Material,
 }
 
 impl Evoxel {
@@ -292,6 +304,7 @@ impl Evoxel {
         color: Rgba::TRANSPARENT,
         selectable: false,
         collision: block::BlockCollision::None,
+        material: block::Material::DIFFUSE,
     };
 
     /// Construct an [`Evoxel`] which represents the given evaluated block.
@@ -302,6 +315,7 @@ impl Evoxel {
             color: block.color,
             selectable: block.attributes.selectable,
             collision: block.attributes.collision,
+            material: block.attributes.material,
         }
     }
 
@@ -316,6 +330,7 @@ impl Evoxel {
             color,
             selectable: DA.selectable,
             collision: DA.collision,
+            material: DA.material,
         }
     }
 }
@@ -467,6 +482,10 @@ const AIR_ATTRIBUTES: BlockAttributes = BlockAttributes {
     collision: block::BlockCollision::None,
     rotation_rule: block::RotationPlacementRule::Never,
     light_emission: Rgb::ZERO,
+    material: block::Material::DIFFUSE,
+    dynamic_light: None,
+    container_slots: 0,
+    block_entity: None,
     tick_action: None,
     animation_hint: block::AnimationHint::UNCHANGING,
 };
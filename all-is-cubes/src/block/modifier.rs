@@ -1,14 +1,20 @@
-use crate::block::{Block, BlockChange, EvalBlockError, Evoxels, MinEval};
+use crate::block::{Block, BlockChange, BlockState, Budget, EvalBlockError, Evoxels, MinEval};
 use crate::listen::Listener;
-use crate::math::{GridArray, GridRotation, Rgb};
+use crate::math::{Axis, GridArray, GridRotation, Rgb};
 use crate::universe::{RefVisitor, VisitRefs};
 
 mod composite;
 pub use composite::*;
+mod inset;
+pub use inset::*;
 mod r#move;
 pub use r#move::*;
 mod quote;
 pub use quote::*;
+mod scale;
+pub use scale::*;
+mod tint;
+pub use tint::*;
 mod zoom;
 pub use zoom::*;
 
@@ -70,6 +76,33 @@ pub enum Modifier {
 
     /// Displace the block out of the grid, cropping it.
     Move(Move),
+
+    /// Change the colors of the block's voxels, such as to make differently colored
+    /// variants of the same block.
+    Tint(Tint),
+
+    /// Reflect the block across the plane through its center which is perpendicular to
+    /// the given axis.
+    Mirror(Axis),
+
+    /// Shrink the whole block uniformly to occupy a smaller cube within its original
+    /// volume, leaving the rest empty.
+    Scale(Scale),
+
+    /// Remove all of the block's voxels outside of a box, such as to make a slab or
+    /// inset shape.
+    Inset(Inset),
+
+    /// Specify values of the [properties](crate::block::BlockProperties) declared by the
+    /// [`BlockDef`](crate::block::BlockDef) of a [`Primitive::Indirect`] block, selecting
+    /// which variant of it this block is.
+    ///
+    /// This modifier has no effect of its own wherever it is in the list; rather, the
+    /// primitive uses the first [`Modifier::State`] of the block. It should normally be
+    /// used by means of [`Block::with_property()`], which places it first.
+    ///
+    /// [`Primitive::Indirect`]: crate::block::Primitive::Indirect
+    State(BlockState),
 }
 
 impl Modifier {
@@ -79,8 +112,8 @@ impl Modifier {
     /// * `this_modifier_index` is the index in `block.modifiers()` of `self`.
     /// * `value` is the output of the preceding modifier or primitive, which is what the
     ///   current modifier should be applied to.
-    /// * `depth` is the current block evaluation recursion depth (which is *not*
-    ///   incremented by modifiers; TODO: define a computation limit strategy).
+    /// * `budget` limits the computation of evaluating any other blocks this modifier
+    ///   refers to. (The caller charges for the modifier itself.)
     ///
     /// TODO: Arrange some way to not end up re-computing the `voxel_opacity_mask` and other
     /// derived properties (i.e. we should have some kind of `IncompleteEvaluatedBlock` to pass
@@ -90,52 +123,36 @@ impl Modifier {
         block: &Block,
         this_modifier_index: usize,
         mut value: MinEval,
-        depth: u8,
+        budget: &mut Budget,
     ) -> Result<MinEval, EvalBlockError> {
         Ok(match *self {
             Modifier::Quote(Quote { suppress_ambient }) => {
                 value.attributes.tick_action = None;
+                value.attributes.block_entity = None;
                 if suppress_ambient {
                     value.attributes.light_emission = Rgb::ZERO;
                 }
                 value
             }
 
-            Modifier::Rotate(rotation) => {
-                if matches!(value.voxels, Evoxels::One(_)) {
-                    // Skip computation of transforms
-                    value
-                } else {
-                    // TODO: Add a shuffle-in-place rotation operation to GridArray and try implementing this using that, which should have less arithmetic involved than these matrix ops
-                    let resolution = value.resolution();
-                    let inner_to_outer = rotation.to_positive_octant_matrix(resolution.into());
-                    let outer_to_inner = rotation
-                        .inverse()
-                        .to_positive_octant_matrix(resolution.into());
-
-                    MinEval {
-                        voxels: Evoxels::Many(
-                            resolution,
-                            GridArray::from_fn(
-                                value.voxels.bounds().transform(inner_to_outer).unwrap(),
-                                |cube| {
-                                    value
-                                        .voxels
-                                        .get(outer_to_inner.transform_cube(cube))
-                                        .unwrap()
-                                },
-                            ),
-                        ),
-                        attributes: value.attributes,
-                    }
-                }
-            }
+            Modifier::Rotate(rotation) => rotate_evaluation(rotation, value),
 
-            Modifier::Composite(ref c) => c.evaluate(value, depth)?,
+            Modifier::Mirror(axis) => rotate_evaluation(mirror_rotation(axis), value),
+
+            Modifier::Composite(ref c) => c.evaluate(value, budget)?,
 
             Modifier::Zoom(ref z) => z.evaluate(value)?,
 
-            Modifier::Move(ref m) => m.evaluate(block, this_modifier_index, value, depth)?,
+            Modifier::Move(ref m) => m.evaluate(block, this_modifier_index, value, budget)?,
+
+            Modifier::Tint(ref t) => t.evaluate(value),
+
+            Modifier::Scale(ref m) => m.evaluate(value),
+
+            Modifier::Inset(ref m) => m.evaluate(value),
+
+            // Applied by the primitive instead; see `Block::evaluate_impl()`.
+            Modifier::State(_) => value,
         })
     }
 
@@ -158,6 +175,15 @@ impl Modifier {
             // TODO: Implement deletion of moving blocks.
             // This is essentially a 2-block multiblock situation.
             Modifier::Move(_) => ModifierUnspecialize::Keep,
+
+            // These produce blocks in their own right, not variants of their originals.
+            Modifier::Tint(_) => ModifierUnspecialize::Keep,
+            Modifier::Mirror(_) => ModifierUnspecialize::Keep,
+            Modifier::Scale(_) => ModifierUnspecialize::Keep,
+            Modifier::Inset(_) => ModifierUnspecialize::Keep,
+
+            // The state of a block is, like its rotation, specific to where it is placed.
+            Modifier::State(_) => ModifierUnspecialize::Pop,
         }
     }
 
@@ -178,6 +204,11 @@ impl Modifier {
             }) => source.listen_impl(listener.clone(), super::next_depth(depth)?)?,
             Modifier::Zoom(_) => {}
             Modifier::Move { .. } => {}
+            Modifier::Tint(_) => {}
+            Modifier::Mirror(_) => {}
+            Modifier::Scale(_) => {}
+            Modifier::Inset(_) => {}
+            Modifier::State(_) => {}
         }
         Ok(())
     }
@@ -191,10 +222,55 @@ impl VisitRefs for Modifier {
             Modifier::Composite(m) => m.visit_refs(visitor),
             Modifier::Zoom(m) => m.visit_refs(visitor),
             Modifier::Move(m) => m.visit_refs(visitor),
+            Modifier::Tint(m) => m.visit_refs(visitor),
+            Modifier::Mirror(_) => {}
+            Modifier::Scale(m) => m.visit_refs(visitor),
+            Modifier::Inset(m) => m.visit_refs(visitor),
+            Modifier::State(_) => {}
         }
     }
 }
 
+/// Implementation of [`Modifier::Rotate`] and [`Modifier::Mirror`].
+fn rotate_evaluation(rotation: GridRotation, value: MinEval) -> MinEval {
+    if matches!(value.voxels, Evoxels::One(_)) {
+        // Skip computation of transforms
+        value
+    } else {
+        // TODO: Add a shuffle-in-place rotation operation to GridArray and try implementing this using that, which should have less arithmetic involved than these matrix ops
+        let resolution = value.resolution();
+        let inner_to_outer = rotation.to_positive_octant_matrix(resolution.into());
+        let outer_to_inner = rotation
+            .inverse()
+            .to_positive_octant_matrix(resolution.into());
+
+        MinEval {
+            voxels: Evoxels::Many(
+                resolution,
+                GridArray::from_fn(
+                    value.voxels.bounds().transform(inner_to_outer).unwrap(),
+                    |cube| {
+                        value
+                            .voxels
+                            .get(outer_to_inner.transform_cube(cube))
+                            .unwrap()
+                    },
+                ),
+            ),
+            attributes: value.attributes,
+        }
+    }
+}
+
+/// Returns the [`GridRotation`] which reflects along `axis`, for [`Modifier::Mirror`].
+fn mirror_rotation(axis: Axis) -> GridRotation {
+    match axis {
+        Axis::X => GridRotation::RxYZ,
+        Axis::Y => GridRotation::RXyZ,
+        Axis::Z => GridRotation::RXYz,
+    }
+}
+
 /// Result of [`Modifier::unspecialize()`] returned to [`Block::unspecialize()`].
 #[derive(Debug)]
 pub(crate) enum ModifierUnspecialize {
@@ -210,7 +286,9 @@ pub(crate) enum ModifierUnspecialize {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::block::{BlockCollision, EvaluatedBlock, Evoxel, Primitive, Resolution::R2};
+    use crate::block::{
+        BlockCollision, EvaluatedBlock, Evoxel, Material, Primitive, Resolution::R2,
+    };
     use crate::content::make_some_voxel_blocks;
     use crate::math::{Face6, FaceMap, GridAab, GridPoint, OpacityCategory, Rgba};
     use crate::universe::Universe;
@@ -264,6 +342,7 @@ mod tests {
                             color: rotated_color_fn(cube),
                             selectable: true,
                             collision: BlockCollision::Hard,
+                            material: Material::DIFFUSE,
                         }
                     })
                 ),
@@ -1,11 +1,14 @@
 use std::mem;
 
+use cgmath::EuclideanSpace as _;
 use ordered_float::NotNan;
 
 use crate::block::{
-    self, Block, BlockCollision, Evoxel, Evoxels, MinEval, Modifier, Resolution::R1, AIR,
+    self, Block, BlockCollision, Evoxel, Evoxels, MinEval, Modifier,
+    Resolution::{self, R1, R2},
+    AIR,
 };
-use crate::math::{GridAab, GridArray, GridCoordinate, GridRotation, Rgba};
+use crate::math::{GridAab, GridArray, GridCoordinate, GridPoint, GridRotation, Rgba};
 use crate::universe;
 
 /// Data for [`Modifier::Composite`], describing how to combine the voxels of another
@@ -110,7 +113,7 @@ impl Composite {
     pub(super) fn evaluate(
         &self,
         mut dst_evaluated: MinEval,
-        depth: u8,
+        budget: &mut block::Budget,
     ) -> Result<MinEval, block::EvalBlockError> {
         let Composite {
             ref source,
@@ -121,7 +124,7 @@ impl Composite {
 
         // The destination block is already evaluated (it is the input to this
         // modifier), but we need to evaluate the source block.
-        let mut src_evaluated = source.evaluate_impl(block::next_depth(depth)?)?;
+        let mut src_evaluated = budget.recurse(|budget| source.evaluate_impl(budget))?;
         // Apply the reverse option by swapping everything.
         if reverse {
             mem::swap(&mut src_evaluated, &mut dst_evaluated);
@@ -138,7 +141,10 @@ impl Composite {
 
         let src_resolution = src_voxels.resolution();
         let dst_resolution = dst_voxels.resolution();
-        let effective_resolution = src_resolution.max(dst_resolution);
+        let mut effective_resolution = src_resolution.max(dst_resolution);
+        if operator == CompositeOperator::Bevel {
+            effective_resolution = effective_resolution.max(R2);
+        }
         let src_scale =
             GridCoordinate::from(effective_resolution) / GridCoordinate::from(src_resolution);
         let dst_scale =
@@ -150,6 +156,8 @@ impl Composite {
                 voxels: Evoxels::One(operator.blend_evoxel(
                     src_voxels.single_voxel().unwrap(),
                     dst_voxels.single_voxel().unwrap(),
+                    GridPoint::origin(),
+                    R1,
                 )),
             }
         } else {
@@ -163,6 +171,8 @@ impl Composite {
                         operator.blend_evoxel(
                             src_voxels.get(p / src_scale).unwrap_or(Evoxel::AIR),
                             dst_voxels.get(p / dst_scale).unwrap_or(Evoxel::AIR),
+                            p,
+                            effective_resolution,
                         )
                     }),
                 ),
@@ -214,39 +224,139 @@ pub enum CompositeOperator {
     /// Porter-Duff “over”. If both source and destination are opaque, the source is taken;
     /// otherwise the destination is taken.
     Over,
-    // /// Split the volume in half on the plane perpendicular to `[1, 0, 1]`; all voxels
-    // /// on the side nearer to the origin are taken from the destination, and all voxels
-    // /// on the farther side or exactly on the plane are taken from the source.
-    // Bevel,
+
+    /// Porter-Duff “in”. The source is taken, but only where the destination is present;
+    /// the result's opacity is the product of both inputs' opacities.
+    In,
+
+    /// Porter-Duff “out”. The source is taken, but only where the destination is absent;
+    /// the result's opacity is the source's opacity times the destination's transparency.
+    Out,
+
+    /// Porter-Duff “atop”. The source is drawn over the destination, but only where the
+    /// destination is present; the result has the destination's opacity and collision.
+    Atop,
+
+    /// Porter-Duff “xor”. Each input is taken only where the other is absent.
+    Xor,
+
+    /// Split the volume in half on the plane perpendicular to `[1, 0, 1]`; all voxels
+    /// on the side nearer to the origin are taken from the destination, and all voxels
+    /// on the farther side or exactly on the plane are taken from the source.
+    ///
+    /// The result always has a resolution of at least 2, because at resolution 1 the
+    /// only voxel is on the plane and the destination would not appear at all.
+    Bevel,
 }
 
 impl CompositeOperator {
     fn blend_color(&self, source: Rgba, destination: Rgba) -> Rgba {
         let source = source.clamp();
         let destination = destination.clamp();
+        // Note that this math would be simpler if we used premultiplied alpha.
+        let sa = source.alpha();
+        let da = destination.alpha();
+        let sa_complement = NotNan::new(1. - sa.into_inner()).unwrap();
+        let da_complement = NotNan::new(1. - da.into_inner()).unwrap();
         match self {
             Self::Over => {
                 // TODO: Surely this is not the only place we have implemented rgba blending?
-                // Note that this math would be simpler if we used premultiplied alpha.
-                let sa = source.alpha();
-                let sa_complement = NotNan::new(1. - sa.into_inner()).unwrap();
                 let rgb = source.to_rgb() * sa + destination.to_rgb() * sa_complement;
                 rgb.with_alpha(sa + sa_complement * destination.alpha())
             }
+            Self::In => source.to_rgb().with_alpha(sa * da),
+            Self::Out => source.to_rgb().with_alpha(sa * da_complement),
+            Self::Atop => {
+                let rgb = source.to_rgb() * sa + destination.to_rgb() * sa_complement;
+                rgb.with_alpha(da)
+            }
+            Self::Xor => {
+                let source_weight = sa * da_complement;
+                let destination_weight = da * sa_complement;
+                let alpha = source_weight + destination_weight;
+                if alpha.into_inner() == 0.0 {
+                    Rgba::TRANSPARENT
+                } else {
+                    let rgb = (source.to_rgb() * source_weight
+                        + destination.to_rgb() * destination_weight)
+                        * (1.0 / alpha.into_inner());
+                    rgb.with_alpha(alpha)
+                }
+            }
+            // Not actually used, since blend_evoxel() handles this case entirely.
+            Self::Bevel => source,
         }
     }
 
-    fn blend_evoxel(&self, src_ev: Evoxel, dst_ev: Evoxel) -> Evoxel {
+    /// Combines one voxel of the source and destination blocks.
+    ///
+    /// `position` and `resolution` locate the voxel within the block, for the sake of
+    /// operators which depend on position.
+    fn blend_evoxel(
+        &self,
+        src_ev: Evoxel,
+        dst_ev: Evoxel,
+        position: GridPoint,
+        resolution: Resolution,
+    ) -> Evoxel {
         use BlockCollision as Coll;
-        Evoxel {
-            color: self.blend_color(src_ev.color, dst_ev.color),
-            // TODO: specific operator should control all of these; we need an idea of what mask to
-            // apply to discrete attributes.
-            selectable: src_ev.selectable | dst_ev.selectable,
-            collision: match (src_ev.collision, dst_ev.collision) {
-                (Coll::Hard | Coll::Recur, _) | (_, Coll::Hard | Coll::Recur) => Coll::Hard,
-                (Coll::None, Coll::None) => Coll::None,
+        let src_present = !src_ev.color.fully_transparent();
+        let dst_present = !dst_ev.color.fully_transparent();
+        let color = self.blend_color(src_ev.color, dst_ev.color);
+
+        // For operators which take the non-color attributes of a whole voxel from one
+        // input or the other.
+        let from = |voxel: Evoxel| Evoxel { color, ..voxel };
+
+        match self {
+            Self::Over => Evoxel {
+                color,
+                // TODO: specific operator should control all of these; we need an idea of what mask to
+                // apply to discrete attributes.
+                selectable: src_ev.selectable | dst_ev.selectable,
+                collision: match (src_ev.collision, dst_ev.collision) {
+                    (Coll::Hard | Coll::Recur, _) | (_, Coll::Hard | Coll::Recur) => Coll::Hard,
+                    (Coll::None, Coll::None) => Coll::None,
+                },
+                // The surface seen is the source's, unless it is absent.
+                material: if src_present {
+                    src_ev.material
+                } else {
+                    dst_ev.material
+                },
             },
+            Self::In => from(if dst_present { src_ev } else { Evoxel::AIR }),
+            Self::Out => from(if dst_present { Evoxel::AIR } else { src_ev }),
+            Self::Atop => Evoxel {
+                color,
+                selectable: dst_ev.selectable,
+                collision: dst_ev.collision,
+                material: if src_present {
+                    src_ev.material
+                } else {
+                    dst_ev.material
+                },
+            },
+            Self::Xor => from(match (src_present, dst_present) {
+                (true, false) => src_ev,
+                (false, true) => dst_ev,
+                // Where both are partly present, the greater contribution wins.
+                (true, true) if !color.fully_transparent() => {
+                    if src_ev.color.clamp().alpha() >= dst_ev.color.clamp().alpha() {
+                        src_ev
+                    } else {
+                        dst_ev
+                    }
+                }
+                (true, true) | (false, false) => Evoxel::AIR,
+            }),
+            Self::Bevel => {
+                if position.x + position.z + 1 < GridCoordinate::from(resolution) {
+                    dst_ev
+                } else {
+                    src_ev
+                }
+            }
         }
     }
 }
@@ -0,0 +1,152 @@
+use crate::block::{Evoxel, Evoxels, MinEval, Modifier, Resolution};
+use crate::math::{FaceMap, GridAab, GridArray, GridCoordinate};
+use crate::universe;
+
+/// Data for [`Modifier::Inset`], describing the box within the block outside of which
+/// the block's voxels are removed.
+///
+/// Design note: This is a struct separate from [`Modifier`] so that it can have a
+/// constructor accepting only valid bounds.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Inset {
+    /// Resolution in which `bounds` is expressed.
+    resolution: Resolution,
+
+    /// Box, within [`GridAab::for_block`]`(resolution)`, of the voxels to keep.
+    bounds: GridAab,
+}
+
+impl Inset {
+    /// Construct an [`Inset`] which keeps only the voxels within `bounds`, where `bounds`
+    /// is measured in units of `1 / resolution` of the block.
+    ///
+    /// If the block's own resolution is lower than `resolution`, then its voxels are
+    /// subdivided so that the box can be applied exactly.
+    ///
+    /// Panics if `bounds` is not within [`GridAab::for_block`]`(resolution)`.
+    #[track_caller]
+    pub fn new(resolution: Resolution, bounds: GridAab) -> Self {
+        if !GridAab::for_block(resolution).contains_box(bounds) {
+            panic!("Inset bounds {bounds:?} out of bounds for {resolution}");
+        }
+
+        Self { resolution, bounds }
+    }
+
+    /// Construct an [`Inset`] which removes the given thickness, measured in units of
+    /// `1 / resolution` of the block, from each face of the block.
+    ///
+    /// For example, `Inset::from_padding(R2, FaceMap { py: 1, ..FaceMap::repeat(0) })`
+    /// makes a lower half-block slab.
+    ///
+    /// Panics if the padding on opposite faces adds up to more than `resolution`.
+    #[track_caller]
+    pub fn from_padding(resolution: Resolution, padding: FaceMap<u8>) -> Self {
+        let size = GridCoordinate::from(resolution);
+        let total = |a: u8, b: u8| GridCoordinate::from(a) + GridCoordinate::from(b);
+        if total(padding.nx, padding.px) > size
+            || total(padding.ny, padding.py) > size
+            || total(padding.nz, padding.pz) > size
+        {
+            panic!("Inset padding {padding:?} too large for {resolution}");
+        }
+
+        Self::new(
+            resolution,
+            GridAab::for_block(resolution).expand(padding.map(|_, p| -GridCoordinate::from(p))),
+        )
+    }
+
+    /// Returns the resolution in which [`Self::bounds()`] is expressed.
+    pub fn resolution(&self) -> Resolution {
+        self.resolution
+    }
+
+    /// Returns the box of voxels which are kept.
+    pub fn bounds(&self) -> GridAab {
+        self.bounds
+    }
+
+    /// Decompose into parts, for serialization.
+    pub(crate) fn to_serial_schema(&self) -> crate::save::schema::ModifierSer {
+        let Inset { resolution, bounds } = *self;
+        crate::save::schema::ModifierSer::InsetV1 { resolution, bounds }
+    }
+
+    pub(super) fn evaluate(&self, input: MinEval) -> MinEval {
+        let Inset {
+            resolution: inset_resolution,
+            bounds,
+        } = *self;
+
+        let original_resolution = input.resolution();
+        let MinEval { attributes, voxels } = input;
+
+        let resolution = original_resolution.max(inset_resolution);
+        let region = bounds.multiply(GridCoordinate::from(
+            (resolution / inset_resolution).unwrap(),
+        ));
+        let voxel_scale = GridCoordinate::from((resolution / original_resolution).unwrap());
+        let scaled_voxel_bounds = voxels.bounds().multiply(voxel_scale);
+
+        if region.contains_box(scaled_voxel_bounds) {
+            // Nothing is removed.
+            return MinEval { attributes, voxels };
+        }
+
+        MinEval {
+            attributes,
+            voxels: match region.intersection(scaled_voxel_bounds) {
+                // The voxels are entirely outside the kept region.
+                None => Evoxels::One(Evoxel::AIR),
+                Some(kept_bounds) => Evoxels::Many(
+                    resolution,
+                    GridArray::from_fn(kept_bounds, |p| {
+                        voxels[p.map(|c| c.div_euclid(voxel_scale))]
+                    }),
+                ),
+            },
+        }
+    }
+}
+
+impl From<Inset> for Modifier {
+    fn from(value: Inset) -> Self {
+        Modifier::Inset(value)
+    }
+}
+
+impl universe::VisitRefs for Inset {
+    fn visit_refs(&self, _visitor: &mut dyn universe::RefVisitor) {
+        let Inset {
+            resolution: _,
+            bounds: _,
+        } = self;
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Inset {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let resolution: Resolution = u.arbitrary()?;
+        let max = GridCoordinate::from(resolution);
+        let mut axis_range = || -> arbitrary::Result<(GridCoordinate, GridCoordinate)> {
+            let lower = u.int_in_range(0..=max)?;
+            let upper = u.int_in_range(lower..=max)?;
+            Ok((lower, upper))
+        };
+        let (x, y, z) = (axis_range()?, axis_range()?, axis_range()?);
+        Ok(Self::new(
+            resolution,
+            GridAab::from_lower_upper([x.0, y.0, z.0], [x.1, y.1, z.1]),
+        ))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        use arbitrary::{size_hint::and_all, Arbitrary};
+        and_all(&[
+            <Resolution as Arbitrary>::size_hint(depth),
+            <[GridCoordinate; 6] as Arbitrary>::size_hint(depth),
+        ])
+    }
+}
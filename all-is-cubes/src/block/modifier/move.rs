@@ -70,7 +70,7 @@ impl Move {
         block: &Block,
         this_modifier_index: usize,
         mut input: MinEval,
-        depth: u8,
+        budget: &mut block::Budget,
     ) -> Result<MinEval, block::EvalBlockError> {
         let Move {
             direction,
@@ -86,7 +86,7 @@ impl Move {
             block,
             this_modifier_index,
             input,
-            depth,
+            budget,
         )?;
 
         let (original_bounds, effective_resolution) = match input.voxels {
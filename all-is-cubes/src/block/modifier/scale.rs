@@ -0,0 +1,123 @@
+use cgmath::{EuclideanSpace as _, Point3};
+
+use crate::block::{
+    Evoxel, Evoxels, MinEval, Modifier,
+    Resolution::{self, R128},
+};
+use crate::math::{GridAab, GridArray, GridCoordinate, GridPoint};
+use crate::universe;
+
+/// Data for [`Modifier::Scale`], describing a smaller cube within the block that the
+/// whole original block is shrunk down to fit into.
+///
+/// This is the inverse of [`Zoom`](crate::block::Zoom): a block with a [`Zoom`] and then
+/// a [`Scale`] having the same parameters keeps only the zoomed-in region, in its
+/// original place.
+///
+/// Design note: This is a struct separate from [`Modifier`] so that it can have a
+/// constructor accepting only valid bounds.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Scale {
+    /// Scale factor to shrink by.
+    scale: Resolution,
+
+    /// Which of the `scale`³ smaller cubes the block is shrunk into (that is, this
+    /// should have coordinates between `0` and `scale - 1`).
+    offset: Point3<u8>,
+}
+
+impl Scale {
+    /// Construct a [`Scale`] which shrinks the original block by `scale` and places it
+    /// so that its lower corner is at `offset / scale` in the block.
+    ///
+    /// Panics if any of `offset`'s components are out of bounds, i.e. less than 0 or
+    /// greater than `scale - 1`.
+    #[track_caller]
+    pub fn new(scale: Resolution, offset: GridPoint) -> Self {
+        if !GridAab::for_block(scale).contains_cube(offset) {
+            panic!("Scale offset {offset:?} out of bounds for {scale}");
+        }
+
+        Self {
+            scale,
+            offset: offset.map(|c| c as u8),
+        }
+    }
+
+    /// Decompose into parts, for serialization.
+    pub(crate) fn to_serial_schema(&self) -> crate::save::schema::ModifierSer {
+        let Scale { scale, offset } = *self;
+        crate::save::schema::ModifierSer::ScaleV1 {
+            scale,
+            offset: offset.into(),
+        }
+    }
+
+    pub(super) fn evaluate(&self, input: MinEval) -> MinEval {
+        let Scale { scale, offset } = *self;
+
+        let original_resolution = input.resolution();
+        let MinEval { attributes, voxels } = input;
+
+        // If the resolution would be too high, then original voxels are skipped
+        // (`stride` is greater than 1) rather than failing.
+        let scaled_resolution = (original_resolution * scale).unwrap_or(R128);
+        let sub_size = GridCoordinate::from((scaled_resolution / scale).unwrap());
+        let stride = GridCoordinate::from(original_resolution) / sub_size;
+        let origin = offset.map(GridCoordinate::from) * sub_size;
+
+        MinEval {
+            attributes,
+            voxels: Evoxels::Many(
+                scaled_resolution,
+                GridArray::from_fn(
+                    GridAab::from_lower_size(origin, [sub_size, sub_size, sub_size]),
+                    |p| {
+                        voxels
+                            .get(GridPoint::from_vec((p - origin) * stride))
+                            .unwrap_or(Evoxel::AIR)
+                    },
+                ),
+            ),
+        }
+    }
+}
+
+impl From<Scale> for Modifier {
+    fn from(value: Scale) -> Self {
+        Modifier::Scale(value)
+    }
+}
+
+impl universe::VisitRefs for Scale {
+    fn visit_refs(&self, _visitor: &mut dyn universe::RefVisitor) {
+        let Scale {
+            scale: _,
+            offset: _,
+        } = self;
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Scale {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let scale = u.arbitrary()?;
+        let max_offset = GridCoordinate::from(scale) - 1;
+        Ok(Self::new(
+            scale,
+            GridPoint::new(
+                u.int_in_range(0..=max_offset)?,
+                u.int_in_range(0..=max_offset)?,
+                u.int_in_range(0..=max_offset)?,
+            ),
+        ))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        use arbitrary::{size_hint::and_all, Arbitrary};
+        and_all(&[
+            <Resolution as Arbitrary>::size_hint(depth),
+            <[GridCoordinate; 3] as Arbitrary>::size_hint(depth),
+        ])
+    }
+}
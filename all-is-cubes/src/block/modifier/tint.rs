@@ -0,0 +1,283 @@
+use std::sync::Arc;
+
+use cgmath::Vector4;
+
+use crate::block::{Evoxel, Evoxels, MinEval, Modifier};
+use crate::math::{NotNan, Rgb, Rgba};
+use crate::universe;
+
+/// Data for [`Modifier::Tint`], describing how to change the colors of a block.
+///
+/// Colors are modified in the linear (not sRGB) color space that [`Rgba`] uses.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Tint {
+    /// Multiply each voxel's color, including its alpha, by this color.
+    ///
+    /// The block's [`light_emission`](crate::block::BlockAttributes::light_emission) is also
+    /// multiplied by the RGB components.
+    Multiply(Rgba),
+
+    /// Change each voxel's color in terms of hue, saturation, and value (brightness).
+    ///
+    /// The block's [`light_emission`](crate::block::BlockAttributes::light_emission) is also
+    /// changed in the same way. Alpha is not changed.
+    Hsv {
+        /// Amount to rotate the hue by, in turns. 0.0, or any whole number of turns, is no change.
+        hue_shift: NotNan<f32>,
+        /// Factor to multiply saturation by. The result is limited to 1.
+        saturation: NotNan<f32>,
+        /// Factor to multiply value by.
+        value: NotNan<f32>,
+    },
+
+    /// Replace each voxel whose color exactly equals the first color of one of these
+    /// pairs with the second color of that pair. Other voxels are unchanged.
+    ///
+    /// This is intended for recoloring blocks drawn with a small set of colors.
+    /// The first matching pair is used.
+    Palette(Arc<[(Rgba, Rgba)]>),
+}
+
+impl Tint {
+    /// Constructs a [`Tint::Palette`] from a list of (original, replacement) color pairs.
+    pub fn palette(pairs: impl IntoIterator<Item = (Rgba, Rgba)>) -> Self {
+        Tint::Palette(pairs.into_iter().collect())
+    }
+
+    pub(super) fn evaluate(&self, input: MinEval) -> MinEval {
+        let MinEval {
+            mut attributes,
+            voxels,
+        } = input;
+
+        attributes.light_emission = self.map_emission(attributes.light_emission);
+        let map_voxel = |voxel: Evoxel| Evoxel {
+            color: self.map_color(voxel.color),
+            ..voxel
+        };
+
+        MinEval {
+            attributes,
+            voxels: match voxels {
+                Evoxels::One(voxel) => Evoxels::One(map_voxel(voxel)),
+                Evoxels::Many(resolution, voxels) => {
+                    Evoxels::Many(resolution, voxels.map(map_voxel))
+                }
+            },
+        }
+    }
+
+    fn map_color(&self, color: Rgba) -> Rgba {
+        match self {
+            Tint::Multiply(tint) => {
+                let color = Vector4::<f32>::from(color);
+                let tint = Vector4::<f32>::from(*tint);
+                Rgba::new(
+                    multiply(color.x, tint.x),
+                    multiply(color.y, tint.y),
+                    multiply(color.z, tint.z),
+                    multiply(color.w, tint.w),
+                )
+            }
+            &Tint::Hsv {
+                hue_shift,
+                saturation,
+                value,
+            } => color.map_rgb(|rgb| shift_hsv(rgb, hue_shift, saturation, value)),
+            Tint::Palette(pairs) => pairs
+                .iter()
+                .find(|&&(original, _)| original == color)
+                .map_or(color, |&(_, replacement)| replacement),
+        }
+    }
+
+    fn map_emission(&self, emission: Rgb) -> Rgb {
+        match self {
+            Tint::Multiply(tint) => {
+                let emission = <[f32; 3]>::from(emission);
+                let tint = <[f32; 3]>::from(tint.to_rgb());
+                Rgb::new(
+                    multiply(emission[0], tint[0]),
+                    multiply(emission[1], tint[1]),
+                    multiply(emission[2], tint[2]),
+                )
+            }
+            &Tint::Hsv {
+                hue_shift,
+                saturation,
+                value,
+            } => shift_hsv(emission, hue_shift, saturation, value),
+            Tint::Palette(_) => emission,
+        }
+    }
+}
+
+/// Multiplies color components, treating zero times infinity as zero rather than NaN.
+fn multiply(a: f32, b: f32) -> f32 {
+    if a == 0.0 || b == 0.0 {
+        0.0
+    } else {
+        a * b
+    }
+}
+
+fn shift_hsv(rgb: Rgb, hue_shift: NotNan<f32>, saturation: NotNan<f32>, value: NotNan<f32>) -> Rgb {
+    let [r, g, b] = <[f32; 3]>::from(rgb).map(|c| c.max(0.0));
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    if !max.is_finite() {
+        // Can't do meaningful arithmetic.
+        return rgb;
+    }
+
+    // Convert to HSV, with hue in turns.
+    let delta = max - min;
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / delta / 6.0).rem_euclid(1.0)
+    } else if max == g {
+        ((b - r) / delta + 2.0) / 6.0
+    } else {
+        ((r - g) / delta + 4.0) / 6.0
+    };
+    let sat = if max > 0.0 { delta / max } else { 0.0 };
+
+    // Modify.
+    let hue = (hue + hue_shift.into_inner()).rem_euclid(1.0);
+    let sat = (sat * saturation.into_inner()).clamp(0.0, 1.0);
+    let val = multiply(max, value.into_inner()).max(0.0);
+
+    // Convert back to RGB.
+    let sector = hue * 6.0;
+    let fraction = sector - sector.floor();
+    let p = val * (1.0 - sat);
+    let q = val * (1.0 - sat * fraction);
+    let t = val * (1.0 - sat * (1.0 - fraction));
+    let (r, g, b) = match sector as u8 {
+        0 => (val, t, p),
+        1 => (q, val, p),
+        2 => (p, val, t),
+        3 => (p, q, val),
+        4 => (t, p, val),
+        _ => (val, p, q),
+    };
+    Rgb::try_from(cgmath::Vector3::new(r, g, b)).unwrap_or(rgb)
+}
+
+impl From<Tint> for Modifier {
+    fn from(value: Tint) -> Self {
+        Modifier::Tint(value)
+    }
+}
+
+impl universe::VisitRefs for Tint {
+    fn visit_refs(&self, _visitor: &mut dyn universe::RefVisitor) {}
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Tint {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..=2)? {
+            0 => Tint::Multiply(u.arbitrary()?),
+            1 => Tint::Hsv {
+                hue_shift: u.arbitrary()?,
+                saturation: u.arbitrary()?,
+                value: u.arbitrary()?,
+            },
+            _ => Tint::palette(u.arbitrary_iter()?.collect::<Result<Vec<_>, _>>()?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{Block, Resolution::R2};
+    use crate::content::make_some_voxel_blocks;
+    use crate::math::GridArray;
+    use crate::universe::Universe;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn multiply_atom() {
+        let block = Block::builder()
+            .color(Rgba::new(1.0, 0.5, 0.5, 1.0))
+            .light_emission(Rgb::new(2.0, 2.0, 2.0))
+            .build()
+            .with_modifier(Tint::Multiply(Rgba::new(1.0, 0.0, 0.5, 0.5)));
+        let ev = block.evaluate().unwrap();
+        assert_eq!(ev.color, Rgba::new(1.0, 0.0, 0.25, 0.5));
+        assert_eq!(ev.attributes.light_emission, Rgb::new(2.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn multiply_voxels() {
+        let mut universe = Universe::new();
+        let [block] = make_some_voxel_blocks(&mut universe);
+        let tint = Rgba::new(0.5, 0.5, 0.5, 1.0);
+        let original = block.evaluate().unwrap();
+        let tinted = block
+            .with_modifier(Tint::Multiply(tint))
+            .evaluate()
+            .unwrap();
+
+        let Evoxels::Many(resolution, original_voxels) = original.voxels else {
+            panic!("expected voxels");
+        };
+        assert_eq!(
+            tinted.voxels,
+            Evoxels::Many(
+                resolution,
+                GridArray::from_fn(original_voxels.bounds(), |cube| {
+                    let voxel = original_voxels[cube];
+                    Evoxel {
+                        color: voxel.color.map_rgb(|rgb| rgb * notnan!(0.5)),
+                        ..voxel
+                    }
+                })
+            )
+        );
+    }
+
+    #[test]
+    fn hsv_hue_shift() {
+        let red = Rgb::new(1.0, 0.0, 0.0);
+        let shift =
+            |turns: f32| shift_hsv(red, NotNan::new(turns).unwrap(), notnan!(1.0), notnan!(1.0));
+        assert_eq!(shift(0.0), red);
+        assert_eq!(shift(0.25), Rgb::new(0.5, 1.0, 0.0));
+        assert_eq!(shift(0.5), Rgb::new(0.0, 1.0, 1.0));
+        assert_eq!(shift(-0.5), Rgb::new(0.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn hsv_desaturate() {
+        let color = Rgb::new(1.0, 0.5, 0.0);
+        assert_eq!(
+            shift_hsv(color, notnan!(0.0), notnan!(0.0), notnan!(0.5)),
+            Rgb::new(0.5, 0.5, 0.5)
+        );
+    }
+
+    #[test]
+    fn palette() {
+        let [a, b, c] = [
+            Rgba::new(1.0, 0.0, 0.0, 1.0),
+            Rgba::new(0.0, 1.0, 0.0, 1.0),
+            Rgba::new(0.0, 0.0, 1.0, 1.0),
+        ];
+        let mut universe = Universe::new();
+        let block = Block::builder()
+            .voxels_fn(&mut universe, R2, |cube| {
+                Block::from(if cube.x == 0 { a } else { c })
+            })
+            .unwrap()
+            .build()
+            .with_modifier(Tint::palette([(a, b), (a, c)]));
+        let ev = block.evaluate().unwrap();
+        assert_eq!(ev.voxels[[0, 0, 0].into()].color, b);
+        assert_eq!(ev.voxels[[1, 0, 0].into()].color, c);
+    }
+}
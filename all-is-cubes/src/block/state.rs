@@ -0,0 +1,490 @@
+//! Block state properties, which allow one [`BlockDef`] to define several related
+//! variants of a block, such as a door which may be open or closed.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::block::{Block, Budget, EvalBlockError, MinEval, Modifier};
+use crate::universe::{RefVisitor, VisitRefs};
+#[cfg(doc)]
+use crate::{block::BlockDef, block::Primitive, space::SpaceTransaction};
+
+/// Declaration of a property which blocks defined by a [`BlockDef`] have.
+///
+/// The value of the property for a particular block is specified by
+/// [`Modifier::State`], and if not specified there, is the property's default value.
+///
+/// Construct properties using [`Property::bool()`], [`Property::int()`], or
+/// [`Property::enumeration()`], and declare them using [`BlockProperties`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Property {
+    name: Cow<'static, str>,
+    kind: PropertyKind,
+    default: PropertyValue,
+}
+
+/// The set of values a [`Property`] may have.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum PropertyKind {
+    /// The property is either true or false.
+    Bool,
+    /// The property is an integer from zero to `max`, inclusive.
+    Int {
+        /// Largest allowed value.
+        max: u8,
+    },
+    /// The property is one of the given names. There is always at least one name.
+    Enum(Arc<[Cow<'static, str>]>),
+}
+
+/// A value of a [`Property`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::exhaustive_enums)]
+pub enum PropertyValue {
+    /// Value of a [`PropertyKind::Bool`] property.
+    Bool(bool),
+    /// Value of a [`PropertyKind::Int`] property.
+    Int(u8),
+    /// Value of a [`PropertyKind::Enum`] property.
+    Enum(Cow<'static, str>),
+}
+
+impl Property {
+    /// Declares a boolean property.
+    pub fn bool(name: impl Into<Cow<'static, str>>, default: bool) -> Self {
+        Self {
+            name: name.into(),
+            kind: PropertyKind::Bool,
+            default: PropertyValue::Bool(default),
+        }
+    }
+
+    /// Declares an integer property whose values range from 0 to `max`, inclusive.
+    ///
+    /// Panics if `default` is greater than `max`.
+    #[track_caller]
+    pub fn int(name: impl Into<Cow<'static, str>>, max: u8, default: u8) -> Self {
+        let name = name.into();
+        assert!(
+            default <= max,
+            "default {default} of property {name:?} exceeds max {max}"
+        );
+        Self {
+            name,
+            kind: PropertyKind::Int { max },
+            default: PropertyValue::Int(default),
+        }
+    }
+
+    /// Declares a property whose values are the given names. The first name is the
+    /// default value.
+    ///
+    /// Panics if `values` is empty or contains duplicates.
+    #[track_caller]
+    pub fn enumeration<V>(name: impl Into<Cow<'static, str>>, values: V) -> Self
+    where
+        V: IntoIterator,
+        V::Item: Into<Cow<'static, str>>,
+    {
+        let name = name.into();
+        let values: Arc<[Cow<'static, str>]> = values.into_iter().map(Into::into).collect();
+        let Some(first) = values.first().cloned() else {
+            panic!("property {name:?} must have at least one value");
+        };
+        for (i, value) in values.iter().enumerate() {
+            assert!(
+                !values[..i].contains(value),
+                "property {name:?} has duplicate value {value:?}"
+            );
+        }
+        Self {
+            name,
+            kind: PropertyKind::Enum(values),
+            default: PropertyValue::Enum(first),
+        }
+    }
+
+    /// Returns the name of this property.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the set of values this property may have.
+    pub fn kind(&self) -> &PropertyKind {
+        &self.kind
+    }
+
+    /// Returns the value this property has when it is not otherwise specified.
+    pub fn default_value(&self) -> &PropertyValue {
+        &self.default
+    }
+}
+
+impl PropertyKind {
+    /// Returns whether `value` is one of the values of this kind.
+    pub fn allows(&self, value: &PropertyValue) -> bool {
+        match (self, value) {
+            (PropertyKind::Bool, PropertyValue::Bool(_)) => true,
+            (&PropertyKind::Int { max }, &PropertyValue::Int(value)) => value <= max,
+            (PropertyKind::Enum(values), PropertyValue::Enum(value)) => values.contains(value),
+            _ => false,
+        }
+    }
+
+    /// Returns the value following `value`, wrapping around from the last value to the
+    /// first. For a boolean property, this toggles the value.
+    ///
+    /// If `value` is not [allowed](Self::allows), returns the first value.
+    pub fn next(&self, value: &PropertyValue) -> PropertyValue {
+        match (self, value) {
+            (PropertyKind::Bool, &PropertyValue::Bool(value)) => PropertyValue::Bool(!value),
+            (PropertyKind::Bool, _) => PropertyValue::Bool(false),
+            (&PropertyKind::Int { max }, &PropertyValue::Int(value)) if value < max => {
+                PropertyValue::Int(value + 1)
+            }
+            (PropertyKind::Int { .. }, _) => PropertyValue::Int(0),
+            (PropertyKind::Enum(values), value) => {
+                let index = match value {
+                    PropertyValue::Enum(value) => values.iter().position(|v| v == value),
+                    _ => None,
+                };
+                let next = index.map_or(0, |i| (i + 1) % values.len());
+                PropertyValue::Enum(values[next].clone())
+            }
+        }
+    }
+}
+
+impl From<bool> for PropertyValue {
+    fn from(value: bool) -> Self {
+        PropertyValue::Bool(value)
+    }
+}
+
+impl From<u8> for PropertyValue {
+    fn from(value: u8) -> Self {
+        PropertyValue::Int(value)
+    }
+}
+
+impl From<&'static str> for PropertyValue {
+    fn from(value: &'static str) -> Self {
+        PropertyValue::Enum(Cow::Borrowed(value))
+    }
+}
+
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyValue::Bool(value) => write!(f, "{value}"),
+            PropertyValue::Int(value) => write!(f, "{value}"),
+            PropertyValue::Enum(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// The [`Property`]s declared by a [`BlockDef`], and the variants of its block which
+/// they select.
+///
+/// Each variant is a list of [`Modifier`]s which is applied to the definition's block
+/// when one property has one particular value. All variants whose conditions are met
+/// are applied, in the order they were added.
+///
+/// ```
+/// use all_is_cubes::block::{Block, BlockDef, BlockProperties, Modifier, Primitive, Property};
+/// use all_is_cubes::math::{GridRotation, Rgba};
+/// use all_is_cubes::universe::Universe;
+///
+/// let mut universe = Universe::new();
+/// let door = BlockDef::new(Block::from(Rgba::WHITE)).with_properties(
+///     BlockProperties::new()
+///         .property(Property::bool("open", false))
+///         .variant("open", true, [Modifier::Rotate(GridRotation::CLOCKWISE)]),
+/// );
+/// let door = Block::from(Primitive::Indirect(universe.insert_anonymous(door)));
+///
+/// assert_eq!(door.property("open").unwrap(), Some(false.into()));
+/// let open_door = door.with_property("open", true);
+/// assert_eq!(open_door.property("open").unwrap(), Some(true.into()));
+/// ```
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct BlockProperties {
+    properties: Vec<Property>,
+    variants: Vec<StateVariant>,
+}
+
+/// A variant of a block, as part of [`BlockProperties`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct StateVariant {
+    pub(crate) property: Cow<'static, str>,
+    pub(crate) value: PropertyValue,
+    pub(crate) modifiers: Vec<Modifier>,
+}
+
+impl BlockProperties {
+    /// Constructs a [`BlockProperties`] with no properties.
+    pub const fn new() -> Self {
+        Self {
+            properties: Vec::new(),
+            variants: Vec::new(),
+        }
+    }
+
+    /// Declares a property.
+    ///
+    /// Panics if a property with the same name is already declared.
+    #[must_use]
+    #[track_caller]
+    pub fn property(mut self, property: Property) -> Self {
+        assert!(
+            self.get(property.name()).is_none(),
+            "property {:?} is already declared",
+            property.name()
+        );
+        self.properties.push(property);
+        self
+    }
+
+    /// Adds a variant: when the named property has the given value, the given modifiers
+    /// are applied to the block.
+    ///
+    /// Panics if the property has not been declared, or does not allow the value.
+    #[must_use]
+    #[track_caller]
+    pub fn variant(
+        mut self,
+        property: impl Into<Cow<'static, str>>,
+        value: impl Into<PropertyValue>,
+        modifiers: impl IntoIterator<Item = Modifier>,
+    ) -> Self {
+        let property = property.into();
+        let value = value.into();
+        match self.get(&property) {
+            Some(declaration) => assert!(
+                declaration.kind().allows(&value),
+                "property {property:?} does not allow the value {value:?}"
+            ),
+            None => panic!("property {property:?} is not declared"),
+        }
+        self.variants.push(StateVariant {
+            property,
+            value,
+            modifiers: modifiers.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Returns the declaration of the property with the given name, if there is one.
+    pub fn get(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name() == name)
+    }
+
+    /// Returns all the declared properties.
+    pub fn iter(&self) -> impl Iterator<Item = &Property> + '_ {
+        self.properties.iter()
+    }
+
+    /// Returns whether no properties are declared.
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+
+    /// Returns the value the named property has in the given state: the value given by
+    /// `state` if it is allowed, and otherwise the default value.
+    ///
+    /// Returns [`None`] if no such property is declared.
+    pub fn value(&self, state: &BlockState, name: &str) -> Option<PropertyValue> {
+        let declaration = self.get(name)?;
+        Some(match state.get(name) {
+            Some(value) if declaration.kind().allows(value) => value.clone(),
+            _ => declaration.default_value().clone(),
+        })
+    }
+
+    pub(crate) fn variants(&self) -> &[StateVariant] {
+        &self.variants
+    }
+
+    /// Applies the variants selected by `state` to `value`, which is the evaluation of
+    /// `block`, the block these properties belong to.
+    ///
+    /// The selected variants' modifiers are evaluated as if they were appended to
+    /// `block`'s own modifiers, so that modifiers which refer to their position in the
+    /// block (such as [`Modifier::Move`]) find themselves there.
+    pub(crate) fn evaluate(
+        &self,
+        state: &BlockState,
+        block: &Block,
+        mut value: MinEval,
+        budget: &mut Budget,
+    ) -> Result<MinEval, EvalBlockError> {
+        let mut selected = self
+            .variants
+            .iter()
+            .filter(|variant| self.value(state, &variant.property).as_ref() == Some(&variant.value))
+            .flat_map(|variant| variant.modifiers.iter().cloned())
+            .peekable();
+        if selected.peek().is_none() {
+            return Ok(value);
+        }
+        let mut combined = block.clone();
+        combined.modifiers_mut().extend(selected);
+
+        for (index, modifier) in combined
+            .modifiers()
+            .iter()
+            .enumerate()
+            .skip(block.modifiers().len())
+        {
+            value = modifier.evaluate(&combined, index, value, budget)?;
+            budget.spend_component(value.voxels.bounds().volume())?;
+        }
+        Ok(value)
+    }
+}
+
+impl VisitRefs for BlockProperties {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        for variant in &self.variants {
+            for modifier in &variant.modifiers {
+                modifier.visit_refs(visitor);
+            }
+        }
+    }
+}
+
+/// Values of [`Property`]s for a particular block; the data of [`Modifier::State`].
+///
+/// Names which are not declared by the block's [`BlockDef`], and values which the
+/// declared property does not allow, are ignored.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct BlockState {
+    /// Sorted by name, with no duplicate names.
+    values: Vec<(Cow<'static, str>, PropertyValue)>,
+}
+
+impl BlockState {
+    /// A [`BlockState`] which does not specify any values.
+    pub const EMPTY: Self = Self { values: Vec::new() };
+
+    /// Returns the value specified for the named property, if any.
+    pub fn get(&self, name: &str) -> Option<&PropertyValue> {
+        self.find(name).ok().map(|index| &self.values[index].1)
+    }
+
+    /// Specifies the value of the named property, replacing any previous value.
+    pub fn set(&mut self, name: impl Into<Cow<'static, str>>, value: impl Into<PropertyValue>) {
+        let name = name.into();
+        let value = value.into();
+        match self.find(&name) {
+            Ok(index) => self.values[index].1 = value,
+            Err(index) => self.values.insert(index, (name, value)),
+        }
+    }
+
+    /// As [`Self::set()`], but taking and returning `self`.
+    #[must_use]
+    pub fn with(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<PropertyValue>,
+    ) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// Returns all specified values, in order by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &PropertyValue)> + '_ {
+        self.values.iter().map(|(name, value)| (&**name, value))
+    }
+
+    /// Returns whether no values are specified.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn find(&self, name: &str) -> Result<usize, usize> {
+        self.values.binary_search_by(|(n, _)| (**n).cmp(name))
+    }
+}
+
+impl From<BlockState> for Modifier {
+    fn from(value: BlockState) -> Self {
+        Modifier::State(value)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for PropertyValue {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.int_in_range(0..=2)? {
+            0 => PropertyValue::Bool(u.arbitrary()?),
+            1 => PropertyValue::Int(u.arbitrary()?),
+            _ => PropertyValue::Enum(Cow::Owned(u.arbitrary()?)),
+        })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for BlockState {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut state = BlockState::default();
+        for entry in u.arbitrary_iter::<(String, PropertyValue)>()? {
+            let (name, value) = entry?;
+            state.set(name, value);
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn next_values() {
+        assert_eq!(PropertyKind::Bool.next(&true.into()), false.into());
+        assert_eq!(PropertyKind::Bool.next(&false.into()), true.into());
+
+        let int = PropertyKind::Int { max: 2 };
+        assert_eq!(int.next(&1.into()), 2.into());
+        assert_eq!(int.next(&2.into()), 0.into());
+
+        let Property {
+            kind: enumeration, ..
+        } = Property::enumeration("e", ["a", "b"]);
+        assert_eq!(enumeration.next(&"a".into()), "b".into());
+        assert_eq!(enumeration.next(&"b".into()), "a".into());
+        // Not allowed values restart
+        assert_eq!(enumeration.next(&"z".into()), "a".into());
+        assert_eq!(enumeration.next(&true.into()), "a".into());
+    }
+
+    #[test]
+    fn allows() {
+        assert!(PropertyKind::Bool.allows(&true.into()));
+        assert!(!PropertyKind::Bool.allows(&0.into()));
+        assert!(PropertyKind::Int { max: 3 }.allows(&3.into()));
+        assert!(!PropertyKind::Int { max: 3 }.allows(&4.into()));
+    }
+
+    #[test]
+    fn value_uses_default_when_unspecified_or_invalid() {
+        let properties = BlockProperties::new()
+            .property(Property::int("level", 3, 1))
+            .property(Property::bool("lit", true));
+        let state = BlockState::default().with("level", 7u8).with("lit", false);
+        assert_eq!(properties.value(&state, "level"), Some(1.into()));
+        assert_eq!(properties.value(&state, "lit"), Some(false.into()));
+        assert_eq!(properties.value(&state, "missing"), None);
+    }
+
+    #[test]
+    #[should_panic = "property \"open\" is not declared"]
+    fn variant_of_undeclared_property() {
+        let _ = BlockProperties::new().variant("open", true, []);
+    }
+}
@@ -13,13 +13,16 @@ use cgmath::EuclideanSpace as _;
 use pretty_assertions::assert_eq;
 
 use crate::block::{
-    Block, BlockAttributes, BlockCollision, BlockDef, BlockDefTransaction, EvalBlockError, Evoxel,
-    Evoxels, Modifier, Primitive, Resolution, Resolution::*, AIR, AIR_EVALUATED,
+    Block, BlockAttributes, BlockCollision, BlockDef, BlockDefTransaction, BlockProperties,
+    BlockState, Composite, CompositeOperator, Cost, EvalBlockError, EvalCacheStats, Evoxel,
+    Evoxels, Inset, Material, Modifier, Move, Primitive, Property, Resolution, Resolution::*,
+    Scale, AIR, AIR_EVALUATED,
 };
 use crate::content::make_some_blocks;
+use crate::drawing::VoxelBrush;
 use crate::listen::{NullListener, Sink};
 use crate::math::{
-    Face6, FaceMap, GridAab, GridArray, GridCoordinate, GridPoint, GridRotation, GridVector,
+    Axis, Face6, FaceMap, GridAab, GridArray, GridCoordinate, GridPoint, GridRotation, GridVector,
     OpacityCategory, Rgb, Rgba,
 };
 use crate::space::{Space, SpaceTransaction};
@@ -179,6 +182,7 @@ fn evaluate_voxels_checked_individually() {
                     color: Rgba::new(point.x, point.y, point.z, 1.0),
                     selectable: true,
                     collision: BlockCollision::Hard,
+                    material: Material::DIFFUSE,
                 }
             })
         )
@@ -320,6 +324,7 @@ fn recur_with_offset() {
                     color: Rgba::new(point.x, point.y, point.z, 1.0),
                     selectable: true,
                     collision: BlockCollision::Hard,
+                    material: Material::DIFFUSE,
                 }
             })
         )
@@ -369,6 +374,365 @@ fn indirect_equivalence() {
     assert_eq!(eval_bare, eval_def);
 }
 
+/// Helper for modifier tests: a block whose voxels all have distinct colors.
+fn distinct_voxels_block(universe: &mut Universe, resolution: Resolution) -> Block {
+    Block::builder()
+        .voxels_fn(universe, resolution, |point| {
+            let point = point.cast::<f32>().unwrap();
+            Block::from(Rgba::new(point.x, point.y, point.z, 1.0))
+        })
+        .unwrap()
+        .build()
+}
+
+#[test]
+fn mirror_evaluation() {
+    let mut universe = Universe::new();
+    let block = distinct_voxels_block(&mut universe, R2);
+    let original = block.evaluate().unwrap();
+    let mirrored = block
+        .with_modifier(Modifier::Mirror(Axis::X))
+        .evaluate()
+        .unwrap();
+
+    assert_eq!(
+        mirrored.voxels,
+        Evoxels::Many(
+            R2,
+            GridArray::from_fn(GridAab::for_block(R2), |p| {
+                original.voxels[GridPoint::new(1 - p.x, p.y, p.z)]
+            })
+        )
+    );
+}
+
+#[test]
+fn scale_atom_evaluation() {
+    let [block] = make_some_blocks();
+    let voxel = Evoxel::from_block(&block.evaluate().unwrap());
+    let scaled = block
+        .with_modifier(Scale::new(R2, GridPoint::new(1, 0, 0)))
+        .evaluate()
+        .unwrap();
+
+    assert_eq!(
+        scaled.voxels,
+        Evoxels::Many(
+            R2,
+            GridArray::repeat(GridAab::from_lower_size([1, 0, 0], [1, 1, 1]), voxel)
+        )
+    );
+}
+
+#[test]
+fn scale_voxels_evaluation() {
+    let mut universe = Universe::new();
+    let block = distinct_voxels_block(&mut universe, R2);
+    let original = block.evaluate().unwrap();
+    let scaled = block
+        .with_modifier(Scale::new(R2, GridPoint::new(0, 1, 0)))
+        .evaluate()
+        .unwrap();
+
+    assert_eq!(
+        scaled.voxels,
+        Evoxels::Many(
+            R4,
+            GridArray::from_fn(GridAab::from_lower_size([0, 2, 0], [2, 2, 2]), |p| {
+                original.voxels[p - GridVector::new(0, 2, 0)]
+            })
+        )
+    );
+}
+
+#[test]
+fn scale_beyond_max_resolution() {
+    let mut universe = Universe::new();
+    let block = Block::builder()
+        .voxels_fn(&mut universe, R128, |point| {
+            Block::from(Rgba::new(point.x.rem_euclid(4) as f32, 0.0, 0.0, 1.0))
+        })
+        .unwrap()
+        .build();
+    let original = block.evaluate().unwrap();
+    let scaled = block
+        .with_modifier(Scale::new(R2, GridPoint::new(0, 0, 0)))
+        .evaluate()
+        .unwrap();
+
+    // Every other voxel is kept.
+    assert_eq!(scaled.resolution(), R128);
+    assert_eq!(scaled.voxels.bounds(), GridAab::for_block(R64));
+    assert_eq!(
+        scaled.voxels[GridPoint::new(3, 4, 5)],
+        original.voxels[GridPoint::new(6, 8, 10)]
+    );
+}
+
+#[test]
+fn inset_slab_evaluation() {
+    let [block] = make_some_blocks();
+    let voxel = Evoxel::from_block(&block.evaluate().unwrap());
+    let slab = block
+        .with_modifier(Inset::from_padding(
+            R2,
+            FaceMap::repeat(0).with(Face6::PY, 1),
+        ))
+        .evaluate()
+        .unwrap();
+
+    assert_eq!(
+        slab.voxels,
+        Evoxels::Many(
+            R2,
+            GridArray::repeat(GridAab::from_lower_size([0, 0, 0], [2, 1, 2]), voxel)
+        )
+    );
+    assert_eq!(slab.opaque, FaceMap::repeat(false).with(Face6::NY, true));
+}
+
+#[test]
+fn inset_voxels_evaluation() {
+    let mut universe = Universe::new();
+    let block = distinct_voxels_block(&mut universe, R4);
+    let original = block.evaluate().unwrap();
+    let bounds = GridAab::from_lower_upper([1, 1, 1], [3, 3, 3]);
+    let inset = block
+        .with_modifier(Inset::new(R4, bounds))
+        .evaluate()
+        .unwrap();
+
+    assert_eq!(
+        inset.voxels,
+        Evoxels::Many(R4, GridArray::from_fn(bounds, |p| original.voxels[p]))
+    );
+}
+
+#[test]
+fn inset_whole_block_is_unchanged() {
+    let [block] = make_some_blocks();
+    assert_eq!(
+        block
+            .clone()
+            .with_modifier(Inset::new(R4, GridAab::for_block(R4)))
+            .evaluate(),
+        block.evaluate()
+    );
+}
+
+/// Evaluates `destination` composited with `source` using `operator`, at resolution 1.
+fn composite_atoms(source: Rgba, destination: Rgba, operator: CompositeOperator) -> Evoxel {
+    Block::from(destination)
+        .with_modifier(Composite::new(Block::from(source), operator))
+        .evaluate()
+        .unwrap()
+        .voxels
+        .single_voxel()
+        .unwrap()
+}
+
+#[test]
+fn composite_operator_colors() {
+    let red = Rgba::new(1.0, 0.0, 0.0, 0.5);
+    let green = Rgba::new(0.0, 1.0, 0.0, 0.5);
+    let color = |operator| composite_atoms(red, green, operator).color;
+
+    assert_eq!(
+        color(CompositeOperator::Over),
+        Rgba::new(0.5, 0.5, 0.0, 0.75)
+    );
+    assert_eq!(color(CompositeOperator::In), Rgba::new(1.0, 0.0, 0.0, 0.25));
+    assert_eq!(
+        color(CompositeOperator::Out),
+        Rgba::new(1.0, 0.0, 0.0, 0.25)
+    );
+    assert_eq!(
+        color(CompositeOperator::Atop),
+        Rgba::new(0.5, 0.5, 0.0, 0.5)
+    );
+    assert_eq!(color(CompositeOperator::Xor), Rgba::new(0.5, 0.5, 0.0, 0.5));
+}
+
+#[test]
+fn composite_operator_presence() {
+    let opaque = Rgba::new(1.0, 0.0, 0.0, 1.0);
+    let solid = composite_atoms(opaque, opaque, CompositeOperator::Over);
+    assert_eq!(solid.collision, BlockCollision::Hard);
+    let assert_absent = |voxel: Evoxel| {
+        assert!(voxel.color.fully_transparent(), "{voxel:?}");
+        assert_eq!(
+            (voxel.selectable, voxel.collision),
+            (false, BlockCollision::None)
+        );
+    };
+
+    // Source present only where destination is present
+    assert_absent(composite_atoms(
+        opaque,
+        Rgba::TRANSPARENT,
+        CompositeOperator::In,
+    ));
+    assert_eq!(
+        composite_atoms(opaque, opaque, CompositeOperator::In),
+        solid
+    );
+
+    // Source present only where destination is absent
+    assert_absent(composite_atoms(opaque, opaque, CompositeOperator::Out));
+    assert_eq!(
+        composite_atoms(opaque, Rgba::TRANSPARENT, CompositeOperator::Out),
+        solid
+    );
+
+    // Xor of two present voxels is absent
+    assert_absent(composite_atoms(opaque, opaque, CompositeOperator::Xor));
+    assert_eq!(
+        composite_atoms(Rgba::TRANSPARENT, opaque, CompositeOperator::Xor),
+        solid
+    );
+}
+
+#[test]
+fn composite_bevel() {
+    let mut universe = Universe::new();
+    let [destination] = make_some_blocks();
+    let source = distinct_voxels_block(&mut universe, R2);
+    let source_ev = source.evaluate().unwrap();
+    let destination_voxel = Evoxel::from_block(&destination.evaluate().unwrap());
+
+    let beveled = destination
+        .with_modifier(Composite::new(source, CompositeOperator::Bevel))
+        .evaluate()
+        .unwrap();
+
+    assert_eq!(
+        beveled.voxels,
+        Evoxels::Many(
+            R2,
+            GridArray::from_fn(GridAab::for_block(R2), |p| {
+                if p.x == 0 && p.z == 0 {
+                    destination_voxel
+                } else {
+                    source_ev.voxels[p]
+                }
+            })
+        )
+    );
+}
+
+/// Bevel needs more than one voxel to have any effect, so it increases the resolution.
+#[test]
+fn composite_bevel_atoms() {
+    let [source, destination] = make_some_blocks();
+    let source_voxel = Evoxel::from_block(&source.evaluate().unwrap());
+    let destination_voxel = Evoxel::from_block(&destination.evaluate().unwrap());
+
+    let beveled = destination
+        .with_modifier(Composite::new(source, CompositeOperator::Bevel))
+        .evaluate()
+        .unwrap();
+
+    assert_eq!(
+        beveled.voxels,
+        Evoxels::Many(
+            R2,
+            GridArray::from_fn(GridAab::for_block(R2), |p| {
+                if p.x == 0 && p.z == 0 {
+                    destination_voxel
+                } else {
+                    source_voxel
+                }
+            })
+        )
+    );
+}
+
+/// A block with state properties, each of whose values has a visible effect.
+/// Returns the base block and the stateful block.
+fn stateful_block(universe: &mut Universe) -> (Block, Block) {
+    let base = distinct_voxels_block(universe, R2);
+    let def = BlockDef::new(base.clone()).with_properties(
+        BlockProperties::new()
+            .property(Property::bool("open", false))
+            .property(Property::enumeration("facing", ["north", "south"]))
+            .variant("open", true, [Modifier::Mirror(Axis::X)])
+            .variant("facing", "north", [Modifier::Mirror(Axis::Z)]),
+    );
+    let block = Block::from(Primitive::Indirect(universe.insert_anonymous(def)));
+    (base, block)
+}
+
+#[test]
+fn state_default_variant_applies() {
+    let mut universe = Universe::new();
+    let (base, block) = stateful_block(&mut universe);
+    assert_eq!(block.property("open"), Ok(Some(false.into())));
+    assert_eq!(block.property("facing"), Ok(Some("north".into())));
+    assert_eq!(
+        block.evaluate().unwrap().voxels,
+        base.with_modifier(Modifier::Mirror(Axis::Z))
+            .evaluate()
+            .unwrap()
+            .voxels
+    );
+}
+
+#[test]
+fn state_selects_variants() {
+    let mut universe = Universe::new();
+    let (base, block) = stateful_block(&mut universe);
+    let block = block
+        .with_property("open", true)
+        .with_property("facing", "south");
+    assert_eq!(
+        block.state(),
+        &BlockState::default()
+            .with("open", true)
+            .with("facing", "south")
+    );
+    assert_eq!(block.modifiers().len(), 1);
+    assert_eq!(
+        block.evaluate().unwrap().voxels,
+        base.with_modifier(Modifier::Mirror(Axis::X))
+            .evaluate()
+            .unwrap()
+            .voxels
+    );
+}
+
+/// Modifiers in variants which refer to their own position, like [`Modifier::Move`],
+/// are evaluated as if they followed the block's own modifiers.
+#[test]
+fn state_variant_modifier_index() {
+    let mut universe = Universe::new();
+    let base = distinct_voxels_block(&mut universe, R2).with_modifier(Modifier::Mirror(Axis::Y));
+    let def = BlockDef::new(base.clone()).with_properties(
+        BlockProperties::new()
+            .property(Property::bool("moved", false))
+            .variant("moved", true, [Modifier::Move(Move::new(Face6::PX, 0, 0))]),
+    );
+    let block = Block::from(Primitive::Indirect(universe.insert_anonymous(def)))
+        .with_property("moved", true);
+
+    // A stationary, zero-distance move removes itself, leaving the block it was on.
+    let ev = block.evaluate().unwrap();
+    assert_eq!(ev.attributes.tick_action, Some(VoxelBrush::single(base)));
+}
+
+#[test]
+fn state_ignores_undeclared_and_invalid_values() {
+    let mut universe = Universe::new();
+    let (_, block) = stateful_block(&mut universe);
+    let expected = block.evaluate().unwrap();
+    let block = block
+        .with_property("open", 1u8)
+        .with_property("facing", "west")
+        .with_property("color", "red");
+    assert_eq!(block.property("open"), Ok(Some(false.into())));
+    assert_eq!(block.property("color"), Ok(None));
+    assert_eq!(block.evaluate().unwrap().voxels, expected.voxels);
+}
+
 #[test]
 fn listen_atom() {
     let block = Block::from(Rgba::WHITE);
@@ -470,6 +834,61 @@ fn listen_recur() {
     assert_eq!(sink.drain(), vec![]);
 }
 
+/// Evaluates `block` and returns the [`BlockDef`] cache usage that resulted.
+fn eval_cache_usage(block: &Block) -> EvalCacheStats {
+    let before = EvalCacheStats::current_thread();
+    block.evaluate().unwrap();
+    EvalCacheStats::current_thread() - before
+}
+
+#[test]
+fn indirect_evaluation_is_cached() {
+    let mut universe = Universe::new();
+    let block_def_ref = universe.insert_anonymous(BlockDef::new(Block::from(Rgba::WHITE)));
+    let indirect = Block::from_primitive(Primitive::Indirect(block_def_ref.clone()));
+
+    let first = eval_cache_usage(&indirect);
+    assert_eq!((first.hits, first.misses), (0, 1));
+    let second = eval_cache_usage(&indirect);
+    assert_eq!((second.hits, second.misses), (1, 0));
+    assert_eq!((first + second).hit_rate(), Some(0.5));
+
+    // Replacing the block invalidates the cache.
+    block_def_ref
+        .execute(
+            &BlockDefTransaction::overwrite(Block::from(Rgba::BLACK)),
+            &mut transaction::no_outputs,
+        )
+        .unwrap();
+    let after_overwrite = eval_cache_usage(&indirect);
+    assert_eq!((after_overwrite.hits, after_overwrite.misses), (0, 1));
+    assert_eq!(indirect.evaluate().unwrap().color, Rgba::BLACK);
+}
+
+#[test]
+fn indirect_evaluation_cache_invalidated_by_space() {
+    let mut universe = Universe::new();
+    let [block_0] = make_some_blocks();
+    let space_ref = universe.insert_anonymous(Space::empty_positive(2, 2, 2));
+    let block_def_ref = universe.insert_anonymous(BlockDef::new(
+        Block::builder().voxels_ref(R2, space_ref.clone()).build(),
+    ));
+    let indirect = Block::from_primitive(Primitive::Indirect(block_def_ref));
+    let eval_before = indirect.evaluate().unwrap();
+    assert_eq!(eval_cache_usage(&indirect).hits, 1);
+
+    // Changing the voxels must invalidate the cache.
+    space_ref
+        .execute(
+            &SpaceTransaction::set_cube([0, 0, 0], None, Some(block_0)),
+            &mut transaction::no_outputs,
+        )
+        .unwrap();
+    let usage = eval_cache_usage(&indirect);
+    assert_eq!((usage.hits, usage.misses), (0, 1));
+    assert_ne!(indirect.evaluate().unwrap(), eval_before);
+}
+
 #[test]
 fn overflow_evaluate() {
     let mut universe = Universe::new();
@@ -477,6 +896,58 @@ fn overflow_evaluate() {
     assert_eq!(block.evaluate(), Err(EvalBlockError::StackOverflow));
 }
 
+/// Cached evaluations of a [`BlockDef`] must not let an evaluation recurse deeper than
+/// it could without the cache.
+#[test]
+fn overflow_evaluate_with_cached_def() {
+    let mut universe = Universe::new();
+    let mut block = Block::from(Rgba::WHITE);
+    for _ in 0..20 {
+        block = Block::from_primitive(Primitive::Indirect(
+            universe.insert_anonymous(BlockDef::new(block)),
+        ));
+    }
+    // This evaluation succeeds and fills the caches.
+    block.evaluate().unwrap();
+
+    for _ in 0..20 {
+        block = Block::from_primitive(Primitive::Indirect(
+            universe.insert_anonymous(BlockDef::new(block)),
+        ));
+    }
+    assert_eq!(block.evaluate(), Err(EvalBlockError::StackOverflow));
+}
+
+#[test]
+fn budget_exceeded_by_modifiers() {
+    let mut block = Block::from(Rgba::WHITE);
+    block.modifiers_mut().extend(vec![
+        Modifier::Rotate(GridRotation::CLOCKWISE);
+        Cost::DEFAULT_BUDGET.components
+    ]);
+    assert!(matches!(
+        block.evaluate(),
+        Err(EvalBlockError::BudgetExceeded { budget, used })
+            if budget == Cost::DEFAULT_BUDGET && used.components == budget.components + 1
+    ));
+}
+
+/// A block whose definition is small but whose evaluation is exponentially large must
+/// fail promptly rather than taking practically forever.
+#[test]
+fn budget_exceeded_by_composite_tree() {
+    let mut block = Block::from(Rgba::WHITE);
+    for _ in 0..30 {
+        block = block
+            .clone()
+            .with_modifier(Composite::new(block, CompositeOperator::Over));
+    }
+    assert!(matches!(
+        block.evaluate(),
+        Err(EvalBlockError::BudgetExceeded { .. })
+    ));
+}
+
 #[test]
 fn overflow_listen() {
     let mut universe = Universe::new();
@@ -1,9 +1,11 @@
 //! Projection and view matrices, viewport and aspect ratio, visibility,
 //! raycasting into the scene, etc.
 
+use std::f64::consts::PI;
+
 use cgmath::{
-    Basis3, Decomposed, Deg, EuclideanSpace as _, InnerSpace as _, Matrix4, One as _, Point2,
-    Point3, SquareMatrix, Transform, Vector2, Vector3,
+    Angle as _, Basis3, Decomposed, Deg, EuclideanSpace as _, InnerSpace as _, Matrix4, One as _,
+    Point2, Point3, SquareMatrix, Transform, Vector2, Vector3,
 };
 use itertools::Itertools as _;
 use ordered_float::NotNan;
@@ -29,6 +31,10 @@ mod tests;
 
 type M = Matrix4<FreeCoordinate>;
 
+/// Distance from the eye to the near plane of perspective projections:
+/// half a voxel at resolution=16.
+const NEAR: FreeCoordinate = 1. / 32.;
+
 /// Representation of a camera viewpoint and orientation, using [`cgmath`] types.
 ///
 /// Note that this is treated as a transform **from** the origin looking in the &minus;Z
@@ -177,6 +183,10 @@ impl Camera {
     }
 
     /// Returns a projection matrix suitable for OpenGL use.
+    ///
+    /// If the [projection](GraphicsOptions::projection) is not
+    /// [linear](ProjectionOption::is_linear), then this is a perspective projection
+    /// which approximates it.
     pub fn projection(&self) -> M {
         self.projection
     }
@@ -193,6 +203,10 @@ impl Camera {
 
     /// Returns an [`OctantMask`] including all directions this camera's field of view includes.
     pub fn view_direction_mask(&self) -> OctantMask {
+        if self.options.projection == ProjectionOption::Panorama {
+            return OctantMask::ALL;
+        }
+
         #[rustfmt::skip]
         let FrustumPoints { lbf, rbf, ltf, rtf, lbn, rbn, ltn, rtn, .. } = self.view_frustum;
 
@@ -225,7 +239,46 @@ impl Camera {
     /// Converts a screen position in normalized device coordinates (as produced by
     /// [`Viewport::normalize_nominal_point`]) into a ray in world space.
     /// Uses the view transformation given by [`set_view_transform`](Self::set_view_transform).
+    ///
+    /// If the [projection](GraphicsOptions::projection) is not
+    /// [linear](ProjectionOption::is_linear), then the ray is computed according to that
+    /// projection rather than [`Self::projection()`].
     pub fn project_ndc_into_world(&self, ndc: Point2<FreeCoordinate>) -> Ray {
+        match self.options.projection {
+            ProjectionOption::Perspective | ProjectionOption::Orthographic { .. } => {
+                self.project_ndc_into_world_linear(ndc)
+            }
+            ProjectionOption::Panorama => {
+                let longitude = ndc.x * PI;
+                let latitude = ndc.y * (PI / 2.);
+                let direction = Vector3::new(
+                    longitude.sin() * latitude.cos(),
+                    latitude.sin(),
+                    -longitude.cos() * latitude.cos(),
+                );
+                self.eye_ray_into_world(Point3::origin(), direction * self.view_distance())
+            }
+            ProjectionOption::Stereo { eye_separation } => {
+                // The left half of the viewport is the left eye's view, and vice versa.
+                let (eye_x, ndc_x) = if ndc.x < 0. {
+                    (-0.5, ndc.x * 2. + 1.)
+                } else {
+                    (0.5, ndc.x * 2. - 1.)
+                };
+                let tan_y = (self.fov_y() / 2.).tan();
+                let tan_x = tan_y * self.viewport.nominal_aspect_ratio() / 2.;
+                let direction = Vector3::new(ndc_x * tan_x, ndc.y * tan_y, -1.0);
+                self.eye_ray_into_world(
+                    Point3::new(eye_x * eye_separation.into_inner(), 0., 0.) + direction * NEAR,
+                    direction * (self.view_distance() - NEAR),
+                )
+            }
+        }
+    }
+
+    /// Implementation of [`Self::project_ndc_into_world()`] for projections which
+    /// `self.projection` represents exactly.
+    fn project_ndc_into_world_linear(&self, ndc: Point2<FreeCoordinate>) -> Ray {
         let ndc_near = ndc.to_vec().extend(-1.0).extend(1.0);
         let ndc_far = ndc.to_vec().extend(1.0).extend(1.0);
         // World-space endpoints of the ray.
@@ -238,6 +291,18 @@ impl Camera {
         }
     }
 
+    /// Converts a ray in eye coordinates to world coordinates.
+    fn eye_ray_into_world(
+        &self,
+        origin: Point3<FreeCoordinate>,
+        direction: Vector3<FreeCoordinate>,
+    ) -> Ray {
+        Ray {
+            origin: self.eye_to_world_transform.transform_point(origin),
+            direction: self.eye_to_world_transform.transform_vector(direction),
+        }
+    }
+
     fn project_point_into_world(&self, p: Point3<FreeCoordinate>) -> Point3<FreeCoordinate> {
         Point3::from_homogeneous(self.inverse_projection_view * p.to_homogeneous())
     }
@@ -261,6 +326,10 @@ impl Camera {
         if !aab.intersects(self.view_frustum.bounds) {
             return false;
         }
+        if self.options.projection == ProjectionOption::Panorama {
+            // The view is in all directions, so the bounds are all we have.
+            return true;
+        }
 
         // Test the view frustum's face normals.
         // (Benchmarking has shown testing this first to be better, though not shown that
@@ -333,12 +402,30 @@ impl Camera {
     }
 
     fn compute_matrices(&mut self) {
-        self.projection = cgmath::perspective(
-            self.fov_y(),
-            self.viewport.nominal_aspect_ratio(),
-            /* near: */ 1. / 32., // half a voxel at resolution=16
-            /* far: */ self.view_distance(),
-        );
+        let aspect_ratio = self.viewport.nominal_aspect_ratio();
+        self.projection = match self.options.projection {
+            ProjectionOption::Orthographic { height } => {
+                let half_height = height.into_inner() / 2.;
+                let half_width = half_height * aspect_ratio;
+                cgmath::ortho(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    /* near: */ 0.,
+                    /* far: */ self.view_distance(),
+                )
+            }
+            // Non-linear projections use a perspective projection as their approximation.
+            ProjectionOption::Perspective
+            | ProjectionOption::Panorama
+            | ProjectionOption::Stereo { .. } => cgmath::perspective(
+                self.fov_y(),
+                aspect_ratio,
+                NEAR,
+                /* far: */ self.view_distance(),
+            ),
+        };
 
         self.world_to_eye_matrix = self.eye_to_world_transform
             .inverse_transform()
@@ -365,6 +452,11 @@ impl Camera {
             bounds: Aab::ZERO,
         };
         self.view_frustum.compute_bounds();
+        if self.options.projection == ProjectionOption::Panorama {
+            let radius = Vector3::new(1., 1., 1.) * self.view_distance();
+            self.view_frustum.bounds =
+                Aab::from_lower_upper(self.view_position - radius, self.view_position + radius);
+        }
     }
 }
 
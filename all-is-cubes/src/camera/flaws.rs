@@ -44,6 +44,14 @@ bitflags::bitflags! {
         /// Surfaces that should have textures rather than a solid color don't.
         // TODO: Should this just be one of the things TOO_MUCH means?
         const MISSING_TEXTURES = 1 << 6;
+
+        /// A perspective projection has been used instead of the projection requested
+        /// by the graphics options.
+        const UNSUPPORTED_PROJECTION = 1 << 7;
+
+        /// [`DynamicLight`](crate::space::DynamicLight)s have not been rendered, despite
+        /// being requested by the graphics options.
+        const NO_DYNAMIC_LIGHTS = 1 << 8;
     }
 }
 
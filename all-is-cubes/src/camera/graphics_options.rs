@@ -20,6 +20,10 @@ pub struct GraphicsOptions {
     /// Field of view, in degrees from top to bottom edge of the viewport.
     pub fov_y: NotNan<FreeCoordinate>,
 
+    /// Kind of projection from the scene to the image, such as perspective or
+    /// orthographic.
+    pub projection: ProjectionOption,
+
     /// Method to use to remap colors to fit within the displayable range.
     pub tone_mapping: ToneMappingOperator,
 
@@ -37,10 +41,36 @@ pub struct GraphicsOptions {
     /// TODO: Implement view distance limit (and fog) in raytracer.
     pub view_distance: NotNan<FreeCoordinate>,
 
+    /// Whether to draw distant chunks of the world with less detail, reducing the
+    /// rendering cost of large view distances. This has no effect on the raytracer.
+    ///
+    /// See [`LevelOfDetail`](crate::mesh::LevelOfDetail) for the details of the
+    /// simplification.
+    pub level_of_detail: bool,
+
     /// Style in which to draw the lighting of [`Space`](crate::space::Space)s.
     /// This does not affect the *computation* of lighting.
     pub lighting_display: LightingOption,
 
+    /// Whether to draw [`DynamicLight`](crate::space::DynamicLight)s, real-time light
+    /// sources with shadows, in addition to the light stored in the
+    /// [`Space`](crate::space::Space).
+    ///
+    /// This has no effect if [`lighting_display`](Self::lighting_display) is
+    /// [`LightingOption::None`].
+    pub dynamic_lights: bool,
+
+    /// Whether to darken corners and crevices according to the surrounding geometry,
+    /// at the resolution of individual voxels. This is finer-grained than the ambient
+    /// occlusion included in the light stored in the [`Space`](crate::space::Space),
+    /// which has a resolution of whole cubes.
+    ///
+    /// GPU renderers compute this in screen space; the raytracer computes it from the
+    /// voxels adjacent to each surface. It has no effect if
+    /// [`lighting_display`](Self::lighting_display) is [`LightingOption::None`], or on
+    /// path tracing, which accounts for occlusion already.
+    pub ambient_occlusion: bool,
+
     /// Method/fidelity to use for transparency.
     pub transparency: TransparencyOption,
 
@@ -66,6 +96,14 @@ pub struct GraphicsOptions {
     /// visible effects.
     pub use_frustum_culling: bool,
 
+    /// Whether to skip drawing chunks which are hidden behind opaque blocks, as
+    /// determined by analyzing which faces of each chunk are connected to each other.
+    ///
+    /// This option is for debugging and performance testing and should not have any
+    /// visible effects. Chunks culled this way are shown by
+    /// [`debug_chunk_boxes`](Self::debug_chunk_boxes).
+    pub use_occlusion_culling: bool,
+
     /// Draw text overlay showing debug information.
     pub debug_info_text: bool,
 
@@ -95,6 +133,7 @@ impl GraphicsOptions {
     ///
     /// * [`Self::bloom_intensity`] = `0`
     /// * [`Self::fog`] = [`FogOption::None`]
+    /// * [`Self::level_of_detail`] = `false`
     /// * [`Self::lighting_display`] = [`LightingOption::None`]
     /// * [`Self::tone_mapping`] = [`ToneMappingOperator::Clamp`]
     ///
@@ -103,16 +142,21 @@ impl GraphicsOptions {
     pub const UNALTERED_COLORS: Self = Self {
         fog: FogOption::None,
         fov_y: notnan!(90.),
+        projection: ProjectionOption::Perspective,
         // TODO: Change tone mapping default once we have a good implementation.
         tone_mapping: ToneMappingOperator::Clamp,
         exposure: ExposureOption::Fixed(notnan!(1.)),
         bloom_intensity: notnan!(0.),
         view_distance: notnan!(200.),
+        level_of_detail: false,
         lighting_display: LightingOption::None,
+        dynamic_lights: true,
+        ambient_occlusion: false,
         transparency: TransparencyOption::Volumetric,
         show_ui: true,
         antialiasing: AntialiasingOption::None,
         use_frustum_culling: true,
+        use_occlusion_culling: true,
         debug_info_text: true,
         debug_behaviors: false,
         debug_chunk_boxes: false,
@@ -124,6 +168,7 @@ impl GraphicsOptions {
     #[must_use]
     pub fn repair(mut self) -> Self {
         self.fov_y = self.fov_y.clamp(NotNan::from(1), NotNan::from(189));
+        self.projection = self.projection.repair();
         self.bloom_intensity = self.bloom_intensity.clamp(notnan!(0.0), notnan!(1.0));
         self.view_distance = self
             .view_distance
@@ -141,16 +186,21 @@ impl Default for GraphicsOptions {
         Self {
             fog: FogOption::Abrupt,
             fov_y: NotNan::from(90),
+            projection: ProjectionOption::Perspective,
             // TODO: Change tone mapping default once we have a good implementation.
             tone_mapping: ToneMappingOperator::Clamp,
             exposure: ExposureOption::default(),
             bloom_intensity: notnan!(0.125),
             view_distance: NotNan::from(200),
+            level_of_detail: true,
             lighting_display: LightingOption::Smooth,
+            dynamic_lights: true,
+            ambient_occlusion: false,
             transparency: TransparencyOption::Volumetric,
             show_ui: true,
             antialiasing: AntialiasingOption::default(),
             use_frustum_culling: true,
+            use_occlusion_culling: true,
             debug_info_text: true,
             debug_behaviors: false,
             debug_chunk_boxes: false,
@@ -174,6 +224,65 @@ pub enum FogOption {
     Physical,
 }
 
+/// Choices for [`GraphicsOptions::projection`].
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[non_exhaustive]
+pub enum ProjectionOption {
+    /// Conventional perspective projection, with the field of view given by
+    /// [`GraphicsOptions::fov_y`].
+    Perspective,
+
+    /// Orthographic projection: parallel lines stay parallel and distant objects are
+    /// not smaller. Combined with a suitable view direction, this produces isometric
+    /// views.
+    Orthographic {
+        /// Distance, in cubes, from the bottom to the top edge of the viewport.
+        height: NotNan<FreeCoordinate>,
+    },
+
+    /// Equirectangular 360° panorama: the horizontal axis of the viewport spans all
+    /// directions around the vertical axis, and the vertical axis spans from straight
+    /// down to straight up. The viewport should have an aspect ratio of 2:1.
+    ///
+    /// This is currently only supported by the raytracer; other renderers treat it
+    /// as [`ProjectionOption::Perspective`].
+    Panorama,
+
+    /// Side-by-side stereo: the left and right halves of the viewport are perspective
+    /// views from the left and right eye respectively, with parallel view directions.
+    ///
+    /// This is currently only supported by the raytracer; other renderers treat it
+    /// as [`ProjectionOption::Perspective`].
+    Stereo {
+        /// Distance, in cubes, between the two eye positions.
+        eye_separation: NotNan<FreeCoordinate>,
+    },
+}
+
+impl ProjectionOption {
+    /// Returns whether this projection can be expressed as a single projection matrix,
+    /// as returned by [`Camera::projection()`](crate::camera::Camera::projection).
+    pub fn is_linear(&self) -> bool {
+        match self {
+            Self::Perspective | Self::Orthographic { .. } => true,
+            Self::Panorama | Self::Stereo { .. } => false,
+        }
+    }
+
+    #[must_use]
+    fn repair(self) -> Self {
+        match self {
+            Self::Orthographic { height } => Self::Orthographic {
+                height: height.clamp(NotNan::from(1), NotNan::from(10000)),
+            },
+            Self::Stereo { eye_separation } => Self::Stereo {
+                eye_separation: eye_separation.clamp(NotNan::from(0), NotNan::from(10)),
+            },
+            other => other,
+        }
+    }
+}
+
 /// Choices for [`GraphicsOptions::tone_mapping`].
 #[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
 #[non_exhaustive]
@@ -248,6 +357,13 @@ pub enum LightingOption {
     Flat,
     /// Light varies across surfaces.
     Smooth,
+    /// Light is computed by Monte Carlo path tracing: rays are bounced off surfaces,
+    /// and light-emitting blocks and the sky act as light sources. The result is noisy
+    /// and is refined by averaging successive frames while the scene is unchanged.
+    ///
+    /// This is currently only supported by the raytracer; other renderers treat it
+    /// as [`LightingOption::Smooth`].
+    PathTraced,
 }
 
 /// How to render transparent objects; part of a [`GraphicsOptions`].
@@ -356,6 +472,7 @@ mod tests {
                 bloom_intensity: NotNan::from(0u8),
                 lighting_display: LightingOption::None,
                 antialiasing: AntialiasingOption::None,
+                level_of_detail: false,
                 ..GraphicsOptions::default()
             }
         )
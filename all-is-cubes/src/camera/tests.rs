@@ -87,3 +87,79 @@ fn post_process() {
         color.map_rgb(|rgb| rgb * 0.5)
     );
 }
+
+#[test]
+fn orthographic_rays_are_parallel() {
+    let camera = Camera::new(
+        GraphicsOptions {
+            projection: ProjectionOption::Orthographic {
+                height: NotNan::from(10),
+            },
+            ..GraphicsOptions::default()
+        },
+        Viewport::with_scale(1.0, Vector2::new(20, 10)),
+    );
+    let center = camera.project_ndc_into_world(Point2::new(0.0, 0.0));
+    let corner = camera.project_ndc_into_world(Point2::new(1.0, 1.0));
+    assert_eq!(center.origin, Point3::new(0.0, 0.0, 0.0));
+    assert_eq!(corner.origin, Point3::new(10.0, 5.0, 0.0));
+    assert_eq!(center.direction, corner.direction);
+    assert_eq!((center.direction.x, center.direction.y), (0.0, 0.0));
+}
+
+#[test]
+fn panorama_rays() {
+    let camera = Camera::new(
+        GraphicsOptions {
+            projection: ProjectionOption::Panorama,
+            view_distance: NotNan::from(10),
+            ..GraphicsOptions::default()
+        },
+        Viewport::with_scale(1.0, Vector2::new(20, 10)),
+    );
+    let ray = |x, y| {
+        let ray = camera.project_ndc_into_world(Point2::new(x, y));
+        assert_eq!(ray.origin, Point3::origin());
+        ray.direction.map(|c| (c * 1e6).round() / 1e6)
+    };
+    assert_eq!(ray(0.0, 0.0), Vector3::new(0.0, 0.0, -10.0));
+    assert_eq!(ray(0.5, 0.0), Vector3::new(10.0, 0.0, 0.0));
+    assert_eq!(ray(-0.5, 0.0), Vector3::new(-10.0, 0.0, 0.0));
+    assert_eq!(ray(1.0, 0.0), Vector3::new(0.0, 0.0, 10.0));
+    assert_eq!(ray(0.0, 1.0), Vector3::new(0.0, 10.0, 0.0));
+
+    assert_eq!(camera.view_direction_mask(), OctantMask::ALL);
+    assert!(camera.aab_in_view(Aab::new(-1.0, 0.0, -1.0, 0.0, 1.0, 2.0)));
+    assert!(!camera.aab_in_view(Aab::new(-1.0, 0.0, -1.0, 0.0, 11.0, 12.0)));
+}
+
+#[test]
+fn stereo_rays() {
+    let camera = Camera::new(
+        GraphicsOptions {
+            projection: ProjectionOption::Stereo {
+                eye_separation: NotNan::from(2),
+            },
+            fov_y: NotNan::from(90),
+            ..GraphicsOptions::default()
+        },
+        Viewport::with_scale(1.0, Vector2::new(20, 10)),
+    );
+    // The center of each half of the viewport looks straight ahead from each eye.
+    let left = camera.project_ndc_into_world(Point2::new(-0.5, 0.0));
+    let right = camera.project_ndc_into_world(Point2::new(0.5, 0.0));
+    assert_eq!(left.origin, Point3::new(-1.0, 0.0, -NEAR));
+    assert_eq!(right.origin, Point3::new(1.0, 0.0, -NEAR));
+    assert_eq!(left.direction, right.direction);
+    assert_eq!(left.direction.x, 0.0);
+    assert_eq!(left.direction.y, 0.0);
+
+    // Each half has a square aspect ratio, so the edges are at 45°.
+    // This point is the top left corner of the right eye's view.
+    let edge = camera
+        .project_ndc_into_world(Point2::new(0.0, 1.0))
+        .direction
+        .map(|c| (c * 1e6).round() / 1e6);
+    assert_eq!(edge.x, edge.z);
+    assert_eq!(edge.y, -edge.z);
+}
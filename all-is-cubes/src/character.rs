@@ -5,8 +5,8 @@ use std::fmt;
 use std::sync::Arc;
 
 use cgmath::{
-    Angle as _, Basis3, Decomposed, Deg, ElementWise as _, EuclideanSpace as _, Matrix3, Point3,
-    Rotation3, Transform, Vector3,
+    Angle as _, Basis3, Decomposed, Deg, EuclideanSpace as _, InnerSpace as _, Matrix3, Point3,
+    Quaternion, Rotation3, Transform, Vector3,
 };
 use num_traits::identities::Zero;
 use ordered_float::NotNan;
@@ -17,7 +17,7 @@ use crate::inv::{
     Inventory, InventoryChange, InventoryTransaction, Slot, Tool, ToolError, TOOL_SELECTIONS,
 };
 use crate::listen::{Listen, Listener, Notifier};
-use crate::math::{Aab, Face6, Face7, FreeCoordinate, Rgb};
+use crate::math::{Aab, Face6, FreeCoordinate, Rgb};
 use crate::physics::{Body, BodyStepInfo, BodyTransaction, Contact};
 use crate::raycast::Ray;
 use crate::space::Space;
@@ -31,6 +31,9 @@ use crate::util::{ConciseDebug, CustomFormat, StatusText};
 mod cursor;
 pub use cursor::*;
 
+mod replay;
+pub use replay::*;
+
 mod spawn;
 pub use spawn::*;
 
@@ -61,6 +64,10 @@ pub struct Character {
     /// towards.
     velocity_input: Vector3<FreeCoordinate>,
 
+    /// Unit vector opposite to the gravity most recently experienced by the body.
+    /// The view and movement controls are oriented relative to this direction.
+    up: Vector3<FreeCoordinate>,
+
     /// Offset to be added to `body.position` to produce the drawn eye position.
     /// Used to produce camera shifting effects when the body is stopped by an obstacle
     /// or otherwise moves suddenly.
@@ -105,6 +112,7 @@ impl fmt::Debug for Character {
                 "velocity_input",
                 &self.velocity_input.custom_format(ConciseDebug),
             )
+            .field("up", &self.up.custom_format(ConciseDebug))
             .field("colliding_cubes", &self.colliding_cubes)
             // TODO: report light samples
             .field("exposure", &self.exposure_log.exp())
@@ -195,6 +203,7 @@ impl Character {
             },
             space,
             velocity_input: Vector3::zero(),
+            up: Vector3::unit_y(),
             eye_displacement_pos: Vector3::zero(),
             eye_displacement_vel: Vector3::zero(),
             colliding_cubes: HashSet::new(),
@@ -223,12 +232,26 @@ impl Character {
     pub fn view(&self) -> ViewTransform {
         Decomposed {
             scale: 1.0,
-            rot: Basis3::from_angle_y(Deg(-self.body.yaw))
+            rot: self.up_rotation()
+                * Basis3::from_angle_y(Deg(-self.body.yaw))
                 * Basis3::from_angle_x(Deg(-self.body.pitch)),
             disp: self.body.position.to_vec() + self.eye_displacement_pos,
         }
     }
 
+    /// Returns the direction which the character currently considers “up”, which is
+    /// opposite to the local gravity (as found by [`Space::gravity_at()`]).
+    ///
+    /// If the character is in zero gravity, this is the last known up direction.
+    pub fn up(&self) -> Vector3<FreeCoordinate> {
+        self.up
+    }
+
+    /// Rotation from the +Y axis to [`Self::up`], applied to the view and controls.
+    fn up_rotation(&self) -> Basis3<FreeCoordinate> {
+        Basis3::from(Quaternion::from_arc(Vector3::unit_y(), self.up, None))
+    }
+
     /// Returns the character's current inventory.
     pub fn inventory(&self) -> &Inventory {
         &self.inventory
@@ -284,26 +307,34 @@ impl Character {
         let flying = find_jetpacks(&self.inventory).any(|(_slot_index, active)| active);
         self.body.flying = flying;
 
+        // Orient ourselves according to the local gravity.
+        if let Ok(space) = self.space.read() {
+            let gravity = space.gravity_at(self.body.position);
+            if gravity.magnitude2() > 0.0 && gravity.magnitude2().is_finite() {
+                self.up = -gravity.normalize();
+            }
+        }
+
         let dt = tick.delta_t.as_secs_f64();
         let control_orientation: Matrix3<FreeCoordinate> =
-            Matrix3::from_angle_y(-Deg(self.body.yaw));
+            Matrix3::from(self.up_rotation()) * Matrix3::from_angle_y(-Deg(self.body.yaw));
         // TODO: apply pitch too, but only if wanted for flying (once we have not-flying)
         let initial_body_velocity = self.body.velocity;
 
         let speed = if flying { FLYING_SPEED } else { WALKING_SPEED };
         let mut velocity_target = control_orientation * self.velocity_input * speed;
         if !flying {
-            velocity_target.y = 0.0;
+            velocity_target -= self.up * velocity_target.dot(self.up);
         }
         // TODO should have an on-ground condition...
-        let stiffness = if flying {
-            Vector3::new(10.8, 10.8, 10.8)
-        } else {
-            Vector3::new(10.8, 0., 10.8)
-        }; // TODO constants/tables...
+        let stiffness = 10.8; // TODO constants/tables...
+        let mut velocity_change = velocity_target - self.body.velocity;
+        if !flying {
+            // Walking does not control vertical movement; leave that to gravity.
+            velocity_change -= self.up * velocity_change.dot(self.up);
+        }
 
-        self.body.velocity +=
-            (velocity_target - self.body.velocity).mul_element_wise(stiffness) * dt;
+        self.body.velocity += velocity_change * stiffness * dt;
 
         let body_step_info = if let Ok(space) = self.space.read() {
             self.update_exposure(&space, dt);
@@ -491,20 +522,19 @@ impl Character {
     /// Figure out what the correct overall thing is.
     pub fn jump_if_able(&mut self) {
         if self.is_on_ground() {
-            self.body.velocity += Vector3 {
-                x: 0.,
-                y: JUMP_SPEED,
-                z: 0.,
-            };
+            self.body.velocity += self.up * JUMP_SPEED;
         }
     }
 
     fn is_on_ground(&self) -> bool {
-        self.body.velocity.y <= 0.0
-            && self
-                .colliding_cubes
-                .iter()
-                .any(|contact| contact.normal() == Face7::PY)
+        self.body.velocity.dot(self.up) <= 0.0
+            && self.colliding_cubes.iter().any(|contact| {
+                contact
+                    .normal()
+                    .normal_vector::<FreeCoordinate>()
+                    .dot(self.up)
+                    > 0.5
+            })
     }
 }
 
@@ -516,6 +546,7 @@ impl VisitRefs for Character {
             body: _,
             space,
             velocity_input: _,
+            up: _,
             eye_displacement_pos: _,
             eye_displacement_vel: _,
             colliding_cubes: _,
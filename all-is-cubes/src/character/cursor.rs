@@ -65,6 +65,7 @@ pub fn cursor_raycast(
 
         return Some(Cursor {
             space: space_ref.clone(),
+            ray,
             face_entered: step.face(),
             point_entered: step.intersection_point(ray),
             distance_to_point: step.t_distance(),
@@ -98,6 +99,9 @@ pub struct Cursor {
     /// The space the selected cube is in.
     space: URef<Space>,
 
+    /// The ray that was cast to find this cursor, with its direction normalized.
+    ray: Ray,
+
     /// The face that the cursor ray entered the cube via.
     ///
     /// Note that this is not necessarily the same as “the face of the block” in the case
@@ -138,6 +142,14 @@ impl Cursor {
         &self.space
     }
 
+    /// The ray that was cast to find this cursor, with its direction normalized.
+    ///
+    /// Casting it again with [`cursor_raycast()`] in the same space, in the same state,
+    /// will find the same cursor.
+    pub fn ray(&self) -> Ray {
+        self.ray
+    }
+
     /// Which cube of the space that the cursor ray selected/hit.
     pub fn cube(&self) -> GridPoint {
         self.hit.position
@@ -0,0 +1,217 @@
+use std::fmt;
+
+use cgmath::Vector3;
+
+use crate::character::{cursor_raycast, Character};
+use crate::math::FreeCoordinate;
+use crate::raycast::Ray;
+use crate::time::{Duration, Tick};
+use crate::transaction::{self, Transaction as _};
+use crate::universe::{URef, Universe, UniverseStepInfo};
+
+/// A single user input to a [`Character`], in a form which can be recorded and later
+/// replayed by [`InputRecording`].
+///
+/// Inputs are expressed as the resulting state rather than as relative changes
+/// (e.g. look direction rather than mouse movement) so that replaying them does not
+/// accumulate error.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum CharacterInput {
+    /// Call [`Character::set_velocity_input()`] with this value.
+    Velocity(Vector3<FreeCoordinate>),
+    /// Set the body's look direction to these yaw and pitch values, in degrees.
+    Look {
+        #[allow(missing_docs)]
+        yaw: FreeCoordinate,
+        #[allow(missing_docs)]
+        pitch: FreeCoordinate,
+    },
+    /// Call [`Character::jump_if_able()`].
+    Jump,
+    /// Call [`Character::set_selected_slot()`] with these values.
+    SelectSlot {
+        #[allow(missing_docs)]
+        which_selection: usize,
+        #[allow(missing_docs)]
+        slot: usize,
+    },
+    /// Use the character's selected tool, as by [`Character::click()`], with the
+    /// [`Cursor`](crate::character::Cursor) found by casting `cursor_ray` (which should
+    /// be the original cursor's [`ray()`](crate::character::Cursor::ray)) into the
+    /// character's space, or no cursor if `cursor_ray` is [`None`].
+    ///
+    /// This input cannot be performed by [`CharacterInput::apply()`], since it affects
+    /// the universe rather than only the character; [`InputRecording::replay()`]
+    /// performs it.
+    UseTool {
+        #[allow(missing_docs)]
+        button: usize,
+        #[allow(missing_docs)]
+        cursor_ray: Option<Ray>,
+    },
+}
+
+impl CharacterInput {
+    /// Applies this input to the character.
+    ///
+    /// [`CharacterInput::UseTool`] has no effect here; see its documentation.
+    pub fn apply(&self, character: &mut Character) {
+        match *self {
+            CharacterInput::Velocity(velocity) => character.set_velocity_input(velocity),
+            CharacterInput::Look { yaw, pitch } => {
+                character.body.yaw = yaw;
+                character.body.pitch = pitch;
+            }
+            CharacterInput::Jump => character.jump_if_able(),
+            CharacterInput::SelectSlot {
+                which_selection,
+                slot,
+            } => character.set_selected_slot(which_selection, slot),
+            CharacterInput::UseTool { .. } => {}
+        }
+    }
+
+    /// Performs this input, which must be [`CharacterInput::UseTool`], on behalf of
+    /// `character`. Failure to use the tool is not an error, since it was also
+    /// presumably a failure when the input was recorded.
+    fn use_tool(&self, universe: &mut Universe, character: &URef<Character>) {
+        let &CharacterInput::UseTool { button, cursor_ray } = self else {
+            return;
+        };
+        let space = character
+            .read()
+            .expect("character borrowed during replay")
+            .space
+            .clone();
+        // The ray cannot find a different cube than it did originally, unless the state
+        // differs, so the distance need not be limited.
+        let cursor =
+            cursor_ray.and_then(|ray| cursor_raycast(ray, &space, FreeCoordinate::INFINITY));
+        if let Ok(transaction) = Character::click(character.clone(), cursor.as_ref(), button) {
+            if transaction
+                .execute(universe, &mut transaction::no_outputs)
+                .is_ok()
+            {
+                // Match the interactive session, which updates lighting after tool use
+                // so that the change is not displayed with stale light.
+                let _ = space.try_modify(|space| space.update_lighting_from_queue());
+            }
+        }
+    }
+}
+
+/// The inputs recorded for one step of an [`InputRecording`].
+#[derive(Clone, Debug, Default, PartialEq)]
+#[non_exhaustive]
+pub struct RecordedStep {
+    /// Whether the step was taken with a [paused](Tick::paused) tick. Inputs are still
+    /// applied while paused, so such steps are recorded too.
+    pub paused: bool,
+    /// The inputs applied immediately before the step, in order.
+    pub inputs: Vec<CharacterInput>,
+}
+
+/// A sequence of [`CharacterInput`]s, grouped by the fixed-length [`Tick`] before which
+/// they were applied, which can be replayed against a [`Universe`] to reproduce a session.
+///
+/// Given a [`Universe`] in the same initial state, replaying the same recording produces
+/// the same final state; see [`Universe::step()`] for the conditions of this guarantee.
+///
+/// Recordings can be saved and loaded using [`serde`].
+#[derive(Clone, PartialEq)]
+pub struct InputRecording {
+    step_length: Duration,
+    steps: Vec<RecordedStep>,
+}
+
+impl InputRecording {
+    /// Constructs an empty recording whose steps are all of length `step_length`.
+    pub fn new(step_length: Duration) -> Self {
+        Self {
+            step_length,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Returns the length of time of each step in this recording.
+    pub fn step_length(&self) -> Duration {
+        self.step_length
+    }
+
+    /// Returns the [`Tick`] that each step of this recording should use.
+    pub fn tick(&self) -> Tick {
+        Tick::from_duration(self.step_length)
+    }
+
+    /// Returns the number of steps recorded.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns whether no steps have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Returns the recorded steps, in order.
+    pub fn steps(&self) -> &[RecordedStep] {
+        &self.steps
+    }
+
+    /// Appends a step, with the inputs that were applied before it, to the recording.
+    pub fn record_step(&mut self, inputs: impl IntoIterator<Item = CharacterInput>) {
+        self.steps.push(RecordedStep {
+            paused: false,
+            inputs: inputs.into_iter().collect(),
+        });
+    }
+
+    /// Appends a step taken with a [paused](Tick::paused) tick, with the inputs that were
+    /// applied before it, to the recording.
+    pub fn record_paused_step(&mut self, inputs: impl IntoIterator<Item = CharacterInput>) {
+        self.steps.push(RecordedStep {
+            paused: true,
+            inputs: inputs.into_iter().collect(),
+        });
+    }
+
+    /// Replays this recording: for each recorded step, applies its inputs to `character`
+    /// (or, for [`CharacterInput::UseTool`], to the universe) and then calls
+    /// [`Universe::step()`].
+    ///
+    /// `after_step` is called after each step, allowing the caller to inspect the state
+    /// as it progresses.
+    ///
+    /// Panics if `character` is currently borrowed.
+    pub fn replay(
+        &self,
+        universe: &mut Universe,
+        character: &URef<Character>,
+        mut after_step: impl FnMut(&mut Universe, UniverseStepInfo),
+    ) {
+        let tick = self.tick();
+        for RecordedStep { paused, inputs } in &self.steps {
+            for input in inputs {
+                if let CharacterInput::UseTool { .. } = input {
+                    input.use_tool(universe, character);
+                } else {
+                    character
+                        .try_modify(|character| input.apply(character))
+                        .expect("character borrowed during replay");
+                }
+            }
+            let info = universe.step(if *paused { tick.pause() } else { tick });
+            after_step(universe, info);
+        }
+    }
+}
+
+impl fmt::Debug for InputRecording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InputRecording")
+            .field("step_length", &self.step_length)
+            .field("steps", &self.steps.len())
+            .finish()
+    }
+}
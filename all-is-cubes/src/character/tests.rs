@@ -1,18 +1,22 @@
 use std::sync::Arc;
 
-use cgmath::{Angle as _, Deg, Point3, Vector3};
+use cgmath::{Angle as _, Deg, InnerSpace as _, Point3, Transform as _, Vector3};
+use ordered_float::NotNan;
 
 use crate::block::{Block, AIR};
-use crate::character::{cursor_raycast, Character, CharacterChange, CharacterTransaction, Spawn};
+use crate::character::{
+    cursor_raycast, Character, CharacterChange, CharacterInput, CharacterTransaction,
+    InputRecording, Spawn,
+};
 use crate::inv::{InventoryChange, InventoryTransaction, Slot, Tool, ToolError};
 use crate::listen::{Listen as _, Sink};
 use crate::math::{Aab, Face6, GridAab, Rgb};
-use crate::physics::BodyTransaction;
+use crate::physics::{BodyTransaction, GravityZone};
 use crate::raycast::Ray;
-use crate::space::Space;
-use crate::time::Tick;
+use crate::space::{Space, SpaceTransaction};
+use crate::time::{Duration, Tick};
 use crate::transaction::{self, Transaction as _, TransactionTester};
-use crate::universe::Universe;
+use crate::universe::{URef, Universe};
 
 fn test_spawn(f: impl Fn(&mut Space) -> Spawn) -> Character {
     let mut universe = Universe::new();
@@ -199,6 +203,123 @@ fn no_superjumping() {
     assert_eq!(character.body.velocity, velocity);
 }
 
+#[test]
+fn up_follows_gravity_zone() {
+    let mut universe = Universe::new();
+    let space = universe.insert_anonymous({
+        let mut space = Space::empty_positive(4, 4, 4);
+        SpaceTransaction::add_behavior(
+            space.bounds(),
+            GravityZone::new(Vector3::new(-10, 0, 0).map(NotNan::from)),
+        )
+        .execute(&mut space, &mut transaction::no_outputs)
+        .unwrap();
+        space
+    });
+    let mut character = Character::spawn_default(space);
+    character.body.position = Point3::new(2., 2., 2.);
+    character.body.flying = false;
+    assert_eq!(character.up(), Vector3::unit_y());
+
+    let _ = character.step(None, Tick::from_seconds(0.125));
+
+    assert_eq!(character.up(), Vector3::unit_x());
+    // The view's local up vector should now point along +X.
+    let view_up = character.view().transform_vector(Vector3::unit_y());
+    assert!(
+        (view_up - Vector3::unit_x()).magnitude() < 1e-6,
+        "{view_up:?}"
+    );
+    // Falling is now towards -X.
+    assert!(character.body.velocity.x < 0.0);
+}
+
+#[test]
+fn replay_is_deterministic() {
+    fn new_universe() -> (Universe, URef<Character>) {
+        let mut universe = Universe::new();
+        let mut space = Space::empty_positive(10, 4, 10);
+        space
+            .fill_uniform(
+                GridAab::from_lower_size([0, 0, 0], [10, 1, 10]),
+                Block::from(Rgb::ONE),
+            )
+            .unwrap();
+        let space = universe.insert_anonymous(space);
+        let mut character = Character::spawn_default(space);
+        character.body.position = Point3::new(5., 3., 5.);
+        let character = universe.insert_anonymous(character);
+        (universe, character)
+    }
+    /// Exact representation of the state we're comparing.
+    fn body_bits(character: &URef<Character>) -> Vec<u64> {
+        let character = character.read().unwrap();
+        let body = &character.body;
+        let vectors: [[f64; 3]; 2] = [body.position.into(), body.velocity.into()];
+        vectors
+            .into_iter()
+            .flatten()
+            .chain([body.yaw, body.pitch])
+            .map(f64::to_bits)
+            .collect()
+    }
+
+    let mut recording = InputRecording::new(Duration::from_micros(1_000_000 / 60));
+    for i in 0u32..120 {
+        let mut inputs = vec![CharacterInput::Velocity(Vector3::new(
+            (f64::from(i) * 0.1).sin(),
+            0.,
+            -1.,
+        ))];
+        if i % 7 == 0 {
+            inputs.push(CharacterInput::Look {
+                yaw: f64::from(i) * 3.0,
+                pitch: 10.0,
+            });
+        }
+        if i % 30 == 5 {
+            inputs.push(CharacterInput::Jump);
+        }
+        recording.record_step(inputs);
+    }
+
+    let run = |recording: &InputRecording| {
+        let (mut universe, character) = new_universe();
+        let mut history = Vec::new();
+        recording.replay(&mut universe, &character, |_, _| {
+            history.push(body_bits(&character));
+        });
+        history
+    };
+
+    let first = run(&recording);
+    assert_ne!(first.first(), first.last(), "character should have moved");
+    assert_eq!(first, run(&recording));
+}
+
+#[test]
+fn replay_tool_use_while_paused() {
+    let mut universe = Universe::new();
+    let mut space = Space::empty_positive(1, 3, 1);
+    space.set([0, 0, 0], Block::from(Rgb::ONE)).unwrap();
+    let mut spawn = Spawn::default_for_new_space(space.bounds());
+    spawn.set_inventory(vec![Tool::RemoveBlock { keep: false }.into()]);
+    let space = universe.insert_anonymous(space);
+    let character = universe.insert_anonymous(Character::spawn(&spawn, space.clone()));
+    let initial_position = character.read().unwrap().body.position;
+
+    let mut recording = InputRecording::new(Duration::from_micros(1_000_000 / 60));
+    recording.record_paused_step([CharacterInput::UseTool {
+        button: 0,
+        cursor_ray: Some(Ray::new([0.5, 2.5, 0.5], [0., -1., 0.])),
+    }]);
+    recording.replay(&mut universe, &character, |_, _| {});
+
+    assert_eq!(space.read().unwrap()[[0, 0, 0]], AIR);
+    // The step was paused, so the character did not fall.
+    assert_eq!(character.read().unwrap().body.position, initial_position);
+}
+
 #[test]
 fn click_wrong_space_or_correct_space() {
     let mut universe = Universe::new();
@@ -138,6 +138,7 @@ palette! {
     DEBUG_COLLISION_CUBES = srgb[0xFF 0x00 0x00 0xFF];
     DEBUG_CHUNK_MAJOR = srgb[0x00 0x00 0xE8 0xFF];
     DEBUG_CHUNK_MINOR = srgb[0x00 0xE8 0xE8 0xFF];
+    DEBUG_CHUNK_OCCLUDED = srgb[0xE8 0x00 0x00 0xFF];
 }
 
 palette! {
@@ -104,9 +104,7 @@ pub fn lighting_bench_space(_universe: &mut Universe) -> Result<Space, InGenErro
     }
 
     space.set_physics(SpacePhysics {
-        light: LightPhysics::Rays {
-            maximum_distance: side_length_in_blocks as _,
-        },
+        light: LightPhysics::rays(side_length_in_blocks as _),
         ..SpacePhysics::default()
     });
     Ok(space)
@@ -305,8 +305,7 @@ impl StackLimit {
 ///
 /// The output type is the change notification which should be passed on after commit,
 /// if any change is made.
-#[derive(Clone, Debug, Default, PartialEq)]
-#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[must_use]
 pub struct InventoryTransaction {
     replace: BTreeMap<usize, (Slot, Slot)>,
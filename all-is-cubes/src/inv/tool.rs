@@ -27,6 +27,8 @@ pub enum Tool {
     /// as opposed to editing it.
     ///
     /// This can activate an [`ActivatableRegion`](crate::space::ActivatableRegion).
+    /// User interfaces may also use it to open a container block (one with
+    /// [`BlockAttributes::container_slots`](crate::block::BlockAttributes::container_slots)).
     /// It may have more functions in the future.
     Activate,
 
@@ -54,6 +56,13 @@ pub enum Tool {
     /// Push targeted block into adjacent cube.
     PushPull,
 
+    /// Change the targeted block's state [property](block::Property) of the given name
+    /// to its next value (toggling booleans and cycling through other values).
+    ToggleProperty {
+        /// Name of the property to change.
+        property: Cow<'static, str>,
+    },
+
     /// Allows flight.
     ///
     /// TODO: This should probably be a feature a tool can have rather than a
@@ -211,6 +220,18 @@ impl Tool {
                         .expect("Push transactions conflicted???"),
                 ))
             }
+            Self::ToggleProperty { ref property } => {
+                let cursor = input.cursor()?;
+                match cursor.hit().block.property(property) {
+                    Ok(Some(_)) => {}
+                    Ok(None) | Err(_) => return Err(ToolError::NotUsable),
+                }
+                Ok((
+                    Some(self.clone()),
+                    SpaceTransaction::cycle_property(cursor.cube(), property.clone())
+                        .bind(cursor.space().clone()),
+                ))
+            }
             Self::Jetpack { active } => Ok((
                 Some(Self::Jetpack { active: !active }),
                 UniverseTransaction::default(),
@@ -261,6 +282,7 @@ impl Tool {
             Self::CopyFromSpace => Cow::Borrowed(&predefined[Icons::CopyFromSpace]),
             Self::EditBlock => Cow::Borrowed(&predefined[Icons::EditBlock]),
             Self::PushPull => Cow::Borrowed(&predefined[Icons::PushPull]),
+            Self::ToggleProperty { .. } => Cow::Borrowed(&predefined[Icons::Activate]),
             Self::Jetpack { active } => {
                 Cow::Borrowed(&predefined[Icons::Jetpack { active: *active }])
             }
@@ -280,6 +302,7 @@ impl Tool {
             Tool::CopyFromSpace => One,
             Tool::EditBlock => One,
             Tool::PushPull => One,
+            Tool::ToggleProperty { .. } => One,
             Tool::Jetpack { .. } => One,
             Tool::ExternalAction { .. } => One,
         }
@@ -296,6 +319,7 @@ impl VisitRefs for Tool {
             Tool::CopyFromSpace => {}
             Tool::EditBlock => {}
             Tool::PushPull => {}
+            Tool::ToggleProperty { property: _ } => {}
             Tool::Jetpack { active: _ } => {}
             Tool::ExternalAction { function: _, icon } => {
                 icon.visit_refs(visitor);
@@ -515,7 +539,7 @@ impl<'a, T: arbitrary::Arbitrary<'a>> arbitrary::Arbitrary<'a> for EphemeralOpaq
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::block::Primitive;
+    use crate::block::{BlockDef, BlockProperties, Primitive};
     use crate::character::cursor_raycast;
     use crate::content::{make_some_blocks, make_some_voxel_blocks};
     use crate::inv::Slot;
@@ -645,6 +669,46 @@ mod tests {
         // (unless the transaction fails), so there are no tests for that.
     }
 
+    #[test]
+    fn use_toggle_property() {
+        let [base] = make_some_blocks();
+        let mut tester = ToolTester::new(|_space| {});
+        let lamp = Block::from(Primitive::Indirect(tester.universe.insert_anonymous(
+            BlockDef::new(base).with_properties(
+                BlockProperties::new().property(block::Property::bool("lit", false)),
+            ),
+        )));
+        tester
+            .space_ref
+            .try_modify(|space| space.set((1, 0, 0), &lamp))
+            .unwrap()
+            .unwrap();
+        let tool = Tool::ToggleProperty {
+            property: "lit".into(),
+        };
+
+        assert_eq!(
+            tester.equip_and_use_tool(tool.clone()),
+            Ok(SpaceTransaction::cycle_property([1, 0, 0], "lit").bind(tester.space_ref.clone()))
+        );
+        tester.equip_use_commit(tool).unwrap();
+        assert_eq!(tester.space()[(1, 0, 0)], lamp.with_property("lit", true));
+    }
+
+    #[test]
+    fn use_toggle_property_without_property() {
+        let [existing] = make_some_blocks();
+        let tester = ToolTester::new(|space| {
+            space.set((1, 0, 0), &existing).unwrap();
+        });
+        assert_eq!(
+            tester.equip_and_use_tool(Tool::ToggleProperty {
+                property: "lit".into()
+            }),
+            Err(ToolError::NotUsable)
+        );
+    }
+
     #[tokio::test]
     async fn icon_remove_block() {
         let dummy_icons = dummy_icons().await;
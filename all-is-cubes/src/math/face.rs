@@ -63,6 +63,35 @@ pub enum Face7 {
     PZ,
 }
 
+/// Identifies one of the three coordinate axes.
+#[allow(clippy::exhaustive_enums)]
+#[derive(
+    Clone, Copy, Debug, Hash, Eq, PartialEq, exhaust::Exhaust, serde::Deserialize, serde::Serialize,
+)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[repr(u8)]
+pub enum Axis {
+    #[allow(missing_docs)]
+    X = 0,
+    #[allow(missing_docs)]
+    Y = 1,
+    #[allow(missing_docs)]
+    Z = 2,
+}
+
+impl Axis {
+    /// All the values of [`Axis`].
+    pub const ALL: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+    /// Returns the index of this axis, X = 0, Y = 1, Z = 2, which matches the indexes
+    /// used by most arrays and by [`Face6::axis_number()`].
+    #[inline]
+    #[must_use]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+}
+
 impl Face6 {
     /// All the values of [`Face6`].
     pub const ALL: [Face6; 6] = [
@@ -95,10 +124,17 @@ impl Face6 {
     #[inline]
     #[must_use]
     pub const fn axis_number(self) -> usize {
+        self.axis().index()
+    }
+
+    /// Returns which axis this face's normal vector is parallel to.
+    #[inline]
+    #[must_use]
+    pub const fn axis(self) -> Axis {
         match self {
-            Self::NX | Self::PX => 0,
-            Self::NY | Self::PY => 1,
-            Self::NZ | Self::PZ => 2,
+            Self::NX | Self::PX => Axis::X,
+            Self::NY | Self::PY => Axis::Y,
+            Self::NZ | Self::PZ => Axis::Z,
         }
     }
 
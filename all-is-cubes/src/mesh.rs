@@ -13,6 +13,8 @@ mod block_mesh;
 pub use block_mesh::*;
 #[doc(hidden)] // TODO: candidate for being public
 pub mod chunked_mesh;
+mod lod;
+pub use lod::LevelOfDetail;
 mod space_mesh;
 use cgmath::Point3;
 pub use space_mesh::*;
@@ -40,6 +42,9 @@ pub struct MeshOptions {
     ///
     /// [`voxels`]: crate::block::EvaluatedBlock::voxels
     ignore_voxels: bool,
+
+    /// Merge coplanar adjacent faces of identical opaque atom blocks into larger quads.
+    greedy_merge: bool,
 }
 
 impl MeshOptions {
@@ -48,9 +53,29 @@ impl MeshOptions {
         Self {
             transparency: graphics_options.transparency.clone(),
             ignore_voxels: false,
+            greedy_merge: false,
         }
     }
 
+    /// Sets whether [`SpaceMesh::compute()`] should merge the faces of adjacent blocks
+    /// into larger quads where it can do so without changing the appearance, reducing
+    /// the number of vertices. This is off by default.
+    ///
+    /// Only faces of opaque blocks with no [`voxels`] are merged, and only when they are
+    /// the same block and have the same light falling on them; all other faces,
+    /// including textured ones, are meshed as usual. Because the merging depends on
+    /// the [`Space`]'s light data, meshes computed with this option must be recomputed
+    /// when the lighting changes if [`LightingOption::Flat`] is in use.
+    ///
+    /// [`voxels`]: crate::block::EvaluatedBlock::voxels
+    /// [`Space`]: crate::space::Space
+    /// [`LightingOption::Flat`]: crate::camera::LightingOption::Flat
+    #[must_use]
+    pub fn greedy_merge(mut self, enabled: bool) -> Self {
+        self.greedy_merge = enabled;
+        self
+    }
+
     /// Placeholder for use in tests which do not care about any of the
     /// characteristics that are affected by options (yet).
     #[doc(hidden)]
@@ -58,6 +83,7 @@ impl MeshOptions {
         Self {
             transparency: TransparencyOption::Volumetric,
             ignore_voxels: false,
+            greedy_merge: false,
         }
     }
 }
@@ -246,7 +246,9 @@ where
         };
         match *voxels {
             Evoxels::One(Evoxel {
-                color: block_color, ..
+                color: block_color,
+                material,
+                ..
             }) => {
                 let block_color = options.transparency.limit_alpha(block_color);
                 for (face, face_mesh) in self.face_vertices.iter_mut() {
@@ -267,6 +269,7 @@ where
                             Point2 { x: 1., y: 1. },
                             // TODO: Respect the prefer_textures option.
                             QuadColoring::<A::Tile>::Solid(block_color),
+                            material,
                         );
                     }
                     face_mesh.fully_opaque = block_color.fully_opaque();
@@ -480,6 +483,9 @@ where
                                 low_corner.map(FreeCoordinate::from),
                                 high_corner.map(FreeCoordinate::from),
                                 coloring,
+                                // TODO: Use per-voxel materials, which requires
+                                // splitting quads where the material changes.
+                                block.attributes.material,
                             );
                         });
                     }
@@ -4,6 +4,7 @@ use std::fmt;
 
 use cgmath::{EuclideanSpace as _, Point3, Vector3};
 
+use crate::block::Material;
 use crate::math::{Face6, FreeCoordinate, GridPoint, Rgba};
 use crate::util::{ConciseDebug, CustomFormat};
 
@@ -29,6 +30,8 @@ pub struct BlockVertex<T> {
     pub face: Face6,
     /// Surface color or texture coordinate.
     pub coloring: Coloring<T>,
+    /// Optical properties of the surface, such as reflectance, beyond its color.
+    pub material: Material,
 }
 
 impl<T: Clone> BlockVertex<T> {
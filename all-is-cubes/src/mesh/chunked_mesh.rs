@@ -9,17 +9,27 @@ use indoc::indoc;
 use instant::{Duration, Instant};
 
 use crate::block::{EvaluatedBlock, Resolution};
+#[cfg(doc)]
+use crate::camera::GraphicsOptions;
 use crate::camera::{Camera, Flaws};
 use crate::chunking::{cube_to_chunk, point_to_chunk, ChunkChart, ChunkPos, OctantMask};
+use crate::content::palette;
 use crate::listen::{Listen as _, Listener};
 use crate::math::{Aab, FreeCoordinate, Geometry as _, GridCoordinate, GridPoint};
+#[cfg(feature = "threads")]
+use crate::mesh::MeshBlockSummary;
 use crate::mesh::{
-    BlockMesh, BlockMeshProvider, GfxVertex, LineVertex, MeshOptions, SpaceMesh, TextureAllocator,
-    TextureTile,
+    BlockMesh, BlockMeshProvider, GfxVertex, LevelOfDetail, LineVertex, MeshOptions, SpaceMesh,
+    TextureAllocator, TextureTile,
 };
 use crate::space::{BlockIndex, Space, SpaceChange};
 use crate::universe::URef;
-use crate::util::{ConciseDebug, CustomFormat, StatusText, TimeStats};
+use crate::util::{ConciseDebug, CustomFormat, MapExtend, StatusText, TimeStats};
+
+mod occlusion;
+use occlusion::ChunkConnectivity;
+#[cfg(feature = "threads")]
+mod threaded;
 
 /// If true, enables reporting chunk update timing at [`log::trace`] level.
 const LOG_CHUNK_UPDATES: bool = false;
@@ -50,6 +60,12 @@ where
     /// Resized as needed upon each [`Self::update_blocks_and_some_chunks()`].
     chunk_chart: ChunkChart<CHUNK_SIZE>,
 
+    /// Chunks in `chunk_chart` which occlusion culling has not found to be hidden from
+    /// `view_chunk`, or [`None`] if occlusion culling is disabled.
+    visible_chunks: Option<FnvHashSet<ChunkPos<CHUNK_SIZE>>>,
+    /// Whether `visible_chunks` is out of date.
+    visible_chunks_dirty: bool,
+
     /// The chunk in which the last [`Camera`] provided is located.
     view_chunk: ChunkPos<CHUNK_SIZE>,
 
@@ -62,6 +78,11 @@ where
     /// The [`MeshOptions`] specified by the last [`Camera`] provided.
     last_mesh_options: Option<MeshOptions>,
 
+    /// If [threaded meshing](Self::set_threaded_meshing) is enabled, runs chunk meshing
+    /// jobs in the background.
+    #[cfg(feature = "threads")]
+    job_runner: Option<Box<dyn threaded::MeshJobRunner<Vert, Tex::Tile, CHUNK_SIZE> + Send + Sync>>,
+
     /// Most recent time at which we reset to no data.
     zero_time: Instant,
     /// Earliest time prior to `zero_time` at which we finished everything in the queues.
@@ -87,9 +108,13 @@ where
             block_meshes: VersionedBlockMeshes::new(),
             chunks: FnvHashMap::default(),
             chunk_chart: ChunkChart::new(0.0),
+            visible_chunks: None,
+            visible_chunks_dirty: true,
             view_chunk: ChunkPos(Point3::new(0, 0, 0)),
             did_not_finish_chunks: true,
             last_mesh_options: None,
+            #[cfg(feature = "threads")]
+            job_runner: None,
             zero_time: Instant::now(),
             complete_time: None,
         }
@@ -120,23 +145,47 @@ where
     /// in front-to-back order. (Use `.rev()` to iterate in back-to-front order.)
     ///
     /// Uses `camera`'s position, rotation, and options to decide which chunks to return.
+    /// Chunks which [are occluded](Self::is_occluded) are skipped, but only if `camera`
+    /// is in [`Self::view_chunk()`], since occlusion is computed from there; other
+    /// cameras, such as those of shadow-casting lights, are not occlusion culled.
     pub fn iter_in_view<'a>(
         &'a self,
         camera: &'a Camera,
     ) -> impl Iterator<Item = &'a ChunkMesh<D, Vert, Tex, CHUNK_SIZE>> + DoubleEndedIterator + 'a
     {
         // TODO: can we make fewer details (like view_direction_mask) public, now that this method exists? Should we?
+        let occlusion_culling = self.occlusion_applies_to(camera);
         self.chunk_chart
             .chunks(self.view_chunk(), camera.view_direction_mask())
             // Chunk existence lookup is faster than the frustum culling test,
             // so we do that first.
             .filter_map(|pos| self.chunk(pos))
+            .filter(move |chunk| !occlusion_culling || !self.is_occluded(chunk.position))
             .filter(|chunk| {
                 !camera.options().use_frustum_culling
                     || camera.aab_in_view(chunk.position.bounds().into())
             })
     }
 
+    /// Returns whether occlusion culling, as of the most recent
+    /// [`Self::update_blocks_and_some_chunks()`], found that the chunk at `position`
+    /// cannot be seen from anywhere in [`Self::view_chunk()`], because it is separated
+    /// from it by fully opaque blocks.
+    ///
+    /// Always returns false if [`GraphicsOptions::use_occlusion_culling`] was disabled.
+    pub fn is_occluded(&self, position: ChunkPos<CHUNK_SIZE>) -> bool {
+        self.visible_chunks
+            .as_ref()
+            .map_or(false, |visible| !visible.contains(&position))
+    }
+
+    /// Whether the occlusion culling results are valid for `camera`, which they are only
+    /// if it is located where they were computed from.
+    fn occlusion_applies_to(&self, camera: &Camera) -> bool {
+        camera.options().use_occlusion_culling
+            && point_to_chunk(camera.view_position()) == self.view_chunk
+    }
+
     /// Retrieves a [`ChunkMesh`] for the specified chunk position, if one exists.
     ///
     /// Call this while drawing, after [`Self::update_blocks_and_some_chunks`]
@@ -209,7 +258,11 @@ where
             self.complete_time = None;
         }
 
+        let old_chart_size = self.chunk_chart.count_all();
         self.chunk_chart.resize_if_needed(camera.view_distance());
+        if view_chunk_is_different || self.chunk_chart.count_all() != old_chart_size {
+            self.visible_chunks_dirty = true;
+        }
 
         let prep_to_update_meshes_time = Instant::now();
 
@@ -244,10 +297,35 @@ where
             });
         }
 
-        // Update some chunk geometry.
-        let chunk_bounds = space.bounds().divide(CHUNK_SIZE);
         let mut chunk_mesh_generation_times = TimeStats::default();
         let mut chunk_mesh_callback_times = TimeStats::default();
+
+        // Replace chunk meshes with ones finished in the background.
+        #[cfg(feature = "threads")]
+        if let Some(runner) = &self.job_runner {
+            for output in runner.take_completed() {
+                let Some(chunk) = self.chunks.get_mut(&output.position) else {
+                    // Chunk was dropped.
+                    continue;
+                };
+                let old_connectivity = chunk.connectivity;
+                if chunk.install_job_output(output) {
+                    self.visible_chunks_dirty |= chunk.connectivity != old_connectivity;
+                    let callback_start_time = Instant::now();
+                    chunk_render_updater(chunk.borrow_for_update(false));
+                    chunk_mesh_callback_times +=
+                        TimeStats::one(Instant::now().duration_since(callback_start_time));
+                }
+            }
+        }
+        // Data shared by all the background jobs started in this update.
+        #[cfg(feature = "threads")]
+        let mut job_block_summaries: Option<Arc<[MeshBlockSummary]>> = None;
+        #[cfg(feature = "threads")]
+        let mut job_block_meshes = None;
+
+        // Update some chunk geometry.
+        let chunk_bounds = space.bounds().divide(CHUNK_SIZE);
         let mut did_not_finish = false;
         for p in self.chunk_chart.chunks(view_chunk, OctantMask::ALL) {
             if !chunk_bounds.contains_cube(p.0) {
@@ -261,18 +339,29 @@ where
                 break;
             }
 
+            let lod = if graphics_options.level_of_detail {
+                LevelOfDetail::for_distance(
+                    FreeCoordinate::from(p.min_distance_squared_from(view_chunk)).sqrt()
+                        * FreeCoordinate::from(CHUNK_SIZE),
+                )
+            } else {
+                LevelOfDetail::FULL
+            };
+
             let chunk_entry = self.chunks.entry(p);
-            // If the chunk needs updating or never existed, update it.
-            if (todo
+            let chunk_dirty = todo
                 .chunks
                 .get(&p)
                 .map(|ct| ct.recompute_mesh)
-                .unwrap_or(false)
-                && !self.did_not_finish_chunks)
+                .unwrap_or(false);
+            // If the chunk needs updating, never existed, or is at the wrong level of detail,
+            // update it.
+            if (chunk_dirty && !self.did_not_finish_chunks)
                 || matches!(chunk_entry, Vacant(_))
                 || matches!(
                     chunk_entry,
-                    Occupied(ref oe) if oe.get().stale_blocks(&self.block_meshes))
+                    Occupied(ref oe) if oe.get().stale_blocks(&self.block_meshes)
+                        || oe.get().lod != lod)
             {
                 //let compute_start = Instant::now();
                 let chunk = chunk_entry.or_insert_with(|| {
@@ -281,12 +370,59 @@ where
                     // Generate new chunk.
                     ChunkMesh::new(p)
                 });
+
+                #[cfg(feature = "threads")]
+                if let Some(runner) = &self.job_runner {
+                    let block_version = self.block_meshes.last_version_counter;
+                    let already_pending = chunk.pending_job.as_ref().map_or(false, |pending| {
+                        !chunk_dirty && pending.lod == lod && pending.block_version == block_version
+                    });
+                    if !already_pending {
+                        let cancel = threaded::CancelFlag::default();
+                        runner.submit(threaded::MeshJob {
+                            position: p,
+                            priority: p.min_distance_squared_from(view_chunk),
+                            lod,
+                            options: mesh_options.clone(),
+                            space: threaded::ChunkSnapshot::new(
+                                space,
+                                p.bounds(),
+                                Arc::clone(job_block_summaries.get_or_insert_with(|| {
+                                    space
+                                        .block_data()
+                                        .iter()
+                                        .map(|bd| MeshBlockSummary::new(bd.evaluated()))
+                                        .collect()
+                                })),
+                            ),
+                            block_meshes: Arc::clone(
+                                job_block_meshes
+                                    .get_or_insert_with(|| Arc::from(&*self.block_meshes.meshes)),
+                            ),
+                            cancel: Arc::clone(&cancel),
+                        });
+                        // Replacing any previous pending job cancels it.
+                        chunk.pending_job = Some(threaded::PendingJob {
+                            cancel,
+                            lod,
+                            block_version,
+                        });
+                        todo.chunks.get_mut(&p).unwrap().recompute_mesh = false;
+                        chunk_mesh_generation_times +=
+                            TimeStats::one(Instant::now().duration_since(this_chunk_start_time));
+                    }
+                    continue;
+                }
+
+                let old_connectivity = chunk.connectivity;
                 chunk.recompute_mesh(
                     todo.chunks.get_mut(&p).unwrap(), // TODO: can we eliminate the double lookup with a todo entry?
                     space,
                     mesh_options,
                     &self.block_meshes,
+                    lod,
                 );
+                self.visible_chunks_dirty |= chunk.connectivity != old_connectivity;
                 let compute_end_update_start = Instant::now();
                 chunk_render_updater(chunk.borrow_for_update(false));
 
@@ -297,6 +433,23 @@ where
             }
         }
         self.did_not_finish_chunks = did_not_finish;
+
+        if !graphics_options.use_occlusion_culling {
+            self.visible_chunks = None;
+        } else if self.visible_chunks_dirty || self.visible_chunks.is_none() {
+            let chunks = &self.chunks;
+            self.visible_chunks = Some(occlusion::visible_chunks(
+                &self.chunk_chart,
+                view_chunk,
+                |pos| {
+                    chunks
+                        .get(&pos)
+                        .map_or(ChunkConnectivity::ALL, |c| c.connectivity)
+                },
+            ));
+            self.visible_chunks_dirty = false;
+        }
+
         let chunk_scan_end_time = Instant::now();
 
         // Update the drawing order of transparent parts of the chunk the camera is in.
@@ -311,7 +464,15 @@ where
             None
         };
 
-        let complete = all_done_with_blocks && !did_not_finish;
+        #[cfg(feature = "threads")]
+        let jobs_incomplete = self
+            .job_runner
+            .as_ref()
+            .map_or(false, |runner| runner.incomplete_count() > 0);
+        #[cfg(not(feature = "threads"))]
+        let jobs_incomplete = false;
+
+        let complete = all_done_with_blocks && !did_not_finish && !jobs_incomplete;
         if complete && self.complete_time.is_none() {
             let t = Instant::now();
             log::debug!(
@@ -360,9 +521,34 @@ where
         self.view_chunk
     }
 
-    /// Produces lines that visualize the boundaries of visible nonempty chunks.
+    /// Produces lines that visualize the boundaries of visible nonempty chunks, and of
+    /// nonempty chunks that are within the view frustum but culled due to occlusion.
     #[doc(hidden)] // TODO: good public API?
     pub fn chunk_debug_lines(&self, camera: &Camera, output: &mut impl Extend<LineVertex>) {
+        if self.occlusion_applies_to(camera) {
+            let occluded_color = Some(palette::DEBUG_CHUNK_OCCLUDED);
+            for chunk_mesh in self
+                .chunk_chart
+                .chunks(self.view_chunk(), camera.view_direction_mask())
+                .filter(|&pos| self.is_occluded(pos))
+                .filter_map(|pos| self.chunk(pos))
+                .filter(|chunk| camera.aab_in_view(chunk.position.bounds().into()))
+            {
+                if !chunk_mesh.mesh.is_empty() {
+                    // Draw a smaller box, so that it is distinguishable from the boxes
+                    // of adjacent visible chunks.
+                    Aab::from(chunk_mesh.position().bounds())
+                        .expand(-0.25 * FreeCoordinate::from(CHUNK_SIZE))
+                        .wireframe_points(&mut MapExtend::new(output, |v: LineVertex| {
+                            LineVertex {
+                                color: occluded_color,
+                                ..v
+                            }
+                        }));
+                }
+            }
+        }
+
         for chunk_mesh in self.iter_in_view(camera) {
             if !chunk_mesh.mesh.is_empty() {
                 let aab = Aab::from(chunk_mesh.position().bounds());
@@ -380,6 +566,49 @@ where
     }
 }
 
+impl<D, Vert, Tex, const CHUNK_SIZE: GridCoordinate> ChunkedSpaceMesh<D, Vert, Tex, CHUNK_SIZE>
+where
+    D: Default,
+    Vert: GfxVertex<TexPoint = <<Tex as TextureAllocator>::Tile as TextureTile>::Point>
+        + PartialEq
+        + Send
+        + Sync
+        + 'static,
+    Tex: TextureAllocator,
+    Tex::Tile: PartialEq + Send + Sync + 'static,
+{
+    /// Sets whether chunk meshes are computed on background threads.
+    ///
+    /// When enabled, [`Self::update_blocks_and_some_chunks()`] does not compute chunk
+    /// meshes itself, but starts jobs to compute them, nearest chunks first, and replaces
+    /// each chunk's mesh when its job has finished; jobs whose chunk has changed again
+    /// or gone out of range are cancelled. Block meshes are still computed on the calling
+    /// thread. This keeps the time spent in each update low and predictable when many
+    /// chunks change at once.
+    ///
+    /// This has no effect unless the `threads` feature of this crate is enabled.
+    pub fn set_threaded_meshing(&mut self, enabled: bool) {
+        #[cfg(feature = "threads")]
+        if enabled {
+            if self.job_runner.is_none() {
+                self.job_runner = Some(Box::new(threaded::MeshJobQueue::new()));
+            }
+        } else if self.job_runner.take().is_some() {
+            // Cancel all jobs, and ensure the synchronous update will redo them.
+            let mut todo = self.todo.lock().unwrap();
+            for (pos, chunk) in self.chunks.iter_mut() {
+                if chunk.pending_job.take().is_some() {
+                    if let Some(chunk_todo) = todo.chunks.get_mut(pos) {
+                        chunk_todo.recompute_mesh = true;
+                    }
+                }
+            }
+        }
+        #[cfg(not(feature = "threads"))]
+        let _ = enabled;
+    }
+}
+
 /// Performance info from a [`ChunkedSpaceMesh`]'s per-frame update.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 #[non_exhaustive]
@@ -529,22 +758,22 @@ where
                             // If the block has voxels, generate a placeholder mesh,
                             // marked as not-ready so it will be replaced eventually.
                             VersionedBlockMesh {
-                                mesh: BlockMesh::new(
+                                mesh: Arc::new(BlockMesh::new(
                                     evaluated,
                                     block_texture_allocator,
                                     &fast_options,
-                                ),
+                                )),
                                 version: BlockMeshVersion::NotReady,
                             }
                         } else {
                             // If the block does not have voxels, then we can just generate the
                             // final mesh as quick as the placeholder.
                             VersionedBlockMesh {
-                                mesh: BlockMesh::new(
+                                mesh: Arc::new(BlockMesh::new(
                                     evaluated,
                                     block_texture_allocator,
                                     mesh_options,
-                                ),
+                                )),
                                 version: current_version_number,
                             }
                         });
@@ -571,10 +800,11 @@ where
             //     None => 1,
             // };
 
-            if current_mesh_entry
-                .mesh
-                .try_update_texture_only(new_evaluated_block)
-            {
+            // If the mesh is shared with a background meshing job, then we can't update it
+            // in place, but the job is about to be stale anyway, so just compute a new one.
+            if Arc::get_mut(&mut current_mesh_entry.mesh).map_or(false, |mesh| {
+                mesh.try_update_texture_only(new_evaluated_block)
+            }) {
                 // Updated the texture in-place. No need for mesh updates.
             } else {
                 // Compute a new mesh.
@@ -592,11 +822,11 @@ where
                 // (If they were, we'd need to consider what we want to do about stale chunks with
                 // updated texture tiles, which might have geometry gaps or otherwise be obviously
                 // inconsistent.)
-                if new_block_mesh != current_mesh_entry.mesh
+                if new_block_mesh != *current_mesh_entry.mesh
                     || current_mesh_entry.version == BlockMeshVersion::NotReady
                 {
                     *current_mesh_entry = VersionedBlockMesh {
-                        mesh: new_block_mesh,
+                        mesh: Arc::new(new_block_mesh),
                         version: current_version_number,
                     };
                 } else {
@@ -622,19 +852,36 @@ where
 
 impl<'a, Vert, Tile> BlockMeshProvider<'a, Vert, Tile> for &'a VersionedBlockMeshes<Vert, Tile> {
     fn get(&mut self, index: BlockIndex) -> Option<&'a BlockMesh<Vert, Tile>> {
-        Some(&self.meshes.get(usize::from(index))?.mesh)
+        BlockMeshProvider::get(&mut &*self.meshes, index)
+    }
+}
+
+impl<'a, Vert, Tile> BlockMeshProvider<'a, Vert, Tile> for &'a [VersionedBlockMesh<Vert, Tile>] {
+    fn get(&mut self, index: BlockIndex) -> Option<&'a BlockMesh<Vert, Tile>> {
+        Some(&<[_]>::get(self, usize::from(index))?.mesh)
     }
 }
 
 /// Entry in [`VersionedBlockMeshes`].
 #[derive(Debug)]
 struct VersionedBlockMesh<Vert, Tile> {
-    mesh: BlockMesh<Vert, Tile>,
+    /// The mesh, which may be shared with background meshing jobs.
+    mesh: Arc<BlockMesh<Vert, Tile>>,
     /// Version ID used to track whether chunks have stale block meshes (ones that don't
     /// match the current definition of that block-index in the space).
     version: BlockMeshVersion,
 }
 
+// Manual impl to avoid requiring `Vert: Clone, Tile: Clone`.
+impl<Vert, Tile> Clone for VersionedBlockMesh<Vert, Tile> {
+    fn clone(&self) -> Self {
+        Self {
+            mesh: Arc::clone(&self.mesh),
+            version: self.version,
+        }
+    }
+}
+
 /// Together with a [`BlockIndex`], uniquely identifies a block mesh.
 /// Used to determine when chunk meshes need updating.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -660,6 +907,17 @@ where
     pub render_data: D,
     block_dependencies: Vec<(BlockIndex, BlockMeshVersion)>,
 
+    /// Level of detail at which `mesh` was most recently computed.
+    lod: LevelOfDetail,
+
+    /// Which faces of the chunk are connected by non-opaque cubes, as of the most
+    /// recent mesh computation; used for occlusion culling.
+    connectivity: ChunkConnectivity,
+
+    /// Background job computing the next version of `mesh`, if any.
+    #[cfg(feature = "threads")]
+    pending_job: Option<threaded::PendingJob>,
+
     /// Toggled whenever the mesh is updated. Value is arbitrary (this is a looping
     /// 2-state counter).
     update_debug: bool,
@@ -677,6 +935,10 @@ where
             mesh: SpaceMesh::default(),
             render_data: D::default(),
             block_dependencies: Vec::new(),
+            lod: LevelOfDetail::FULL,
+            connectivity: ChunkConnectivity::ALL,
+            #[cfg(feature = "threads")]
+            pending_job: None,
             update_debug: false,
         }
     }
@@ -691,6 +953,12 @@ where
         self.position
     }
 
+    /// Returns the level of detail at which the mesh was most recently computed.
+    #[inline]
+    pub fn lod(&self) -> LevelOfDetail {
+        self.lod
+    }
+
     fn borrow_for_update(
         &mut self,
         indices_only: bool,
@@ -709,10 +977,14 @@ where
         space: &Space,
         options: &MeshOptions,
         block_meshes: &VersionedBlockMeshes<Vert, Tex::Tile>,
+        lod: LevelOfDetail,
     ) {
         let compute_start: Option<Instant> = LOG_CHUNK_UPDATES.then(Instant::now);
         let bounds = self.position.bounds();
-        self.mesh.compute(space, bounds, options, block_meshes);
+        self.mesh
+            .compute_lod(space, bounds, options, block_meshes, lod);
+        self.lod = lod;
+        self.connectivity = ChunkConnectivity::compute(space, bounds);
 
         // Logging
         if let Some(start) = compute_start {
@@ -749,6 +1021,26 @@ where
         chunk_todo.recompute_mesh = false;
     }
 
+    /// Replace the mesh with the output of a background job, if that job is the one
+    /// this chunk is waiting for. Returns whether it was.
+    #[cfg(feature = "threads")]
+    fn install_job_output(
+        &mut self,
+        output: threaded::MeshJobOutput<Vert, Tex::Tile, CHUNK_SIZE>,
+    ) -> bool {
+        match self.pending_job {
+            Some(ref pending) if Arc::ptr_eq(&pending.cancel, &output.cancel) => {}
+            _ => return false,
+        }
+        self.pending_job = None;
+        self.mesh = output.mesh;
+        self.block_dependencies = output.block_dependencies;
+        self.lod = output.lod;
+        self.connectivity = output.connectivity;
+        self.update_debug = !self.update_debug;
+        true
+    }
+
     /// Sort the existing indices of `self.transparent_range(DepthOrdering::Within)` for
     /// the given view position in world coordinates.
     ///
@@ -859,8 +1151,8 @@ impl<const CHUNK_SIZE: GridCoordinate> Listener<SpaceChange> for TodoListener<CH
                             chunk_todo.recompute_mesh = true;
                         });
                     }
-                    SpaceChange::Lighting(_p) => {
-                        // Meshes are not affected by light
+                    SpaceChange::Lighting(_) | SpaceChange::Physics | SpaceChange::CubeData(_) => {
+                        // Meshes are not affected by light, physics, or cube data
                     }
                     SpaceChange::Number(index) => {
                         if !todo.all_blocks_and_chunks {
@@ -900,7 +1192,7 @@ mod tests {
     use ordered_float::NotNan;
 
     use super::*;
-    use crate::block::Block;
+    use crate::block::{Block, AIR};
     use crate::camera::{GraphicsOptions, TransparencyOption, Viewport};
     use crate::math::{FreeCoordinate, GridAab, GridCoordinate};
     use crate::mesh::{BlockVertex, NoTexture, NoTextures};
@@ -1153,6 +1445,218 @@ mod tests {
         assert_eq!(vertices, Some(0));
     }
 
+    /// Distant chunks are meshed at lower levels of detail, unless disabled.
+    #[test]
+    fn level_of_detail_by_distance() {
+        let mut space = Space::builder(GridAab::from_lower_size(
+            [0, 0, 0],
+            [CHUNK_SIZE * 10, CHUNK_SIZE, CHUNK_SIZE],
+        ))
+        .build();
+        space
+            .fill_uniform(space.bounds(), Block::from(rgba_const!(1., 1., 1., 1.)))
+            .unwrap();
+        let mut tester = CsmTester::new(space, f64::from(CHUNK_SIZE) * 12.0);
+        tester.move_camera_to([0.5, 0.5, 0.5]);
+
+        tester.update(|_| {});
+        let lods: Vec<LevelOfDetail> = (0..10)
+            .map(|x| tester.csm.chunk(ChunkPos::new(x, 0, 0)).unwrap().lod())
+            .collect();
+        assert_eq!(lods[0], LevelOfDetail::FULL);
+        assert!(lods[9] > LevelOfDetail::BLOCK_COLORS, "{lods:?}");
+        assert!(lods.windows(2).all(|w| w[0] <= w[1]), "{lods:?}");
+
+        tester.camera.set_options(GraphicsOptions {
+            level_of_detail: false,
+            ..tester.camera.options().clone()
+        });
+        tester.update(|_| {});
+        for x in 0..10 {
+            assert_eq!(
+                tester.csm.chunk(ChunkPos::new(x, 0, 0)).unwrap().lod(),
+                LevelOfDetail::FULL
+            );
+        }
+    }
+
+    #[cfg(feature = "threads")]
+    #[test]
+    fn threaded_meshing_matches_synchronous() {
+        let make_space = || {
+            let mut space = Space::empty_positive(CHUNK_SIZE * 3, CHUNK_SIZE, CHUNK_SIZE);
+            space
+                .fill_uniform(
+                    GridAab::from_lower_size([0, 0, 0], [CHUNK_SIZE * 3, 1, CHUNK_SIZE]),
+                    Block::from(rgba_const!(1., 1., 1., 1.)),
+                )
+                .unwrap();
+            space
+        };
+        let mut threaded = CsmTester::new(make_space(), LARGE_VIEW_DISTANCE);
+        threaded.csm.set_threaded_meshing(true);
+        let mut synchronous = CsmTester::new(make_space(), LARGE_VIEW_DISTANCE);
+
+        let update_until_finished = |tester: &mut CsmTester| {
+            let mut updated = Vec::new();
+            for _ in 0..1000 {
+                let info = tester.update(|u| updated.push(u.position));
+                if !info.flaws.contains(Flaws::UNFINISHED) {
+                    return updated;
+                }
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            panic!("threaded meshing did not finish");
+        };
+        let assert_same_meshes = |threaded: &CsmTester, synchronous: &CsmTester| {
+            for x in 0..3 {
+                let p = ChunkPos::new(x, 0, 0);
+                assert_eq!(
+                    threaded.csm.chunk(p).unwrap().mesh(),
+                    synchronous.csm.chunk(p).unwrap().mesh(),
+                    "{p:?}"
+                );
+            }
+        };
+
+        assert_eq!(update_until_finished(&mut threaded).len(), 3);
+        synchronous.update(|_| {});
+        assert_same_meshes(&threaded, &synchronous);
+
+        // An edit causes only the affected chunk to be remeshed.
+        for tester in [&mut threaded, &mut synchronous] {
+            tester
+                .space
+                .execute(
+                    &SpaceTransaction::set_cube(
+                        [1, 1, 1],
+                        None,
+                        Some(Block::from(rgba_const!(0., 1., 0., 1.))),
+                    ),
+                    &mut transaction::no_outputs,
+                )
+                .unwrap();
+        }
+        assert_eq!(
+            update_until_finished(&mut threaded),
+            vec![ChunkPos::new(0, 0, 0)]
+        );
+        synchronous.update(|_| {});
+        assert_same_meshes(&threaded, &synchronous);
+
+        // Disabling threading leaves the meshes intact.
+        threaded.csm.set_threaded_meshing(false);
+        threaded.update(|_| {});
+        assert_same_meshes(&threaded, &synchronous);
+    }
+
+    #[test]
+    fn occlusion_culling_behind_wall() {
+        let mut space = Space::empty_positive(CHUNK_SIZE * 4, CHUNK_SIZE, CHUNK_SIZE);
+        space
+            .fill_uniform(
+                GridAab::from_lower_size([CHUNK_SIZE, 0, 0], [CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE]),
+                Block::from(rgba_const!(1., 1., 1., 1.)),
+            )
+            .unwrap();
+        let mut tester = CsmTester::new(space, LARGE_VIEW_DISTANCE);
+        tester.move_camera_to([0.5, 0.5, 0.5]);
+        tester.update(|_| {});
+
+        assert!(!tester.csm.is_occluded(ChunkPos::new(0, 0, 0)));
+        assert!(!tester.csm.is_occluded(ChunkPos::new(1, 0, 0)));
+        assert!(tester.csm.is_occluded(ChunkPos::new(2, 0, 0)));
+        assert!(tester.csm.is_occluded(ChunkPos::new(3, 0, 0)));
+        // Chunks outside the space are empty, so they do not occlude.
+        assert!(!tester.csm.is_occluded(ChunkPos::new(2, 1, 0)));
+
+        // Removing part of the wall makes the chunks behind it visible.
+        tester
+            .space
+            .execute(
+                &SpaceTransaction::set_cube([CHUNK_SIZE + 1, 1, 1], None, Some(AIR)),
+                &mut transaction::no_outputs,
+            )
+            .unwrap();
+        tester.update(|_| {});
+        assert!(tester.csm.is_occluded(ChunkPos::new(2, 0, 0)));
+        tester
+            .space
+            .try_modify(|space| {
+                space.fill_uniform(
+                    GridAab::from_lower_size([CHUNK_SIZE, 1, 1], [CHUNK_SIZE, 1, 1]),
+                    AIR,
+                )
+            })
+            .unwrap()
+            .unwrap();
+        tester.update(|_| {});
+        assert!(!tester.csm.is_occluded(ChunkPos::new(2, 0, 0)));
+        assert!(!tester.csm.is_occluded(ChunkPos::new(3, 0, 0)));
+    }
+
+    #[test]
+    fn occlusion_culling_option() {
+        let mut space = Space::empty_positive(CHUNK_SIZE * 3, CHUNK_SIZE, CHUNK_SIZE);
+        space
+            .fill_uniform(
+                GridAab::from_lower_size([CHUNK_SIZE, 0, 0], [CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE]),
+                Block::from(rgba_const!(1., 1., 1., 1.)),
+            )
+            .unwrap();
+        let mut tester = CsmTester::new(space, LARGE_VIEW_DISTANCE);
+        tester.move_camera_to([0.5, 0.5, 0.5]);
+        tester.camera.set_options(GraphicsOptions {
+            use_frustum_culling: false,
+            ..tester.camera.options().clone()
+        });
+        tester.update(|_| {});
+        let in_view = |tester: &CsmTester| -> Vec<ChunkPos<CHUNK_SIZE>> {
+            tester
+                .csm
+                .iter_in_view(&tester.camera)
+                .map(ChunkMesh::position)
+                .collect()
+        };
+        assert_eq!(
+            in_view(&tester),
+            vec![ChunkPos::new(0, 0, 0), ChunkPos::new(1, 0, 0)]
+        );
+
+        tester.camera.set_options(GraphicsOptions {
+            use_occlusion_culling: false,
+            ..tester.camera.options().clone()
+        });
+        tester.update(|_| {});
+        assert!(!tester.csm.is_occluded(ChunkPos::new(2, 0, 0)));
+        assert_eq!(in_view(&tester).len(), 3);
+    }
+
+    /// Occlusion is computed from the view chunk, so it must not be applied to cameras
+    /// elsewhere, such as a light's shadow camera.
+    #[test]
+    fn occlusion_culling_only_for_view_chunk() {
+        let mut space = Space::empty_positive(CHUNK_SIZE * 3, CHUNK_SIZE, CHUNK_SIZE);
+        space
+            .fill_uniform(
+                GridAab::from_lower_size([CHUNK_SIZE, 0, 0], [CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE]),
+                Block::from(rgba_const!(1., 1., 1., 1.)),
+            )
+            .unwrap();
+        let mut tester = CsmTester::new(space, LARGE_VIEW_DISTANCE);
+        tester.move_camera_to([0.5, 0.5, 0.5]);
+        tester.camera.set_options(GraphicsOptions {
+            use_frustum_culling: false,
+            ..tester.camera.options().clone()
+        });
+        tester.update(|_| {});
+        assert!(tester.csm.is_occluded(ChunkPos::new(2, 0, 0)));
+
+        // Move the camera without updating, as if it were a different camera.
+        tester.move_camera_to([2.5, 0.5, 0.5]);
+        assert_eq!(tester.csm.iter_in_view(&tester.camera).count(), 3);
+    }
+
     /// Check that chunks out of view are eventually dropped.
     #[test]
     fn drop_chunks_when_moving() {
@@ -0,0 +1,222 @@
+//! Occlusion culling: finding the chunks which cannot be seen from the view chunk
+//! because opaque blocks separate them from it.
+//!
+//! This uses the technique sometimes called “cave culling”. For each chunk we record
+//! which of its faces are connected to each other by paths through cubes that are not
+//! fully opaque ([`ChunkConnectivity`]). Then, starting from the chunk containing the
+//! viewpoint, visibility is propagated to neighboring chunks, but only through faces that
+//! are connected to a face the chunk was itself seen through, and only in directions
+//! leading away from the view chunk (as every line of sight does). Chunks never reached
+//! this way are hidden.
+//!
+//! The result is approximate but conservative: a chunk which is reached may still be
+//! hidden, but a chunk which is not reached cannot be seen from anywhere in the view
+//! chunk.
+
+use std::ops::{BitOr, BitOrAssign};
+
+use fnv::{FnvHashMap, FnvHashSet};
+
+use crate::chunking::{ChunkChart, ChunkPos, OctantMask};
+use crate::math::{Face6, FaceMap, GridAab, GridArray, GridCoordinate};
+use crate::mesh::MeshSource;
+
+/// Set of [`Face6`]s, stored as bits numbered by their discriminants.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+struct FaceSet(u8);
+
+impl FaceSet {
+    const ALL: Self = Self(0b111_1110);
+
+    fn single(face: Face6) -> Self {
+        Self(1 << face as u8)
+    }
+
+    fn contains(self, face: Face6) -> bool {
+        self.0 & Self::single(face).0 != 0
+    }
+
+    fn iter(self) -> impl Iterator<Item = Face6> {
+        Face6::ALL
+            .into_iter()
+            .filter(move |&face| self.contains(face))
+    }
+}
+
+impl BitOr for FaceSet {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for FaceSet {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// For each face of a chunk, the faces of the same chunk which can be reached from it
+/// through cubes that are not fully opaque.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) struct ChunkConnectivity(FaceMap<FaceSet>);
+
+impl ChunkConnectivity {
+    /// Every face is connected to every face. This is the connectivity of a chunk
+    /// without any fully opaque blocks, and is used for chunks whose contents are not
+    /// yet known.
+    pub const ALL: Self = Self(FaceMap::repeat_copy(FaceSet::ALL));
+
+    /// Computes the connectivity of the part of `space` within `bounds`.
+    pub fn compute<S: MeshSource + ?Sized>(space: &S, bounds: GridAab) -> Self {
+        let mut any_opaque = false;
+        let passable = GridArray::from_fn(bounds, |cube| {
+            let passable = match space.get_block_index(cube) {
+                Some(index) => !space.block_summary(index).all_faces_opaque,
+                None => true,
+            };
+            any_opaque |= !passable;
+            passable
+        });
+        if !any_opaque {
+            return Self::ALL;
+        }
+
+        // Flood fill each connected region of passable cubes, and connect all the faces
+        // of the chunk it touches.
+        let mut connectivity = Self(FaceMap::repeat(FaceSet::default()));
+        let mut visited = GridArray::repeat(bounds, false);
+        let mut stack = Vec::new();
+        for start in bounds.interior_iter() {
+            if !passable[start] || visited[start] {
+                continue;
+            }
+            *visited.get_mut(start).unwrap() = true;
+            stack.push(start);
+            let mut touched = FaceSet::default();
+            while let Some(cube) = stack.pop() {
+                for face in Face6::ALL {
+                    let neighbor = cube + face.normal_vector();
+                    match visited.get_mut(neighbor) {
+                        None => touched |= FaceSet::single(face),
+                        Some(v) if !*v && passable[neighbor] => {
+                            *v = true;
+                            stack.push(neighbor);
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+            for face in touched.iter() {
+                connectivity.0[face] |= touched;
+            }
+        }
+        connectivity
+    }
+}
+
+/// Returns the chunks in `chart` around `view_chunk` which are not known to be hidden,
+/// given the connectivity of each chunk.
+pub(super) fn visible_chunks<const CHUNK_SIZE: GridCoordinate>(
+    chart: &ChunkChart<CHUNK_SIZE>,
+    view_chunk: ChunkPos<CHUNK_SIZE>,
+    mut connectivity: impl FnMut(ChunkPos<CHUNK_SIZE>) -> ChunkConnectivity,
+) -> FnvHashSet<ChunkPos<CHUNK_SIZE>> {
+    let mut visible = FnvHashSet::default();
+    // For each chunk not yet processed, the faces through which it can be seen.
+    let mut entry_faces: FnvHashMap<ChunkPos<CHUNK_SIZE>, FaceSet> = FnvHashMap::default();
+
+    // The chart is ordered by distance in such a way that every step away from the
+    // view chunk is a step to a later chunk, so a chunk's entry faces are all known by
+    // the time we get to it.
+    for pos in chart.chunks(view_chunk, OctantMask::ALL) {
+        let exit_faces = if pos == view_chunk {
+            FaceSet::ALL
+        } else if let Some(entered) = entry_faces.remove(&pos) {
+            let ChunkConnectivity(connected) = connectivity(pos);
+            entered
+                .iter()
+                .fold(FaceSet::default(), |exits, face| exits | connected[face])
+        } else {
+            continue;
+        };
+        visible.insert(pos);
+
+        let offset = pos.0 - view_chunk.0;
+        for face in exit_faces.iter() {
+            if face.dot(offset) < 0 {
+                // This direction leads back towards the view chunk.
+                continue;
+            }
+            *entry_faces
+                .entry(ChunkPos(pos.0 + face.normal_vector()))
+                .or_default() |= FaceSet::single(face.opposite());
+        }
+    }
+    visible
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::math::Rgba;
+    use crate::space::Space;
+
+    const CHUNK_SIZE: GridCoordinate = 16;
+
+    #[test]
+    fn connectivity_of_wall() {
+        let bounds = ChunkPos::<CHUNK_SIZE>::new(0, 0, 0).bounds();
+        let mut space = Space::builder(bounds).build();
+        assert_eq!(
+            ChunkConnectivity::compute(&space, bounds),
+            ChunkConnectivity::ALL
+        );
+
+        // A wall across the middle of the chunk separates −X from +X.
+        space
+            .fill_uniform(
+                GridAab::from_lower_size([8, 0, 0], [1, 16, 16]),
+                Block::from(Rgba::WHITE),
+            )
+            .unwrap();
+        let ChunkConnectivity(connected) = ChunkConnectivity::compute(&space, bounds);
+        assert!(!connected[Face6::NX].contains(Face6::PX));
+        assert!(!connected[Face6::PX].contains(Face6::NX));
+        assert!(connected[Face6::NX].contains(Face6::PY));
+        assert!(connected[Face6::PX].contains(Face6::PY));
+        assert!(connected[Face6::PY].contains(Face6::NX));
+        assert!(connected[Face6::PY].contains(Face6::PX));
+    }
+
+    #[test]
+    fn wall_hides_chunks_behind_it() {
+        let chart = ChunkChart::<CHUNK_SIZE>::new(f64::from(CHUNK_SIZE) * 4.0);
+        let view_chunk = ChunkPos::new(0, 0, 0);
+        let not_nx = FaceSet(FaceSet::ALL.0 & !FaceSet::single(Face6::NX).0);
+        let wall = ChunkConnectivity(FaceMap::repeat(not_nx).with(Face6::NX, FaceSet::default()));
+
+        // An infinite wall at x = 2, passable in other directions.
+        let visible = visible_chunks(&chart, view_chunk, |pos| {
+            if pos.0.x == 2 {
+                wall
+            } else {
+                ChunkConnectivity::ALL
+            }
+        });
+        assert!(visible.contains(&ChunkPos::new(1, 0, 0)));
+        assert!(visible.contains(&ChunkPos::new(2, 0, 0)));
+        assert!(visible.contains(&ChunkPos::new(2, 1, 0)));
+        assert!(!visible.contains(&ChunkPos::new(3, 0, 0)));
+        assert!(!visible.contains(&ChunkPos::new(3, 1, 1)));
+        assert!(visible.contains(&ChunkPos::new(-3, 0, 0)));
+
+        // With no occluders, everything in the chart is visible.
+        let visible = visible_chunks(&chart, view_chunk, |_| ChunkConnectivity::ALL);
+        assert_eq!(
+            visible.len(),
+            chart.chunks(view_chunk, OctantMask::ALL).count()
+        );
+    }
+}
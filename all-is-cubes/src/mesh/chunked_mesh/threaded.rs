@@ -0,0 +1,294 @@
+//! Computing chunk meshes on background threads, for
+//! [`ChunkedSpaceMesh::set_threaded_meshing()`].
+//!
+//! The [`ChunkedSpaceMesh`] copies the data each chunk mesh depends on into a
+//! [`MeshJob`], which is queued and executed on the [`rayon`] thread pool, nearest chunks
+//! first. The resulting [`MeshJobOutput`]s are collected by the next update and replace
+//! the chunks' meshes.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::num::NonZeroU32;
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::{Arc, Mutex};
+
+use crate::chunking::ChunkPos;
+use crate::math::{FaceMap, GridAab, GridArray, GridCoordinate, GridPoint};
+use crate::mesh::chunked_mesh::occlusion::ChunkConnectivity;
+#[cfg(doc)]
+use crate::mesh::chunked_mesh::ChunkedSpaceMesh;
+use crate::mesh::chunked_mesh::{BlockMeshVersion, VersionedBlockMesh};
+#[cfg(doc)]
+use crate::mesh::chunked_mesh::{ChunkMesh, VersionedBlockMeshes};
+use crate::mesh::{
+    GfxVertex, LevelOfDetail, MeshBlockSummary, MeshOptions, MeshSource, SpaceMesh, TextureTile,
+};
+use crate::space::{BlockIndex, PackedLight, Space};
+
+/// Copy of the data from the part of a [`Space`] which is needed to mesh one chunk:
+/// the chunk and the cubes adjacent to it.
+pub(super) struct ChunkSnapshot {
+    indices: GridArray<Option<BlockIndex>>,
+    lighting: GridArray<PackedLight>,
+    blocks: Arc<[MeshBlockSummary]>,
+}
+
+impl ChunkSnapshot {
+    /// `blocks` must have been produced from the current blocks of `space`.
+    pub fn new(space: &Space, bounds: GridAab, blocks: Arc<[MeshBlockSummary]>) -> Self {
+        let bounds = bounds.expand(FaceMap::repeat(1));
+        Self {
+            indices: GridArray::from_fn(bounds, |cube| space.get_block_index(cube)),
+            lighting: GridArray::from_fn(bounds, |cube| space.get_lighting(cube)),
+            blocks,
+        }
+    }
+}
+
+impl MeshSource for ChunkSnapshot {
+    fn get_block_index(&self, cube: GridPoint) -> Option<BlockIndex> {
+        self.indices.get(cube).copied().flatten()
+    }
+
+    fn get_lighting(&self, cube: GridPoint) -> PackedLight {
+        // Meshing never looks further than the snapshot's bounds.
+        self.lighting
+            .get(cube)
+            .copied()
+            .unwrap_or(PackedLight::NO_RAYS)
+    }
+
+    fn block_summary(&self, index: BlockIndex) -> MeshBlockSummary {
+        self.blocks[usize::from(index)]
+    }
+}
+
+/// Flag shared between a [`MeshJob`] and the chunk it is for, which is set when the
+/// chunk no longer wants the job's result.
+pub(super) type CancelFlag = Arc<AtomicBool>;
+
+/// Record, kept by a [`ChunkMesh`], of the job computing its next mesh.
+///
+/// Dropping this cancels the job.
+#[derive(Debug)]
+pub(super) struct PendingJob {
+    pub cancel: CancelFlag,
+    pub lod: LevelOfDetail,
+    /// [`VersionedBlockMeshes::last_version_counter`] when the job was started.
+    pub block_version: NonZeroU32,
+}
+
+impl Drop for PendingJob {
+    fn drop(&mut self) {
+        self.cancel.store(true, atomic::Ordering::Relaxed);
+    }
+}
+
+// Allows `ChunkMesh` to derive `PartialEq`.
+impl PartialEq for PendingJob {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.cancel, &other.cancel)
+    }
+}
+impl Eq for PendingJob {}
+
+/// Everything needed to compute one chunk's mesh.
+pub(super) struct MeshJob<Vert, Tile, const CHUNK_SIZE: GridCoordinate> {
+    pub position: ChunkPos<CHUNK_SIZE>,
+    /// Jobs with lower values are done first.
+    pub priority: i32,
+    pub lod: LevelOfDetail,
+    pub options: MeshOptions,
+    pub space: ChunkSnapshot,
+    pub block_meshes: Arc<[VersionedBlockMesh<Vert, Tile>]>,
+    pub cancel: CancelFlag,
+}
+
+impl<Vert, Tile, const CHUNK_SIZE: GridCoordinate> MeshJob<Vert, Tile, CHUNK_SIZE>
+where
+    Vert: GfxVertex<TexPoint = <Tile as TextureTile>::Point>,
+    Tile: TextureTile,
+{
+    fn run(self) -> MeshJobOutput<Vert, Tile, CHUNK_SIZE> {
+        let mut mesh = SpaceMesh::default();
+        mesh.compute_lod_from(
+            &self.space,
+            self.position.bounds(),
+            &self.options,
+            &*self.block_meshes,
+            self.lod,
+        );
+        let block_dependencies = mesh
+            .blocks_used_iter()
+            .map(|index| (index, self.block_meshes[usize::from(index)].version))
+            .collect();
+        MeshJobOutput {
+            position: self.position,
+            mesh,
+            block_dependencies,
+            lod: self.lod,
+            connectivity: ChunkConnectivity::compute(&self.space, self.position.bounds()),
+            cancel: self.cancel,
+        }
+    }
+}
+
+/// Result of a [`MeshJob`].
+pub(super) struct MeshJobOutput<Vert, Tile, const CHUNK_SIZE: GridCoordinate> {
+    pub position: ChunkPos<CHUNK_SIZE>,
+    pub mesh: SpaceMesh<Vert, Tile>,
+    pub block_dependencies: Vec<(BlockIndex, BlockMeshVersion)>,
+    pub lod: LevelOfDetail,
+    pub connectivity: ChunkConnectivity,
+    /// The same flag as the job's, identifying which job this is the output of.
+    pub cancel: CancelFlag,
+}
+
+/// Runs [`MeshJob`]s somewhere other than the calling thread.
+///
+/// This trait exists so that [`ChunkedSpaceMesh`] can hold a [`MeshJobQueue`] without
+/// itself requiring the [`Send`] bounds that the queue does.
+pub(super) trait MeshJobRunner<Vert, Tile, const CHUNK_SIZE: GridCoordinate>:
+    fmt::Debug
+{
+    /// Adds a job to the queue.
+    fn submit(&self, job: MeshJob<Vert, Tile, CHUNK_SIZE>);
+
+    /// Removes and returns all outputs of jobs that have finished since the last call.
+    /// Outputs of cancelled jobs may or may not be included.
+    fn take_completed(&self) -> Vec<MeshJobOutput<Vert, Tile, CHUNK_SIZE>>;
+
+    /// Returns the number of jobs submitted whose outputs have not yet been returned by
+    /// [`Self::take_completed()`] (or were discarded due to cancellation).
+    fn incomplete_count(&self) -> usize;
+}
+
+/// [`MeshJobRunner`] which uses the [`rayon`] global thread pool.
+pub(super) struct MeshJobQueue<Vert, Tile, const CHUNK_SIZE: GridCoordinate> {
+    state: Arc<Mutex<QueueState<Vert, Tile, CHUNK_SIZE>>>,
+}
+
+struct QueueState<Vert, Tile, const CHUNK_SIZE: GridCoordinate> {
+    pending: BinaryHeap<Prioritized<MeshJob<Vert, Tile, CHUNK_SIZE>>>,
+    completed: Vec<MeshJobOutput<Vert, Tile, CHUNK_SIZE>>,
+    incomplete_count: usize,
+    /// Counter used to run jobs of equal priority in the order they were submitted.
+    next_sequence_number: u64,
+}
+
+impl<Vert, Tile, const CHUNK_SIZE: GridCoordinate> MeshJobQueue<Vert, Tile, CHUNK_SIZE> {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(QueueState {
+                pending: BinaryHeap::new(),
+                completed: Vec::new(),
+                incomplete_count: 0,
+                next_sequence_number: 0,
+            })),
+        }
+    }
+}
+
+impl<Vert, Tile, const CHUNK_SIZE: GridCoordinate> MeshJobRunner<Vert, Tile, CHUNK_SIZE>
+    for MeshJobQueue<Vert, Tile, CHUNK_SIZE>
+where
+    Vert: GfxVertex<TexPoint = <Tile as TextureTile>::Point> + Send + Sync + 'static,
+    Tile: TextureTile + Send + Sync + 'static,
+{
+    fn submit(&self, job: MeshJob<Vert, Tile, CHUNK_SIZE>) {
+        {
+            let state = &mut *self.state.lock().unwrap();
+            state.pending.push(Prioritized {
+                priority: job.priority,
+                sequence_number: state.next_sequence_number,
+                value: job,
+            });
+            state.next_sequence_number += 1;
+            state.incomplete_count += 1;
+        }
+
+        // Each task runs whichever job is most important at the time it starts,
+        // not necessarily the one just submitted.
+        let state = Arc::clone(&self.state);
+        rayon::spawn(move || {
+            let job = loop {
+                let mut state = state.lock().unwrap();
+                match state.pending.pop() {
+                    Some(Prioritized { value: job, .. }) => {
+                        if job.cancel.load(atomic::Ordering::Relaxed) {
+                            state.incomplete_count -= 1;
+                            continue;
+                        }
+                        break job;
+                    }
+                    // Another task took our job.
+                    None => return,
+                }
+            };
+
+            let output = job.run();
+
+            let mut state = state.lock().unwrap();
+            if output.cancel.load(atomic::Ordering::Relaxed) {
+                state.incomplete_count -= 1;
+            } else {
+                state.completed.push(output);
+            }
+        });
+    }
+
+    fn take_completed(&self) -> Vec<MeshJobOutput<Vert, Tile, CHUNK_SIZE>> {
+        let state = &mut *self.state.lock().unwrap();
+        state.incomplete_count -= state.completed.len();
+        std::mem::take(&mut state.completed)
+    }
+
+    fn incomplete_count(&self) -> usize {
+        self.state.lock().unwrap().incomplete_count
+    }
+}
+
+impl<Vert, Tile, const CHUNK_SIZE: GridCoordinate> fmt::Debug
+    for MeshJobQueue<Vert, Tile, CHUNK_SIZE>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("MeshJobQueue")
+            .field("pending", &state.pending.len())
+            .field("completed", &state.completed.len())
+            .field("incomplete_count", &state.incomplete_count)
+            .finish()
+    }
+}
+
+/// Orders `value`s for [`BinaryHeap`] so that the lowest `priority`, then the lowest
+/// `sequence_number`, is the greatest.
+struct Prioritized<T> {
+    priority: i32,
+    sequence_number: u64,
+    value: T,
+}
+
+impl<T> Prioritized<T> {
+    fn key(&self) -> impl Ord {
+        std::cmp::Reverse((self.priority, self.sequence_number))
+    }
+}
+
+impl<T> PartialEq for Prioritized<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+impl<T> Eq for Prioritized<T> {}
+impl<T> PartialOrd for Prioritized<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl<T> Ord for Prioritized<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
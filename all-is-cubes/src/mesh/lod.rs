@@ -0,0 +1,204 @@
+//! Reduced-detail representations of [`Space`] contents, for meshing distant regions.
+
+use cgmath::{Vector3, Zero as _};
+
+use crate::math::{FreeCoordinate, GridAab, GridArray, GridCoordinate, Rgba};
+use crate::mesh::MeshSource;
+#[cfg(doc)]
+use crate::mesh::SpaceMesh;
+use crate::space::BlockIndex;
+#[cfg(doc)]
+use crate::space::Space;
+
+/// How much detail to include when meshing a region of a [`Space`].
+///
+/// At [`LevelOfDetail::FULL`], every block is meshed as it is, voxels and all.
+/// At all other levels, the space is divided into cubical _cells_, each of which is
+/// drawn as a single box of the average color of the blocks within it:
+///
+/// * At level 1, [`BLOCK_COLORS`](Self::BLOCK_COLORS), each cell is one cube.
+/// * At each further level, each cell is a 2×2×2 group of the previous level's cells.
+///
+/// A cell is drawn if any block within it is visible, so that the simplified geometry
+/// always encloses the full-detail geometry. This means that adjacent regions meshed at
+/// different levels of detail never have gaps between them.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LevelOfDetail(u8);
+
+impl LevelOfDetail {
+    /// Full detail; the normal mesh.
+    pub const FULL: Self = Self(0);
+
+    /// Each block is drawn as a cube of its average color.
+    pub const BLOCK_COLORS: Self = Self(1);
+
+    /// The least detailed level, with cells 4 cubes on a side.
+    pub const MIN: Self = Self(3);
+
+    /// Distance, in cubes, within which [`LevelOfDetail::for_distance()`] chooses full
+    /// detail. Each further level is used up to twice the distance of the previous one.
+    pub const FULL_DETAIL_DISTANCE: FreeCoordinate = 64.0;
+
+    /// Constructs a [`LevelOfDetail`] from its numeric level, where 0 is full detail and
+    /// higher numbers are less detailed. Levels beyond [`LevelOfDetail::MIN`] are
+    /// clamped to it.
+    pub fn new(level: u8) -> Self {
+        Self(level.min(Self::MIN.0))
+    }
+
+    /// Returns the numeric level, where 0 is full detail and higher numbers are less
+    /// detailed.
+    pub fn level(self) -> u8 {
+        self.0
+    }
+
+    /// Returns the size, in cubes, of the edges of the cells this level of detail
+    /// divides space into. Full detail is considered to have a cell size of 1.
+    pub fn cell_size(self) -> GridCoordinate {
+        1 << self.0.saturating_sub(1)
+    }
+
+    /// Chooses the level of detail for content at the given distance, in cubes, from
+    /// the viewpoint.
+    pub fn for_distance(distance: FreeCoordinate) -> Self {
+        let mut level = 0;
+        let mut threshold = Self::FULL_DETAIL_DISTANCE;
+        while distance >= threshold && level < Self::MIN.0 {
+            level += 1;
+            threshold *= 2.0;
+        }
+        Self(level)
+    }
+}
+
+/// A cell of a downsampled region; see [`downsample()`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct LodCell {
+    /// The cubes this cell covers, clipped to the region that was downsampled.
+    pub bounds: GridAab,
+    /// Average color of the visible blocks in the cell, or [`None`] if there are none.
+    /// The color is fully opaque if any block in the cell has an opaque face.
+    pub color: Option<Rgba>,
+}
+
+/// Divides the part of `space` within `bounds` into cells for the given level of detail,
+/// computing their colors, and calls `block_used` for every [`BlockIndex`] consulted.
+///
+/// Cells are aligned to multiples of [`LevelOfDetail::cell_size()`] in the space's
+/// coordinate system, so that adjacent regions have consistent cells.
+///
+/// The returned array is indexed by cell coordinates, which are cube coordinates divided
+/// by the cell size.
+pub(crate) fn downsample<S: MeshSource + ?Sized>(
+    space: &S,
+    bounds: GridAab,
+    lod: LevelOfDetail,
+    mut block_used: impl FnMut(BlockIndex),
+) -> GridArray<LodCell> {
+    let cell_size = lod.cell_size();
+    GridArray::from_fn(bounds.divide(cell_size), |cell| {
+        let Some(cell_bounds) =
+            GridAab::from_lower_size(cell * cell_size, [cell_size, cell_size, cell_size])
+                .intersection(bounds)
+        else {
+            unreachable!("cell outside of bounds")
+        };
+
+        // Sum of premultiplied colors of visible blocks, and their count.
+        let mut color_sum = Vector3::<f32>::zero();
+        let mut alpha_sum = 0.0;
+        let mut visible_count = 0;
+        let mut any_opaque = false;
+        for cube in cell_bounds.interior_iter() {
+            let Some(index) = space.get_block_index(cube) else {
+                continue;
+            };
+            block_used(index);
+            let block = space.block_summary(index);
+            if !block.visible {
+                continue;
+            }
+            let alpha = block.color.alpha().into_inner();
+            color_sum += Vector3::from(block.color.to_rgb()) * alpha;
+            alpha_sum += alpha;
+            visible_count += 1;
+            any_opaque |= block.any_face_opaque;
+        }
+
+        let color = (visible_count > 0).then(|| {
+            let rgb = if alpha_sum > 0.0 {
+                color_sum / alpha_sum
+            } else {
+                Vector3::zero()
+            };
+            let alpha = if any_opaque {
+                1.0
+            } else {
+                alpha_sum / visible_count as f32
+            };
+            Rgba::try_from(rgb.extend(alpha)).unwrap_or(Rgba::BLACK)
+        });
+        LodCell {
+            bounds: cell_bounds,
+            color,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{Block, AIR};
+    use crate::space::Space;
+
+    #[test]
+    fn for_distance() {
+        assert_eq!(LevelOfDetail::for_distance(0.0), LevelOfDetail::FULL);
+        assert_eq!(LevelOfDetail::for_distance(63.9), LevelOfDetail::FULL);
+        assert_eq!(
+            LevelOfDetail::for_distance(64.0),
+            LevelOfDetail::BLOCK_COLORS
+        );
+        assert_eq!(LevelOfDetail::for_distance(128.0), LevelOfDetail::new(2));
+        assert_eq!(LevelOfDetail::for_distance(1e9), LevelOfDetail::MIN);
+    }
+
+    #[test]
+    fn cell_size() {
+        assert_eq!(LevelOfDetail::FULL.cell_size(), 1);
+        assert_eq!(LevelOfDetail::BLOCK_COLORS.cell_size(), 1);
+        assert_eq!(LevelOfDetail::new(2).cell_size(), 2);
+        assert_eq!(LevelOfDetail::MIN.cell_size(), 4);
+    }
+
+    #[test]
+    fn downsample_averages_colors() {
+        let red = Block::from(Rgba::new(1.0, 0.0, 0.0, 1.0));
+        let blue = Block::from(Rgba::new(0.0, 0.0, 1.0, 1.0));
+        let mut space = Space::empty_positive(3, 2, 2);
+        space.set([0, 0, 0], &red).unwrap();
+        space.set([1, 1, 1], &blue).unwrap();
+        space.set([2, 0, 0], &AIR).unwrap();
+
+        let cells = downsample(&space, space.bounds(), LevelOfDetail::new(2), |_| {});
+        assert_eq!(
+            cells.bounds(),
+            GridAab::from_lower_size([0, 0, 0], [2, 1, 1])
+        );
+        assert_eq!(
+            cells[[0, 0, 0]],
+            LodCell {
+                bounds: GridAab::from_lower_size([0, 0, 0], [2, 2, 2]),
+                color: Some(Rgba::new(0.5, 0.0, 0.5, 1.0)),
+            }
+        );
+        // Clipped to the space bounds, and containing nothing visible.
+        assert_eq!(
+            cells[[1, 0, 0]],
+            LodCell {
+                bounds: GridAab::from_lower_size([2, 0, 0], [1, 2, 2]),
+                color: None,
+            }
+        );
+    }
+}
@@ -6,7 +6,7 @@ use cgmath::{
     ElementWise as _, EuclideanSpace as _, Matrix4, Point2, Point3, Transform as _, Vector2,
 };
 
-use crate::block::Resolution;
+use crate::block::{Material, Resolution};
 use crate::math::{Face6, FreeCoordinate, GridCoordinate, Rgba};
 use crate::mesh::{BlockVertex, Coloring, TextureCoordinate, TextureTile};
 
@@ -158,6 +158,7 @@ pub(super) fn push_quad<V: From<BlockVertex<Tex::Point>>, Tex: TextureTile>(
     low_corner: Point2<FreeCoordinate>,
     high_corner: Point2<FreeCoordinate>,
     coloring: QuadColoring<'_, Tex>,
+    material: Material,
 ) {
     let index_origin: u32 = vertices.len().try_into().expect("vertex index overflow");
     let half_texel = 0.5;
@@ -180,6 +181,7 @@ pub(super) fn push_quad<V: From<BlockVertex<Tex::Point>>, Tex: TextureTile>(
                     position: transform.transform_position(voxel_grid_point),
                     face,
                     coloring: Coloring::Solid(color),
+                    material,
                 })
             }));
         }
@@ -216,6 +218,7 @@ pub(super) fn push_quad<V: From<BlockVertex<Tex::Point>>, Tex: TextureTile>(
                         clamp_min,
                         clamp_max,
                     },
+                    material,
                 })
             }));
         }
@@ -271,7 +274,7 @@ impl QuadTransform {
     }
 }
 
-const QUAD_VERTICES: &[Vector2<FreeCoordinate>; 4] = &[
+pub(super) const QUAD_VERTICES: &[Vector2<FreeCoordinate>; 4] = &[
     // Two-triangle quad.
     // Note that looked at from a X-right Y-up view, these triangles are
     // clockwise, but they're properly counterclockwise from the perspective
@@ -283,4 +286,4 @@ const QUAD_VERTICES: &[Vector2<FreeCoordinate>; 4] = &[
     Vector2::new(1.0, 1.0),
 ];
 
-const QUAD_INDICES: &[u32; 6] = &[0, 1, 2, 2, 1, 3];
+pub(super) const QUAD_INDICES: &[u32; 6] = &[0, 1, 2, 2, 1, 3];
@@ -1,13 +1,23 @@
 use bitvec::vec::BitVec;
-use cgmath::{EuclideanSpace as _, MetricSpace as _, Point3, Vector3, Zero as _};
+use cgmath::{
+    ElementWise as _, EuclideanSpace as _, MetricSpace as _, Point3, Transform as _, Vector3,
+    Zero as _,
+};
 use ordered_float::OrderedFloat;
 use std::fmt::Debug;
 use std::ops::Range;
 
+use crate::block::{EvaluatedBlock, Evoxel, Evoxels, Material};
 use crate::camera::Flaws;
-use crate::math::{Face6, GridAab, GridCoordinate, GridPoint, GridRotation};
-use crate::mesh::{BlockMesh, GfxVertex, MeshOptions, TextureTile};
-use crate::space::{BlockIndex, Space};
+use crate::math::{
+    Face6, FaceMap, FreeCoordinate, GridAab, GridCoordinate, GridPoint, GridRotation, Rgba,
+};
+use crate::mesh::lod::{self, LodCell};
+use crate::mesh::planar::{QUAD_INDICES, QUAD_VERTICES};
+use crate::mesh::{
+    BlockMesh, BlockVertex, Coloring, GfxVertex, LevelOfDetail, MeshOptions, TextureTile,
+};
+use crate::space::{BlockIndex, PackedLight, Space};
 
 /// A triangle mesh representation of a [`Space`] (or part of it) which may
 /// then be rasterized.
@@ -184,19 +194,36 @@ impl<V: GfxVertex, T: TextureTile> SpaceMesh<V, T> {
     /// and must be up-to-date with the [`Space`]'s blocks or the result will be inaccurate
     /// and may contain severe lighting errors.
     ///
-    /// Note about edge case behavior: This algorithm does not use the [`Space`]'s block data
-    /// at all. Thus, it always has a consistent interpretation based on
-    /// `block_meshes` (as opposed to, for example, using face opacity data not the
-    /// same as the meshes and thus producing a rendering with gaps in it).
+    /// Note about edge case behavior: Apart from [`MeshOptions::greedy_merge()`], this
+    /// algorithm does not use the [`Space`]'s block data at all. Thus, it always has a
+    /// consistent interpretation based on `block_meshes` (as opposed to, for example,
+    /// using face opacity data not the same as the meshes and thus producing a rendering
+    /// with gaps in it).
     ///
     /// [`block_meshes_for_space`]: super::block_meshes_for_space
     pub fn compute<'p, P>(
         &mut self,
         space: &Space,
         bounds: GridAab,
-        _options: &MeshOptions,
+        options: &MeshOptions,
+        block_meshes: P,
+    ) where
+        P: BlockMeshProvider<'p, V, T>,
+        V: 'p,
+        T: 'p,
+    {
+        self.compute_from(space, bounds, options, block_meshes)
+    }
+
+    /// Implementation of [`Self::compute()`], for any [`MeshSource`].
+    pub(crate) fn compute_from<'p, S, P>(
+        &mut self,
+        space: &S,
+        bounds: GridAab,
+        options: &MeshOptions,
         mut block_meshes: P,
     ) where
+        S: MeshSource + ?Sized,
         P: BlockMeshProvider<'p, V, T>,
         V: 'p,
         T: 'p,
@@ -212,6 +239,9 @@ impl<V: GfxVertex, T: TextureTile> SpaceMesh<V, T> {
         // TODO: Consider reuse
         let mut transparent_indices = Vec::new();
 
+        // Faces set aside to be merged and drawn after all the blocks have been visited.
+        let mut greedy_faces = options.greedy_merge.then(|| GreedyFaces::new(bounds));
+
         for cube in bounds.interior_iter() {
             // TODO: On out-of-range, draw an obviously invalid block instead of an invisible one?
             // Do we want to make it the caller's responsibility to specify in-bounds?
@@ -233,6 +263,9 @@ impl<V: GfxVertex, T: TextureTile> SpaceMesh<V, T> {
                 self.flaws |= block_mesh.flaws();
             }
 
+            let mergeable = greedy_faces.is_some()
+                && greedy_color_and_material(space.block_summary(index), options).is_some();
+
             write_block_mesh_to_space_mesh(
                 block_mesh,
                 // translate mesh to be always located at lower_bounds
@@ -256,17 +289,213 @@ impl<V: GfxVertex, T: TextureTile> SpaceMesh<V, T> {
                             return true;
                         }
                     }
+                    if let (true, Some(greedy_faces)) = (mergeable, &mut greedy_faces) {
+                        if block_mesh.face_vertices[face].fully_opaque {
+                            // Draw this face later, merged with its neighbors.
+                            greedy_faces.insert(
+                                face,
+                                cube,
+                                GreedyFace {
+                                    block_index: index,
+                                    light: space.get_lighting(adjacent_cube),
+                                },
+                            );
+                            return true;
+                        }
+                    }
                     false
                 },
             );
         }
 
+        if let Some(greedy_faces) = greedy_faces {
+            self.write_greedy_faces(space, bounds, options, greedy_faces);
+        }
+
         self.sort_and_store_transparent_indices(transparent_indices);
 
         #[cfg(debug_assertions)]
         self.consistency_check();
     }
 
+    /// Computes simplified triangles for the contents of `space` within `bounds`, at the
+    /// given [`LevelOfDetail`], and stores them in `self`.
+    ///
+    /// At [`LevelOfDetail::FULL`], this is identical to [`Self::compute()`]. Otherwise,
+    /// the block meshes are not used; instead, each cell of the level of detail is drawn
+    /// as a box of its blocks' average color, using only the [`Space`]'s block data.
+    ///
+    /// Faces between adjacent opaque cells are omitted, but faces on the boundary of
+    /// `bounds` are always drawn regardless of what is beyond it, so that neighboring
+    /// meshes at a different level of detail never leave gaps.
+    pub fn compute_lod<'p, P>(
+        &mut self,
+        space: &Space,
+        bounds: GridAab,
+        options: &MeshOptions,
+        block_meshes: P,
+        lod: LevelOfDetail,
+    ) where
+        P: BlockMeshProvider<'p, V, T>,
+        V: 'p,
+        T: 'p,
+    {
+        self.compute_lod_from(space, bounds, options, block_meshes, lod)
+    }
+
+    /// Implementation of [`Self::compute_lod()`], for any [`MeshSource`].
+    pub(crate) fn compute_lod_from<'p, S, P>(
+        &mut self,
+        space: &S,
+        bounds: GridAab,
+        options: &MeshOptions,
+        block_meshes: P,
+        lod: LevelOfDetail,
+    ) where
+        S: MeshSource + ?Sized,
+        P: BlockMeshProvider<'p, V, T>,
+        V: 'p,
+        T: 'p,
+    {
+        if lod == LevelOfDetail::FULL {
+            self.compute_from(space, bounds, options, block_meshes);
+            return;
+        }
+
+        self.vertices.clear();
+        self.indices.clear();
+        self.block_indices_used.clear();
+        self.textures_used.clear();
+        self.flaws = Flaws::empty();
+
+        let mut transparent_indices = Vec::new();
+
+        let block_indices_used = &mut self.block_indices_used;
+        let cells = lod::downsample(space, bounds, lod, |index| {
+            bitset_set_and_get(block_indices_used, index.into());
+        });
+
+        for cell in cells.bounds().interior_iter() {
+            let LodCell {
+                bounds: cell_bounds,
+                color,
+            } = cells[cell];
+            let Some(color) = color else {
+                continue;
+            };
+            let opaque = color.fully_opaque();
+            let size = cell_bounds.size().map(FreeCoordinate::from);
+            let inst =
+                V::instantiate_block(cell_bounds.lower_bounds() - bounds.lower_bounds().to_vec());
+
+            for face in Face6::ALL {
+                let neighbor_hides_face = cells
+                    .get(cell + face.normal_vector())
+                    .and_then(|neighbor| neighbor.color)
+                    .map_or(false, Rgba::fully_opaque);
+                if neighbor_hides_face {
+                    continue;
+                }
+
+                push_box_face(
+                    &mut self.vertices,
+                    if opaque {
+                        &mut self.indices
+                    } else {
+                        &mut transparent_indices
+                    },
+                    face,
+                    size,
+                    inst,
+                    color,
+                    Material::DIFFUSE,
+                );
+            }
+        }
+
+        self.sort_and_store_transparent_indices(transparent_indices);
+
+        #[cfg(debug_assertions)]
+        self.consistency_check();
+    }
+
+    /// Draw the faces collected by [`Self::compute()`] for greedy merging, joining each
+    /// group of adjacent identical faces into the fewest rectangles this simple algorithm
+    /// finds.
+    fn write_greedy_faces<S: MeshSource + ?Sized>(
+        &mut self,
+        space: &S,
+        bounds: GridAab,
+        options: &MeshOptions,
+        mut greedy_faces: GreedyFaces,
+    ) {
+        let size = bounds.size();
+        for face in Face6::ALL {
+            let [normal_axis, u_axis, v_axis] = GreedyFaces::axes(face);
+            let u_size = size[u_axis];
+            let v_size = size[v_axis];
+            let plane_area = (u_size * v_size) as usize;
+            let GreedyPlanes {
+                ref mut faces,
+                ref layers_used,
+            } = greedy_faces.planes[face];
+
+            for (layer, plane) in faces.chunks_exact_mut(plane_area).enumerate() {
+                if !layers_used[layer] {
+                    continue;
+                }
+                let at = |u: GridCoordinate, v: GridCoordinate| (v * u_size + u) as usize;
+                for v in 0..v_size {
+                    for u in 0..u_size {
+                        let Some(key) = plane[at(u, v)] else {
+                            continue;
+                        };
+
+                        // Extend the rectangle along u as far as possible, then along v as
+                        // far as every face in the next row matches.
+                        let mut width = 1;
+                        while u + width < u_size && plane[at(u + width, v)] == Some(key) {
+                            width += 1;
+                        }
+                        let mut height = 1;
+                        while v + height < v_size
+                            && plane[at(u, v + height)..at(u + width, v + height)]
+                                .iter()
+                                .all(|&f| f == Some(key))
+                        {
+                            height += 1;
+                        }
+                        for v in v..v + height {
+                            plane[at(u, v)..at(u + width, v)].fill(None);
+                        }
+
+                        let mut cube = GridPoint::origin();
+                        cube[normal_axis] = layer as GridCoordinate;
+                        cube[u_axis] = u;
+                        cube[v_axis] = v;
+                        let mut rect_size = Vector3::new(1.0, 1.0, 1.0);
+                        rect_size[u_axis] = FreeCoordinate::from(width);
+                        rect_size[v_axis] = FreeCoordinate::from(height);
+                        let (color, material) = greedy_color_and_material(
+                            space.block_summary(key.block_index),
+                            options,
+                        )
+                        .expect("block not mergeable");
+                        push_box_face(
+                            &mut self.vertices,
+                            &mut self.indices,
+                            face,
+                            rect_size,
+                            V::instantiate_block(cube),
+                            color,
+                            material,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
     /// Given the indices of vertices of transparent quads (triangle pairs), copy them in
     /// various depth-sorted permutations into `self.indices` and record the array-index
     /// ranges which contain each of the orderings in `self.opaque_range` and
@@ -409,6 +638,177 @@ impl<V: GfxVertex, T: TextureTile> SpaceMesh<V, T> {
     }
 }
 
+/// Append to `vertices` and `indices` a quad covering the `face` of a box with corner
+/// `inst` and size `size`, which is colored with `color`.
+#[allow(clippy::too_many_arguments)]
+fn push_box_face<V: GfxVertex>(
+    vertices: &mut Vec<V>,
+    indices: &mut Vec<u32>,
+    face: Face6,
+    size: Vector3<FreeCoordinate>,
+    inst: V::BlockInst,
+    color: Rgba,
+    material: Material,
+) {
+    let index_origin: u32 = vertices.len().try_into().expect("vertex index overflow");
+    // Transform the unit square onto the face of a unit cube, then stretch the unit cube
+    // to the size of the box.
+    let face_transform = face.matrix(1).to_free();
+    vertices.extend(QUAD_VERTICES.iter().map(|&unit_square_point| {
+        let unit_cube_point =
+            face_transform.transform_point(Point3::from_vec(unit_square_point.extend(0.0)));
+        let mut vertex = V::from(BlockVertex {
+            position: Point3::from_vec(unit_cube_point.to_vec().mul_element_wise(size)),
+            face,
+            coloring: Coloring::Solid(color),
+            material,
+        });
+        vertex.instantiate_vertex(inst);
+        vertex
+    }));
+    indices.extend(QUAD_INDICES.iter().map(|&i| index_origin + i));
+}
+
+/// A face which [`SpaceMesh::compute()`] has set aside for greedy merging.
+/// Faces can be merged if they are equal.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct GreedyFace {
+    block_index: BlockIndex,
+    /// Light in the cube the face faces.
+    light: PackedLight,
+}
+
+/// The faces which [`SpaceMesh::compute()`] has set aside for greedy merging, within
+/// some bounds.
+///
+/// For each face direction, they are stored as a stack of planes perpendicular to the
+/// face's normal, so that each plane can be merged as a simple 2D array.
+struct GreedyFaces {
+    bounds: GridAab,
+    planes: FaceMap<GreedyPlanes>,
+}
+
+struct GreedyPlanes {
+    /// Faces, indexed by layer, then v, then u, relative to the lower bounds.
+    faces: Vec<Option<GreedyFace>>,
+    /// Whether each layer contains any faces, so that empty layers can be skipped.
+    layers_used: Vec<bool>,
+}
+
+impl GreedyFaces {
+    fn new(bounds: GridAab) -> Self {
+        Self {
+            bounds,
+            planes: FaceMap::from_fn(|face| GreedyPlanes {
+                faces: vec![None; bounds.volume()],
+                layers_used: vec![false; bounds.size()[face.axis_number()] as usize],
+            }),
+        }
+    }
+
+    /// Returns the normal axis and the two in-plane (u and v) axes for `face`.
+    fn axes(face: Face6) -> [usize; 3] {
+        let normal_axis = face.axis_number();
+        [normal_axis, (normal_axis + 1) % 3, (normal_axis + 2) % 3]
+    }
+
+    fn insert(&mut self, face: Face6, cube: GridPoint, value: GreedyFace) {
+        let [normal_axis, u_axis, v_axis] = Self::axes(face);
+        let size = self.bounds.size();
+        let local = cube - self.bounds.lower_bounds();
+        let layer = local[normal_axis];
+        let planes = &mut self.planes[face];
+        planes.faces
+            [((layer * size[v_axis] + local[v_axis]) * size[u_axis] + local[u_axis]) as usize] =
+            Some(value);
+        planes.layers_used[layer as usize] = true;
+    }
+}
+
+/// If faces of the given block may be merged with each other when
+/// [`MeshOptions::greedy_merge()`] is enabled, returns the color and material of those
+/// faces, as [`BlockMesh`] would draw them.
+///
+/// The block must have no voxels and be opaque, so that its mesh is exactly one
+/// solid-colored quad per face.
+fn greedy_color_and_material(
+    block: MeshBlockSummary,
+    options: &MeshOptions,
+) -> Option<(Rgba, Material)> {
+    let (color, material) = if options.ignore_voxels {
+        (block.color, Material::DIFFUSE)
+    } else {
+        block.atom?
+    };
+    let color = options.transparency.limit_alpha(color);
+    color.fully_opaque().then_some((color, material))
+}
+
+/// The data about a region of a [`Space`] that [`SpaceMesh`] needs, other than the
+/// block meshes.
+///
+/// This is implemented by [`Space`] itself, and by copies of parts of it which can be
+/// sent to other threads.
+pub(crate) trait MeshSource {
+    /// As [`Space::get_block_index()`].
+    fn get_block_index(&self, cube: GridPoint) -> Option<BlockIndex>;
+
+    /// As [`Space::get_lighting()`].
+    fn get_lighting(&self, cube: GridPoint) -> PackedLight;
+
+    /// Returns the [`MeshBlockSummary`] of the block with the given index, which must
+    /// be one returned by [`Self::get_block_index()`].
+    fn block_summary(&self, index: BlockIndex) -> MeshBlockSummary;
+}
+
+impl MeshSource for Space {
+    fn get_block_index(&self, cube: GridPoint) -> Option<BlockIndex> {
+        Space::get_block_index(self, cube)
+    }
+
+    fn get_lighting(&self, cube: GridPoint) -> PackedLight {
+        Space::get_lighting(self, cube)
+    }
+
+    fn block_summary(&self, index: BlockIndex) -> MeshBlockSummary {
+        MeshBlockSummary::new(self.block_data()[usize::from(index)].evaluated())
+    }
+}
+
+/// The properties of an [`EvaluatedBlock`] which are used for meshing beyond those
+/// captured in its [`BlockMesh`]: by [greedy merging](MeshOptions::greedy_merge), by
+/// [`LevelOfDetail`] simplification, and by chunk occlusion culling.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct MeshBlockSummary {
+    /// As [`EvaluatedBlock::color`].
+    pub color: Rgba,
+    /// As [`EvaluatedBlock::visible`].
+    pub visible: bool,
+    /// Whether any of [`EvaluatedBlock::opaque`] is true.
+    pub any_face_opaque: bool,
+    /// Whether all of [`EvaluatedBlock::opaque`] are true.
+    pub all_faces_opaque: bool,
+    /// If the block has no voxels, the color and material of its single voxel.
+    pub atom: Option<(Rgba, Material)>,
+}
+
+impl MeshBlockSummary {
+    pub fn new(evaluated: &EvaluatedBlock) -> Self {
+        Self {
+            color: evaluated.color,
+            visible: evaluated.visible,
+            any_face_opaque: evaluated.opaque.values().any(|&o| o),
+            all_faces_opaque: evaluated.opaque.values().all(|&o| o),
+            atom: match evaluated.voxels {
+                Evoxels::One(Evoxel {
+                    color, material, ..
+                }) => Some((color, material)),
+                Evoxels::Many(..) => None,
+            },
+        }
+    }
+}
+
 /// Copy and adjust vertices from a [`BlockMesh`] into the storage of a [`SpaceMesh`].
 ///
 /// This does not perform depth sorting and does not account for mesh or texture dependencies.
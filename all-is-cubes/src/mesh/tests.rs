@@ -4,7 +4,7 @@ use cgmath::{MetricSpace as _, Point3, Transform as _, Vector3};
 use pretty_assertions::assert_eq;
 
 use super::*;
-use crate::block::{Block, BlockAttributes, Primitive, Resolution::*, AIR};
+use crate::block::{Block, BlockAttributes, Material, Primitive, Resolution::*, Tint, AIR};
 use crate::camera::{Flaws, GraphicsOptions, TransparencyOption};
 use crate::content::{make_some_blocks, make_some_voxel_blocks};
 use crate::math::{
@@ -21,6 +21,7 @@ fn v_c<T>(position: [FreeCoordinate; 3], face: Face6, color: [f32; 4]) -> BlockV
         position: position.into(),
         face,
         coloring: Coloring::Solid(Rgba::new(color[0], color[1], color[2], color[3])),
+        material: Material::DIFFUSE,
     }
 }
 
@@ -39,6 +40,7 @@ fn v_t(
             clamp_min: texture,
             clamp_max: texture,
         },
+        material: Material::DIFFUSE,
     }
 }
 
@@ -125,6 +127,124 @@ fn excludes_hidden_faces_of_blocks() {
     );
 }
 
+fn greedy_mesh(space: &Space) -> SpaceMesh<BlockVertex<TtPoint>, TestTextureTile> {
+    let options = &MeshOptions::new(&GraphicsOptions::default()).greedy_merge(true);
+    let block_meshes = block_meshes_for_space(space, &TestTextureAllocator::new(), options);
+    SpaceMesh::new(space, space.bounds(), options, &*block_meshes)
+}
+
+#[test]
+fn greedy_merge_floor() {
+    let [block] = make_some_blocks();
+    let mut space = Space::empty_positive(8, 1, 8);
+    space.fill_uniform(space.bounds(), &block).unwrap();
+
+    let (_, _, unmerged) = mesh_blocks_and_space(&space);
+    assert_eq!(unmerged.vertices().len(), 4 * (8 * 8 * 2 + 8 * 4));
+
+    let merged = greedy_mesh(&space);
+    assert_eq!(merged.flaws(), Flaws::empty());
+    assert_eq!(merged.vertices().len(), 4 * 6);
+    assert_eq!(merged.opaque_range(), 0..merged.indices().len());
+    let top_positions: Vec<Point3<FreeCoordinate>> = merged
+        .vertices()
+        .iter()
+        .filter(|v| v.face == PY)
+        .map(|v| v.position)
+        .collect();
+    for corner in [[0., 1., 0.], [8., 1., 0.], [0., 1., 8.], [8., 1., 8.]] {
+        assert!(
+            top_positions.contains(&Point3::from(corner)),
+            "missing {corner:?} in {top_positions:?}"
+        );
+    }
+}
+
+/// Faces of different blocks, or of blocks with voxels, are not merged.
+#[test]
+fn greedy_merge_requires_identical_atoms() {
+    let mut space = Space::empty_positive(4, 1, 4);
+    space
+        .fill(space.bounds(), |p| Some(non_uniform_fill(p)))
+        .unwrap();
+    let (_, _, unmerged) = mesh_blocks_and_space(&space);
+    assert_eq!(
+        greedy_mesh(&space).vertices().len(),
+        unmerged.vertices().len()
+    );
+
+    let mut universe = Universe::new();
+    let [voxel_block] = make_some_voxel_blocks(&mut universe);
+    let mut space = Space::empty_positive(4, 1, 4);
+    space.fill_uniform(space.bounds(), &voxel_block).unwrap();
+    let (_, _, unmerged) = mesh_blocks_and_space(&space);
+    assert_eq!(greedy_mesh(&space), unmerged);
+}
+
+/// Faces are not merged across differences in the light falling on them.
+#[test]
+fn greedy_merge_respects_lighting() {
+    let [floor, roof] = make_some_blocks();
+    let mut space = Space::empty_positive(8, 3, 8);
+    space
+        .fill_uniform(GridAab::from_lower_size([0, 0, 0], [8, 1, 8]), &floor)
+        .unwrap();
+    space
+        .fill_uniform(GridAab::from_lower_size([0, 2, 0], [4, 1, 8]), &roof)
+        .unwrap();
+    space.bake_lighting(10, |_| {});
+    assert_ne!(space.get_lighting([1, 1, 1]), space.get_lighting([6, 1, 1]));
+
+    let floor_top_quads = greedy_mesh(&space)
+        .vertices()
+        .iter()
+        .filter(|v| v.face == PY && v.position.y == 1.0)
+        .count()
+        / 4;
+    assert!(floor_top_quads >= 2, "{floor_top_quads}");
+}
+
+/// [`SpaceMesh::compute_lod`] merges blocks into cells of their average color, omits
+/// faces between cells, and keeps faces on the boundary of the meshed region.
+#[test]
+fn lod_merges_cells_and_keeps_boundary_faces() {
+    let mut space = Space::empty_positive(4, 4, 4);
+    space
+        .fill(space.bounds(), |p| Some(non_uniform_fill(p)))
+        .unwrap();
+    let (_, block_meshes, _) = mesh_blocks_and_space(&space);
+    let options = &MeshOptions::dont_care_for_test();
+    let lod = LevelOfDetail::new(2);
+
+    let mut whole: SpaceMesh<BlockVertex<TtPoint>, TestTextureTile> = SpaceMesh::default();
+    whole.compute_lod(&space, space.bounds(), options, &*block_meshes, lod);
+    assert_eq!(
+        whole.vertices().len(),
+        4 /* vertices per face */
+        * 4 /* cell faces per exterior side of space */
+        * 6, /* sides of space */
+        "wrong number of faces"
+    );
+    for vertex in whole.vertices() {
+        assert_eq!(
+            vertex.coloring,
+            Coloring::Solid(Rgba::new(0.5, 0.5, 0.5, 1.0))
+        );
+        assert!(
+            vertex.position.x == 0.0 || vertex.position.x == 2.0 || vertex.position.x == 4.0,
+            "vertex not on cell grid: {vertex:?}"
+        );
+    }
+    assert_eq!(whole.opaque_range(), 0..whole.indices().len());
+    assert_eq!(whole.blocks_used_iter().count(), 2);
+
+    // Meshing half of the space must draw the face along the cut.
+    let half_bounds = GridAab::from_lower_size([0, 0, 0], [2, 4, 4]);
+    let mut half: SpaceMesh<BlockVertex<TtPoint>, TestTextureTile> = SpaceMesh::default();
+    half.compute_lod(&space, half_bounds, options, &*block_meshes, lod);
+    assert_eq!(half.vertices().len(), 4 * (2 * 4 + 4 * 2));
+}
+
 /// Run [`SpaceMesh::new`] with stale block data and confirm it does not panic.
 #[test]
 fn no_panic_on_missing_blocks() {
@@ -399,6 +519,46 @@ fn atom_transparency_thresholded() {
     // TODO: also test voxels -- including self-occlusion (thresholded voxel in front of truly opaque voxel)
 }
 
+#[test]
+fn atom_material_in_vertices() {
+    let block = Block::builder()
+        .color(Rgba::WHITE)
+        .material(Material::MIRROR)
+        .build();
+    let mesh = test_block_mesh(block);
+    let mut count = 0;
+    for (_, face_mesh) in mesh.all_face_meshes() {
+        for vertex in &face_mesh.vertices {
+            assert_eq!(vertex.material, Material::MIRROR);
+            count += 1;
+        }
+    }
+    assert_eq!(count, 24);
+}
+
+/// [`Tint`](crate::block::Tint) applies to the voxels, so the mesh uses the tinted colors
+/// and opacity, whether or not the block is textured.
+#[test]
+fn tinted_voxels() {
+    let mut u = Universe::new();
+    let block = Block::builder()
+        .voxels_fn(&mut u, R2, |_| Block::from(Rgba::WHITE))
+        .unwrap()
+        .build();
+    let red = Rgba::new(1.0, 0.0, 0.0, 1.0);
+
+    let mesh = test_block_mesh(block.clone().with_modifier(Tint::Multiply(red)));
+    for (_, face_mesh) in mesh.all_face_meshes() {
+        for vertex in &face_mesh.vertices {
+            assert_eq!(vertex.coloring, Coloring::Solid(red));
+        }
+    }
+    assert_eq!(opacities(&mesh), FaceMap::repeat(true));
+
+    let mesh = test_block_mesh(block.with_modifier(Tint::Multiply(Rgba::new(1.0, 1.0, 1.0, 0.5))));
+    assert_eq!(opacities(&mesh), FaceMap::repeat(false));
+}
+
 /// Test [`BlockMesh::fully_opaque`] results from basic voxels.
 #[test]
 fn fully_opaque_voxels() {
@@ -6,6 +6,9 @@ mod body;
 pub use body::*;
 mod collision;
 pub use collision::*;
+mod gravity;
+pub(crate) use gravity::gravity_at;
+pub use gravity::GravityZone;
 
 /// Close-but-not-intersecting objects are set to this separation.
 pub(crate) const POSITION_EPSILON: FreeCoordinate = 1e-6 * 1e-6;
@@ -16,8 +19,9 @@ mod tests {
     use crate::block::{Resolution, AIR};
     use crate::content::{make_slab, make_some_blocks};
     use crate::math::{Aab, CubeFace, Face7, Geometry, GridAab, GridPoint};
-    use crate::space::{Space, SpacePhysics};
+    use crate::space::{Space, SpacePhysics, SpaceTransaction};
     use crate::time::Tick;
+    use crate::transaction::{self, Transaction as _};
     use crate::universe::Universe;
     use cgmath::{EuclideanSpace, InnerSpace as _, Point3, Vector3, Zero as _};
     use ordered_float::NotNan;
@@ -66,6 +70,50 @@ mod tests {
         assert_eq!(body.position, Point3::new(6.0, -133.0, 0.0));
     }
 
+    #[test]
+    fn gravity_zone_overrides_space_gravity() {
+        let mut space = Space::empty(GridAab::from_lower_size([-10, -10, -10], [20, 20, 20]));
+        space.set_physics(SpacePhysics {
+            gravity: Vector3::new(0, -20, 0).map(NotNan::from),
+            ..SpacePhysics::default()
+        });
+        SpaceTransaction::add_behavior(
+            GridAab::from_lower_size([0, 0, 0], [10, 10, 10]),
+            GravityZone::new(Vector3::new(0, 0, 4).map(NotNan::from)),
+        )
+        .execute(&mut space, &mut transaction::no_outputs)
+        .unwrap();
+        // Nested zone which should take precedence over the larger one.
+        SpaceTransaction::add_behavior(
+            GridAab::from_lower_size([0, 0, 0], [2, 2, 2]),
+            GravityZone::new(Vector3::new(1, 0, 0).map(NotNan::from)),
+        )
+        .execute(&mut space, &mut transaction::no_outputs)
+        .unwrap();
+
+        assert_eq!(
+            space.gravity_at(Point3::new(-5., -5., -5.)),
+            Vector3::new(0., -20., 0.)
+        );
+        assert_eq!(
+            space.gravity_at(Point3::new(5., 5., 5.)),
+            Vector3::new(0., 0., 4.)
+        );
+        assert_eq!(
+            space.gravity_at(Point3::new(1.5, 0.5, 0.5)),
+            Vector3::new(1., 0., 0.)
+        );
+
+        let mut body = Body {
+            flying: false,
+            noclip: false,
+            ..Body::new_minimal((5., 5., 5.), Aab::new(-0.1, 0.1, -0.1, 0.1, -0.1, 0.1))
+        };
+        body.step(Tick::from_seconds(0.5), Some(&space), collision_noop);
+        assert_eq!(body.velocity, Vector3::new(0.0, 0.0, 2.0));
+        assert_eq!(body.position, Point3::new(5.0, 5.0, 6.0));
+    }
+
     #[test]
     fn paused_does_not_move() {
         let mut body = Body {
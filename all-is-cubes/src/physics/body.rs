@@ -9,7 +9,7 @@ use crate::block::{BlockCollision, Resolution};
 use crate::math::{Aab, Face7, FreeCoordinate, Geometry as _};
 use crate::physics::{StopAt, POSITION_EPSILON};
 use crate::raycast::Ray;
-use crate::space::Space;
+use crate::space::{DynamicLight, Space};
 use crate::time::Tick;
 use crate::transaction::{self, Transaction};
 use crate::util::{ConciseDebug, CustomFormat, StatusText};
@@ -61,6 +61,11 @@ pub struct Body {
     /// This does not affect the behavior of the [`Body`] itself; it has nothing to do with
     /// the direction of the velocity.
     pub pitch: FreeCoordinate,
+
+    /// Light carried by the body, such as a lantern or flashlight, which is positioned at
+    /// [`position`](Self::position) and oriented by [`yaw`](Self::yaw) and
+    /// [`pitch`](Self::pitch).
+    pub light: Option<DynamicLight>,
     // When adding a field, don't forget to expand the Debug impl.
 }
 
@@ -74,6 +79,7 @@ impl fmt::Debug for Body {
             .field("noclip", &self.noclip)
             .field("yaw", &self.yaw)
             .field("pitch", &self.pitch)
+            .field("light", &self.light)
             .finish()
     }
 }
@@ -113,6 +119,7 @@ impl Body {
             noclip: false,
             yaw: 0.0,
             pitch: 0.0,
+            light: None,
         }
     }
 
@@ -158,7 +165,7 @@ impl Body {
 
         if !self.flying && !tick.paused() {
             if let Some(space) = colliding_space {
-                self.velocity += space.physics().gravity.map(|c| c.into_inner()) * dt;
+                self.velocity += space.gravity_at(self.position) * dt;
             }
         }
 
@@ -0,0 +1,60 @@
+use cgmath::{Point3, Vector3};
+use ordered_float::NotNan;
+
+use crate::behavior::{Behavior, BehaviorContext};
+use crate::math::{point_to_enclosing_cube, FreeCoordinate};
+use crate::space::Space;
+#[cfg(doc)]
+use crate::space::{SpaceBehaviorAttachment, SpacePhysics};
+use crate::universe::{RefVisitor, VisitRefs};
+
+/// A [`Behavior`] which replaces the [`SpacePhysics::gravity`] of a [`Space`] within the
+/// bounds of its [`SpaceBehaviorAttachment`].
+///
+/// This may be used for low-gravity zones, rooms whose floor is a wall, jump pads
+/// (with gravity pointing upward), and so on.
+///
+/// If several zones contain the same point, the one with the smallest bounds applies,
+/// so that zones can be nested within each other.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct GravityZone {
+    /// Gravity vector for moving objects within the zone, in cubes/s².
+    pub gravity: Vector3<NotNan<FreeCoordinate>>,
+}
+
+impl GravityZone {
+    /// Constructs a [`GravityZone`] with the given gravity vector.
+    pub fn new(gravity: Vector3<NotNan<FreeCoordinate>>) -> Self {
+        Self { gravity }
+    }
+}
+
+impl Behavior<Space> for GravityZone {
+    fn alive(&self, _: &BehaviorContext<'_, Space>) -> bool {
+        true
+    }
+
+    fn ephemeral(&self) -> bool {
+        false
+    }
+}
+
+impl VisitRefs for GravityZone {
+    fn visit_refs(&self, _: &mut dyn RefVisitor) {}
+}
+
+/// Implementation of [`Space::gravity_at()`].
+pub(crate) fn gravity_at(space: &Space, point: Point3<FreeCoordinate>) -> Vector3<FreeCoordinate> {
+    let zone_gravity = point_to_enclosing_cube(point).and_then(|cube| {
+        space
+            .behaviors()
+            .query::<GravityZone>()
+            .filter(|item| item.attachment.bounds().contains_cube(cube))
+            .min_by_key(|item| item.attachment.bounds().volume())
+            .map(|item| item.behavior.gravity)
+    });
+    zone_gravity
+        .unwrap_or(space.physics().gravity)
+        .map(NotNan::into_inner)
+}
@@ -281,6 +281,36 @@ impl Raycaster {
         self.bounds = None;
     }
 
+    /// Moves the raycaster forward along the ray, without reporting any of the cubes
+    /// passed over, so that the next step reported is the first one whose
+    /// [`RaycastStep::t_distance`] is greater than `t`.
+    ///
+    /// Has no effect if `t` is not greater than the `t_distance` of the last step.
+    ///
+    /// TODO: This function was added for the needs of the raytracer. Think about API design more.
+    #[mutants::skip] // an optimization not a behavior change
+    pub(crate) fn skip_to(&mut self, t: FreeCoordinate) {
+        if t.is_nan() || t <= self.last_t_distance || self.emit_current {
+            return;
+        }
+        let mut new_state = self.ray.advance(t).cast();
+        if !new_state.emit_current {
+            // The new origin is outside the integer range, so we can't skip.
+            return;
+        }
+        new_state.bounds = self.bounds.clone(); // .within() would recurse
+
+        // Keep the original ray, so that later skips are measured from the same origin.
+        new_state.ray = self.ray;
+
+        // Adapt t values, and don't report the cube we are starting within.
+        new_state.t_max = new_state.t_max.map(|t_axis| t_axis + t);
+        new_state.last_t_distance = t;
+        new_state.emit_current = false;
+
+        *self = new_state;
+    }
+
     /// Determine the axis to step on and move in the appropriate direction along that axis.
     ///
     /// If this step would overflow the [`GridCoordinate`] range, returns [`Err`].
@@ -422,6 +452,9 @@ impl Raycaster {
 
             new_state.bounds = self.bounds.clone(); // .within() would recurse
 
+            // Keep the original ray, so that t values computed from it stay consistent.
+            new_state.ray = self.ray;
+
             // Adapt t values
             new_state.t_max = new_state.t_max.map(|t| t + t_start);
             new_state.last_t_distance = t_start;
@@ -1026,6 +1059,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn skip_to() {
+        let ray = Ray::new([0.5, 0.25, 0.125], [1.0, 0.375, 0.25]);
+        let reference: Vec<RaycastStep> = ray.cast().take(60).collect();
+
+        let mut r = ray.cast();
+        r.next();
+        r.skip_to(5.2);
+        r.skip_to(10.3);
+        let skipped: Vec<RaycastStep> = r.take_while(|s| s.t_distance() < 20.0).collect();
+        let expected: Vec<RaycastStep> = reference
+            .into_iter()
+            .filter(|s| s.t_distance() > 10.3 && s.t_distance() < 20.0)
+            .collect();
+        assert_eq!(skipped.len(), expected.len());
+        for (a, b) in skipped.iter().zip(expected.iter()) {
+            assert_eq!(a.cube_face(), b.cube_face());
+            assert!((a.t_distance() - b.t_distance()).abs() < 1e-9);
+        }
+    }
+
     #[test]
     fn within_bounds() {
         // Ray oriented diagonally on the -X side of bounds that are short on the X axis.
@@ -8,43 +8,58 @@
 //! it is much simpler. It continues to serve as a “reference implementation” and is used
 //! by the terminal UI and in unit tests via [`print_space`].
 
+use std::f64::consts::TAU;
 use std::fmt;
+use std::marker::PhantomData;
 
 use cgmath::{EuclideanSpace as _, InnerSpace as _, Point2, Vector2, Vector3, VectorSpace as _};
 use cgmath::{Point3, Vector4};
 use ordered_float::NotNan;
+use rand::{Rng as _, SeedableRng as _};
+use rand_xoshiro::Xoshiro256Plus;
 #[cfg(feature = "threads")]
 use rayon::iter::{IntoParallelIterator as _, ParallelIterator as _};
 
-use crate::block::{Evoxels, AIR};
-use crate::camera::{Camera, GraphicsOptions, TransparencyOption};
+use crate::block::Evoxels;
+use crate::camera::{Camera, FogOption, GraphicsOptions, LightingOption, TransparencyOption};
 use crate::math::{
-    point_to_enclosing_cube, smoothstep, Face7, FreeCoordinate, GridAab, GridArray, GridPoint, Rgb,
-    Rgba,
+    point_to_enclosing_cube, smoothstep, Face6, Face7, FreeCoordinate, GridPoint, Rgb, Rgba,
 };
 use crate::raycast::Ray;
-use crate::space::{BlockIndex, PackedLight, Space, SpaceBlockData};
+use crate::space::{BlockIndex, Fog, PackedLight, Sky, Space, SpaceBlockData, SpacePhysics};
 use crate::util::{CustomFormat, StatusText};
 
 mod pixel_buf;
 pub use pixel_buf::*;
 mod renderer;
 pub use renderer::*;
+mod accel;
+use accel::OccupancyPyramid;
+mod cubes;
+use cubes::TracingCubes;
 mod surface;
 use surface::{DepthIter, DepthStep, Span, Surface, SurfaceIter, TraceStep};
 // TODO: pub use surface::*;
 mod text;
 pub use text::*;
+mod auxiliary;
+pub use auxiliary::*;
 
 /// Precomputed data for raytracing a single frame of a single [`Space`], and bearer of
 /// the methods for actually performing raytracing.
 pub struct SpaceRaytracer<D: RtBlockData> {
     blocks: Vec<TracingBlock<D>>,
-    cubes: GridArray<TracingCubeData>,
+    cubes: TracingCubes,
+    /// Which regions of `cubes` are empty, for skipping them.
+    occupancy: OccupancyPyramid,
 
     graphics_options: GraphicsOptions,
     custom_options: D::Options,
     sky_color: Rgb,
+    /// Directional sky, if any; see [`SpacePhysics::sky`].
+    sky: Option<Sky>,
+    /// Volumetric fog, if any and if enabled; see [`SpacePhysics::fog`].
+    fog: Option<Fog>,
     sky_data: D,
     packed_sky_color: PackedLight,
 }
@@ -61,14 +76,18 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
             custom_options: &custom_options,
         };
         let sky_color = space.physics().sky_color;
+        let cubes = TracingCubes::new(space);
         SpaceRaytracer {
             blocks: space
                 .block_data()
                 .iter()
                 .map(|sbd| TracingBlock::<D>::from_block(options, sbd))
                 .collect(),
-            cubes: prepare_cubes(space),
+            occupancy: OccupancyPyramid::new(&cubes),
+            cubes,
             sky_color,
+            sky: space.physics().sky.clone(),
+            fog: enabled_fog(&graphics_options, space.physics()),
             sky_data: D::sky(options),
             packed_sky_color: sky_color.into(),
 
@@ -87,11 +106,14 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
             graphics_options: &graphics_options,
             custom_options: &custom_options,
         };
+        let cubes = TracingCubes::empty();
         SpaceRaytracer {
             blocks: vec![],
-            cubes: GridArray::from_elements(GridAab::from_lower_upper([0, 0, 0], [0, 0, 0]), [])
-                .unwrap(),
+            occupancy: OccupancyPyramid::new(&cubes),
+            cubes,
             sky_color,
+            sky: None,
+            fog: None,
             sky_data: D::sky(options),
             packed_sky_color: sky_color.into(),
 
@@ -100,11 +122,50 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
         }
     }
 
+    /// Replaces the sky data with that from the given physics.
+    pub(crate) fn set_sky(&mut self, physics: &SpacePhysics) {
+        self.sky_color = physics.sky_color;
+        self.sky = physics.sky.clone();
+        self.fog = enabled_fog(&self.graphics_options, physics);
+        self.packed_sky_color = physics.sky_color.into();
+    }
+
     /// Computes a single image pixel from the given ray.
     pub fn trace_ray<P: PixelBuf<BlockData = D>>(
         &self,
         ray: Ray,
         include_sky: bool,
+    ) -> (P, RaytraceInfo) {
+        self.trace_ray_sample(ray, include_sky, 0)
+    }
+
+    /// As [`Self::trace_ray()`], but with a sample index which selects the random
+    /// choices made by [`LightingOption::PathTraced`], so that multiple samples of the
+    /// same pixel may be averaged.
+    ///
+    /// [`LightingOption::PathTraced`]: crate::camera::LightingOption::PathTraced
+    pub(crate) fn trace_ray_sample<P: PixelBuf<BlockData = D>>(
+        &self,
+        ray: Ray,
+        include_sky: bool,
+        sample: u32,
+    ) -> (P, RaytraceInfo) {
+        self.trace_ray_impl(
+            ray,
+            include_sky,
+            RayContext {
+                direction: ray.direction,
+                sample,
+                depth: 0,
+            },
+        )
+    }
+
+    fn trace_ray_impl<P: PixelBuf<BlockData = D>>(
+        &self,
+        ray: Ray,
+        include_sky: bool,
+        context: RayContext,
     ) -> (P, RaytraceInfo) {
         let options = RtOptionsRef {
             graphics_options: &self.graphics_options,
@@ -114,14 +175,19 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
         let mut state: TracingState<P> = TracingState {
             t_to_absolute_distance: ray.direction.magnitude(),
             cubes_traced: 0,
+            context,
             pixel_buf: P::default(),
         };
-        let surface_iter = SurfaceIter::new(self, ray);
+        // The ray may be bent by refraction, in which case this is the last segment.
+        let mut ray = ray;
 
         // Use the more expensive volumetric tracing strategy only if we need it.
         match self.graphics_options.transparency {
             TransparencyOption::Volumetric => {
-                for step in DepthIter::new(surface_iter) {
+                // TODO: Implement refraction for volumetric transparency.
+                // Distance along the ray up to which fog has been applied.
+                let mut fog_t: FreeCoordinate = 0.0;
+                for step in DepthIter::new(SurfaceIter::new(self, ray)) {
                     if state.count_step_should_stop(options) {
                         break;
                     }
@@ -132,33 +198,104 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
                         }
                         DepthStep::Span(span) => {
                             debug_assert!(!span.surface.diffuse_color.fully_transparent());
+                            state.trace_through_fog(self, ray, fog_t, span.surface.t_distance);
+                            fog_t = span.exit_t_distance;
                             state.trace_through_span(span, self);
                         }
                     }
                 }
+                state.trace_through_fog(self, ray, fog_t, FreeCoordinate::INFINITY);
             }
             _ => {
-                for step in surface_iter {
-                    if state.count_step_should_stop(options) {
-                        break;
-                    }
-
-                    use TraceStep::*;
-                    match step {
-                        Invisible { .. } | EnterBlock { .. } => {
-                            // Side effect: called count_step_should_stop.
+                // Refractive index of the material the ray is currently passing through.
+                let mut medium: f32 = 1.0;
+                let mut segments = 0;
+                // Distance along the current segment up to which fog has been applied.
+                let mut fog_t: FreeCoordinate = 0.0;
+                'segment: loop {
+                    for step in SurfaceIter::new(self, ray) {
+                        if state.count_step_should_stop(options) {
+                            break 'segment;
                         }
-                        EnterSurface(surface) => {
-                            debug_assert!(!surface.diffuse_color.fully_transparent());
-                            state.trace_through_surface(surface, self);
+
+                        use TraceStep::*;
+                        match step {
+                            EnterBlock { .. } => {
+                                // Side effect: called count_step_should_stop.
+                            }
+                            Invisible { t_distance } => {
+                                if medium != 1.0 && segments < MAX_REFRACTIONS {
+                                    // Leaving a refractive material into empty space.
+                                    let exit_point = ray.origin + ray.direction * t_distance;
+                                    if let Some(face) = self.exit_face(exit_point, ray.direction) {
+                                        let (new_ray, refracted) = refract_ray(
+                                            exit_point,
+                                            ray.direction,
+                                            -face.normal_vector(),
+                                            medium,
+                                        );
+                                        if refracted {
+                                            medium = 1.0;
+                                        }
+                                        state.trace_through_fog(self, ray, fog_t, t_distance);
+                                        fog_t = 0.0;
+                                        ray = new_ray;
+                                        state.context.direction = ray.direction;
+                                        segments += 1;
+                                        continue 'segment;
+                                    }
+                                    medium = 1.0;
+                                }
+                            }
+                            EnterSurface(surface) => {
+                                debug_assert!(!surface.diffuse_color.fully_transparent());
+                                if segments > 0 && surface.normal == Face7::Within {
+                                    // This is the surface the ray was just refracted at.
+                                    continue;
+                                }
+                                let Surface {
+                                    material,
+                                    normal,
+                                    intersection_point,
+                                    ..
+                                } = surface;
+                                state.trace_through_fog(self, ray, fog_t, surface.t_distance);
+                                fog_t = surface.t_distance;
+                                state.trace_through_surface(surface, self);
+
+                                let index = material.refractive_index.into_inner();
+                                if index != medium
+                                    && normal != Face7::Within
+                                    && segments < MAX_REFRACTIONS
+                                    && !state.pixel_buf.opaque()
+                                {
+                                    // Entering a refractive material.
+                                    let (new_ray, refracted) = refract_ray(
+                                        intersection_point,
+                                        ray.direction,
+                                        normal.normal_vector(),
+                                        medium / index,
+                                    );
+                                    if refracted {
+                                        medium = index;
+                                    }
+                                    fog_t = 0.0;
+                                    ray = new_ray;
+                                    state.context.direction = ray.direction;
+                                    segments += 1;
+                                    continue 'segment;
+                                }
+                            }
                         }
                     }
+                    state.trace_through_fog(self, ray, fog_t, FreeCoordinate::INFINITY);
+                    break;
                 }
             }
         }
         state.finish(
             if include_sky {
-                self.sky_color.with_alpha_one()
+                self.sky_light(ray.direction).with_alpha_one()
             } else {
                 Rgba::TRANSPARENT
             },
@@ -182,6 +319,118 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
             .unwrap_or(self.sky_color)
     }
 
+    /// Returns the light emitted by the block in the given cube.
+    #[inline]
+    fn get_emission(&self, cube: GridPoint) -> Rgb {
+        match self.cubes.get(cube) {
+            Some(cube_data) if !cube_data.always_invisible => {
+                self.blocks[cube_data.block_index as usize].light_emission
+            }
+            _ => Rgb::ZERO,
+        }
+    }
+
+    /// Computes the color seen along a secondary ray, such as a reflection, including the
+    /// sky.
+    fn trace_secondary(&self, ray: Ray, context: RayContext) -> Rgb {
+        let (buf, _info) = self.trace_ray_impl::<SecondaryBuf<D>>(ray, true, context);
+        Rgba::from(buf.0).to_rgb()
+    }
+
+    /// Given a point where a ray traveling in `direction` leaves a surface, finds the
+    /// face of that surface, by tracing backwards.
+    fn exit_face(
+        &self,
+        point: Point3<FreeCoordinate>,
+        direction: Vector3<FreeCoordinate>,
+    ) -> Option<Face7> {
+        let direction = direction.normalize();
+        let probe = Ray {
+            origin: point + direction * SURFACE_EPSILON,
+            direction: -direction,
+        };
+        SurfaceIter::new(self, probe)
+            .take(4)
+            .find_map(|step| match step {
+                TraceStep::EnterSurface(surface) => Some(surface.normal),
+                _ => None,
+            })
+            .filter(|&face| face != Face7::Within)
+    }
+
+    /// Returns the light arriving from outside the space in the given direction.
+    fn sky_light(&self, direction: Vector3<FreeCoordinate>) -> Rgb {
+        match &self.sky {
+            Some(sky) => sky.sample(direction),
+            None => self.sky_color,
+        }
+    }
+
+    /// Estimates the light falling on a surface by tracing one random path from it,
+    /// for [`LightingOption::PathTraced`](crate::camera::LightingOption::PathTraced).
+    ///
+    /// Each bounce picks a cosine-weighted direction in the hemisphere above the
+    /// surface, so that the result need only be multiplied by the diffuse color of each
+    /// surface to give a correct (if noisy) estimate for Lambertian reflection.
+    /// Light-emitting blocks and the sky are the light sources.
+    fn path_traced_light(
+        &self,
+        cube: GridPoint,
+        point: Point3<FreeCoordinate>,
+        face: Face7,
+        rng: &mut Xoshiro256Plus,
+    ) -> Rgb {
+        /// Maximum number of surfaces a path may reflect from.
+        const MAX_BOUNCES: usize = 4;
+        /// Maximum number of raytracing steps per bounce, as in [`TracingState`].
+        const MAX_STEPS: usize = 1000;
+
+        let mut light = Rgb::ZERO;
+        let mut throughput = Rgb::ONE;
+        let (mut cube, mut point, mut face) = (cube, point, face);
+        'bounce: for _ in 0..MAX_BOUNCES {
+            if face == Face7::Within {
+                // No meaningful hemisphere to sample; use the precomputed light instead.
+                light += throughput * self.get_lighting(cube);
+                break;
+            }
+            let direction = cosine_weighted_direction(face, rng);
+            let ray = Ray {
+                origin: point + face.normal_vector() * (0.5 / 256.0),
+                direction,
+            };
+            for (steps, step) in SurfaceIter::new(self, ray).enumerate() {
+                if steps > MAX_STEPS {
+                    break 'bounce;
+                }
+                let TraceStep::EnterSurface(surface) = step else {
+                    continue;
+                };
+                // Treat partially transparent surfaces as opaque with probability equal
+                // to their alpha, so that on average the right amount of light passes.
+                let alpha = self
+                    .graphics_options
+                    .transparency
+                    .limit_alpha(surface.diffuse_color)
+                    .alpha()
+                    .into_inner();
+                if rng.gen::<f32>() >= alpha {
+                    continue;
+                }
+                light += throughput * self.get_emission(surface.cube);
+                throughput = throughput * surface.diffuse_color.to_rgb();
+                cube = surface.cube;
+                point = surface.intersection_point;
+                face = surface.normal;
+                continue 'bounce;
+            }
+            // The ray left the space without hitting anything.
+            light += throughput * self.sky_light(direction);
+            break;
+        }
+        light
+    }
+
     fn get_interpolated_light(&self, point: Point3<FreeCoordinate>, face: Face7) -> Rgb {
         // This implementation is duplicated in GLSL at all-is-cubes-gpu/src/shaders/fragment.glsl
 
@@ -255,6 +504,93 @@ impl<D: RtBlockData> SpaceRaytracer<D> {
         );
         Rgb::try_from(v.truncate() / v.w.max(0.1)).unwrap()
     }
+
+    /// Computes the darkening of a surface by nearby geometry, at the resolution of the
+    /// voxels of the block in `cube`, for [`GraphicsOptions::ambient_occlusion`].
+    ///
+    /// This is the classic voxel technique: the voxels beside and diagonal to the one in
+    /// front of the surface are examined to find how occluded each corner of the surface
+    /// is, and the result is interpolated across the surface. It approximates the
+    /// screen-space ambient occlusion of the GPU renderer.
+    fn voxel_ambient_occlusion(
+        &self,
+        cube: GridPoint,
+        point: Point3<FreeCoordinate>,
+        face: Face7,
+    ) -> f32 {
+        /// Fraction by which a fully occluded corner is darkened.
+        const STRENGTH: f32 = 0.5;
+
+        let Ok(face) = Face6::try_from(face) else {
+            return 1.0;
+        };
+        let voxel_size = match self.cubes.get(cube) {
+            Some(cube_data) => FreeCoordinate::from(
+                self.blocks[cube_data.block_index as usize]
+                    .voxels
+                    .resolution(),
+            )
+            .recip(),
+            None => 1.0,
+        };
+
+        // Center of the voxel-sized cell in front of the surface.
+        let front = point + face.normal_vector() * (voxel_size / 2.0);
+        let front_center = (front / voxel_size).map(|c| (c.floor() + 0.5) * voxel_size);
+
+        let reference_frame = face.matrix(0).to_free();
+        let tangent_1 = reference_frame.x.truncate();
+        let tangent_2 = reference_frame.y.truncate();
+        let mix_1 = (front.to_vec().dot(tangent_1) / voxel_size).rem_euclid(1.0) as f32;
+        let mix_2 = (front.to_vec().dot(tangent_2) / voxel_size).rem_euclid(1.0) as f32;
+
+        let occupied =
+            |offset: Vector3<FreeCoordinate>| self.is_opaque_at(front_center + offset * voxel_size);
+        let corner = |sign_1: FreeCoordinate, sign_2: FreeCoordinate| -> f32 {
+            let side_1 = occupied(tangent_1 * sign_1);
+            let side_2 = occupied(tangent_2 * sign_2);
+            let diagonal = occupied(tangent_1 * sign_1 + tangent_2 * sign_2);
+            if side_1 && side_2 {
+                0.0
+            } else {
+                1.0 - f32::from(u8::from(side_1) + u8::from(side_2) + u8::from(diagonal)) / 3.0
+            }
+        };
+
+        let unoccluded = lerp(
+            lerp(corner(-1.0, -1.0), corner(-1.0, 1.0), mix_2),
+            lerp(corner(1.0, -1.0), corner(1.0, 1.0), mix_2),
+            mix_1,
+        );
+        1.0 - STRENGTH * (1.0 - unoccluded)
+    }
+
+    /// Returns whether the voxel (or cube) containing `point` is fully opaque.
+    fn is_opaque_at(&self, point: Point3<FreeCoordinate>) -> bool {
+        let Some(cube) = point_to_enclosing_cube(point) else {
+            return false;
+        };
+        let Some(cube_data) = self.cubes.get(cube) else {
+            return false;
+        };
+        if cube_data.always_invisible {
+            return false;
+        }
+        match &self.blocks[cube_data.block_index as usize].voxels {
+            Evoxels::One(voxel) => voxel.color.fully_opaque(),
+            Evoxels::Many(resolution, array) => {
+                let within_block =
+                    (point - cube.map(FreeCoordinate::from)) * FreeCoordinate::from(*resolution);
+                point_to_enclosing_cube(Point3::from_vec(within_block))
+                    .and_then(|voxel| array.get(voxel))
+                    .map_or(false, |voxel| voxel.color.fully_opaque())
+            }
+        }
+    }
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
 }
 
 /// Text-specific methods.
@@ -376,6 +712,7 @@ where
             .field("graphics_options", &self.graphics_options)
             .field("custom_options", &self.custom_options)
             .field("sky_color", &self.sky_color)
+            .field("sky", &self.sky)
             .finish_non_exhaustive()
     }
 }
@@ -414,23 +751,92 @@ impl CustomFormat<StatusText> for RaytraceInfo {
     }
 }
 
-/// Get cube data out of [`Space`].
-#[inline]
-fn prepare_cubes(space: &Space) -> GridArray<TracingCubeData> {
-    space.extract(space.bounds(), |index, block_data, lighting| {
-        TracingCubeData {
-            block_index: index.unwrap(),
-            lighting,
-            always_invisible: block_data.block() == &AIR,
-        }
-    })
+/// Picks a random direction in the hemisphere above `face`, with probability
+/// proportional to the cosine of the angle from the surface normal.
+///
+/// `face` must not be [`Face7::Within`].
+fn cosine_weighted_direction(face: Face7, rng: &mut Xoshiro256Plus) -> Vector3<FreeCoordinate> {
+    let normal = face.normal_vector::<FreeCoordinate>();
+    // Since the normal is axis-aligned, permuting its components gives a perpendicular.
+    let tangent = Vector3::new(normal.z, normal.x, normal.y);
+    let bitangent = normal.cross(tangent);
+
+    let u: FreeCoordinate = rng.gen();
+    let angle = rng.gen::<FreeCoordinate>() * TAU;
+    let radius = u.sqrt();
+    tangent * (radius * angle.cos())
+        + bitangent * (radius * angle.sin())
+        + normal * (1.0 - u).sqrt()
 }
 
-#[derive(Clone, Debug)]
+/// Constructs the random number generator used for path tracing a surface,
+/// such that it is different for each point and sample but deterministic.
+fn path_tracing_rng(point: Point3<FreeCoordinate>, sample: u32) -> Xoshiro256Plus {
+    Xoshiro256Plus::seed_from_u64(
+        point.x.to_bits()
+            ^ point.y.to_bits().rotate_left(21)
+            ^ point.z.to_bits().rotate_left(42)
+            ^ u64::from(sample).wrapping_mul(0x9E37_79B9_7F4A_7C15),
+    )
+}
+
+/// Distance from a surface at which to start secondary rays, so that they do not
+/// intersect the surface they started from. This is about half the size of the smallest
+/// permissible voxel.
+const SURFACE_EPSILON: FreeCoordinate = 0.5 / 256.0;
+
+/// Maximum number of times a ray may be refracted.
+const MAX_REFRACTIONS: usize = 8;
+
+/// Maximum number of reflections that will be traced from a single pixel.
+const MAX_REFLECTION_DEPTH: u8 = 3;
+
+/// Computes the continuation of a ray crossing a surface from a material with refractive
+/// index `n1` to one with index `n2`, where `eta = n1 / n2`, and `normal` is the
+/// surface normal pointing towards the side the ray came from.
+///
+/// Returns the new ray and whether it was refracted (true) or totally internally
+/// reflected (false).
+fn refract_ray(
+    point: Point3<FreeCoordinate>,
+    direction: Vector3<FreeCoordinate>,
+    normal: Vector3<FreeCoordinate>,
+    eta: f32,
+) -> (Ray, bool) {
+    let eta = FreeCoordinate::from(eta);
+    let direction = direction.normalize();
+    let cos_incident = -direction.dot(normal);
+    let k = 1.0 - eta * eta * (1.0 - cos_incident * cos_incident);
+    let (new_direction, refracted) = if k < 0.0 {
+        (reflect(direction, normal), false)
+    } else {
+        (
+            direction * eta + normal * (eta * cos_incident - k.sqrt()),
+            true,
+        )
+    };
+    (
+        Ray {
+            origin: point + new_direction * SURFACE_EPSILON,
+            direction: new_direction,
+        },
+        refracted,
+    )
+}
+
+/// Reflects `direction` about the plane with the given unit `normal`.
+fn reflect(
+    direction: Vector3<FreeCoordinate>,
+    normal: Vector3<FreeCoordinate>,
+) -> Vector3<FreeCoordinate> {
+    direction - normal * (2.0 * direction.dot(normal))
+}
+
+#[derive(Clone, Debug, PartialEq)]
 struct TracingCubeData {
     block_index: BlockIndex,
     lighting: PackedLight,
-    /// True if the block is [`AIR`].
+    /// True if the block is [`AIR`](crate::block::AIR).
     ///
     /// This special information allows us to skip an indirect memory access in this
     /// extremely common case. We could generalize it to any block which is fully
@@ -443,6 +849,7 @@ struct TracingCubeData {
 #[derive(Clone, Debug)]
 struct TracingBlock<D> {
     block_data: D,
+    light_emission: Rgb,
     // TODO: `Evoxels` carries more data than we actually need (color). Experiment with using a packed format.
     voxels: Evoxels,
 }
@@ -454,14 +861,61 @@ impl<D: RtBlockData> TracingBlock<D> {
     ) -> Self {
         TracingBlock {
             block_data: D::from_block(options, space_block_data),
+            light_emission: space_block_data.evaluated().attributes.light_emission,
             voxels: space_block_data.evaluated().voxels.clone(),
         }
     }
 }
 
+/// Information about the ray being traced which is needed to compute the appearance of
+/// surfaces.
+#[derive(Clone, Copy, Debug)]
+struct RayContext {
+    /// Current direction of the ray (which may differ from the original direction after
+    /// refraction).
+    direction: Vector3<FreeCoordinate>,
+    /// Index of the sample being computed, for random choices such as those made by
+    /// path tracing.
+    sample: u32,
+    /// Number of reflections which led to this ray.
+    depth: u8,
+}
+
+/// [`PixelBuf`] for secondary rays, which need only color regardless of the
+/// [`RtBlockData`] in use.
+struct SecondaryBuf<D>(ColorBuf, PhantomData<fn() -> D>);
+
+impl<D> Default for SecondaryBuf<D> {
+    fn default() -> Self {
+        Self(ColorBuf::default(), PhantomData)
+    }
+}
+
+impl<D: RtBlockData> PixelBuf for SecondaryBuf<D> {
+    type BlockData = D;
+
+    #[inline]
+    fn opaque(&self) -> bool {
+        self.0.opaque()
+    }
+
+    #[inline]
+    fn add(&mut self, surface_color: Rgba, _block_data: &Self::BlockData) {
+        self.0.add(surface_color, &())
+    }
+
+    fn hit_nothing(&mut self) {
+        self.0.hit_nothing()
+    }
+
+    fn mean<const N: usize>(items: [Self; N]) -> Self {
+        Self(ColorBuf::mean(items.map(|item| item.0)), PhantomData)
+    }
+}
+
 /// Holds a [`PixelBuf`] and other per-ray state, and updates it
 /// according to the things it encounters.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug)]
 struct TracingState<P: PixelBuf> {
     /// Conversion factor from raycaster `t` values to “true” [`Space`] distance values
     /// where 1 unit = 1 block thickness.
@@ -471,6 +925,8 @@ struct TracingState<P: PixelBuf> {
     /// equal to the number of calls to [`Self::trace_through_surface()`].
     cubes_traced: usize,
 
+    context: RayContext,
+
     pixel_buf: P,
 }
 impl<P: PixelBuf> TracingState<P> {
@@ -520,6 +976,59 @@ impl<P: PixelBuf> TracingState<P> {
         )
     }
 
+    /// Apply the effect of the space's [`Fog`], if any, on the part of `ray` between
+    /// `t_start` and `t_end`: light from behind is attenuated and the light falling on
+    /// the fog is scattered towards the viewer.
+    ///
+    /// Only the portion of the ray inside the bounds of the space is affected.
+    fn trace_through_fog(
+        &mut self,
+        rt: &SpaceRaytracer<P::BlockData>,
+        ray: Ray,
+        t_start: FreeCoordinate,
+        t_end: FreeCoordinate,
+    ) {
+        /// Limit on the number of samples per ray segment, for long rays.
+        const MAX_STEPS: usize = 64;
+
+        let Some(fog) = rt.fog else { return };
+        let bounds = rt.cubes.bounds();
+        let t_start = t_start.max(accel::entry_t(ray, bounds));
+        let t_end = t_end.min(accel::exit_t(ray, bounds));
+        let ray_length = ray.direction.magnitude();
+        if t_end <= t_start || ray_length == 0.0 {
+            return;
+        }
+
+        // Take approximately one sample per cube, since that is the resolution of the
+        // light data.
+        let steps = (((t_end - t_start) * ray_length).ceil() as usize).clamp(1, MAX_STEPS);
+        let step_t = (t_end - t_start) / steps as FreeCoordinate;
+        let step_distance = (step_t * ray_length) as f32;
+        for i in 0..steps {
+            if self.pixel_buf.opaque() {
+                break;
+            }
+            let point = ray.origin + ray.direction * (t_start + step_t * (i as f64 + 0.5));
+            let density = fog.density_at(point.y);
+            let Ok(alpha) = NotNan::new(1.0 - (-density * step_distance).exp()) else {
+                continue;
+            };
+            if alpha.into_inner() <= 0.0 {
+                continue;
+            }
+            let light = match rt.graphics_options.lighting_display {
+                LightingOption::None => Rgb::ONE,
+                _ => match point_to_enclosing_cube(point) {
+                    Some(cube) => rt.get_lighting(cube),
+                    None => rt.sky_color,
+                },
+            };
+            self.pixel_buf
+                .add((fog.color * light).with_alpha(alpha), &rt.sky_data);
+        }
+    }
+
     /// Apply the effect of a given surface color.
     #[inline]
     fn trace_through_surface(
@@ -527,7 +1036,18 @@ impl<P: PixelBuf> TracingState<P> {
         surface: Surface<'_, P::BlockData>,
         rt: &SpaceRaytracer<P::BlockData>,
     ) {
-        if let Some(color) = surface.to_lit_color(rt) {
+        if let Some(color) = surface.to_lit_color(rt, self.context) {
+            if let Some(cube_data) = rt.cubes.get(surface.cube) {
+                self.pixel_buf.hit_surface(
+                    &SurfaceHit {
+                        distance: surface.t_distance * self.t_to_absolute_distance,
+                        normal: surface.normal,
+                        cube: surface.cube,
+                        block_index: cube_data.block_index,
+                    },
+                    surface.block_data,
+                );
+            }
             self.pixel_buf.add(color, surface.block_data);
         }
     }
@@ -562,6 +1082,14 @@ impl<P: PixelBuf> TracingState<P> {
     }
 }
 
+/// Returns the fog to be rendered for the given physics, if the options permit.
+fn enabled_fog(graphics_options: &GraphicsOptions, physics: &SpacePhysics) -> Option<Fog> {
+    match graphics_options.fog {
+        FogOption::None => None,
+        _ => physics.fog,
+    }
+}
+
 pub use updating::*;
 mod updating;
 
@@ -0,0 +1,247 @@
+//! Acceleration structure allowing rays to skip over empty regions of space.
+
+use crate::math::{FreeCoordinate, GridAab, GridArray, GridCoordinate, GridPoint};
+use crate::raycast::Ray;
+use crate::raytracer::cubes::TracingCubes;
+
+/// Ratio of the cell sizes of successive levels of an [`OccupancyPyramid`].
+const LEVEL_SCALE: GridCoordinate = 4;
+
+/// Records which regions of a [`SpaceRaytracer`]'s cubes might contain anything visible,
+/// at several levels of detail, so that rays can skip over empty regions without
+/// visiting every cube in them.
+///
+/// Level `n` divides space into cells which are <code>LEVEL_SCALE<sup>n+1</sup></code>
+/// cubes on a side, aligned to multiples of that size. Each cell is `true` if any cube
+/// within it is not [`TracingCubeData::always_invisible`]. The last level is the first
+/// one which has only a single cell, or, if the space extends to both sides of the
+/// origin, the first which has only the two cells on either side of it along each such
+/// axis (since no coarser level would have fewer cells).
+///
+/// [`SpaceRaytracer`]: super::SpaceRaytracer
+/// [`TracingCubeData::always_invisible`]: super::TracingCubeData::always_invisible
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct OccupancyPyramid {
+    levels: Vec<GridArray<bool>>,
+}
+
+impl OccupancyPyramid {
+    pub fn new(cubes: &TracingCubes) -> Self {
+        let mut levels = vec![GridArray::from_fn(
+            cubes.bounds().divide(LEVEL_SCALE),
+            |cell| compute_base_cell(cubes, cell),
+        )];
+        loop {
+            let previous = levels.last().unwrap();
+            let size = previous.bounds().size();
+            let next_bounds = previous.bounds().divide(LEVEL_SCALE);
+            if (size.x <= 1 && size.y <= 1 && size.z <= 1) || next_bounds == previous.bounds() {
+                break;
+            }
+            let next = GridArray::from_fn(next_bounds, |cell| compute_upper_cell(previous, cell));
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    /// Updates the pyramid after the contents of `cubes` at `cube` have changed.
+    pub fn update(&mut self, cubes: &TracingCubes, cube: GridPoint) {
+        let mut cell = cell_containing(cube);
+        let mut occupied = compute_base_cell(cubes, cell);
+        for level_index in 0..self.levels.len() {
+            if level_index > 0 {
+                occupied = compute_upper_cell(&self.levels[level_index - 1], cell);
+            }
+            match self.levels[level_index].get_mut(cell) {
+                Some(stored) if *stored == occupied => {
+                    // No change, so no higher level can change either.
+                    return;
+                }
+                Some(stored) => *stored = occupied,
+                None => return,
+            }
+            cell = cell_containing(cell);
+        }
+    }
+
+    /// Updates the pyramid after the contents of `cubes` within `region` have changed.
+    pub fn update_region(&mut self, cubes: &TracingCubes, region: GridAab) {
+        for cell in region.divide(LEVEL_SCALE).interior_iter() {
+            self.update(cubes, cell * LEVEL_SCALE);
+        }
+    }
+
+    /// Returns the bounds of the largest cell which contains `cube` and is entirely
+    /// empty, or [`None`] if there is no such cell.
+    #[inline]
+    pub fn empty_cell(&self, cube: GridPoint) -> Option<GridAab> {
+        let mut cell = cell_containing(cube);
+        let mut cell_size = LEVEL_SCALE;
+        let mut found = None;
+        for level in &self.levels {
+            match level.get(cell) {
+                Some(false) => found = Some(GridAab::single_cube(cell).multiply(cell_size)),
+                _ => break,
+            }
+            cell = cell_containing(cell);
+            cell_size *= LEVEL_SCALE;
+        }
+        found
+    }
+}
+
+/// Returns the cell of the next level up which contains `cube` (which may be a cube or
+/// a cell of the current level).
+#[inline]
+fn cell_containing(cube: GridPoint) -> GridPoint {
+    cube.map(|c| c.div_euclid(LEVEL_SCALE))
+}
+
+fn compute_base_cell(cubes: &TracingCubes, cell: GridPoint) -> bool {
+    match GridAab::single_cube(cell)
+        .multiply(LEVEL_SCALE)
+        .intersection(cubes.bounds())
+    {
+        Some(region) => region
+            .interior_iter()
+            .any(|cube| !cubes[cube].always_invisible),
+        None => false,
+    }
+}
+
+fn compute_upper_cell(lower_level: &GridArray<bool>, cell: GridPoint) -> bool {
+    match GridAab::single_cube(cell)
+        .multiply(LEVEL_SCALE)
+        .intersection(lower_level.bounds())
+    {
+        Some(region) => region
+            .interior_iter()
+            .any(|lower_cell| lower_level[lower_cell]),
+        None => false,
+    }
+}
+
+/// Returns the `t` value (in units of `ray.direction`) at which `ray` enters `bounds`,
+/// which is negative if the origin of the ray is inside `bounds`, or infinite if the
+/// ray never enters `bounds`.
+pub(crate) fn entry_t(ray: Ray, bounds: GridAab) -> FreeCoordinate {
+    let lower = bounds.lower_bounds();
+    let upper = bounds.upper_bounds();
+    let mut t = FreeCoordinate::NEG_INFINITY;
+    for axis in 0..3 {
+        let direction = ray.direction[axis];
+        let origin = ray.origin[axis];
+        let (lower, upper) = (
+            FreeCoordinate::from(lower[axis]),
+            FreeCoordinate::from(upper[axis]),
+        );
+        let plane = if direction > 0.0 {
+            lower
+        } else if direction < 0.0 {
+            upper
+        } else if (lower..=upper).contains(&origin) {
+            continue;
+        } else {
+            return FreeCoordinate::INFINITY;
+        };
+        t = t.max((plane - origin) / direction);
+    }
+    if t > exit_t(ray, bounds) {
+        FreeCoordinate::INFINITY
+    } else {
+        t
+    }
+}
+
+/// Returns the `t` value (in units of `ray.direction`) at which `ray`, which is assumed
+/// to start inside or before `bounds`, leaves `bounds`.
+pub(crate) fn exit_t(ray: Ray, bounds: GridAab) -> FreeCoordinate {
+    let lower = bounds.lower_bounds();
+    let upper = bounds.upper_bounds();
+    let mut t = FreeCoordinate::INFINITY;
+    for axis in 0..3 {
+        let direction = ray.direction[axis];
+        let plane = if direction > 0.0 {
+            upper[axis]
+        } else if direction < 0.0 {
+            lower[axis]
+        } else {
+            continue;
+        };
+        t = t.min((FreeCoordinate::from(plane) - ray.origin[axis]) / direction);
+    }
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::math::Rgba;
+    use crate::space::Space;
+
+    #[test]
+    fn empty_cells() {
+        let mut space = Space::empty_positive(40, 40, 40);
+        space.set([1, 1, 1], Block::from(Rgba::WHITE)).unwrap();
+        let cubes = TracingCubes::new(&space);
+        let pyramid = OccupancyPyramid::new(&cubes);
+
+        assert_eq!(pyramid.empty_cell(GridPoint::new(0, 0, 0)), None);
+        assert_eq!(
+            pyramid.empty_cell(GridPoint::new(5, 0, 0)),
+            Some(GridAab::from_lower_size([4, 0, 0], [4, 4, 4]))
+        );
+        assert_eq!(
+            pyramid.empty_cell(GridPoint::new(20, 0, 0)),
+            Some(GridAab::from_lower_size([16, 0, 0], [16, 16, 16]))
+        );
+        assert_eq!(
+            pyramid.empty_cell(GridPoint::new(39, 39, 39)),
+            Some(GridAab::from_lower_size([32, 32, 32], [16, 16, 16]))
+        );
+    }
+
+    /// Bounds which include negative coordinates divide down to the cells on either
+    /// side of the origin, which must not cause the construction to loop forever.
+    #[test]
+    fn bounds_straddling_origin() {
+        let mut space =
+            Space::builder(GridAab::from_lower_size([-10, -10, -1], [20, 20, 5])).build();
+        space.set([-9, 5, 0], Block::from(Rgba::WHITE)).unwrap();
+        let cubes = TracingCubes::new(&space);
+        let pyramid = OccupancyPyramid::new(&cubes);
+
+        assert_eq!(
+            pyramid.levels.last().unwrap().bounds(),
+            GridAab::from_lower_upper([-1, -1, -1], [1, 1, 1])
+        );
+        assert_eq!(pyramid.empty_cell(GridPoint::new(-9, 5, 0)), None);
+        assert_eq!(
+            pyramid.empty_cell(GridPoint::new(5, 5, 0)),
+            Some(GridAab::from_lower_size([0, 0, 0], [16, 16, 16]))
+        );
+    }
+
+    #[test]
+    fn update_matches_new() {
+        let mut space = Space::empty_positive(40, 40, 40);
+        space.set([1, 1, 1], Block::from(Rgba::WHITE)).unwrap();
+        let mut cubes = TracingCubes::new(&space);
+        let mut pyramid = OccupancyPyramid::new(&cubes);
+
+        for (cube, block) in [
+            (GridPoint::new(30, 2, 20), Block::from(Rgba::WHITE)),
+            (GridPoint::new(1, 1, 1), crate::block::AIR),
+        ] {
+            space.set(cube, block).unwrap();
+            cubes = TracingCubes::new(&space);
+            pyramid.update(&cubes, cube);
+            assert_eq!(
+                pyramid.levels,
+                OccupancyPyramid::new(&cubes).levels,
+                "after setting {cube:?}"
+            );
+        }
+    }
+}
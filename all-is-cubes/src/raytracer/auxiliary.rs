@@ -0,0 +1,267 @@
+//! Raytracing output of information other than color: depth, normals, and block
+//! identity, for each pixel.
+
+use cgmath::{Vector2, Vector3};
+use image::{ImageBuffer, LumaA, Rgba as ImageRgba, RgbaImage};
+
+use crate::block::Primitive;
+use crate::math::{FreeCoordinate, Rgba};
+use crate::raytracer::{
+    ColorBuf, PixelBuf, RaytraceInfo, RtBlockData, RtOptionsRef, RtRenderer, SurfaceHit,
+};
+use crate::space::SpaceBlockData;
+use crate::universe::Name;
+
+/// Implements [`RtBlockData`] by recording the name of the [`BlockDef`] that each block
+/// refers to, if it is a [`Primitive::Indirect`] block.
+///
+/// [`BlockDef`]: crate::block::BlockDef
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct AuxRtData(pub Option<Name>);
+
+impl RtBlockData for AuxRtData {
+    type Options = ();
+
+    fn from_block(_: RtOptionsRef<'_, Self::Options>, s: &SpaceBlockData) -> Self {
+        match s.block().primitive() {
+            Primitive::Indirect(def_ref) => Self(Some(def_ref.name())),
+            _ => Self(None),
+        }
+    }
+
+    fn error(_: RtOptionsRef<'_, Self::Options>) -> Self {
+        Self(None)
+    }
+
+    fn sky(_: RtOptionsRef<'_, Self::Options>) -> Self {
+        Self(None)
+    }
+}
+
+/// Implements [`PixelBuf`] for [`AuxPixel`] output: color, plus information about the
+/// first surface the ray hit.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuxBuf {
+    color: ColorBuf,
+    hit: Option<(SurfaceHit, Option<Name>)>,
+}
+
+impl PixelBuf for AuxBuf {
+    type BlockData = AuxRtData;
+
+    #[inline]
+    fn opaque(&self) -> bool {
+        self.color.opaque()
+    }
+
+    #[inline]
+    fn add(&mut self, surface_color: Rgba, _block_data: &Self::BlockData) {
+        self.color.add(surface_color, &());
+    }
+
+    #[inline]
+    fn hit_surface(&mut self, hit: &SurfaceHit, block_data: &Self::BlockData) {
+        if self.hit.is_none() {
+            self.hit = Some((*hit, block_data.0.clone()));
+        }
+    }
+
+    fn hit_nothing(&mut self) {
+        self.color.hit_nothing();
+    }
+
+    fn mean<const N: usize>(items: [Self; N]) -> Self {
+        // Geometric information cannot be meaningfully averaged, so take the first hit.
+        let hit = items.iter().find_map(|buf| buf.hit.clone());
+        Self {
+            color: ColorBuf::mean(items.map(|buf| buf.color)),
+            hit,
+        }
+    }
+}
+
+/// A single pixel of raytracing output produced by [`AuxBuf`].
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct AuxPixel {
+    /// The color of the pixel, as [`ColorBuf`] would produce.
+    pub color: Rgba,
+    /// The first surface the ray hit, if any.
+    pub hit: Option<SurfaceHit>,
+    /// The name of the [`BlockDef`] of the block that was hit, if it has one.
+    ///
+    /// [`BlockDef`]: crate::block::BlockDef
+    pub block_name: Option<Name>,
+}
+
+impl From<AuxBuf> for AuxPixel {
+    fn from(buf: AuxBuf) -> Self {
+        let (hit, block_name) = match buf.hit {
+            Some((hit, name)) => (Some(hit), name),
+            None => (None, None),
+        };
+        AuxPixel {
+            color: Rgba::from(buf.color),
+            hit,
+            block_name,
+        }
+    }
+}
+
+/// An image of [`AuxPixel`]s, as produced by [`RtRenderer::draw_aux()`], with methods
+/// to convert each kind of information to a conventional image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuxImage {
+    size: Vector2<u32>,
+    pixels: Vec<AuxPixel>,
+}
+
+impl AuxImage {
+    /// Returns the width and height of the image.
+    pub fn size(&self) -> Vector2<u32> {
+        self.size
+    }
+
+    /// Returns the pixels in row-major order, starting from the top left.
+    pub fn pixels(&self) -> &[AuxPixel] {
+        &self.pixels
+    }
+
+    /// Returns the pixel at the given position, or [`None`] if it is out of bounds.
+    pub fn get(&self, x: u32, y: u32) -> Option<&AuxPixel> {
+        if x < self.size.x && y < self.size.y {
+            self.pixels
+                .get(y as usize * self.size.x as usize + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the distance to the first surface hit by each pixel's ray, scaled so
+    /// that a distance of `far` or more is the maximum value.
+    ///
+    /// This is the distance along the ray, not the view-space Z coordinate; under a
+    /// perspective projection, a flat surface facing the camera therefore appears
+    /// farther away towards the edges of the image.
+    ///
+    /// Pixels which hit nothing are transparent.
+    pub fn depth_image(&self, far: FreeCoordinate) -> ImageBuffer<LumaA<u16>, Vec<u16>> {
+        self.to_image(|pixel| match pixel.hit {
+            Some(hit) => LumaA([
+                ((hit.distance / far).clamp(0.0, 1.0) * FreeCoordinate::from(u16::MAX)) as u16,
+                u16::MAX,
+            ]),
+            None => LumaA([u16::MAX, 0]),
+        })
+    }
+
+    /// Returns the surface normal of the first surface hit by each pixel's ray, with
+    /// each component mapped from the range −1 to 1 to the range 0 to 255.
+    ///
+    /// Pixels which hit nothing are transparent, and [`Face7::Within`] is gray.
+    ///
+    /// [`Face7::Within`]: crate::math::Face7::Within
+    pub fn normal_image(&self) -> RgbaImage {
+        self.to_image(|pixel| match pixel.hit {
+            Some(hit) => {
+                let n = hit.normal.normal_vector::<i32>() * 127 + Vector3::new(128, 128, 128);
+                ImageRgba([n.x as u8, n.y as u8, n.z as u8, 255])
+            }
+            None => ImageRgba([0, 0, 0, 0]),
+        })
+    }
+
+    /// Returns the [`BlockIndex`](crate::space::BlockIndex) of the block hit by each
+    /// pixel's ray.
+    ///
+    /// Pixels which hit nothing are transparent.
+    pub fn block_index_image(&self) -> ImageBuffer<LumaA<u16>, Vec<u16>> {
+        self.to_image(|pixel| match pixel.hit {
+            Some(hit) => LumaA([hit.block_index, u16::MAX]),
+            None => LumaA([0, 0]),
+        })
+    }
+
+    fn to_image<P: image::Pixel>(
+        &self,
+        f: impl Fn(&AuxPixel) -> P,
+    ) -> ImageBuffer<P, Vec<P::Subpixel>> {
+        ImageBuffer::from_fn(self.size.x, self.size.y, |x, y| f(self.get(x, y).unwrap()))
+    }
+}
+
+impl RtRenderer<AuxRtData> {
+    /// As [`Self::draw()`], but producing an [`AuxImage`], which contains the
+    /// distance, normal, cube, and block of the surface seen in each pixel
+    /// as well as its color.
+    ///
+    /// [`Camera::post_process_color()`] is applied to the colors.
+    ///
+    /// [`Camera::post_process_color()`]: crate::camera::Camera::post_process_color
+    pub fn draw_aux(&self) -> (AuxImage, RaytraceInfo) {
+        let camera = self.cameras().cameras().world.clone();
+        let size = self.modified_viewport().framebuffer_size;
+        let blank = AuxPixel {
+            color: Rgba::TRANSPARENT,
+            hit: None,
+            block_name: None,
+        };
+        let mut pixels = vec![blank; size.x as usize * size.y as usize];
+        let info = self.draw::<AuxBuf, _, AuxPixel, _>(
+            |_| String::new(),
+            |buf| {
+                let mut pixel = AuxPixel::from(buf);
+                pixel.color = camera.post_process_color(pixel.color);
+                pixel
+            },
+            &mut pixels,
+        );
+        (AuxImage { size, pixels }, info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::{Block, BlockDef};
+    use crate::camera::GraphicsOptions;
+    use crate::math::{Face7, GridPoint};
+    use crate::raycast::Ray;
+    use crate::raytracer::SpaceRaytracer;
+    use crate::space::Space;
+    use crate::universe::{Universe, UniverseIndex as _};
+
+    #[test]
+    fn aux_buf_records_first_hit() {
+        let mut universe = Universe::new();
+        let def = universe
+            .insert("b".into(), BlockDef::new(Block::from(Rgba::WHITE)))
+            .unwrap();
+        let mut space = Space::empty_positive(1, 1, 2);
+        space
+            .set([0, 0, 1], Block::from_primitive(Primitive::Indirect(def)))
+            .unwrap();
+        let rt = SpaceRaytracer::<AuxRtData>::new(&space, GraphicsOptions::default(), ());
+
+        let (buf, _) = rt.trace_ray::<AuxBuf>(Ray::new([0.5, 0.5, -1.0], [0., 0., 0.5]), true);
+        let pixel = AuxPixel::from(buf);
+        assert_eq!(
+            pixel.hit,
+            Some(SurfaceHit {
+                distance: 2.0,
+                normal: Face7::NZ,
+                cube: GridPoint::new(0, 0, 1),
+                block_index: 1,
+            })
+        );
+        assert_eq!(pixel.block_name, Some(Name::from("b")));
+
+        let image = AuxImage {
+            size: Vector2::new(1, 1),
+            pixels: vec![pixel],
+        };
+        assert_eq!(image.normal_image().get_pixel(0, 0).0, [128, 128, 1, 255]);
+        assert_eq!(image.block_index_image().get_pixel(0, 0).0, [1, u16::MAX]);
+    }
+}
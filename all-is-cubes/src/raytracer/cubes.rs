@@ -0,0 +1,99 @@
+//! Per-cube tracing data, stored in chunks so that it can be updated piecewise.
+
+use crate::block::AIR;
+use crate::chunking::{cube_to_chunk, ChunkPos};
+use crate::math::{GridAab, GridArray, GridCoordinate, GridPoint};
+use crate::raytracer::TracingCubeData;
+use crate::space::Space;
+
+/// Edge length of the chunks of [`TracingCubes`].
+pub(super) const CHUNK_SIZE: GridCoordinate = 16;
+
+/// The [`TracingCubeData`] for every cube in a [`Space`]'s bounds.
+///
+/// The data is divided into chunks so that [`UpdatingSpaceRaytracer`] can re-read only
+/// the regions of the space that changed, rather than taking a new snapshot of the
+/// whole space.
+///
+/// [`UpdatingSpaceRaytracer`]: super::UpdatingSpaceRaytracer
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct TracingCubes {
+    bounds: GridAab,
+    /// Indexed by chunk position. Each chunk's bounds are the intersection of its
+    /// [`ChunkPos::bounds()`] with `bounds`.
+    chunks: GridArray<GridArray<TracingCubeData>>,
+}
+
+impl TracingCubes {
+    /// Snapshots the entire contents of `space`.
+    pub fn new(space: &Space) -> Self {
+        let bounds = space.bounds();
+        Self {
+            bounds,
+            chunks: GridArray::from_fn(bounds.divide(CHUNK_SIZE), |chunk| {
+                extract_chunk(space, bounds, ChunkPos(chunk))
+            }),
+        }
+    }
+
+    /// Returns a [`TracingCubes`] containing no cubes.
+    pub fn empty() -> Self {
+        let bounds = GridAab::from_lower_upper([0, 0, 0], [0, 0, 0]);
+        Self {
+            bounds,
+            chunks: GridArray::from_elements(bounds, []).unwrap(),
+        }
+    }
+
+    pub fn bounds(&self) -> GridAab {
+        self.bounds
+    }
+
+    #[inline]
+    pub fn get(&self, cube: GridPoint) -> Option<&TracingCubeData> {
+        self.chunks
+            .get(cube_to_chunk::<CHUNK_SIZE>(cube).0)?
+            .get(cube)
+    }
+
+    /// Re-reads the given chunk from `space`, which must have the same bounds as the
+    /// space this was created from.
+    ///
+    /// Returns the region which was updated, or [`None`] if the chunk is outside the
+    /// bounds.
+    pub fn update_chunk(&mut self, space: &Space, chunk: ChunkPos<CHUNK_SIZE>) -> Option<GridAab> {
+        debug_assert_eq!(space.bounds(), self.bounds);
+        let bounds = self.bounds;
+        let stored = self.chunks.get_mut(chunk.0)?;
+        *stored = extract_chunk(space, bounds, chunk);
+        Some(stored.bounds())
+    }
+}
+
+impl std::ops::Index<GridPoint> for TracingCubes {
+    type Output = TracingCubeData;
+
+    #[track_caller]
+    fn index(&self, cube: GridPoint) -> &Self::Output {
+        match self.get(cube) {
+            Some(data) => data,
+            None => panic!("cube {cube:?} is out of bounds {:?}", self.bounds),
+        }
+    }
+}
+
+fn extract_chunk(
+    space: &Space,
+    bounds: GridAab,
+    chunk: ChunkPos<CHUNK_SIZE>,
+) -> GridArray<TracingCubeData> {
+    let region = chunk
+        .bounds()
+        .intersection(bounds)
+        .expect("chunk should intersect the space bounds");
+    space.extract(region, |index, block_data, lighting| TracingCubeData {
+        block_index: index.unwrap(),
+        lighting,
+        always_invisible: block_data.block() == &AIR,
+    })
+}
@@ -5,8 +5,8 @@ use std::convert::TryFrom as _;
 use cgmath::{Vector3, Zero as _};
 
 use crate::camera::GraphicsOptions;
-use crate::math::Rgba;
-use crate::space::SpaceBlockData;
+use crate::math::{Face7, FreeCoordinate, GridPoint, Rgba};
+use crate::space::{BlockIndex, SpaceBlockData};
 
 /// Borrowed data which may be used to customize the result of raytracing.
 #[derive(Debug, Eq, PartialEq)]
@@ -58,6 +58,16 @@ pub trait PixelBuf: Default {
     /// more future-proof.
     fn add(&mut self, surface_color: Rgba, block_data: &Self::BlockData);
 
+    /// Informs the buffer of the geometry of a surface which the ray intersected,
+    /// immediately before the surface's color is passed to [`Self::add`].
+    ///
+    /// This may be used to produce outputs other than color, such as depth or normal
+    /// buffers. The default implementation does nothing.
+    #[inline]
+    fn hit_surface(&mut self, hit: &SurfaceHit, block_data: &Self::BlockData) {
+        let _ = (hit, block_data);
+    }
+
     /// Indicates that the trace did not intersect any space that could have contained
     /// anything to draw. May be used for special diagnostic drawing. If used, should
     /// disable the effects of future [`Self::add`] calls.
@@ -86,6 +96,23 @@ pub trait PixelBuf: Default {
     fn mean<const N: usize>(items: [Self; N]) -> Self;
 }
 
+/// Geometric information about a surface intersected by a ray,
+/// as passed to [`PixelBuf::hit_surface()`].
+///
+/// [`Space`]: crate::space::Space
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct SurfaceHit {
+    /// Distance from the origin of the ray to the intersection point, in units of cubes.
+    pub distance: FreeCoordinate,
+    /// Normal vector of the surface, or [`Face7::Within`] if the ray started inside it.
+    pub normal: Face7,
+    /// The cube of the [`Space`] containing the block which was hit.
+    pub cube: GridPoint,
+    /// The index, in the [`Space`], of the block which was hit.
+    pub block_index: BlockIndex,
+}
+
 /// Precomputed data about a [`Space`]'s blocks that may be used by a [`PixelBuf`].
 ///
 /// Design note: This is a trait of the data type itself so as to require that there
@@ -1,18 +1,20 @@
 use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
 
-use cgmath::{ElementWise, Point2, Vector2};
+use cgmath::{ElementWise, Matrix4, Point2, Vector2, Vector4};
 use futures_core::future::BoxFuture;
 use image::RgbaImage;
 use ordered_float::NotNan;
 
 use crate::camera::{
     AntialiasingOption, Camera, Flaws, FogOption, GraphicsOptions, HeadlessRenderer, Layers,
-    RenderError, StandardCameras, Viewport,
+    LightingOption, RenderError, StandardCameras, Viewport,
 };
 use crate::character::Cursor;
 use crate::content::palette;
 use crate::listen::ListenableSource;
-use crate::math::Rgba;
+use crate::math::{FreeCoordinate, Rgba};
 use crate::raytracer::{
     ColorBuf, PixelBuf, RaytraceInfo, RtBlockData, RtOptionsRef, SpaceRaytracer,
     UpdatingSpaceRaytracer,
@@ -36,6 +38,17 @@ pub struct RtRenderer<D: RtBlockData = ()> {
     /// Whether there was a [`Cursor`] to be drawn.
     /// Raytracing doesn't yet support cursors but we need to report that.
     had_cursor: bool,
+
+    /// Incremented whenever the contents of `rts` change.
+    scene_generation: u64,
+
+    /// Number of images drawn, used to make each one a different sample
+    /// for [`LightingOption::PathTraced`].
+    sample_counter: AtomicU32,
+
+    /// Samples accumulated by [`RtRenderer::draw_rgba()`] for
+    /// [`LightingOption::PathTraced`].
+    accumulation: Mutex<Accumulation>,
 }
 
 impl<D: RtBlockData> RtRenderer<D>
@@ -58,6 +71,9 @@ where
             size_policy,
             custom_options,
             had_cursor: false,
+            scene_generation: 0,
+            sample_counter: AtomicU32::new(0),
+            accumulation: Mutex::new(Accumulation::default()),
         }
     }
 
@@ -80,16 +96,17 @@ where
             optional_space: Option<&URef<Space>>,
             graphics_options_source: &ListenableSource<GraphicsOptions>,
             custom_options_source: &ListenableSource<D::Options>,
-        ) -> Result<(), RenderError>
+        ) -> Result<bool, RenderError>
         where
             D::Options: Clone + Sync + 'static,
         {
             // TODO: this Option-synchronization pattern is recurring in renderers but also ugly ... look for ways to make it nicer
 
             // Check whether we need to replace the raytracer:
+            let mut changed = true;
             match (optional_space, &mut *cached_rt) {
                 // Matches already
-                (Some(space), Some(rt)) if space == rt.space() => {}
+                (Some(space), Some(rt)) if space == rt.space() => changed = false,
                 // Needs replacement
                 (Some(space), rt) => {
                     *rt = Some(UpdatingSpaceRaytracer::new(
@@ -99,27 +116,32 @@ where
                     ))
                 }
                 // Space is None, so drop raytracer if any
-                (None, c) => *c = None,
+                (None, c) => changed = c.take().is_some(),
             }
             // Now that we have one if we should have one, update it.
             if let Some(rt) = cached_rt {
+                let generation = rt.generation();
                 rt.update().map_err(RenderError::Read)?;
+                changed |= rt.generation() != generation;
             }
-            Ok(())
+            Ok(changed)
         }
         let gs = self.cameras.graphics_options_source();
-        sync_space(
+        let world_changed = sync_space(
             &mut self.rts.world,
             Option::as_ref(&self.cameras.world_space().get()),
             &gs,
             &self.custom_options,
         )?;
-        sync_space(
+        let ui_changed = sync_space(
             &mut self.rts.ui,
             self.cameras.ui_space(),
             &gs,
             &self.custom_options,
         )?;
+        if world_changed || ui_changed {
+            self.scene_generation += 1;
+        }
 
         Ok(())
     }
@@ -139,6 +161,9 @@ where
     /// with any [`PixelBuf`] instead of requiring [`ColorBuf`] and [`Rgba`] output,
     /// is not async, and does not require `&mut self`.
     ///
+    /// If [`LightingOption::PathTraced`] is in use, each call produces an independent
+    /// noisy sample of the image; [`Self::draw_rgba()`] averages them.
+    ///
     /// [`Universe`]: crate::universe::Universe
     pub fn draw<P, E, O, IF>(&self, info_text_fn: IF, encoder: E, output: &mut [O]) -> RaytraceInfo
     where
@@ -169,6 +194,7 @@ where
                 .map(|opt_urt| opt_urt.as_ref().map(|urt| urt.get())),
             cameras: &cameras,
             options,
+            sample: self.sample_counter.fetch_add(1, Ordering::Relaxed),
         };
 
         let info = trace_image::trace_scene_to_image_impl(scene, &encoder, output);
@@ -209,6 +235,10 @@ impl RtRenderer<()> {
     /// As [`Self::draw()`], but the output is an [`RgbaImage`], and
     /// [`Camera::post_process_color()`] is applied to the pixels.
     ///
+    /// If [`LightingOption::PathTraced`] is in use, the output is the average of all
+    /// images drawn since the scene, camera, or options last changed, so that repeated
+    /// calls progressively reduce noise.
+    ///
     ///  [`Camera::post_process_color()`]: crate::camera::Camera::post_process_color
     pub fn draw_rgba(
         &self,
@@ -216,17 +246,50 @@ impl RtRenderer<()> {
     ) -> (RgbaImage, RaytraceInfo, Flaws) {
         let camera = self.cameras.cameras().world.clone();
 
+        let viewport = self.modified_viewport();
         let Vector2 {
             x: width,
             y: height,
-        } = self.modified_viewport().framebuffer_size;
+        } = viewport.framebuffer_size;
         let mut image = RgbaImage::new(width, height);
+        let output = bytemuck::cast_slice_mut::<u8, [u8; 4]>(image.as_mut());
 
-        let info = self.draw::<ColorBuf, _, [u8; 4], _>(
-            info_text_fn,
-            |pixel_buf| camera.post_process_color(Rgba::from(pixel_buf)).to_srgb8(),
-            bytemuck::cast_slice_mut::<u8, [u8; 4]>(image.as_mut()),
-        );
+        let info = if self.cameras.graphics_options().lighting_display == LightingOption::PathTraced
+        {
+            let mut samples = vec![Rgba::TRANSPARENT; output.len()];
+            let info =
+                self.draw::<ColorBuf, _, Rgba, _>(|_| String::new(), Rgba::from, &mut samples);
+
+            let key = AccumulationKey {
+                scene_generation: self.scene_generation,
+                viewport,
+                options: self.cameras.graphics_options().clone(),
+                view_matrices: self.cameras.cameras().as_refs().map(Camera::view_matrix),
+                projections: self.cameras.cameras().as_refs().map(Camera::projection),
+            };
+            let mut accumulation = self.accumulation.lock().unwrap();
+            accumulation.add(key, &samples);
+            for (pixel_out, color) in output.iter_mut().zip(accumulation.mean()) {
+                *pixel_out = camera.post_process_color(color).to_srgb8();
+            }
+
+            let info_text: String = info_text_fn(&info);
+            if !info_text.is_empty() && camera.options().debug_info_text {
+                eg::draw_info_text(
+                    output,
+                    viewport,
+                    [Rgba::BLACK.to_srgb8(), Rgba::WHITE.to_srgb8()],
+                    &info_text,
+                );
+            }
+            info
+        } else {
+            self.draw::<ColorBuf, _, [u8; 4], _>(
+                info_text_fn,
+                |pixel_buf| camera.post_process_color(Rgba::from(pixel_buf)).to_srgb8(),
+                output,
+            )
+        };
 
         let options = self.cameras.graphics_options();
         let mut flaws = Flaws::empty();
@@ -239,6 +302,9 @@ impl RtRenderer<()> {
         if !matches!(options.fog, FogOption::None) {
             flaws |= Flaws::NO_FOG;
         }
+        if options.dynamic_lights && options.lighting_display != LightingOption::None {
+            flaws |= Flaws::NO_DYNAMIC_LIGHTS;
+        }
 
         (image, info, flaws)
     }
@@ -258,6 +324,45 @@ where
     }
 }
 
+/// Running sum of path-traced images, used by [`RtRenderer::draw_rgba()`].
+#[derive(Debug, Default)]
+struct Accumulation {
+    /// The conditions under which `sum` was drawn; if they change, it is discarded.
+    key: Option<AccumulationKey>,
+    sum: Vec<Vector4<f32>>,
+    count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct AccumulationKey {
+    scene_generation: u64,
+    viewport: Viewport,
+    options: GraphicsOptions,
+    view_matrices: Layers<Matrix4<FreeCoordinate>>,
+    projections: Layers<Matrix4<FreeCoordinate>>,
+}
+
+impl Accumulation {
+    fn add(&mut self, key: AccumulationKey, samples: &[Rgba]) {
+        if self.key.as_ref() != Some(&key) || self.sum.len() != samples.len() {
+            self.key = Some(key);
+            self.sum = vec![Vector4::new(0.0, 0.0, 0.0, 0.0); samples.len()];
+            self.count = 0;
+        }
+        for (sum, &sample) in self.sum.iter_mut().zip(samples) {
+            *sum += Vector4::from(sample);
+        }
+        self.count += 1;
+    }
+
+    fn mean(&self) -> impl Iterator<Item = Rgba> + '_ {
+        let scale = (self.count.max(1) as f32).recip();
+        self.sum
+            .iter()
+            .map(move |&sum| Rgba::try_from(sum * scale).unwrap())
+    }
+}
+
 impl HeadlessRenderer for RtRenderer<()> {
     fn update<'a>(
         &'a mut self,
@@ -288,6 +393,8 @@ struct RtScene<'a, P: PixelBuf> {
     /// Cameras *with* size_policy applied.
     cameras: &'a Layers<Camera>,
     options: RtOptionsRef<'a, <P::BlockData as RtBlockData>::Options>,
+    /// Sample index passed to [`SpaceRaytracer::trace_ray_sample()`].
+    sample: u32,
 }
 
 impl<'a, P: PixelBuf> Clone for RtScene<'a, P> {
@@ -304,7 +411,7 @@ impl<P: PixelBuf> RtScene<'_, P> {
     fn trace_patch(&self, patch: NdcRect) -> (P, RaytraceInfo) {
         if let Some(ui) = self.rts.ui {
             let (pixel, info): (P, RaytraceInfo) =
-                trace_patch_in_one_space(ui, &self.cameras.ui, patch, false);
+                trace_patch_in_one_space(ui, &self.cameras.ui, patch, false, self.sample);
             if pixel.opaque() {
                 // TODO: We should be doing alpha blending, but doing that requires
                 // having control over the PixelBuf that trace_ray starts with.
@@ -312,7 +419,7 @@ impl<P: PixelBuf> RtScene<'_, P> {
             }
         }
         if let Some(world) = self.rts.world {
-            return trace_patch_in_one_space(world, &self.cameras.world, patch, true);
+            return trace_patch_in_one_space(world, &self.cameras.world, patch, true, self.sample);
         }
         (
             P::paint(palette::NO_WORLD_TO_SHOW, self.options),
@@ -326,11 +433,14 @@ fn trace_patch_in_one_space<P: PixelBuf>(
     camera: &Camera,
     patch: NdcRect,
     include_sky: bool,
+    sample: u32,
 ) -> (P, RaytraceInfo) {
     match camera.options().antialiasing {
-        AntialiasingOption::None | AntialiasingOption::IfCheap => {
-            space.trace_ray(camera.project_ndc_into_world(patch.center()), include_sky)
-        }
+        AntialiasingOption::None | AntialiasingOption::IfCheap => space.trace_ray_sample(
+            camera.project_ndc_into_world(patch.center()),
+            include_sky,
+            sample,
+        ),
         AntialiasingOption::Always => {
             const N: usize = 4;
             const SAMPLE_POINTS: [Vector2<f64>; N] = [
@@ -341,9 +451,10 @@ fn trace_patch_in_one_space<P: PixelBuf>(
             ];
             let mut info = RaytraceInfo::default();
             let samples: [P; N] = std::array::from_fn(|i| {
-                let (p, i) = space.trace_ray(
+                let (p, i) = space.trace_ray_sample(
                     camera.project_ndc_into_world(patch.point_within(SAMPLE_POINTS[i])),
                     include_sky,
+                    sample,
                 );
                 info += i;
                 p
@@ -581,4 +692,35 @@ mod tests {
     fn renderer_is_send_sync() {
         assert_send_sync::<RtRenderer>()
     }
+
+    #[test]
+    fn accumulation_averages_until_key_changes() {
+        let key = |scene_generation| AccumulationKey {
+            scene_generation,
+            viewport: Viewport::with_scale(1.0, Vector2::new(1, 1)),
+            options: GraphicsOptions::default(),
+            view_matrices: Layers {
+                world: Matrix4::from_scale(1.0),
+                ui: Matrix4::from_scale(1.0),
+            },
+            projections: Layers {
+                world: Matrix4::from_scale(1.0),
+                ui: Matrix4::from_scale(1.0),
+            },
+        };
+        let mut accumulation = Accumulation::default();
+
+        accumulation.add(key(0), &[Rgba::new(1.0, 0.0, 0.0, 1.0)]);
+        accumulation.add(key(0), &[Rgba::new(0.0, 1.0, 0.0, 1.0)]);
+        assert_eq!(
+            accumulation.mean().collect::<Vec<_>>(),
+            vec![Rgba::new(0.5, 0.5, 0.0, 1.0)]
+        );
+
+        accumulation.add(key(1), &[Rgba::new(0.0, 0.0, 1.0, 1.0)]);
+        assert_eq!(
+            accumulation.mean().collect::<Vec<_>>(),
+            vec![Rgba::new(0.0, 0.0, 1.0, 1.0)]
+        );
+    }
 }
@@ -1,10 +1,19 @@
-use cgmath::{EuclideanSpace as _, Point3, Vector3};
+use cgmath::{EuclideanSpace as _, InnerSpace as _, Point3, Vector3};
+use ordered_float::NotNan;
+use rand::Rng as _;
 
-use crate::block::{recursive_ray, Evoxel, Evoxels};
+use crate::block::{recursive_ray, Evoxel, Evoxels, Material};
+#[cfg(doc)]
+use crate::camera::GraphicsOptions;
 use crate::camera::LightingOption;
 use crate::math::{Face7, FaceMap, FreeCoordinate, GridArray, GridPoint, Rgb, Rgba};
 use crate::raycast::{Ray, Raycaster};
-use crate::raytracer::{RtBlockData, SpaceRaytracer, TracingBlock, TracingCubeData};
+use crate::raytracer::accel::{self, OccupancyPyramid};
+use crate::raytracer::cubes::TracingCubes;
+use crate::raytracer::{
+    path_tracing_rng, reflect, RayContext, RtBlockData, SpaceRaytracer, TracingBlock,
+    TracingCubeData, MAX_REFLECTION_DEPTH, SURFACE_EPSILON,
+};
 
 /// Description of a surface the ray passes through (or from the volumetric perspective,
 /// a transition from one material to another).
@@ -14,13 +23,14 @@ pub(crate) struct Surface<'a, D> {
     pub block_data: &'a D,
     // pub voxel_data: ...?,
     pub diffuse_color: Rgba,
+    pub material: Material,
     /// The cube of the [`Space`] which contains the block this surface belongs to.
-    cube: GridPoint,
+    pub cube: GridPoint,
     /// The distance along the ray, in units of the ray's direction vector,
     /// where it intersected the surface.
     pub t_distance: FreeCoordinate,
     /// The point in the [`Space`]'s coordinate system where the ray intersected the surface.
-    intersection_point: Point3<FreeCoordinate>,
+    pub intersection_point: Point3<FreeCoordinate>,
     pub normal: Face7,
 }
 
@@ -28,10 +38,13 @@ impl<D: RtBlockData> Surface<'_, D> {
     /// Convert the surface and its lighting to a single RGBA value as determined by
     /// the given graphics options, or [`None`] if it is invisible.
     ///
+    /// `context` describes the ray, which is needed to compute reflections and
+    /// to select the random choices of [`LightingOption::PathTraced`].
+    ///
     /// Note this is not true volumetric ray tracing: we're considering each
     /// voxel surface to be discrete.
     #[inline]
-    pub(crate) fn to_lit_color(&self, rt: &SpaceRaytracer<D>) -> Option<Rgba> {
+    pub(crate) fn to_lit_color(&self, rt: &SpaceRaytracer<D>, context: RayContext) -> Option<Rgba> {
         let diffuse_color = rt
             .graphics_options
             .transparency
@@ -39,8 +52,63 @@ impl<D: RtBlockData> Surface<'_, D> {
         if diffuse_color.fully_transparent() {
             return None;
         }
-        let adjusted_rgb = diffuse_color.to_rgb() * self.compute_illumination(rt);
-        Some(adjusted_rgb.with_alpha(diffuse_color.alpha()))
+        let adjusted_rgb = match rt.graphics_options.lighting_display {
+            LightingOption::PathTraced => {
+                let mut rng = path_tracing_rng(self.intersection_point, context.sample);
+                diffuse_color.to_rgb()
+                    * rt.path_traced_light(
+                        self.cube,
+                        self.intersection_point,
+                        self.normal,
+                        &mut rng,
+                    )
+                    + rt.get_emission(self.cube)
+            }
+            _ => diffuse_color.to_rgb() * self.compute_illumination(rt),
+        };
+        let alpha = diffuse_color.alpha().into_inner();
+
+        let reflectance = self.material.reflectance.into_inner();
+        if reflectance <= 0.0
+            || self.normal == Face7::Within
+            || context.depth >= MAX_REFLECTION_DEPTH
+        {
+            return Some(adjusted_rgb.with_alpha(diffuse_color.alpha()));
+        }
+
+        // Reflect the ray, perturbing it according to the roughness.
+        let normal = self.normal.normal_vector::<FreeCoordinate>();
+        let incident = context.direction.normalize();
+        let cosine = (-incident.dot(normal)).clamp(0.0, 1.0);
+        let mut direction = reflect(incident, normal);
+        let roughness = FreeCoordinate::from(self.material.roughness.into_inner());
+        if roughness > 0.0 {
+            let mut rng = path_tracing_rng(self.intersection_point, context.sample);
+            let jitter = Vector3::new(rng.gen(), rng.gen(), rng.gen()).map(|c: f64| c * 2.0 - 1.0);
+            direction = (direction + jitter * roughness).normalize();
+            if direction.dot(normal) <= 0.0 {
+                // Don't reflect into the surface itself.
+                direction -= normal * (2.0 * direction.dot(normal));
+            }
+        }
+        let reflected = rt.trace_secondary(
+            Ray {
+                origin: self.intersection_point + normal * SURFACE_EPSILON,
+                direction,
+            },
+            RayContext {
+                direction,
+                sample: context.sample,
+                depth: context.depth + 1,
+            },
+        );
+
+        // Schlick's approximation of the Fresnel factor.
+        let fresnel = reflectance + (1.0 - reflectance) * (1.0 - cosine as f32).powi(5);
+        let new_alpha = alpha + (1.0 - alpha) * fresnel;
+        let rgb =
+            (adjusted_rgb * (alpha * (1.0 - fresnel)) + reflected * fresnel) * new_alpha.recip();
+        Some(rgb.with_alpha(NotNan::new(new_alpha).unwrap_or(notnan!(1.0))))
     }
 
     fn compute_illumination(&self, rt: &SpaceRaytracer<D>) -> Rgb {
@@ -49,13 +117,25 @@ impl<D: RtBlockData> Surface<'_, D> {
             LightingOption::Flat => {
                 rt.get_lighting(self.cube + self.normal.normal_vector())
                     * fixed_directional_lighting(self.normal)
+                    * self.ambient_occlusion(rt)
             }
-            LightingOption::Smooth => {
+            LightingOption::Smooth | LightingOption::PathTraced => {
                 rt.get_interpolated_light(self.intersection_point, self.normal)
                     * fixed_directional_lighting(self.normal)
+                    * self.ambient_occlusion(rt)
             }
         }
     }
+
+    /// Returns the factor by which [`GraphicsOptions::ambient_occlusion`] darkens this
+    /// surface, which is 1 if it is disabled.
+    fn ambient_occlusion(&self, rt: &SpaceRaytracer<D>) -> f32 {
+        if rt.graphics_options.ambient_occlusion {
+            rt.voxel_ambient_occlusion(self.cube, self.intersection_point, self.normal)
+        } else {
+            1.0
+        }
+    }
 }
 
 /// Simple directional lighting used to give corners extra definition.
@@ -111,9 +191,14 @@ pub(crate) struct SurfaceIter<'a, D> {
     // TODO: Should `current_block` become part of the state?
     current_block: Option<VoxelSurfaceIter<'a, D>>,
     blocks: &'a [TracingBlock<D>],
-    array: &'a GridArray<TracingCubeData>,
+    array: &'a TracingCubes,
+    occupancy: &'a OccupancyPyramid,
 }
 
+/// Distance, in cubes, short of the exit of an empty region that [`SurfaceIter`] skips
+/// to.
+const SKIP_MARGIN: FreeCoordinate = 1.0 / 16.0;
+
 #[derive(Clone, Copy, Debug)]
 enum SurfaceIterState {
     Initial,
@@ -134,6 +219,7 @@ impl<'a, D: RtBlockData> SurfaceIter<'a, D> {
             current_block: None,
             blocks: &rt.blocks,
             array: &rt.cubes,
+            occupancy: &rt.occupancy,
         }
     }
 }
@@ -178,6 +264,21 @@ impl<'a, D> Iterator for SurfaceIter<'a, D> {
             }
         };
         if cube_data.always_invisible {
+            // If this cube is part of a larger empty region, skip the rest of the region.
+            // We still report this cube, so that the caller sees where the previous block
+            // ended.
+            if let Some(empty_cell) = self
+                .occupancy
+                .empty_cell(rc_step.cube_ahead())
+                .and_then(|cell| cell.intersection(self.array.bounds()))
+            {
+                let exit_t = accel::exit_t(self.ray, empty_cell);
+                // Stop a little short of the exit, so that the raycaster will correctly
+                // report the cube and face that it enters next.
+                self.block_raycaster
+                    .skip_to(exit_t - SKIP_MARGIN / self.ray.direction.magnitude());
+            }
+
             // Early return that avoids indirecting through self.blocks
             return Some(TraceStep::Invisible {
                 t_distance: rc_step.t_distance(),
@@ -186,7 +287,9 @@ impl<'a, D> Iterator for SurfaceIter<'a, D> {
 
         let tb: &TracingBlock<D> = &self.blocks[cube_data.block_index as usize];
         Some(match tb.voxels {
-            Evoxels::One(Evoxel { color, .. }) => {
+            Evoxels::One(Evoxel {
+                color, material, ..
+            }) => {
                 if color.fully_transparent() {
                     // The caller could generically skip transparent, but if we do it then
                     // we can skip some math too.
@@ -197,6 +300,7 @@ impl<'a, D> Iterator for SurfaceIter<'a, D> {
                     TraceStep::EnterSurface(Surface {
                         block_data: &tb.block_data,
                         diffuse_color: color,
+                        material,
                         cube: rc_step.cube_ahead(),
                         t_distance: rc_step.t_distance(),
                         intersection_point: rc_step.intersection_point(self.ray),
@@ -258,6 +362,7 @@ impl<'a, D> VoxelSurfaceIter<'a, D> {
         Some(TraceStep::EnterSurface(Surface {
             block_data: self.block_data,
             diffuse_color: voxel.color,
+            material: voxel.material,
             cube: self.block_cube,
             // Note: The proper scaling here depends on the direction vector scale, that
             // recursive_ray() _doesn't_ change.
@@ -327,9 +432,11 @@ pub(crate) enum DepthStep<'a, D> {
 mod tests {
     use super::*;
     use crate::block::{Block, Resolution::*, AIR};
-    use crate::camera::GraphicsOptions;
+    use crate::camera::{GraphicsOptions, TransparencyOption};
     use crate::math::GridAab;
-    use crate::space::Space;
+    use crate::raytracer::refract_ray;
+    use crate::raytracer::ColorBuf;
+    use crate::space::{Fog, Space, SpacePhysics};
     use crate::universe::Universe;
     use pretty_assertions::assert_eq;
     use TraceStep::{EnterBlock, EnterSurface, Invisible};
@@ -369,6 +476,7 @@ mod tests {
                 EnterSurface(Surface {
                     block_data: &(),
                     diffuse_color: solid_test_color,
+                    material: Material::DIFFUSE,
                     cube: GridPoint::new(0, 1, 0),
                     t_distance: 1.5, // half-block starting point + 1 empty block
                     intersection_point: Point3::new(0.5, 1.0, 0.5),
@@ -378,6 +486,7 @@ mod tests {
                 EnterSurface(Surface {
                     block_data: &(),
                     diffuse_color: slab_test_color,
+                    material: Material::DIFFUSE,
                     cube: GridPoint::new(0, 2, 0),
                     t_distance: 2.5,
                     intersection_point: Point3::new(0.5, 2.0, 0.5),
@@ -387,6 +496,7 @@ mod tests {
                 EnterSurface(Surface {
                     block_data: &(),
                     diffuse_color: slab_test_color,
+                    material: Material::DIFFUSE,
                     cube: GridPoint::new(0, 2, 0),
                     t_distance: 2.75, // previous surface + 1/4 block of depth
                     intersection_point: Point3::new(0.5, 2.25, 0.5),
@@ -420,6 +530,7 @@ mod tests {
                 EnterSurface(Surface {
                     block_data: &(),
                     diffuse_color: solid_test_color,
+                    material: Material::DIFFUSE,
                     cube: GridPoint::new(0, 0, 0),
                     t_distance: 0.5, // half-block starting point
                     intersection_point: Point3::new(0.0, 0.5, 0.5),
@@ -430,4 +541,240 @@ mod tests {
             ]
         );
     }
+
+    /// [`SurfaceIter`] skips empty regions, but still reports entering them and
+    /// leaving the space.
+    #[test]
+    fn surface_iter_skips_empty_space() {
+        let mut space = Space::empty_positive(100, 1, 1);
+        let color = rgba_const!(1., 0., 0., 1.);
+        space.set([90, 0, 0], Block::from(color)).unwrap();
+        let rt = SpaceRaytracer::<()>::new(&space, GraphicsOptions::default(), ());
+
+        let steps: Vec<TraceStep<'_, ()>> =
+            SurfaceIter::new(&rt, Ray::new([0.5, 0.5, 0.5], [1., 0., 0.])).collect();
+        assert!(steps.len() < 20, "{steps:#?}");
+        assert_eq!(steps[0], Invisible { t_distance: 0.0 });
+        assert_eq!(
+            steps
+                .iter()
+                .find(|step| matches!(step, EnterSurface(_)))
+                .unwrap(),
+            &EnterSurface(Surface {
+                block_data: &(),
+                diffuse_color: color,
+                material: Material::DIFFUSE,
+                cube: GridPoint::new(90, 0, 0),
+                t_distance: 89.5,
+                intersection_point: Point3::new(90.0, 0.5, 0.5),
+                normal: Face7::NX
+            }),
+        );
+        assert_eq!(steps.last().unwrap(), &Invisible { t_distance: 99.5 });
+    }
+
+    fn path_traced_options() -> GraphicsOptions {
+        GraphicsOptions {
+            lighting_display: LightingOption::PathTraced,
+            ..GraphicsOptions::default()
+        }
+    }
+
+    /// A surface with nothing else in view of it is lit by exactly the sky,
+    /// and an emissive surface shows its emission, regardless of the random sample.
+    #[test]
+    fn path_traced_sky_and_emission() {
+        let sky_color = rgb_const!(0.5, 0.25, 0.125);
+        let emitter = Block::builder()
+            .color(Rgba::BLACK)
+            .light_emission(rgb_const!(1., 0., 0.))
+            .build();
+        let mut space = Space::builder(GridAab::from_lower_size([0, 0, 0], [2, 1, 2]))
+            .sky_color(sky_color)
+            .build();
+        space.set([0, 0, 0], Block::from(Rgba::WHITE)).unwrap();
+        space.set([1, 0, 0], &emitter).unwrap();
+        let rt = SpaceRaytracer::<()>::new(&space, path_traced_options(), ());
+
+        for sample in 0..8 {
+            let (white, _) = rt.trace_ray_sample::<ColorBuf>(
+                Ray::new([0.5, 0.5, 1.5], [0., 0., -1.]),
+                true,
+                sample,
+            );
+            assert_eq!(Rgba::from(white), sky_color.with_alpha_one());
+
+            let (emitter, _) = rt.trace_ray_sample::<ColorBuf>(
+                Ray::new([1.5, 0.5, 1.5], [0., 0., -1.]),
+                true,
+                sample,
+            );
+            assert_eq!(Rgba::from(emitter), rgba_const!(1., 0., 0., 1.));
+        }
+    }
+
+    /// A perfectly smooth, fully reflective surface seen head-on shows only what it
+    /// reflects.
+    #[test]
+    fn mirror_reflects_sky() {
+        let sky_color = rgb_const!(0.5, 0.25, 0.125);
+        let mirror = Block::builder()
+            .color(Rgba::BLACK)
+            .material(Material::MIRROR)
+            .build();
+        let mut space = Space::builder(GridAab::from_lower_size([0, 0, 0], [1, 1, 1]))
+            .sky_color(sky_color)
+            .build();
+        space.set([0, 0, 0], &mirror).unwrap();
+        let options = GraphicsOptions {
+            lighting_display: LightingOption::None,
+            ..GraphicsOptions::default()
+        };
+        let rt = SpaceRaytracer::<()>::new(&space, options, ());
+
+        let (buf, _) = rt.trace_ray::<ColorBuf>(Ray::new([0.5, 0.5, 1.5], [0., 0., -1.]), true);
+        assert_eq!(Rgba::from(buf), sky_color.with_alpha_one());
+    }
+
+    /// Refraction doesn't disturb a ray passing straight through a refractive block.
+    #[test]
+    fn refraction_at_normal_incidence() {
+        let glass = Block::builder()
+            .color(rgba_const!(1., 1., 1., 0.5))
+            .material(Material {
+                refractive_index: notnan!(1.5),
+                ..Material::DIFFUSE
+            })
+            .build();
+        let mut space = Space::empty_positive(1, 1, 4);
+        space
+            .set([0, 0, 0], Block::from(rgba_const!(1., 0., 0., 1.)))
+            .unwrap();
+        space.set([0, 0, 2], &glass).unwrap();
+        let options = GraphicsOptions {
+            lighting_display: LightingOption::None,
+            transparency: TransparencyOption::Surface,
+            ..GraphicsOptions::default()
+        };
+        let rt = SpaceRaytracer::<()>::new(&space, options, ());
+
+        let (buf, _) = rt.trace_ray::<ColorBuf>(Ray::new([0.5, 0.5, 3.5], [0., 0., -1.]), true);
+        assert_eq!(Rgba::from(buf), rgba_const!(1., 0.5, 0.5, 1.));
+    }
+
+    /// [`GraphicsOptions::ambient_occlusion`] darkens a surface next to an inside corner
+    /// formed by voxels, but not the rest of the surface.
+    #[test]
+    fn voxel_ambient_occlusion_darkens_inside_corner() {
+        let mut universe = Universe::new();
+        let solid = Block::from(Rgba::WHITE);
+        // A floor with a ridge one voxel high along its +X edge.
+        let block = Block::builder()
+            .voxels_fn(&mut universe, R4, |cube| {
+                if cube.y == 0 || (cube.y == 1 && cube.x == 3) {
+                    &solid
+                } else {
+                    &AIR
+                }
+            })
+            .unwrap()
+            .build();
+        let mut space = Space::empty_positive(1, 1, 1);
+        space.set([0, 0, 0], &block).unwrap();
+        let rt = SpaceRaytracer::<()>::new(&space, GraphicsOptions::default(), ());
+
+        // Top surface of the floor, in the corner beside the ridge, and far from it.
+        let corner = rt.voxel_ambient_occlusion(
+            GridPoint::new(0, 0, 0),
+            Point3::new(0.74, 0.25, 0.5),
+            Face7::PY,
+        );
+        let open = rt.voxel_ambient_occlusion(
+            GridPoint::new(0, 0, 0),
+            Point3::new(0.25, 0.25, 0.5),
+            Face7::PY,
+        );
+        assert_eq!(open, 1.0);
+        assert!(corner < 0.8, "{corner}");
+    }
+
+    /// Fog attenuates the light behind it and adds its own color, within the space only.
+    #[test]
+    fn fog_attenuates_and_scatters() {
+        let sky_color = rgb_const!(0.0, 0.0, 1.0);
+        let fog_color = rgb_const!(1.0, 0.0, 0.0);
+        let space = Space::builder(GridAab::from_lower_size([0, 0, 0], [10, 1, 1]))
+            .physics(SpacePhysics {
+                sky_color,
+                fog: Some(Fog::uniform(fog_color, notnan!(0.5))),
+                ..SpacePhysics::DEFAULT
+            })
+            .build();
+        let transmittance = (-0.5f32 * 10.0).exp();
+        let expected = fog_color * (1.0 - transmittance) + sky_color * transmittance;
+
+        for transparency in [TransparencyOption::Surface, TransparencyOption::Volumetric] {
+            let options = GraphicsOptions {
+                lighting_display: LightingOption::None,
+                transparency: transparency.clone(),
+                ..GraphicsOptions::default()
+            };
+            let rt = SpaceRaytracer::<()>::new(&space, options, ());
+            // Starts outside the space, so the distance traveled is only its length.
+            let (buf, _) = rt.trace_ray::<ColorBuf>(Ray::new([-5.0, 0.5, 0.5], [1., 0., 0.]), true);
+            let actual = Rgba::from(buf).to_rgb();
+            assert!(
+                (Vector3::<f32>::from(actual) - Vector3::from(expected)).magnitude() < 1e-4,
+                "{transparency:?}: {actual:?} != {expected:?}"
+            );
+        }
+    }
+
+    /// Fog in an enclosed room is lit where light enters through an opening in the roof,
+    /// even away from any surface, producing a light shaft.
+    #[test]
+    fn fog_shows_light_shaft() {
+        let mut space = Space::builder(GridAab::from_lower_size([0, 0, 0], [31, 8, 14]))
+            .physics(SpacePhysics {
+                sky_color: Rgb::ONE,
+                fog: Some(Fog::uniform(Rgb::ONE, notnan!(0.2))),
+                ..SpacePhysics::DEFAULT
+            })
+            .build();
+        space
+            .fill_uniform(space.bounds(), &Block::from(Rgba::BLACK))
+            .unwrap();
+        space
+            .fill_uniform(GridAab::from_lower_size([1, 1, 1], [29, 6, 12]), &AIR)
+            .unwrap();
+        space
+            .fill_uniform(GridAab::from_lower_size([14, 7, 9], [3, 1, 3]), &AIR)
+            .unwrap();
+        space.evaluate_light(0, |_| {});
+        let rt = SpaceRaytracer::<()>::new(&space, GraphicsOptions::default(), ());
+
+        // Both rays cross the room, away from its walls, and end at a black wall;
+        // only the first passes under the opening.
+        let (shaft, _) = rt.trace_ray::<ColorBuf>(Ray::new([2.5, 4.5, 10.5], [1., 0., 0.]), true);
+        let (shadow, _) = rt.trace_ray::<ColorBuf>(Ray::new([2.5, 4.5, 3.5], [1., 0., 0.]), true);
+        let shaft = Rgba::from(shaft).luminance();
+        let shadow = Rgba::from(shadow).luminance();
+        assert!(shaft > shadow * 1.5, "shaft {shaft} vs. shadow {shadow}");
+    }
+
+    #[test]
+    fn refract_ray_follows_snells_law() {
+        let normal = Vector3::new(0., 1., 0.);
+        let incident = Vector3::new(1., -1., 0.).normalize();
+
+        let (ray, refracted) = refract_ray(Point3::origin(), incident, normal, 1.0 / 1.5);
+        assert!(refracted);
+        let sin_out = ray.direction.x / ray.direction.magnitude();
+        assert!((sin_out - incident.x / 1.5).abs() < 1e-6, "{sin_out}");
+
+        // Going the other way at this angle is total internal reflection.
+        let (ray, refracted) = refract_ray(Point3::origin(), incident, normal, 1.5);
+        assert!(!refracted);
+        assert!((ray.direction - Vector3::new(1., 1., 0.).normalize()).magnitude() < 1e-9);
+    }
 }
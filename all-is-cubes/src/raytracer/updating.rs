@@ -3,12 +3,12 @@ use std::fmt;
 use std::mem;
 use std::sync::{Arc, Mutex, Weak};
 
-use crate::block::AIR;
 use crate::camera::GraphicsOptions;
+use crate::chunking::{cube_to_chunk, ChunkPos};
 use crate::content::palette;
 use crate::listen::{Listen as _, ListenableSource, Listener};
-use crate::math::GridPoint;
-use crate::raytracer::{RtBlockData, RtOptionsRef, SpaceRaytracer, TracingBlock, TracingCubeData};
+use crate::raytracer::cubes::CHUNK_SIZE;
+use crate::raytracer::{RtBlockData, RtOptionsRef, SpaceRaytracer, TracingBlock};
 use crate::space::{BlockIndex, Space, SpaceChange};
 use crate::universe::{RefError, URef};
 
@@ -20,6 +20,8 @@ pub struct UpdatingSpaceRaytracer<D: RtBlockData> {
     custom_options: ListenableSource<D::Options>,
     state: SpaceRaytracer<D>,
     todo: Arc<Mutex<SrtTodo>>,
+    /// Incremented whenever `state` is changed.
+    generation: u64,
 }
 
 // manual impl avoids `D: Debug` bound
@@ -34,6 +36,7 @@ where
             .field("custom_options", &self.custom_options)
             .field("state", &self.state)
             .field("todo", &self.todo)
+            .field("generation", &self.generation)
             .finish()
     }
 }
@@ -55,13 +58,14 @@ where
         let todo = Arc::new(Mutex::new(SrtTodo {
             listener: true,
             everything: true,
+            physics: false,
+            options: false,
             blocks: HashSet::new(),
-            cubes: HashSet::new(),
+            chunks: HashSet::new(),
         }));
 
-        // TODO: Placeholder for more detailed graphics options updating
-        graphics_options
-            .listen(TodoListener(Arc::downgrade(&todo)).filter(|()| Some(SpaceChange::EveryBlock)));
+        graphics_options.listen(OptionsTodoListener(Arc::downgrade(&todo)));
+        custom_options.listen(OptionsTodoListener(Arc::downgrade(&todo)));
 
         Self {
             state: SpaceRaytracer::new_empty(
@@ -73,6 +77,7 @@ where
             graphics_options,
             custom_options,
             todo,
+            generation: 0,
         }
     }
 
@@ -87,6 +92,11 @@ where
         &self.state
     }
 
+    /// Returns a number which changes whenever [`Self::get()`]'s contents change.
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
     /// Reads the previously provided [`Space`] and updates the local copy of its contents.
     ///
     /// Returns an error if reading fails.
@@ -100,11 +110,18 @@ where
         // we must reorder the actions here (or perhaps acquire the todo lock twice) to
         // avoid deadlock.
         let mut todo = self.todo.lock().unwrap();
-        if !todo.listener && !todo.everything && todo.blocks.is_empty() && todo.cubes.is_empty() {
+        if !todo.listener
+            && !todo.everything
+            && !todo.physics
+            && !todo.options
+            && todo.blocks.is_empty()
+            && todo.chunks.is_empty()
+        {
             // Nothing to do
             return Ok(());
         }
         let space = self.space.read()?;
+        self.generation += 1;
 
         if mem::take(&mut todo.listener) {
             space.listen(TodoListener(Arc::downgrade(&self.todo)));
@@ -116,9 +133,15 @@ where
                 self.graphics_options.snapshot(),
                 self.custom_options.snapshot(),
             );
+            todo.physics = false;
+            todo.options = false;
             todo.blocks.clear();
-            todo.cubes.clear();
+            todo.chunks.clear();
         } else {
+            if mem::take(&mut todo.physics) {
+                self.state.set_sky(space.physics());
+            }
+
             let graphics_options = &*self.graphics_options.get();
             let custom_options = &*self.custom_options.get();
             let options = RtOptionsRef {
@@ -126,6 +149,15 @@ where
                 custom_options,
             };
 
+            if mem::take(&mut todo.options) {
+                // The cubes do not depend on the options, so only the blocks need redoing.
+                self.state.graphics_options = graphics_options.clone();
+                self.state.custom_options = custom_options.clone();
+                self.state.sky_data = D::sky(options);
+                self.state.blocks.clear();
+                todo.blocks.clear();
+            }
+
             let block_data_slice = space.block_data();
             if block_data_slice.len() > self.state.blocks.len() {
                 for block_data in block_data_slice[self.state.blocks.len()..].iter() {
@@ -141,14 +173,12 @@ where
                     TracingBlock::from_block(options, &block_data_slice[block_index]);
             }
 
-            for cube in todo.cubes.drain() {
-                // TODO: this does 2 cube index calculations instead of the 1 it needs
-                let block_index = space.get_block_index(cube).unwrap_or(0);
-                self.state.cubes[cube] = TracingCubeData {
-                    block_index,
-                    lighting: space.get_lighting(cube),
-                    always_invisible: block_data_slice[block_index as usize].block() == &AIR,
-                };
+            for chunk in todo.chunks.drain() {
+                if let Some(region) = self.state.cubes.update_chunk(&space, chunk) {
+                    self.state
+                        .occupancy
+                        .update_region(&self.state.cubes, region);
+                }
             }
         }
 
@@ -164,9 +194,16 @@ struct SrtTodo {
     /// All blocks and cubes must be updated.
     everything: bool,
 
+    /// The sky (from the space's physics) must be updated.
+    physics: bool,
+
+    /// The graphics or custom options changed, so all blocks must be updated.
+    options: bool,
+
     // TODO: Benchmark using a BitVec instead.
     blocks: HashSet<BlockIndex>,
-    cubes: HashSet<GridPoint>,
+    /// Chunks of [`TracingCubes`](super::cubes::TracingCubes) which must be re-read.
+    chunks: HashSet<ChunkPos<CHUNK_SIZE>>,
 }
 
 /// [`Listener`] adapter for [`SpaceRendererTodo`].
@@ -181,14 +218,18 @@ impl Listener<SpaceChange> for TodoListener {
                     SpaceChange::EveryBlock => {
                         todo.everything = true;
                         todo.blocks.clear();
-                        todo.cubes.clear()
+                        todo.chunks.clear()
                     }
                     SpaceChange::Lighting(p) | SpaceChange::Block(p) => {
-                        todo.cubes.insert(p);
+                        todo.chunks.insert(cube_to_chunk(p));
                     }
                     SpaceChange::Number(index) | SpaceChange::BlockValue(index) => {
                         todo.blocks.insert(index);
                     }
+                    SpaceChange::Physics => {
+                        todo.physics = true;
+                    }
+                    SpaceChange::CubeData(_) => {}
                 }
             }
         }
@@ -199,17 +240,38 @@ impl Listener<SpaceChange> for TodoListener {
     }
 }
 
+/// [`Listener`] adapter for [`SrtTodo`] which marks the options as changed.
+#[derive(Clone, Debug)]
+struct OptionsTodoListener(Weak<Mutex<SrtTodo>>);
+
+impl Listener<()> for OptionsTodoListener {
+    fn receive(&self, (): ()) {
+        if let Some(mutex) = self.0.upgrade() {
+            if let Ok(mut todo) = mutex.lock() {
+                todo.options = true;
+            }
+        }
+    }
+
+    fn alive(&self) -> bool {
+        self.0.strong_count() > 0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::block::AIR;
-    use crate::camera::{eye_for_look_at, Camera, Viewport};
-    use crate::content::make_some_voxel_blocks;
+    use crate::block::{Block, AIR};
+    use crate::camera::{eye_for_look_at, Camera, LightingOption, Viewport};
+    use crate::content::{make_some_blocks, make_some_voxel_blocks};
+    use crate::listen::ListenableCell;
     use crate::raytracer::{CharacterBuf, CharacterRtData};
     use crate::universe::Universe;
     use crate::util::{CustomFormat, Unquote};
     use cgmath::{Decomposed, Transform as _, Vector2, Vector3};
     use pretty_assertions::assert_eq;
+    use rand::seq::SliceRandom as _;
+    use rand::SeedableRng as _;
 
     struct EquivalenceTester {
         camera: Camera,
@@ -221,10 +283,18 @@ mod tests {
 
     impl EquivalenceTester {
         fn new(space: URef<Space>) -> Self {
+            Self::with_options(
+                space,
+                ListenableSource::constant(GraphicsOptions::default()),
+            )
+        }
+
+        fn with_options(
+            space: URef<Space>,
+            graphics_options: ListenableSource<GraphicsOptions>,
+        ) -> Self {
             let bounds = space.read().unwrap().bounds();
 
-            // TODO: add tests of changing the options
-            let graphics_options = ListenableSource::constant(GraphicsOptions::default());
             let custom_options = ListenableSource::constant(());
 
             let mut camera = Camera::new(
@@ -265,13 +335,18 @@ mod tests {
                 .get()
                 .trace_scene_to_string::<CharacterBuf>(&self.camera, "\n");
             #[allow(clippy::unit_arg)]
-            let image_fresh = SpaceRaytracer::<CharacterRtData>::new(
+            let fresh = SpaceRaytracer::<CharacterRtData>::new(
                 &self.space.read().unwrap(),
                 self.graphics_options.snapshot(),
                 self.custom_options.snapshot(),
-            )
-            .trace_scene_to_string::<CharacterBuf>(&self.camera, "\n");
+            );
+            let image_fresh = fresh.trace_scene_to_string::<CharacterBuf>(&self.camera, "\n");
 
+            let state = self.updating.get();
+            assert_eq!(state.blocks.len(), fresh.blocks.len());
+            assert!(state.cubes == fresh.cubes, "cubes differ");
+            assert!(state.occupancy == fresh.occupancy, "occupancy differs");
+            assert_eq!(state.graphics_options, fresh.graphics_options);
             assert_eq!(
                 image_updating.custom_format(Unquote),
                 image_fresh.custom_format(Unquote)
@@ -346,4 +421,48 @@ mod tests {
             .unwrap();
         tester.update_and_assert().unwrap();
     }
+
+    /// Makes many random changes to a space large enough to have multiple chunks,
+    /// checking that the incrementally updated data matches a fresh snapshot.
+    #[test]
+    fn updating_random_changes() {
+        let mut universe = Universe::new();
+        let blocks: Vec<Block> = make_some_blocks::<3>().into_iter().chain([AIR]).collect();
+        let space = universe.insert_anonymous(Space::empty_positive(40, 20, 40));
+        let mut tester = EquivalenceTester::new(space.clone());
+        tester.update_and_assert().unwrap();
+
+        let mut rng = rand_xoshiro::Xoshiro256Plus::seed_from_u64(0);
+        for _ in 0..10 {
+            space
+                .try_modify(|space| {
+                    let bounds = space.bounds();
+                    for _ in 0..20 {
+                        let cube = bounds.random_cube(&mut rng).unwrap();
+                        space.set(cube, blocks.choose(&mut rng).unwrap()).unwrap();
+                    }
+                })
+                .unwrap();
+            tester.update_and_assert().unwrap();
+        }
+    }
+
+    #[test]
+    fn updating_options_change() {
+        let mut universe = Universe::new();
+        let [block] = make_some_blocks();
+        let mut space = Space::empty_positive(3, 2, 3);
+        space.set([0, 0, 0], &block).unwrap();
+        let space = universe.insert_anonymous(space);
+
+        let options_cell = ListenableCell::new(GraphicsOptions::default());
+        let mut tester = EquivalenceTester::with_options(space, options_cell.as_source());
+        tester.update_and_assert().unwrap();
+
+        options_cell.set(GraphicsOptions {
+            lighting_display: LightingOption::None,
+            ..GraphicsOptions::default()
+        });
+        tester.update_and_assert().unwrap();
+    }
 }
@@ -37,9 +37,14 @@ macro_rules! impl_serde_via_schema_by_ref {
 
 mod block {
     use super::*;
-    use crate::block::{Block, BlockAttributes, Composite, Modifier, Move, Primitive, Quote, Zoom};
+    use crate::block::{
+        Block, BlockAttributes, BlockDef, BlockProperties, BlockState, Composite, Inset, Material,
+        Modifier, Move, Primitive, Property, PropertyKind, PropertyValue, Quote, Scale, Tint, Zoom,
+    };
     use crate::math::Rgba;
-    use schema::{BlockSer, ModifierSer};
+    use schema::{
+        BlockDefSer, BlockSer, ModifierSer, PropertySer, PropertyValueSer, StateVariantSer, TintSer,
+    };
 
     impl Serialize for Block {
         fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
@@ -130,6 +135,12 @@ mod block {
                 collision: _,
                 rotation_rule: _,
                 light_emission,
+                ref material,
+                dynamic_light: _,
+                container_slots,
+                // Deliberately not saved, since it contains a function, which cannot
+                // be serialized; see `BlockEntityTemplate`.
+                block_entity: _,
                 tick_action: _,
                 animation_hint: _,
             } = value;
@@ -137,6 +148,8 @@ mod block {
                 display_name: display_name.to_string(),
                 selectable,
                 light_emission: light_emission.into(),
+                material: material.into(),
+                container_slots,
             }
         }
     }
@@ -148,16 +161,50 @@ mod block {
                 display_name,
                 selectable,
                 light_emission,
+                material,
+                container_slots,
             } = value;
             Self {
                 display_name: display_name.into(),
                 selectable,
                 light_emission: light_emission.into(),
+                material: material.into(),
+                container_slots,
                 ..Default::default()
             }
         }
     }
 
+    impl From<&Material> for schema::MaterialV1Ser {
+        fn from(value: &Material) -> Self {
+            let &Material {
+                reflectance,
+                roughness,
+                refractive_index,
+            } = value;
+            schema::MaterialV1Ser {
+                reflectance,
+                roughness,
+                refractive_index,
+            }
+        }
+    }
+
+    impl From<schema::MaterialV1Ser> for Material {
+        fn from(value: schema::MaterialV1Ser) -> Self {
+            let schema::MaterialV1Ser {
+                reflectance,
+                roughness,
+                refractive_index,
+            } = value;
+            Material {
+                reflectance,
+                roughness,
+                refractive_index,
+            }
+        }
+    }
+
     impl From<&Modifier> for ModifierSer {
         fn from(value: &Modifier) -> Self {
             match *value {
@@ -186,6 +233,18 @@ mod block {
                     distance,
                     velocity,
                 },
+                Modifier::Tint(ref tint) => ModifierSer::TintV1 {
+                    tint: TintSer::from(tint),
+                },
+                Modifier::Mirror(axis) => ModifierSer::MirrorV1 { axis },
+                Modifier::Scale(ref m) => m.to_serial_schema(),
+                Modifier::Inset(ref m) => m.to_serial_schema(),
+                Modifier::State(ref state) => ModifierSer::StateV1 {
+                    values: state
+                        .iter()
+                        .map(|(name, value)| (name.to_owned(), PropertyValueSer::from(value)))
+                        .collect(),
+                },
             }
         }
     }
@@ -216,11 +275,366 @@ mod block {
                     distance,
                     velocity,
                 } => Modifier::Move(Move::new(direction, distance, velocity)),
+                ModifierSer::TintV1 { tint } => Modifier::Tint(Tint::from(tint)),
+                ModifierSer::MirrorV1 { axis } => Modifier::Mirror(axis),
+                ModifierSer::ScaleV1 { scale, offset } => {
+                    Modifier::Scale(Scale::new(scale, offset.map(i32::from).into()))
+                }
+                ModifierSer::InsetV1 { resolution, bounds } => {
+                    Modifier::Inset(Inset::new(resolution, bounds))
+                }
+                ModifierSer::StateV1 { values } => Modifier::State(
+                    values
+                        .into_iter()
+                        .fold(BlockState::default(), |state, (name, value)| {
+                            state.with(name, PropertyValue::from(value))
+                        }),
+                ),
+            }
+        }
+    }
+
+    impl From<&BlockDef> for BlockDefSer {
+        fn from(block_def: &BlockDef) -> Self {
+            let properties = block_def.properties();
+            BlockDefSer::BlockDefV1 {
+                block: Block::clone(block_def),
+                properties: properties.iter().map(PropertySer::from).collect(),
+                variants: properties
+                    .variants()
+                    .iter()
+                    .map(|variant| StateVariantSer {
+                        property: variant.property.to_string(),
+                        value: PropertyValueSer::from(&variant.value),
+                        modifiers: variant.modifiers.iter().map(ModifierSer::from).collect(),
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    impl From<BlockDefSer> for BlockDef {
+        fn from(value: BlockDefSer) -> Self {
+            match value {
+                BlockDefSer::BlockDefV1 {
+                    block,
+                    properties,
+                    variants,
+                } => {
+                    let properties = properties
+                        .into_iter()
+                        .map(Property::from)
+                        .fold(BlockProperties::new(), BlockProperties::property);
+                    let properties = variants.into_iter().fold(
+                        properties,
+                        |properties,
+                         StateVariantSer {
+                             property,
+                             value,
+                             modifiers,
+                         }| {
+                            properties.variant(
+                                property,
+                                PropertyValue::from(value),
+                                modifiers.into_iter().map(Modifier::from),
+                            )
+                        },
+                    );
+                    BlockDef::new(block).with_properties(properties)
+                }
+            }
+        }
+    }
+
+    impl From<&Property> for PropertySer {
+        fn from(value: &Property) -> Self {
+            let name = value.name().to_owned();
+            match (value.kind(), value.default_value()) {
+                (&PropertyKind::Int { max }, &PropertyValue::Int(default)) => {
+                    PropertySer::IntV1 { name, max, default }
+                }
+                (PropertyKind::Enum(values), _) => PropertySer::EnumV1 {
+                    name,
+                    values: values.iter().map(|v| v.to_string()).collect(),
+                },
+                // Property's constructors ensure the only remaining possibility is Bool.
+                (_, default) => PropertySer::BoolV1 {
+                    name,
+                    default: *default == PropertyValue::Bool(true),
+                },
+            }
+        }
+    }
+
+    impl From<PropertySer> for Property {
+        fn from(value: PropertySer) -> Self {
+            match value {
+                PropertySer::BoolV1 { name, default } => Property::bool(name, default),
+                PropertySer::IntV1 { name, max, default } => Property::int(name, max, default),
+                PropertySer::EnumV1 { name, values } => Property::enumeration(name, values),
+            }
+        }
+    }
+
+    impl From<&PropertyValue> for PropertyValueSer {
+        fn from(value: &PropertyValue) -> Self {
+            match *value {
+                PropertyValue::Bool(value) => PropertyValueSer::Bool(value),
+                PropertyValue::Int(value) => PropertyValueSer::Int(value),
+                PropertyValue::Enum(ref value) => PropertyValueSer::Enum(value.to_string()),
+            }
+        }
+    }
+
+    impl From<PropertyValueSer> for PropertyValue {
+        fn from(value: PropertyValueSer) -> Self {
+            match value {
+                PropertyValueSer::Bool(value) => PropertyValue::Bool(value),
+                PropertyValueSer::Int(value) => PropertyValue::Int(value),
+                PropertyValueSer::Enum(value) => PropertyValue::Enum(value.into()),
+            }
+        }
+    }
+
+    impl From<&Tint> for TintSer {
+        fn from(value: &Tint) -> Self {
+            match *value {
+                Tint::Multiply(color) => TintSer::MultiplyV1 {
+                    color: color.into(),
+                },
+                Tint::Hsv {
+                    hue_shift,
+                    saturation,
+                    value,
+                } => TintSer::HsvV1 {
+                    hue_shift,
+                    saturation,
+                    value,
+                },
+                Tint::Palette(ref pairs) => TintSer::PaletteV1 {
+                    pairs: pairs
+                        .iter()
+                        .map(|&(original, replacement)| (original.into(), replacement.into()))
+                        .collect(),
+                },
+            }
+        }
+    }
+
+    impl From<TintSer> for Tint {
+        fn from(value: TintSer) -> Self {
+            match value {
+                TintSer::MultiplyV1 { color } => Tint::Multiply(Rgba::from(color)),
+                TintSer::HsvV1 {
+                    hue_shift,
+                    saturation,
+                    value,
+                } => Tint::Hsv {
+                    hue_shift,
+                    saturation,
+                    value,
+                },
+                TintSer::PaletteV1 { pairs } => Tint::palette(
+                    pairs
+                        .into_iter()
+                        .map(|(original, replacement)| (original.into(), replacement.into())),
+                ),
             }
         }
     }
 }
 
+mod character {
+    use super::*;
+    use crate::character::{CharacterInput, InputRecording, RecordedStep};
+    use crate::raycast::Ray;
+    use crate::time::Duration;
+    use schema::{CharacterInputSer, InputRecordingSer, RaySer, RecordedStepSer};
+
+    impl Serialize for InputRecording {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            InputRecordingSer::InputRecordingV1 {
+                step_length_micros: u64::try_from(self.step_length().as_micros())
+                    .map_err(serde::ser::Error::custom)?,
+                steps: self
+                    .steps()
+                    .iter()
+                    .map(|&RecordedStep { paused, ref inputs }| RecordedStepSer {
+                        paused,
+                        inputs: inputs.iter().map(CharacterInputSer::from).collect(),
+                    })
+                    .collect(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for InputRecording {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            match InputRecordingSer::deserialize(deserializer)? {
+                InputRecordingSer::InputRecordingV1 {
+                    step_length_micros,
+                    steps,
+                } => {
+                    let mut recording =
+                        InputRecording::new(Duration::from_micros(step_length_micros));
+                    for RecordedStepSer { paused, inputs } in steps {
+                        let inputs = inputs.into_iter().map(CharacterInput::from);
+                        if paused {
+                            recording.record_paused_step(inputs);
+                        } else {
+                            recording.record_step(inputs);
+                        }
+                    }
+                    Ok(recording)
+                }
+            }
+        }
+    }
+
+    impl From<&CharacterInput> for CharacterInputSer {
+        fn from(value: &CharacterInput) -> Self {
+            match *value {
+                CharacterInput::Velocity(velocity) => CharacterInputSer::VelocityV1 {
+                    velocity: velocity.into(),
+                },
+                CharacterInput::Look { yaw, pitch } => CharacterInputSer::LookV1 { yaw, pitch },
+                CharacterInput::Jump => CharacterInputSer::JumpV1,
+                CharacterInput::SelectSlot {
+                    which_selection,
+                    slot,
+                } => CharacterInputSer::SelectSlotV1 {
+                    which_selection,
+                    slot,
+                },
+                CharacterInput::UseTool { button, cursor_ray } => CharacterInputSer::UseToolV1 {
+                    button,
+                    cursor_ray: cursor_ray.map(|ray| RaySer {
+                        origin: ray.origin.into(),
+                        direction: ray.direction.into(),
+                    }),
+                },
+            }
+        }
+    }
+
+    impl From<CharacterInputSer> for CharacterInput {
+        fn from(value: CharacterInputSer) -> Self {
+            match value {
+                CharacterInputSer::VelocityV1 { velocity } => {
+                    CharacterInput::Velocity(velocity.into())
+                }
+                CharacterInputSer::LookV1 { yaw, pitch } => CharacterInput::Look { yaw, pitch },
+                CharacterInputSer::JumpV1 => CharacterInput::Jump,
+                CharacterInputSer::SelectSlotV1 {
+                    which_selection,
+                    slot,
+                } => CharacterInput::SelectSlot {
+                    which_selection,
+                    slot,
+                },
+                CharacterInputSer::UseToolV1 { button, cursor_ray } => CharacterInput::UseTool {
+                    button,
+                    cursor_ray: cursor_ray.map(|RaySer { origin, direction }| Ray {
+                        origin: origin.into(),
+                        direction: direction.into(),
+                    }),
+                },
+            }
+        }
+    }
+}
+
+mod inv {
+    use super::*;
+    use crate::inv::{EphemeralOpaque, Inventory, Slot, Tool};
+    use schema::{InventorySer, SlotSer, ToolSer};
+
+    impl Serialize for Inventory {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            InventorySer::InventoryV1 {
+                slots: self
+                    .slots
+                    .iter()
+                    .map(|slot| match *slot {
+                        Slot::Empty => None,
+                        Slot::Stack(count, ref item) => Some(SlotSer {
+                            count,
+                            item: item.clone(),
+                        }),
+                    })
+                    .collect(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Inventory {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            match InventorySer::deserialize(deserializer)? {
+                InventorySer::InventoryV1 { slots } => Ok(Inventory::from_slots(
+                    slots
+                        .into_iter()
+                        .map(|slot| match slot {
+                            None => Slot::Empty,
+                            Some(SlotSer { count, item }) => Slot::Stack(count, item),
+                        })
+                        .collect(),
+                )),
+            }
+        }
+    }
+
+    impl Serialize for Tool {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match *self {
+                Tool::Activate => ToolSer::ActivateV1,
+                Tool::RemoveBlock { keep } => ToolSer::RemoveBlockV1 { keep },
+                Tool::Block(ref block) => ToolSer::BlockV1 {
+                    block: block.clone(),
+                },
+                Tool::InfiniteBlocks(ref block) => ToolSer::InfiniteBlocksV1 {
+                    block: block.clone(),
+                },
+                Tool::CopyFromSpace => ToolSer::CopyFromSpaceV1,
+                Tool::EditBlock => ToolSer::EditBlockV1,
+                Tool::PushPull => ToolSer::PushPullV1,
+                Tool::ToggleProperty { ref property } => ToolSer::TogglePropertyV1 {
+                    property: property.to_string(),
+                },
+                Tool::Jetpack { active } => ToolSer::JetpackV1 { active },
+                Tool::ExternalAction {
+                    function: _,
+                    ref icon,
+                } => ToolSer::ExternalActionV1 { icon: icon.clone() },
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Tool {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            Ok(match ToolSer::deserialize(deserializer)? {
+                ToolSer::ActivateV1 => Tool::Activate,
+                ToolSer::RemoveBlockV1 { keep } => Tool::RemoveBlock { keep },
+                ToolSer::BlockV1 { block } => Tool::Block(block),
+                ToolSer::InfiniteBlocksV1 { block } => Tool::InfiniteBlocks(block),
+                ToolSer::CopyFromSpaceV1 => Tool::CopyFromSpace,
+                ToolSer::EditBlockV1 => Tool::EditBlock,
+                ToolSer::PushPullV1 => Tool::PushPull,
+                ToolSer::TogglePropertyV1 { property } => Tool::ToggleProperty {
+                    property: property.into(),
+                },
+                ToolSer::JetpackV1 { active } => Tool::Jetpack { active },
+                ToolSer::ExternalActionV1 { icon } => Tool::ExternalAction {
+                    function: EphemeralOpaque(None),
+                    icon,
+                },
+            })
+        }
+    }
+}
+
 mod math {
     use super::*;
     use crate::math::GridAab;
@@ -252,7 +666,9 @@ mod math {
 
 mod space {
     use super::*;
-    use crate::space::Space;
+    use crate::math::GridPoint;
+    use crate::space::{CubeData, Space};
+    use schema::CubeDataSer;
 
     impl Serialize for Space {
         fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -272,6 +688,18 @@ mod space {
                         index.expect("shouldn't happen: serialization went out of bounds")
                     })
                     .into_elements(),
+                cube_data: {
+                    let mut cubes: Vec<(GridPoint, &CubeData)> = self.iter_cube_data().collect();
+                    // Sort for deterministic output
+                    cubes.sort_unstable_by_key(|&(cube, _)| (cube.x, cube.y, cube.z));
+                    cubes
+                        .into_iter()
+                        .map(|(cube, CubeData { inventory })| CubeDataSer::CubeDataV1 {
+                            cube: cube.into(),
+                            inventory: inventory.clone(),
+                        })
+                        .collect()
+                },
             }
             .serialize(serializer)
         }
@@ -287,6 +715,7 @@ mod space {
                     bounds,
                     blocks,
                     contents,
+                    cube_data,
                 } => {
                     // TODO: more efficient loading that sets blocks by index rather than value
                     let mut space = Space::builder(bounds).build();
@@ -304,6 +733,15 @@ mod space {
                             )
                             .unwrap();
                     }
+                    for CubeDataSer::CubeDataV1 { cube, inventory } in cube_data {
+                        let cube = GridPoint::from(cube);
+                        if !bounds.contains_cube(cube) {
+                            return Err(serde::de::Error::custom(format!(
+                                "Space cube data position {cube:?} out of bounds {bounds:?}"
+                            )));
+                        }
+                        space.insert_cube_data(cube, CubeData { inventory });
+                    }
                     Ok(space)
                 }
             }
@@ -321,8 +759,12 @@ mod universe {
 
     impl From<&BlockDef> for schema::MemberSer {
         fn from(block_def: &BlockDef) -> Self {
-            let block: &Block = block_def;
-            schema::MemberSer::BlockDef(block.clone())
+            if block_def.properties().is_empty() {
+                let block: &Block = block_def;
+                schema::MemberSer::BlockDef(block.clone())
+            } else {
+                schema::MemberSer::BlockDefWithProperties(block_def.into())
+            }
         }
     }
 
@@ -376,6 +818,9 @@ mod universe {
                             MemberDe::BlockDef(block) => {
                                 universe.insert(name, BlockDef::new(block)).map(|_| ())
                             }
+                            MemberDe::BlockDefWithProperties(block_def) => {
+                                universe.insert(name, BlockDef::from(block_def)).map(|_| ())
+                            }
                             MemberDe::Space(space) => universe.insert(name, space).map(|_| ()),
                         }
                         .expect("insertion from deserialization failed");
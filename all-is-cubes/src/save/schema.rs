@@ -14,13 +14,15 @@
 //! * 3D vectors/points are represented as 3-element arrays
 //!   (and not, say, as structures with named fields).
 
+use std::collections::BTreeMap;
+use std::num::NonZeroU16;
 use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
-use crate::math::{Face6, GridAab, GridCoordinate, GridRotation};
+use crate::math::{Axis, Face6, GridAab, GridCoordinate, GridRotation};
 use crate::universe::URef;
-use crate::{block, space, universe};
+use crate::{block, inv, space, universe};
 
 /// Placeholder type for when we want to serialize the *contents* of a `URef`,
 /// without cloning or referencing those contents immediately.
@@ -72,9 +74,26 @@ pub(crate) struct BlockAttributesV1Ser {
     //rotation_rule: RotationPlacementRule,
     #[serde(default, skip_serializing_if = "is_default")]
     pub(crate) light_emission: RgbSer,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub(crate) material: MaterialV1Ser,
+    //dynamic_light: Option<DynamicLight>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub(crate) container_slots: u16,
     //tick_action: Option<VoxelBrush<'static>>,
     //animation_hint: AnimationHint,
 }
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct MaterialV1Ser {
+    pub(crate) reflectance: ordered_float::NotNan<f32>,
+    pub(crate) roughness: ordered_float::NotNan<f32>,
+    pub(crate) refractive_index: ordered_float::NotNan<f32>,
+}
+impl Default for MaterialV1Ser {
+    fn default() -> Self {
+        (&block::Material::DIFFUSE).into()
+    }
+}
+
 fn return_true() -> bool {
     true
 }
@@ -109,6 +128,172 @@ pub(crate) enum ModifierSer {
         distance: u16,
         velocity: i16,
     },
+    TintV1 {
+        tint: TintSer,
+    },
+    MirrorV1 {
+        axis: Axis,
+    },
+    ScaleV1 {
+        scale: block::Resolution,
+        offset: [u8; 3],
+    },
+    InsetV1 {
+        resolution: block::Resolution,
+        bounds: GridAab,
+    },
+    StateV1 {
+        values: BTreeMap<String, PropertyValueSer>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum TintSer {
+    MultiplyV1 {
+        color: RgbaSer,
+    },
+    HsvV1 {
+        hue_shift: ordered_float::NotNan<f32>,
+        saturation: ordered_float::NotNan<f32>,
+        value: ordered_float::NotNan<f32>,
+    },
+    PaletteV1 {
+        pairs: Vec<(RgbaSer, RgbaSer)>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum BlockDefSer {
+    BlockDefV1 {
+        block: block::Block,
+        properties: Vec<PropertySer>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        variants: Vec<StateVariantSer>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum PropertySer {
+    BoolV1 {
+        name: String,
+        default: bool,
+    },
+    IntV1 {
+        name: String,
+        max: u8,
+        default: u8,
+    },
+    /// The first value is the default.
+    EnumV1 {
+        name: String,
+        values: Vec<String>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct StateVariantSer {
+    pub(crate) property: String,
+    pub(crate) value: PropertyValueSer,
+    pub(crate) modifiers: Vec<ModifierSer>,
+}
+
+/// Property values are distinguishable by their JSON types, so need no tags.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub(crate) enum PropertyValueSer {
+    Bool(bool),
+    Int(u8),
+    Enum(String),
+}
+
+//------------------------------------------------------------------------------------------------//
+// Schema corresponding to the `character` module
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum InputRecordingSer {
+    InputRecordingV1 {
+        step_length_micros: u64,
+        steps: Vec<RecordedStepSer>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct RecordedStepSer {
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub(crate) paused: bool,
+    pub(crate) inputs: Vec<CharacterInputSer>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum CharacterInputSer {
+    VelocityV1 {
+        velocity: [f64; 3],
+    },
+    LookV1 {
+        yaw: f64,
+        pitch: f64,
+    },
+    JumpV1,
+    SelectSlotV1 {
+        which_selection: usize,
+        slot: usize,
+    },
+    UseToolV1 {
+        button: usize,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cursor_ray: Option<RaySer>,
+    },
+}
+
+//------------------------------------------------------------------------------------------------//
+// Schema corresponding to the `inv` module
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum InventorySer {
+    InventoryV1 {
+        /// Empty slots are `None`.
+        slots: Vec<Option<SlotSer>>,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct SlotSer {
+    pub(crate) count: NonZeroU16,
+    pub(crate) item: inv::Tool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum ToolSer {
+    ActivateV1,
+    RemoveBlockV1 {
+        keep: bool,
+    },
+    BlockV1 {
+        block: block::Block,
+    },
+    InfiniteBlocksV1 {
+        block: block::Block,
+    },
+    CopyFromSpaceV1,
+    EditBlockV1,
+    PushPullV1,
+    TogglePropertyV1 {
+        property: String,
+    },
+    JetpackV1 {
+        active: bool,
+    },
+    /// The function is not saved, so the tool does nothing once loaded.
+    ExternalActionV1 {
+        icon: block::Block,
+    },
 }
 
 //------------------------------------------------------------------------------------------------//
@@ -121,6 +306,12 @@ pub(crate) struct GridAabSer {
     pub(crate) upper: [GridCoordinate; 3],
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct RaySer {
+    pub(crate) origin: [f64; 3],
+    pub(crate) direction: [f64; 3],
+}
+
 type RgbSer = [ordered_float::NotNan<f32>; 3];
 
 type RgbaSer = [ordered_float::NotNan<f32>; 4];
@@ -135,10 +326,21 @@ pub(crate) enum SpaceSer {
         bounds: GridAab,
         blocks: Vec<block::Block>,
         contents: Box<[space::BlockIndex]>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        cube_data: Vec<CubeDataSer>,
         // TODO: bounds, behaviors, lighting, spawn, physics
     },
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+pub(crate) enum CubeDataSer {
+    CubeDataV1 {
+        cube: [GridCoordinate; 3],
+        inventory: inv::Inventory,
+    },
+}
+
 //------------------------------------------------------------------------------------------------//
 // Schema corresponding to the `universe` module
 
@@ -167,6 +369,8 @@ pub(crate) struct MemberEntrySer<T> {
 #[serde(untagged)] // The type-and-version tags of each member suffice
 pub(crate) enum MemberSchema<S> {
     BlockDef(block::Block),
+    /// Used instead of `BlockDef` only when the definition has properties.
+    BlockDefWithProperties(BlockDefSer),
     // TODO: Character(C)
     Space(S),
 }
@@ -2,14 +2,20 @@
 
 use std::fmt;
 
+use cgmath::Vector3;
 use pretty_assertions::assert_eq;
 use serde_json::{from_value, json, to_value};
 
-use crate::block::{self, Block, BlockDef, Modifier, Resolution};
-use crate::character::Character;
+use crate::behavior::NoopBehavior;
+use crate::block::{self, Block, BlockDef, Material, Modifier, Resolution};
+use crate::character::{Character, CharacterInput, InputRecording};
 use crate::content::make_some_blocks;
-use crate::math::{GridAab, GridRotation, Rgb, Rgba};
-use crate::space::Space;
+use crate::inv::{Inventory, InventoryTransaction, Slot, Tool};
+use crate::math::{Axis, GridAab, GridPoint, GridRotation, Rgb, Rgba};
+use crate::raycast::Ray;
+use crate::space::{BlockEntityTemplate, Space, SpaceTransaction, TickSchedule};
+use crate::time::Duration;
+use crate::transaction::{self, Transaction as _};
 use crate::universe::{Name, URef, Universe, UniverseIndex};
 
 #[track_caller]
@@ -91,6 +97,12 @@ fn block_atom_with_all_attributes() {
             .display_name("foo")
             .selectable(false)
             .light_emission(Rgb::new(1.0, 0.0, 10.0))
+            .container_slots(4)
+            .material(Material {
+                reflectance: notnan!(0.5),
+                roughness: notnan!(0.25),
+                refractive_index: notnan!(1.5),
+            })
             .build(),
         json!({
             "type": "BlockV1",
@@ -100,11 +112,44 @@ fn block_atom_with_all_attributes() {
                 "display_name": "foo",
                 "selectable": false,
                 "light_emission": [1.0, 0.0, 10.0],
+                "container_slots": 4,
+                "material": {
+                    "reflectance": 0.5,
+                    "roughness": 0.25,
+                    "refractive_index": 1.5,
+                },
             },
         }),
     );
 }
 
+/// [`BlockEntityTemplate`]s contain functions, so they are not saved, and the
+/// loaded block has none.
+#[test]
+fn block_entity_not_saved() {
+    let block = Block::builder()
+        .color(Rgba::WHITE)
+        .block_entity(Some(BlockEntityTemplate::new(TickSchedule::Never, || {
+            NoopBehavior(1)
+        })))
+        .build();
+    let json_value = to_value(&block).unwrap();
+    assert_eq!(
+        json_value,
+        json!({
+            "type": "BlockV1",
+            "primitive": {
+                "type": "AtomV1",
+                "color": [1.0, 1.0, 1.0, 1.0],
+            },
+        })
+    );
+    assert_eq!(
+        from_value::<Block>(json_value).unwrap(),
+        Block::from(Rgba::WHITE)
+    );
+}
+
 #[test]
 fn block_with_modifiers() {
     assert_round_trip_value(
@@ -127,8 +172,222 @@ fn block_with_modifiers() {
     );
 }
 
+#[test]
+fn block_with_tint() {
+    assert_round_trip_value(
+        &Block::builder()
+            .color(Rgba::WHITE)
+            .modifier(Modifier::Tint(block::Tint::Multiply(Rgba::new(
+                1.0, 0.5, 0.0, 1.0,
+            ))))
+            .modifier(Modifier::Tint(block::Tint::Hsv {
+                hue_shift: notnan!(0.25),
+                saturation: notnan!(0.5),
+                value: notnan!(2.0),
+            }))
+            .modifier(Modifier::Tint(block::Tint::palette([(
+                Rgba::WHITE,
+                Rgba::BLACK,
+            )])))
+            .build(),
+        json!({
+            "type": "BlockV1",
+            "primitive": {
+                "type": "AtomV1",
+                "color": [1.0, 1.0, 1.0, 1.0],
+            },
+            "modifiers": [
+                {
+                    "type": "TintV1",
+                    "tint": { "type": "MultiplyV1", "color": [1.0, 0.5, 0.0, 1.0] },
+                },
+                {
+                    "type": "TintV1",
+                    "tint": {
+                        "type": "HsvV1",
+                        "hue_shift": 0.25,
+                        "saturation": 0.5,
+                        "value": 2.0,
+                    },
+                },
+                {
+                    "type": "TintV1",
+                    "tint": {
+                        "type": "PaletteV1",
+                        "pairs": [[[1.0, 1.0, 1.0, 1.0], [0.0, 0.0, 0.0, 1.0]]],
+                    },
+                },
+            ]
+        }),
+    );
+}
+
+#[test]
+fn block_with_shape_modifiers() {
+    assert_round_trip_value(
+        &Block::builder()
+            .color(Rgba::WHITE)
+            .modifier(Modifier::Mirror(Axis::Y))
+            .modifier(Modifier::Scale(block::Scale::new(
+                Resolution::R2,
+                GridPoint::new(1, 0, 1),
+            )))
+            .modifier(Modifier::Inset(block::Inset::new(
+                Resolution::R4,
+                GridAab::from_lower_upper([0, 0, 0], [4, 2, 4]),
+            )))
+            .modifier(Modifier::Composite(block::Composite::new(
+                block::AIR,
+                block::CompositeOperator::Atop,
+            )))
+            .build(),
+        json!({
+            "type": "BlockV1",
+            "primitive": {
+                "type": "AtomV1",
+                "color": [1.0, 1.0, 1.0, 1.0],
+            },
+            "modifiers": [
+                { "type": "MirrorV1", "axis": "Y" },
+                { "type": "ScaleV1", "scale": 2, "offset": [1, 0, 1] },
+                {
+                    "type": "InsetV1",
+                    "resolution": 4,
+                    "bounds": {
+                        "lower": [0, 0, 0],
+                        "upper": [4, 2, 4],
+                    },
+                },
+                {
+                    "type": "CompositeV1",
+                    "source": {
+                        "type": "BlockV1",
+                        "primitive": { "type": "AirV1" },
+                    },
+                    "operator": "Atop",
+                    "reverse": false,
+                    "disassemblable": false,
+                },
+            ]
+        }),
+    );
+}
+
+#[test]
+fn block_with_state() {
+    assert_round_trip_value(
+        &Block::from(Rgba::WHITE)
+            .with_property("open", true)
+            .with_property("level", 3u8)
+            .with_property("facing", "north"),
+        json!({
+            "type": "BlockV1",
+            "primitive": {
+                "type": "AtomV1",
+                "color": [1.0, 1.0, 1.0, 1.0],
+            },
+            "modifiers": [
+                {
+                    "type": "StateV1",
+                    "values": {
+                        "facing": "north",
+                        "level": 3,
+                        "open": true,
+                    },
+                },
+            ]
+        }),
+    );
+}
+
 // TODO: test serialization of each modifier
 
+//------------------------------------------------------------------------------------------------//
+// Tests corresponding to the `inv` module
+
+#[test]
+fn inventory() {
+    assert_round_trip_value(
+        &Inventory::from_slots(vec![
+            Tool::Activate.into(),
+            Slot::Empty,
+            Slot::stack(10, Tool::RemoveBlock { keep: true }),
+            Tool::ToggleProperty {
+                property: "open".into(),
+            }
+            .into(),
+        ]),
+        json!({
+            "type": "InventoryV1",
+            "slots": [
+                {"count": 1, "item": {"type": "ActivateV1"}},
+                null,
+                {"count": 10, "item": {"type": "RemoveBlockV1", "keep": true}},
+                {"count": 1, "item": {"type": "TogglePropertyV1", "property": "open"}},
+            ],
+        }),
+    );
+}
+
+//------------------------------------------------------------------------------------------------//
+// Tests corresponding to the `character` module
+
+#[test]
+fn input_recording() {
+    let mut recording = InputRecording::new(Duration::from_millis(20));
+    recording.record_step([
+        CharacterInput::Velocity(Vector3::new(1.0, 0.0, -0.5)),
+        CharacterInput::Look {
+            yaw: 90.0,
+            pitch: -10.0,
+        },
+    ]);
+    recording.record_paused_step([]);
+    recording.record_step([
+        CharacterInput::Jump,
+        CharacterInput::SelectSlot {
+            which_selection: 1,
+            slot: 3,
+        },
+        CharacterInput::UseTool {
+            button: 0,
+            cursor_ray: Some(Ray::new([0.5, 1.0, 2.0], [0.0, 0.0, -1.0])),
+        },
+        CharacterInput::UseTool {
+            button: 1,
+            cursor_ray: None,
+        },
+    ]);
+    assert_round_trip_value(
+        &recording,
+        json!({
+            "type": "InputRecordingV1",
+            "step_length_micros": 20000,
+            "steps": [
+                {
+                    "inputs": [
+                        {"type": "VelocityV1", "velocity": [1.0, 0.0, -0.5]},
+                        {"type": "LookV1", "yaw": 90.0, "pitch": -10.0},
+                    ],
+                },
+                {"paused": true, "inputs": []},
+                {
+                    "inputs": [
+                        {"type": "JumpV1"},
+                        {"type": "SelectSlotV1", "which_selection": 1, "slot": 3},
+                        {
+                            "type": "UseToolV1",
+                            "button": 0,
+                            "cursor_ray": {"origin": [0.5, 1.0, 2.0], "direction": [0.0, 0.0, -1.0]},
+                        },
+                        {"type": "UseToolV1", "button": 1},
+                    ],
+                },
+            ],
+        }),
+    );
+}
+
 //------------------------------------------------------------------------------------------------//
 // Tests corresponding to the `space` module
 
@@ -160,6 +419,58 @@ fn space() {
     );
 }
 
+#[test]
+fn space_with_cube_data() {
+    let chest = Block::builder()
+        .color(Rgba::WHITE)
+        .container_slots(2)
+        .build();
+    let mut space = Space::empty_positive(2, 1, 1);
+    space.set([1, 0, 0], &chest).unwrap();
+    SpaceTransaction::inventory([1, 0, 0], InventoryTransaction::insert([Tool::PushPull]))
+        .execute(&mut space, &mut transaction::no_outputs)
+        .unwrap();
+
+    assert_serdeser(
+        &space,
+        json!({
+            "type": "SpaceV1",
+            "bounds": {
+                "lower": [0, 0, 0],
+                "upper": [2, 1, 1],
+            },
+            "blocks": [
+                {
+                    "type": "BlockV1",
+                    "primitive": {"type": "AirV1"},
+                },
+                {
+                    "type": "BlockV1",
+                    "primitive": {
+                        "type": "AtomV1",
+                        "color": [1.0, 1.0, 1.0, 1.0],
+                        "container_slots": 2,
+                    },
+                },
+            ],
+            "contents": [0, 1],
+            "cube_data": [
+                {
+                    "type": "CubeDataV1",
+                    "cube": [1, 0, 0],
+                    "inventory": {
+                        "type": "InventoryV1",
+                        "slots": [
+                            {"count": 1, "item": {"type": "PushPullV1"}},
+                            null,
+                        ],
+                    },
+                },
+            ],
+        }),
+    );
+}
+
 //------------------------------------------------------------------------------------------------//
 // Tests corresponding to the `universe` module
 
@@ -235,6 +546,61 @@ fn universe_with_one_of_each() {
     )
 }
 
+#[test]
+fn universe_with_block_properties() {
+    let mut universe = Universe::new();
+    let properties = block::BlockProperties::new()
+        .property(block::Property::bool("open", false))
+        .property(block::Property::int("level", 3, 1))
+        .property(block::Property::enumeration("facing", ["north", "south"]))
+        .variant("open", true, [Modifier::Mirror(Axis::X)]);
+    universe
+        .insert(
+            "door".into(),
+            BlockDef::new(Block::from(Rgba::WHITE)).with_properties(properties.clone()),
+        )
+        .unwrap();
+
+    let json_value = to_value(&universe).unwrap();
+    assert_eq!(
+        json_value,
+        json!({
+            "type": "UniverseV1",
+            "members": [
+                {
+                    "name": {"Specific": "door"},
+                    "value": {
+                        "type": "BlockDefV1",
+                        "block": {
+                            "type": "BlockV1",
+                            "primitive": {
+                                "type": "AtomV1",
+                                "color": [1.0, 1.0, 1.0, 1.0],
+                            },
+                        },
+                        "properties": [
+                            { "type": "BoolV1", "name": "open", "default": false },
+                            { "type": "IntV1", "name": "level", "max": 3, "default": 1 },
+                            { "type": "EnumV1", "name": "facing", "values": ["north", "south"] },
+                        ],
+                        "variants": [
+                            {
+                                "property": "open",
+                                "value": true,
+                                "modifiers": [{ "type": "MirrorV1", "axis": "X" }],
+                            },
+                        ],
+                    }
+                },
+            ],
+        }),
+    );
+
+    let universe: Universe = from_value(json_value).unwrap();
+    let def: URef<BlockDef> = universe.get(&"door".into()).unwrap();
+    assert_eq!(def.read().unwrap().properties(), &properties);
+}
+
 #[test]
 fn uref_de_named() {
     let r: URef<BlockDef> = from_value(json!({
@@ -1,16 +1,17 @@
 //! That which contains many blocks.
 
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{hash_map, HashMap, HashSet};
 use std::fmt;
 use std::sync::{Arc, Mutex, Weak};
 
-use cgmath::Vector3;
+use cgmath::{Point3, Vector3};
 use instant::{Duration, Instant};
 
 use crate::behavior::{self, BehaviorSet};
 use crate::block::{
-    Block, BlockChange, EvalBlockError, EvaluatedBlock, Resolution, AIR, AIR_EVALUATED,
+    Block, BlockChange, EvalBlockError, EvalCacheStats, EvaluatedBlock, Resolution, AIR,
+    AIR_EVALUATED,
 };
 #[cfg(doc)]
 use crate::character::Character;
@@ -29,15 +30,31 @@ use crate::universe::{RefVisitor, URef, UniverseTransaction, VisitRefs};
 use crate::util::{ConciseDebug, TimeStats};
 use crate::util::{CustomFormat, StatusText};
 
+mod block_entity;
+use block_entity::BlockEntities;
+pub use block_entity::{BlockEntityTemplate, TickSchedule};
+
 mod builder;
 pub use builder::{SpaceBuilder, SpaceBuilderBounds};
 
+mod cube_data;
+pub use cube_data::CubeData;
+
+mod dynamic_light;
+pub use dynamic_light::{DynamicLight, LightShape};
+
+mod fog;
+pub use fog::Fog;
+
 mod light;
 #[doc(hidden)] // pub only for visualization by all-is-cubes-gpu
 pub use light::LightUpdateCubeInfo;
 use light::{opaque_for_light_computation, LightUpdateQueue, PackedLightScalar};
 pub use light::{LightUpdatesInfo, PackedLight};
 
+mod sky;
+pub use sky::{DayNightCycle, Sky};
+
 mod space_txn;
 pub use space_txn::*;
 
@@ -68,6 +85,9 @@ pub struct Space {
     pub(crate) lighting: Box<[PackedLight]>,
     /// Queue of cubes whose light values should be updated.
     light_update_queue: LightUpdateQueue,
+    /// Cubes whose most recently computed light value included light directly from the
+    /// sky, and so should be updated when the sky changes.
+    sky_exposed_cubes: HashSet<GridPoint>,
     /// Debug log of the updated cubes from last frame.
     /// Empty unless this debug function is enabled.
     #[doc(hidden)] // pub to be used by all-is-cubes-gpu
@@ -85,6 +105,13 @@ pub struct Space {
 
     spawn: Spawn,
 
+    /// Data for individual cubes, currently those whose blocks are containers.
+    cube_data: HashMap<GridPoint, CubeData>,
+
+    /// Behaviors of individual cubes, created according to
+    /// [`BlockAttributes::block_entity`](crate::block::BlockAttributes::block_entity).
+    block_entities: BlockEntities,
+
     /// Cubes that should be checked on the next call to step()
     cubes_wanting_ticks: HashSet<GridPoint>,
 
@@ -105,6 +132,8 @@ pub struct SpaceBlockData {
     /// Number of uses of this block in the space.
     count: usize,
     evaluated: EvaluatedBlock,
+    /// Cached [`light::transmission_tint()`] of `evaluated`.
+    light_tint: Rgb,
     #[allow(dead_code)] // Used only for its `Drop`
     block_listen_gate: Option<Gate>,
 }
@@ -117,6 +146,8 @@ impl fmt::Debug for Space {
             .field("block_data", &self.block_data)
             .field("physics", &self.physics)
             .field("behaviors", &self.behaviors)
+            .field("cube_data", &self.cube_data)
+            .field("block_entities", &self.block_entities)
             .field("cubes_wanting_ticks", &self.cubes_wanting_ticks) // TODO: truncate?
             .finish_non_exhaustive()
     }
@@ -185,6 +216,12 @@ impl Space {
         )
         .expect("evaluation of block for newly created space failed");
         block_data.count = volume;
+        let cube_data = uniform_cube_data(bounds, &block_data.evaluated);
+        let mut block_entities = BlockEntities::default();
+        block_entities.reset_uniform(
+            bounds,
+            block_data.evaluated.attributes.block_entity.as_ref(),
+        );
 
         // TODO: light update queue should not necessarily be empty.
         // TODO: handle the block possibly having a tick_action.
@@ -204,11 +241,14 @@ impl Space {
             lighting: physics.light.initialize_lighting(bounds),
             packed_sky_color: physics.sky_color.into(),
             light_update_queue: LightUpdateQueue::new(),
+            sky_exposed_cubes: HashSet::new(),
             last_light_updates: Vec::new(),
 
             physics,
             behaviors: BehaviorSet::new(),
             spawn: spawn.unwrap_or_else(|| Spawn::default_for_new_space(bounds)),
+            cube_data,
+            block_entities,
             cubes_wanting_ticks: HashSet::new(),
             notifier: Notifier::new(),
             todo,
@@ -276,10 +316,16 @@ impl Space {
     /// Gets the [`EvaluatedBlock`] of the block in this space at the given position.
     #[inline(always)]
     pub fn get_evaluated(&self, position: impl Into<GridPoint>) -> &EvaluatedBlock {
+        &self.get_block_data(position).evaluated
+    }
+
+    /// Returns the [`SpaceBlockData`] of the block in the given cube, or
+    /// [`SpaceBlockData::NOTHING`] if it is out of bounds.
+    fn get_block_data(&self, position: impl Into<GridPoint>) -> &SpaceBlockData {
         if let Some(index) = self.bounds.index(position) {
-            &self.block_data[self.contents[index] as usize].evaluated
+            &self.block_data[self.contents[index] as usize]
         } else {
-            &AIR_EVALUATED
+            &SpaceBlockData::NOTHING
         }
     }
 
@@ -305,10 +351,44 @@ impl Space {
         }
     }
 
+    /// Returns the [`CubeData`] of the cube at the given position, if it has any.
+    ///
+    /// A cube has data if its block is a container; see [`CubeData`] for details.
+    pub fn cube_data(&self, position: impl Into<GridPoint>) -> Option<&CubeData> {
+        self.cube_data.get(&position.into())
+    }
+
+    /// Returns the block entity (the behavior created according to
+    /// [`BlockAttributes::block_entity`](crate::block::BlockAttributes::block_entity))
+    /// of the cube at the given position, if it has one.
+    ///
+    /// Use [`Behavior::downcast_ref()`](behavior::Behavior) to access its specific type.
+    pub fn block_entity(
+        &self,
+        position: impl Into<GridPoint>,
+    ) -> Option<&dyn behavior::Behavior<Space>> {
+        self.block_entities.get(position.into()).map(|b| &**b)
+    }
+
+    /// Returns all cubes which have [`CubeData`], in arbitrary order.
+    pub(crate) fn iter_cube_data(&self) -> impl Iterator<Item = (GridPoint, &CubeData)> + '_ {
+        self.cube_data.iter().map(|(&cube, data)| (cube, data))
+    }
+
+    /// Replaces the [`CubeData`] of the cube at the given position, regardless of
+    /// whether the block there is a container. Used when loading saved data.
+    pub(crate) fn insert_cube_data(&mut self, position: GridPoint, data: CubeData) {
+        self.cube_data.insert(position, data);
+        self.notifier.notify(SpaceChange::CubeData(position));
+    }
+
     /// Replace the block in this space at the given position.
     ///
     /// If the position is out of bounds, there is no effect.
     ///
+    /// If the old block was a container, items which the new block has no slots for are
+    /// discarded. (A [`SpaceTransaction`] will fail rather than doing this.)
+    ///
     /// ```
     /// use all_is_cubes::block::*;
     /// use all_is_cubes::math::Rgba;
@@ -414,6 +494,8 @@ impl Space {
         position: GridPoint,
         contents_index: usize,
     ) {
+        self.update_cube_data_and_entity(block_index, position);
+
         let evaluated = &self.block_data[block_index as usize].evaluated;
 
         if evaluated.attributes.tick_action.is_some() {
@@ -435,6 +517,7 @@ impl Space {
                 // lighting performance — these trivial updates are not most of the cost.
                 // But it'll at least save a little bit of memory.)
                 self.light_update_queue.remove(position);
+                self.sky_exposed_cubes.remove(&position);
 
                 self.notifier.notify(SpaceChange::Lighting(position));
             } else {
@@ -453,6 +536,32 @@ impl Space {
         self.notifier.notify(SpaceChange::Block(position));
     }
 
+    /// Creates, resizes, or removes the [`CubeData`] and block entity of the cube at
+    /// `position` to match its block, whose index is `block_index`.
+    fn update_cube_data_and_entity(&mut self, block_index: BlockIndex, position: GridPoint) {
+        let evaluated = &self.block_data[block_index as usize].evaluated;
+
+        let container_slots = evaluated.attributes.container_slots;
+        let cube_data_changed = match (self.cube_data.entry(position), container_slots) {
+            (hash_map::Entry::Vacant(_), 0) => false,
+            (hash_map::Entry::Occupied(entry), 0) => {
+                entry.remove();
+                true
+            }
+            (hash_map::Entry::Vacant(entry), slots) => {
+                entry.insert(CubeData::new(slots));
+                true
+            }
+            (hash_map::Entry::Occupied(mut entry), slots) => entry.get_mut().resize(slots),
+        };
+        if cube_data_changed {
+            self.notifier.notify(SpaceChange::CubeData(position));
+        }
+
+        self.block_entities
+            .update(position, evaluated.attributes.block_entity.as_ref());
+    }
+
     /// Replace blocks in `region` with a block computed by the function.
     ///
     /// The function may return a reference to a block or a block. If it returns [`None`],
@@ -553,6 +662,15 @@ impl Space {
             for i in self.contents.iter_mut() {
                 *i = new_block_index;
             }
+            self.cube_data = uniform_cube_data(region, &self.block_data[0].evaluated);
+            self.block_entities.reset_uniform(
+                region,
+                self.block_data[0]
+                    .evaluated
+                    .attributes
+                    .block_entity
+                    .as_ref(),
+            );
             // TODO: also need to reset lighting and activate tick_action.
             // And see if we can share more of the logic of this with new_from_builder().
             self.notifier.notify(SpaceChange::EveryBlock);
@@ -604,6 +722,8 @@ impl Space {
         // Process changed block definitions.
         let mut last_start_time = Instant::now();
         let mut evaluations = TimeStats::default();
+        let eval_cache_before = EvalCacheStats::current_thread();
+        let mut blocks_changing_cubes: HashSet<BlockIndex> = HashSet::new();
         for block_index in self.todo.lock().unwrap().blocks.drain() {
             self.notifier.notify(SpaceChange::BlockValue(block_index));
             let data: &mut SpaceBlockData = &mut self.block_data[usize::from(block_index)];
@@ -613,7 +733,22 @@ impl Space {
             // continuing with a partly broken world. Right now, we just continue with the
             // placeholder, which may have cascading effects despite the placeholder's
             // design to be innocuous.
-            data.evaluated = data.block.evaluate().unwrap_or_else(|e| e.to_placeholder());
+            let old_evaluated = std::mem::replace(
+                &mut data.evaluated,
+                data.block.evaluate().unwrap_or_else(|e| e.to_placeholder()),
+            );
+            data.light_tint = light::transmission_tint(&data.evaluated);
+
+            // Cube data and block entities are per-cube, so if the block's attributes
+            // controlling them changed, the cubes containing the block must be updated.
+            if data.count > 0
+                && (old_evaluated.attributes.container_slots
+                    != data.evaluated.attributes.container_slots
+                    || old_evaluated.attributes.block_entity
+                        != data.evaluated.attributes.block_entity)
+            {
+                blocks_changing_cubes.insert(block_index);
+            }
 
             // TODO: Process side effects on individual cubes such as reevaluating the
             // lighting influenced by the block.
@@ -621,6 +756,24 @@ impl Space {
             evaluations.record_consecutive_interval(&mut last_start_time, Instant::now());
         }
 
+        if !blocks_changing_cubes.is_empty() {
+            // Find all the affected cubes in a single pass over the contents.
+            let cubes: Vec<(BlockIndex, GridPoint)> = self
+                .bounds
+                .interior_iter()
+                .filter_map(|cube| {
+                    let block_index = self.contents[self.bounds.index(cube).unwrap()];
+                    blocks_changing_cubes
+                        .contains(&block_index)
+                        .then_some((block_index, cube))
+                })
+                .collect();
+            for (block_index, cube) in cubes {
+                self.update_cube_data_and_entity(block_index, cube);
+            }
+        }
+
+        let eval_cache = EvalCacheStats::current_thread() - eval_cache_before;
         let start_cube_ticks = last_start_time;
 
         // Process cubes_wanting_ticks.
@@ -646,14 +799,61 @@ impl Space {
         let cube_ticks_to_space_behaviors = Instant::now();
 
         let mut transaction = UniverseTransaction::default();
+        let mut block_entity_ticks = 0;
+        let mut block_entity_conflicts = 0;
         if let Some(self_ref) = self_ref {
             if !tick.paused() {
+                let host_transaction_binder = |t: SpaceTransaction| t.bind(self_ref.clone());
                 transaction = self.behaviors.step(
                     &*self,
-                    &(|t: SpaceTransaction| t.bind(self_ref.clone())),
+                    &host_transaction_binder,
                     SpaceTransaction::behaviors,
                     tick,
                 );
+
+                let due = self.block_entities.advance(tick.delta_t());
+                block_entity_ticks = due.len();
+                let mut retry = Vec::new();
+                for cube in due {
+                    let Some(entity) = self.block_entities.get(cube).cloned() else {
+                        continue;
+                    };
+                    let attachment = SpaceBehaviorAttachment::new(GridAab::single_cube(cube));
+                    let self_transaction_binder = |new_behavior| {
+                        host_transaction_binder(SpaceTransaction::replace_block_entity(
+                            cube,
+                            entity.clone(),
+                            new_behavior,
+                        ))
+                    };
+                    let context = behavior::BehaviorContext::new(
+                        &*self,
+                        &attachment,
+                        &host_transaction_binder,
+                        &self_transaction_binder,
+                    );
+                    if !entity.alive(&context) {
+                        continue;
+                    }
+                    let entity_transaction = entity.step(&context, tick);
+                    // If block entities' effects conflict, the later one is dropped, and
+                    // the entity is stepped again next time instead.
+                    match transaction.check_merge(&entity_transaction) {
+                        Ok(check) => {
+                            transaction = transaction.commit_merge(entity_transaction, check);
+                        }
+                        Err(conflict) => {
+                            log::debug!(
+                                "block entity at {cube:?} conflicted and will be retried: {conflict}"
+                            );
+                            block_entity_conflicts += 1;
+                            retry.push(cube);
+                        }
+                    }
+                }
+                for cube in retry {
+                    self.block_entities.retry(cube);
+                }
             }
         }
 
@@ -665,7 +865,10 @@ impl Space {
             SpaceStepInfo {
                 spaces: 1,
                 evaluations,
+                eval_cache,
                 cube_ticks: count_cubes_ticked,
+                block_entity_ticks,
+                block_entity_conflicts,
                 cube_time: cube_ticks_to_space_behaviors
                     .saturating_duration_since(start_cube_ticks),
                 behaviors_time: space_behaviors_to_lighting
@@ -710,12 +913,52 @@ impl Space {
         total
     }
 
+    /// Perform lighting updates until the light has fully converged; that is, until
+    /// recomputing every cube's light produces no change.
+    ///
+    /// This is more thorough, and slower, than [`Space::evaluate_light()`], which does
+    /// not propagate the smallest changes. It is intended for offline use, such as
+    /// baking lighting for export or producing reference images.
+    ///
+    /// `maximum_passes` limits the number of times every cube is recomputed, in case the
+    /// light does not converge. Returns the number of passes performed.
+    pub fn bake_lighting(
+        &mut self,
+        maximum_passes: usize,
+        mut progress_callback: impl FnMut(LightUpdatesInfo),
+    ) -> usize {
+        self.evaluate_light(0, &mut progress_callback);
+        for pass in 1..=maximum_passes {
+            for cube in self.bounds.interior_iter() {
+                self.light_needs_update(cube, PackedLightScalar::MAX);
+            }
+            let mut max_difference = 0;
+            self.evaluate_light(0, |info| {
+                max_difference = max_difference.max(info.max_update_difference);
+                progress_callback(info);
+            });
+            if max_difference == 0 {
+                return pass;
+            }
+        }
+        maximum_passes
+    }
+
     /// Returns the current [`SpacePhysics`] data, which determines global characteristics
     /// such as the behavior of light and gravity.
     pub fn physics(&self) -> &SpacePhysics {
         &self.physics
     }
 
+    /// Returns the gravity vector, in cubes/s², which applies to a moving object at the
+    /// given point.
+    ///
+    /// This is [`SpacePhysics::gravity`] unless overridden by a
+    /// [`GravityZone`](crate::physics::GravityZone) behavior containing the point.
+    pub fn gravity_at(&self, point: Point3<FreeCoordinate>) -> Vector3<FreeCoordinate> {
+        crate::physics::gravity_at(self, point)
+    }
+
     /// Sets the physics parameters, as per [`physics`](Self::physics).
     ///
     /// This may cause recomputation of lighting.
@@ -725,6 +968,7 @@ impl Space {
         if self.physics.light != old_physics.light {
             // TODO: == comparison is too broad once there are parameters -- might be a minor change of color etc.
             self.lighting = self.physics.light.initialize_lighting(self.bounds);
+            self.sky_exposed_cubes.clear();
 
             match self.physics.light {
                 LightPhysics::None => {
@@ -736,8 +980,17 @@ impl Space {
             }
 
             // TODO: Need to force light updates
+        } else if self.physics.fog.is_some() != old_physics.fog.is_some() {
+            // Fog changes which cubes need light values.
+            self.fast_evaluate_light();
+        } else if self.physics.sky_color != old_physics.sky_color
+            || self.physics.sky != old_physics.sky
+        {
+            self.queue_light_updates_for_sky_change(&old_physics);
+        }
+        if self.physics != old_physics {
+            self.notifier.notify(SpaceChange::Physics);
         }
-        // TODO: Also send out a SpaceChange notification, if anything is different.
     }
 
     /// Returns the current default [`Spawn`], which determines where new [`Character`]s
@@ -902,11 +1155,14 @@ impl VisitRefs for Space {
             contents: _,
             lighting: _,
             light_update_queue: _,
+            sky_exposed_cubes: _,
             last_light_updates: _,
             physics: _,
             packed_sky_color: _,
             behaviors,
             spawn,
+            cube_data,
+            block_entities,
             cubes_wanting_ticks: _,
             notifier: _,
             todo: _,
@@ -914,6 +1170,10 @@ impl VisitRefs for Space {
         for SpaceBlockData { block, .. } in block_data {
             block.visit_refs(visitor);
         }
+        for data in cube_data.values() {
+            data.visit_refs(visitor);
+        }
+        block_entities.visit_refs(visitor);
         behaviors.visit_refs(visitor);
         spawn.visit_refs(visitor);
     }
@@ -931,6 +1191,17 @@ impl crate::behavior::BehaviorHost for Space {
     type Attachment = SpaceBehaviorAttachment;
 }
 
+/// Computes the [`CubeData`] for a region filled entirely with one block.
+fn uniform_cube_data(region: GridAab, evaluated: &EvaluatedBlock) -> HashMap<GridPoint, CubeData> {
+    match evaluated.attributes.container_slots {
+        0 => HashMap::new(),
+        slots => region
+            .interior_iter()
+            .map(|cube| (cube, CubeData::new(slots)))
+            .collect(),
+    }
+}
+
 impl SpaceBlockData {
     /// A `SpaceBlockData` value used to represent out-of-bounds or placeholder
     /// situations. The block is [`AIR`] and the count is always zero.
@@ -938,6 +1209,7 @@ impl SpaceBlockData {
         block: AIR,
         count: 0,
         evaluated: AIR_EVALUATED,
+        light_tint: Rgb::ONE,
         block_listen_gate: None,
     };
 
@@ -950,6 +1222,7 @@ impl SpaceBlockData {
             block: AIR,
             count: 0,
             evaluated: AIR_EVALUATED,
+            light_tint: Rgb::ONE,
             block_listen_gate: None,
         }
     }
@@ -967,6 +1240,7 @@ impl SpaceBlockData {
         Ok(Self {
             block,
             count: 0,
+            light_tint: light::transmission_tint(&evaluated),
             evaluated,
             block_listen_gate: Some(gate),
         })
@@ -1035,16 +1309,33 @@ impl SpaceBehaviorAttachment {
 pub struct SpacePhysics {
     /// Gravity vector for moving objects, in cubes/s².
     ///
-    /// TODO: Expand this to an enum which allows non-uniform gravity patterns.
+    /// This may be overridden in parts of the space by
+    /// [`GravityZone`](crate::physics::GravityZone) behaviors;
+    /// use [`Space::gravity_at()`] to find the gravity that applies at a given point.
     pub gravity: Vector3<NotNan<FreeCoordinate>>,
 
     /// Color of light arriving from outside the space, used for light calculation
     /// and rendering.
     ///
-    /// TODO: Consider replacing this with some sort of cube map, spherical harmonics,
-    /// or some such to allow for non-uniform illumination.
+    /// If [`sky`](Self::sky) is set, then this is used only where a single
+    /// direction-independent color is needed (such as the light outside the bounds of
+    /// the space), and should be an approximation of it such as [`Sky::average()`].
     pub sky_color: Rgb,
 
+    /// Direction-dependent light arriving from outside the space, including the sun.
+    /// If [`None`], the sky is uniformly [`sky_color`](Self::sky_color).
+    ///
+    /// Use [`SpacePhysics::sky_light()`] to sample whichever applies.
+    pub sky: Option<Sky>,
+
+    /// Participating medium filling the space, which obscures distant objects and
+    /// scatters light. If [`None`], the space is perfectly clear.
+    ///
+    /// This is independent of, and in addition to, the distance fog chosen by
+    /// [`GraphicsOptions::fog`](crate::camera::GraphicsOptions::fog), but is not
+    /// rendered if that is [`FogOption::None`](crate::camera::FogOption::None).
+    pub fog: Option<Fog>,
+
     /// Method used to compute the illumination of individual blocks.
     pub light: LightPhysics,
     // When adding a field, don't forget to expand the Debug impl.
@@ -1054,6 +1345,8 @@ impl SpacePhysics {
     pub(crate) const DEFAULT: Self = Self {
         gravity: Vector3::new(notnan!(0.), notnan!(-20.), notnan!(0.)),
         sky_color: palette::DAY_SKY_COLOR,
+        sky: None,
+        fog: None,
         light: LightPhysics::DEFAULT,
     };
 
@@ -1062,8 +1355,19 @@ impl SpacePhysics {
     pub const DEFAULT_FOR_BLOCK: Self = Self {
         gravity: Vector3::new(notnan!(0.), notnan!(0.), notnan!(0.)),
         sky_color: rgb_const!(0.5, 0.5, 0.5),
+        sky: None,
+        fog: None,
         light: LightPhysics::None,
     };
+
+    /// Returns the light arriving from outside the space along the given direction
+    /// (pointing away from the viewer, towards the sky).
+    pub fn sky_light(&self, direction: Vector3<FreeCoordinate>) -> Rgb {
+        match &self.sky {
+            Some(sky) => sky.sample(direction),
+            None => self.sky_color,
+        }
+    }
 }
 
 impl fmt::Debug for SpacePhysics {
@@ -1077,6 +1381,8 @@ impl fmt::Debug for SpacePhysics {
                     .custom_format(ConciseDebug),
             )
             .field("sky_color", &self.sky_color)
+            .field("sky", &self.sky)
+            .field("fog", &self.fog)
             .field("light", &self.light)
             .finish()
     }
@@ -1095,6 +1401,8 @@ impl<'a> arbitrary::Arbitrary<'a> for SpacePhysics {
         Ok(Self {
             gravity: Vector3::new(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?),
             sky_color: u.arbitrary()?,
+            sky: u.arbitrary()?,
+            fog: u.arbitrary()?,
             light: u.arbitrary()?,
         })
     }
@@ -1105,6 +1413,8 @@ impl<'a> arbitrary::Arbitrary<'a> for SpacePhysics {
             <f64 as Arbitrary>::size_hint(depth),
             <f64 as Arbitrary>::size_hint(depth),
             <Rgb as Arbitrary>::size_hint(depth),
+            <Option<Sky> as Arbitrary>::size_hint(depth),
+            <Option<Fog> as Arbitrary>::size_hint(depth),
             <LightPhysics as Arbitrary>::size_hint(depth),
         ])
     }
@@ -1120,20 +1430,48 @@ pub enum LightPhysics {
     None,
     /// Raycast-based light propagation and diffuse reflections.
     ///
-    /// TODO: Need to provide a builder so that this can be constructed
-    /// even when more parameters are added.
-    // TODO: #[non_exhaustive]
+    /// Use [`LightPhysics::rays()`] to construct this with default parameters.
+    #[non_exhaustive]
     Rays {
         /// The maximum distance a simulated light ray will travel; blocks farther than
         /// that distance apart will never have direct influence on each other.
         maximum_distance: u16,
+
+        /// The set of directions in which light rays are cast from each cube.
+        ///
+        /// More rays give smoother and more accurate light at the cost of proportionally
+        /// more computation. Rays which are farther apart than about one cube at
+        /// `maximum_distance` may miss small features entirely.
+        ray_pattern: RayPattern,
+
+        /// Whether each ray's contribution is weighted by the cosine of the angle between
+        /// it and the faces of the cube it lights. If false, every ray which reaches any
+        /// of those faces contributes equally.
+        cosine_weighting: bool,
+
+        /// Upper limit on the amount of light computation done in each
+        /// [`Space::step()`], in units of approximately one raycast step.
+        /// Remaining updates are deferred to later steps.
+        ///
+        /// This is a count of work rather than a time limit, so that the results of
+        /// stepping are deterministic and platform-independent.
+        update_budget: u32,
     },
 }
 
 impl LightPhysics {
-    pub(crate) const DEFAULT: Self = Self::Rays {
-        maximum_distance: 30,
-    };
+    pub(crate) const DEFAULT: Self = Self::rays(30);
+
+    /// Returns [`LightPhysics::Rays`] with the given `maximum_distance` and default
+    /// values for all other parameters.
+    pub const fn rays(maximum_distance: u16) -> Self {
+        Self::Rays {
+            maximum_distance,
+            ray_pattern: RayPattern::DEFAULT,
+            cosine_weighting: true,
+            update_budget: 100_000,
+        }
+    }
 }
 
 impl Default for LightPhysics {
@@ -1142,6 +1480,49 @@ impl Default for LightPhysics {
     }
 }
 
+/// A set of directions in which to cast light rays; part of [`LightPhysics::Rays`].
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum RayPattern {
+    /// Rays toward each integer point on the surface of a cube of edge length
+    /// `2 * divisions`, giving `24 * divisions² + 2` rays.
+    ///
+    /// The rays are denser toward the corners of the cube than toward the faces.
+    Cube {
+        #[allow(missing_docs)]
+        divisions: u8,
+    },
+    /// Rays toward each integer point on the surface of an octahedron of radius
+    /// `divisions`, giving `4 * divisions² + 2` rays.
+    ///
+    /// The rays are denser along the axes than along the diagonals.
+    Octahedron {
+        #[allow(missing_docs)]
+        divisions: u8,
+    },
+    /// Approximately evenly spaced rays arranged in a spiral (the “Fibonacci
+    /// lattice”), giving exactly `count` rays.
+    ///
+    /// Unlike the other patterns, this is not exactly symmetric, but any ray count
+    /// may be chosen.
+    Spiral {
+        #[allow(missing_docs)]
+        count: u16,
+    },
+}
+
+impl RayPattern {
+    /// The pattern used by [`LightPhysics::rays()`].
+    pub const DEFAULT: Self = Self::Cube { divisions: 5 };
+}
+
+impl Default for RayPattern {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 /// Ways that [`Space::set`] can fail to make a change.
 ///
 /// Note that "already contained the given block" is considered a success.
@@ -1179,9 +1560,14 @@ pub enum SpaceChange {
     /// The definition of the block referred to by the given block index number was
     /// changed; the result of [`Space::get_evaluated`] may differ.
     BlockValue(BlockIndex),
-    /// Equivalent to [`SpaceChange::Block`] for every cube and [`SpaceChange::Number`]
-    /// for every index.
+    /// Equivalent to [`SpaceChange::Block`] and [`SpaceChange::CubeData`] for every cube
+    /// and [`SpaceChange::Number`] for every index.
     EveryBlock,
+    /// The [`CubeData`] of the cube at the given location was created, removed, or
+    /// changed; the result of [`Space::cube_data`] may differ.
+    CubeData(GridPoint),
+    /// The [`SpacePhysics`] changed; the result of [`Space::physics`] may differ.
+    Physics,
 }
 
 /// Performance data returned by [`Space::step`]. The exact contents of this structure
@@ -1199,9 +1585,20 @@ pub struct SpaceStepInfo {
     /// that add new blocks to the space.
     pub evaluations: TimeStats,
 
+    /// Usage of the [`BlockDef`](crate::block::BlockDef) evaluation cache by the block
+    /// re-evaluations counted in [`Self::evaluations`].
+    pub eval_cache: EvalCacheStats,
+
     /// Number of individual cubes processed (`tick_action`).
     cube_ticks: usize,
 
+    /// Number of block entities stepped.
+    block_entity_ticks: usize,
+
+    /// Number of block entity steps whose effects conflicted with other changes, and
+    /// which were therefore dropped, to be retried on the next step.
+    block_entity_conflicts: usize,
+
     /// Time spent on processing individual cube updates
     /// (measured as a whole because transaction conflict checking is needed),
     cube_time: Duration,
@@ -1220,7 +1617,10 @@ impl std::ops::AddAssign<SpaceStepInfo> for SpaceStepInfo {
         }
         self.spaces += other.spaces;
         self.evaluations += other.evaluations;
+        self.eval_cache += other.eval_cache;
         self.cube_ticks += other.cube_ticks;
+        self.block_entity_ticks += other.block_entity_ticks;
+        self.block_entity_conflicts += other.block_entity_conflicts;
         self.cube_time += other.cube_time;
         self.behaviors_time += other.behaviors_time;
         self.light += other.light;
@@ -1231,7 +1631,10 @@ impl CustomFormat<StatusText> for SpaceStepInfo {
         let Self {
             spaces,
             evaluations,
+            eval_cache,
             cube_ticks,
+            block_entity_ticks,
+            block_entity_conflicts,
             cube_time,
             behaviors_time,
             light,
@@ -1240,13 +1643,15 @@ impl CustomFormat<StatusText> for SpaceStepInfo {
             let light = light.custom_format(StatusText);
             let cube_time = cube_time.custom_format(StatusText);
             let behaviors_time = behaviors_time.custom_format(StatusText);
+            let EvalCacheStats { hits, misses } = eval_cache;
             write!(
                 fmt,
                 "\
                 {spaces} spaces' steps:\n\
                 Block reeval: {evaluations}\n\
+                Block def cache: {hits} hits, {misses} misses\n\
                 Cubes: {cube_ticks} cubes ticked in {cube_time}\n\
-                Behaviors: {behaviors_time}\n\
+                Behaviors: {behaviors_time} ({block_entity_ticks} block entities, {block_entity_conflicts} conflicts)\n\
                 Light: {light}\
                 "
             )?;
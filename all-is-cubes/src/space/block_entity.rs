@@ -0,0 +1,276 @@
+//! [`BlockEntityTemplate`] and the per-cube [`Behavior`]s (“block entities”) it creates.
+
+use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use rand::{Rng as _, SeedableRng as _};
+use rand_xoshiro::Xoshiro256Plus;
+
+use crate::behavior::Behavior;
+use crate::inv::EphemeralOpaque;
+use crate::math::{GridAab, GridCoordinate, GridPoint};
+use crate::space::Space;
+use crate::time::Duration;
+use crate::universe::{RefVisitor, VisitRefs};
+
+#[cfg(doc)]
+use crate::{block::BlockAttributes, space::SpaceTransaction};
+
+/// Specifies a [`Behavior`] which is created for each cube of a [`Space`] containing a
+/// block; such a behavior is known as a _block entity_.
+///
+/// This is the type of [`BlockAttributes::block_entity`]. The block entity is removed
+/// when the cube is changed to a block which does not have an equal template (so, for
+/// example, changing only a [property](crate::block::BlockProperties) of the block, with
+/// no effect on the template, keeps the block entity).
+///
+/// Rather than being stepped on every step of the space, each block entity is stepped
+/// only at times chosen by its [`TickSchedule`], so a space may contain many of them at
+/// little cost.
+///
+/// Block entities and templates are not currently saved; a block loaded from saved data
+/// does not have a template.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct BlockEntityTemplate {
+    constructor: EphemeralOpaque<dyn Fn() -> Arc<dyn Behavior<Space>> + Send + Sync>,
+    schedule: TickSchedule,
+}
+
+impl BlockEntityTemplate {
+    /// Constructs a template which calls `constructor` to create the behavior for each
+    /// cube, and steps the behavior according to `schedule` (which may be changed for an
+    /// individual cube by [`SpaceTransaction::schedule_block_entity()`]).
+    pub fn new<B, F>(schedule: TickSchedule, constructor: F) -> Self
+    where
+        B: Behavior<Space>,
+        F: Fn() -> B + Send + Sync + 'static,
+    {
+        Self {
+            constructor: EphemeralOpaque::from(Arc::new(move || {
+                Arc::new(constructor()) as Arc<dyn Behavior<Space>>
+            })
+                as Arc<dyn Fn() -> Arc<dyn Behavior<Space>> + Send + Sync>),
+            schedule,
+        }
+    }
+
+    /// Returns the schedule on which block entities created from this template are
+    /// initially stepped.
+    pub fn schedule(&self) -> TickSchedule {
+        self.schedule
+    }
+
+    fn instantiate(&self) -> Option<Arc<dyn Behavior<Space>>> {
+        self.constructor.try_ref().map(|constructor| constructor())
+    }
+}
+
+/// When a block entity (see [`BlockEntityTemplate`]) should be stepped.
+///
+/// All durations are measured in game time, which does not pass while the space is
+/// paused.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum TickSchedule {
+    /// Never step the block entity (until the schedule is changed).
+    Never,
+    /// Step the block entity once after this much time has passed, then change the
+    /// schedule to [`TickSchedule::Never`].
+    Once(Duration),
+    /// Step the block entity each time this much time has passed.
+    Every(Duration),
+    /// Step the block entity repeatedly, each time after a random amount of time
+    /// between `min` and `max` inclusive.
+    ///
+    /// The randomness is deterministic, depending on the cube and the current time.
+    Random {
+        /// Minimum time until the next step.
+        min: Duration,
+        /// Maximum time until the next step.
+        max: Duration,
+    },
+}
+
+impl TickSchedule {
+    /// Returns the time at which a block entity at `cube` should next be stepped,
+    /// given that the current time is `now`.
+    fn next_wake(self, now: Duration, cube: GridPoint) -> Option<Duration> {
+        match self {
+            TickSchedule::Never => None,
+            TickSchedule::Once(delay) | TickSchedule::Every(delay) => Some(now + delay),
+            TickSchedule::Random { min, max } => {
+                let mut hasher = DefaultHasher::new();
+                let cube: [GridCoordinate; 3] = cube.into();
+                (cube, now).hash(&mut hasher);
+                let mut rng = Xoshiro256Plus::seed_from_u64(hasher.finish());
+                Some(now + rng.gen_range(min.min(max)..=max.max(min)))
+            }
+        }
+    }
+
+    /// Returns the schedule to use after a step has occurred.
+    fn after_step(self) -> Self {
+        match self {
+            TickSchedule::Once(_) => TickSchedule::Never,
+            other => other,
+        }
+    }
+}
+
+/// The block entities of a [`Space`], and the times they should next be stepped.
+#[derive(Default)]
+pub(crate) struct BlockEntities {
+    /// Game time which has passed in the space, used only for scheduling.
+    time: Duration,
+    entities: HashMap<GridPoint, BlockEntity>,
+    /// Scheduled steps in order of time.
+    /// An entry is outdated, and ignored, unless it matches [`BlockEntity::next_wake`].
+    queue: BinaryHeap<Reverse<(Duration, [GridCoordinate; 3])>>,
+}
+
+struct BlockEntity {
+    template: BlockEntityTemplate,
+    behavior: Arc<dyn Behavior<Space>>,
+    schedule: TickSchedule,
+    next_wake: Option<Duration>,
+}
+
+impl BlockEntities {
+    pub(crate) fn get(&self, cube: GridPoint) -> Option<&Arc<dyn Behavior<Space>>> {
+        self.entities.get(&cube).map(|entity| &entity.behavior)
+    }
+
+    /// Creates, removes, or keeps the block entity at `cube` to match a newly placed
+    /// block's template.
+    pub(crate) fn update(&mut self, cube: GridPoint, template: Option<&BlockEntityTemplate>) {
+        if let Some(existing) = self.entities.get(&cube) {
+            if Some(&existing.template) == template {
+                return;
+            }
+            self.entities.remove(&cube);
+        }
+        if let Some(template) = template {
+            self.insert(cube, template);
+        }
+    }
+
+    /// Replaces all block entities with new ones for every cube in `region`
+    /// (if `template` is not [`None`]).
+    pub(crate) fn reset_uniform(
+        &mut self,
+        region: GridAab,
+        template: Option<&BlockEntityTemplate>,
+    ) {
+        self.entities.clear();
+        self.queue.clear();
+        if let Some(template) = template {
+            for cube in region.interior_iter() {
+                self.insert(cube, template);
+            }
+        }
+    }
+
+    fn insert(&mut self, cube: GridPoint, template: &BlockEntityTemplate) {
+        let Some(behavior) = template.instantiate() else {
+            return;
+        };
+        self.entities.insert(
+            cube,
+            BlockEntity {
+                template: template.clone(),
+                behavior,
+                schedule: template.schedule,
+                next_wake: None,
+            },
+        );
+        self.set_schedule(cube, template.schedule);
+    }
+
+    /// Changes the schedule of the block entity at `cube`, if there is one.
+    /// The time until the next step is counted from now.
+    pub(crate) fn set_schedule(&mut self, cube: GridPoint, schedule: TickSchedule) {
+        if let Some(entity) = self.entities.get_mut(&cube) {
+            entity.schedule = schedule;
+            entity.next_wake = schedule.next_wake(self.time, cube);
+            if let Some(time) = entity.next_wake {
+                self.queue.push(Reverse((time, cube.into())));
+            }
+        }
+    }
+
+    /// Replaces the behavior of the block entity at `cube`, if it is currently `old`.
+    #[allow(clippy::vtable_address_comparisons)] // The hazards should be okay for this use case
+    pub(crate) fn replace_behavior(
+        &mut self,
+        cube: GridPoint,
+        old: &Arc<dyn Behavior<Space>>,
+        new: Arc<dyn Behavior<Space>>,
+    ) {
+        if let Some(entity) = self.entities.get_mut(&cube) {
+            if Arc::ptr_eq(&entity.behavior, old) {
+                entity.behavior = new;
+            }
+        }
+    }
+
+    /// Schedules the block entity at `cube`, if there is one, to be stepped again on
+    /// the next [`Self::advance()`], because the effects of its last step were dropped.
+    /// Its schedule is otherwise unchanged.
+    pub(crate) fn retry(&mut self, cube: GridPoint) {
+        if let Some(entity) = self.entities.get_mut(&cube) {
+            entity.next_wake = Some(self.time);
+            self.queue.push(Reverse((self.time, cube.into())));
+        }
+    }
+
+    /// Advances time by `delta_t` and returns the cubes whose block entities should be
+    /// stepped now, in a deterministic order. Those entities are then rescheduled
+    /// according to their schedules.
+    pub(crate) fn advance(&mut self, delta_t: Duration) -> Vec<GridPoint> {
+        self.time += delta_t;
+        let mut due = Vec::new();
+        while let Some(&Reverse((time, cube))) = self.queue.peek() {
+            if time > self.time {
+                break;
+            }
+            self.queue.pop();
+            let cube = GridPoint::from(cube);
+            if self.entities.get(&cube).map(|entity| entity.next_wake) == Some(Some(time))
+                && due.last() != Some(&cube)
+            {
+                due.push(cube);
+            }
+        }
+        for &cube in &due {
+            let schedule = self.entities[&cube].schedule.after_step();
+            self.set_schedule(cube, schedule);
+        }
+        due
+    }
+}
+
+impl fmt::Debug for BlockEntities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Show only the entities, in a consistent order.
+        let mut entities: Vec<(&GridPoint, &BlockEntity)> = self.entities.iter().collect();
+        entities.sort_unstable_by_key(|&(cube, _)| (cube.x, cube.y, cube.z));
+        f.debug_map()
+            .entries(entities.into_iter().map(|(cube, entity)| {
+                let cube: [GridCoordinate; 3] = (*cube).into();
+                (cube, (&entity.behavior, entity.schedule))
+            }))
+            .finish()
+    }
+}
+
+impl VisitRefs for BlockEntities {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        for entity in self.entities.values() {
+            entity.behavior.visit_refs(visitor);
+        }
+    }
+}
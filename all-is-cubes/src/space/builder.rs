@@ -3,7 +3,7 @@ use cgmath::{EuclideanSpace, InnerSpace, Point3};
 use crate::block::{Block, AIR};
 use crate::character::Spawn;
 use crate::math::{FreeCoordinate, Rgb};
-use crate::space::{GridAab, LightPhysics, Space, SpacePhysics};
+use crate::space::{Fog, GridAab, LightPhysics, Space, SpacePhysics};
 
 /// Tool for constructing new [`Space`]s.
 ///
@@ -46,6 +46,12 @@ impl<B> SpaceBuilder<B> {
         self
     }
 
+    /// Sets the value of [`SpacePhysics::fog`] for the space.
+    pub fn fog(mut self, fog: Option<Fog>) -> Self {
+        self.physics.fog = fog;
+        self
+    }
+
     /// Sets the value of [`SpacePhysics::light`] for the space, which determines the
     /// behavior of light within the space.
     pub fn light_physics(mut self, light_physics: LightPhysics) -> Self {
@@ -0,0 +1,56 @@
+//! [`CubeData`], for data about individual cubes of a [`Space`].
+
+use crate::inv::{Inventory, Slot};
+use crate::universe::{RefVisitor, VisitRefs};
+
+#[cfg(doc)]
+use crate::{block::BlockAttributes, space::Space};
+
+/// Data which a [`Space`] stores for an individual cube, in addition to the cube's block.
+///
+/// Currently, a cube has data only if its block is a container (that is, its
+/// [`BlockAttributes::container_slots`] is nonzero). The data is created when such a
+/// block is placed, and removed when the block is replaced by a non-container block.
+/// [`Space::set()`] discards any items that the new block has no slots for, whereas a
+/// [`SpaceTransaction`](crate::space::SpaceTransaction) that would do so fails instead.
+/// Use [`Space::cube_data()`] to read it and
+/// [`SpaceTransaction::inventory()`](crate::space::SpaceTransaction::inventory) to
+/// modify it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct CubeData {
+    /// Items held in the cube.
+    pub inventory: Inventory,
+}
+
+impl CubeData {
+    /// Constructs the data for a newly placed container block.
+    pub(crate) fn new(container_slots: u16) -> Self {
+        Self {
+            inventory: Inventory::new(usize::from(container_slots)),
+        }
+    }
+
+    /// Adapts existing data to a different container block which replaced the
+    /// original block. If the number of slots is smaller, items in the excess
+    /// slots are discarded.
+    ///
+    /// Returns whether anything was changed.
+    pub(crate) fn resize(&mut self, container_slots: u16) -> bool {
+        let slots = &mut self.inventory.slots;
+        let size = usize::from(container_slots);
+        if slots.len() == size {
+            false
+        } else {
+            slots.resize(size, Slot::Empty);
+            true
+        }
+    }
+}
+
+impl VisitRefs for CubeData {
+    fn visit_refs(&self, visitor: &mut dyn RefVisitor) {
+        let Self { inventory } = self;
+        inventory.visit_refs(visitor);
+    }
+}
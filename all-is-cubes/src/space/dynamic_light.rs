@@ -0,0 +1,134 @@
+use cgmath::{InnerSpace as _, Vector3, Zero as _};
+use ordered_float::NotNan;
+
+use crate::math::{FreeCoordinate, Rgb};
+#[cfg(doc)]
+use crate::{
+    block::BlockAttributes, camera::GraphicsOptions, physics::Body, space::PackedLight,
+    space::Space,
+};
+
+/// Largest permitted [`LightShape::Spot`] half-angle, in degrees.
+///
+/// Wider cones cannot be drawn with a single shadow map, and should be made point lights.
+const MAX_SPOT_HALF_ANGLE: FreeCoordinate = 80.0;
+
+/// A light source which renderers may draw in real time, with shadows, on top of the
+/// light stored in the [`Space`] ([`PackedLight`]).
+///
+/// Dynamic lights are attached to blocks by [`BlockAttributes::dynamic_light`] and to
+/// bodies by [`Body::light`]. Whether they are drawn at all is controlled by
+/// [`GraphicsOptions::dynamic_lights`], and renderers may support only a few at a time.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct DynamicLight {
+    /// Color and intensity of the light. This is scaled so that it is the illumination of
+    /// a surface facing the light from a distance of 1 cube.
+    pub color: Rgb,
+    /// Distance, in cubes, beyond which the light has no effect.
+    pub range: NotNan<FreeCoordinate>,
+    /// Which directions the light shines in.
+    pub shape: LightShape,
+}
+
+/// The directions in which a [`DynamicLight`] shines.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum LightShape {
+    /// Light shines equally in all directions.
+    Point,
+    /// Light shines in a cone.
+    Spot {
+        /// Direction of the center of the cone.
+        /// Need not be normalized; if zero, the light shines in no direction.
+        ///
+        /// For a light attached to a block, this is in the coordinate system of the
+        /// [`Space`]. For a light attached to a [`Body`], this is relative to the body's
+        /// look direction, with −Z forward and +Y up.
+        direction: Vector3<NotNan<FreeCoordinate>>,
+        /// Angle, in degrees, between the center and the edge of the cone.
+        /// Values greater than 80° are treated as 80°.
+        half_angle: NotNan<FreeCoordinate>,
+    },
+}
+
+impl DynamicLight {
+    /// Constructs a [`LightShape::Point`] light.
+    pub const fn point(color: Rgb, range: NotNan<FreeCoordinate>) -> Self {
+        Self {
+            color,
+            range,
+            shape: LightShape::Point,
+        }
+    }
+
+    /// Constructs a [`LightShape::Spot`] light.
+    pub fn spot(
+        color: Rgb,
+        range: NotNan<FreeCoordinate>,
+        direction: Vector3<FreeCoordinate>,
+        half_angle: FreeCoordinate,
+    ) -> Self {
+        Self {
+            color,
+            range,
+            shape: LightShape::Spot {
+                direction: direction.map(|c| NotNan::new(c).unwrap_or_default()),
+                half_angle: NotNan::new(half_angle).unwrap_or_default(),
+            },
+        }
+    }
+
+    /// Returns the normalized direction of the center of the cone, and the cosine of its
+    /// half-angle, or [`None`] if this is not a spot light.
+    ///
+    /// The direction is zero if the light's direction is zero.
+    pub fn spot_cone(&self) -> Option<(Vector3<FreeCoordinate>, FreeCoordinate)> {
+        match self.shape {
+            LightShape::Point => None,
+            LightShape::Spot {
+                direction,
+                half_angle,
+            } => {
+                let direction = direction.map(NotNan::into_inner);
+                let direction = if direction.is_zero() {
+                    direction
+                } else {
+                    direction.normalize()
+                };
+                let half_angle = half_angle.into_inner().clamp(0.0, MAX_SPOT_HALF_ANGLE);
+                Some((direction, half_angle.to_radians().cos()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spot_cone() {
+        assert_eq!(
+            DynamicLight::point(Rgb::ONE, notnan!(10.0)).spot_cone(),
+            None
+        );
+        let (direction, cos) =
+            DynamicLight::spot(Rgb::ONE, notnan!(10.0), Vector3::new(0., 0., -2.), 60.0)
+                .spot_cone()
+                .unwrap();
+        assert_eq!(direction, Vector3::new(0., 0., -1.));
+        assert!((cos - 0.5).abs() < 1e-9, "{cos}");
+    }
+
+    #[test]
+    fn spot_half_angle_is_clamped() {
+        let (_, cos) = DynamicLight::spot(Rgb::ONE, notnan!(10.0), Vector3::new(1., 0., 0.), 170.0)
+            .spot_cone()
+            .unwrap();
+        assert!(
+            (cos - MAX_SPOT_HALF_ANGLE.to_radians().cos()).abs() < 1e-9,
+            "{cos}"
+        );
+    }
+}
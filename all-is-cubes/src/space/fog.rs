@@ -0,0 +1,76 @@
+use ordered_float::NotNan;
+
+use crate::math::{FreeCoordinate, Rgb};
+#[cfg(doc)]
+use crate::space::{Space, SpacePhysics};
+
+/// Describes a participating medium (haze, mist, smoke) filling a [`Space`], which
+/// absorbs light passing through it and scatters the light falling on it back towards
+/// the viewer. Since that light is taken from the space's light data, shadows and
+/// openings produce visible light shafts.
+///
+/// Used as [`SpacePhysics::fog`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct Fog {
+    /// Color (albedo) of the fog. This is multiplied by the light at each point to
+    /// produce the scattered light.
+    pub color: Rgb,
+
+    /// Density of the fog, as the fraction of light (approximately, for small values)
+    /// absorbed or scattered per cube traveled.
+    ///
+    /// Negative values are treated as zero.
+    pub density: NotNan<f32>,
+
+    /// Height (Y coordinate) below which the fog has its full [`density`](Self::density).
+    pub height: NotNan<FreeCoordinate>,
+
+    /// Rate at which the density decreases exponentially above [`height`](Self::height),
+    /// per cube. If zero, the fog is uniform at every height.
+    pub height_falloff: NotNan<f32>,
+}
+
+impl Fog {
+    /// Constructs a [`Fog`] which has the same density everywhere.
+    pub const fn uniform(color: Rgb, density: NotNan<f32>) -> Self {
+        Self {
+            color,
+            density,
+            height: notnan!(0.),
+            height_falloff: notnan!(0.),
+        }
+    }
+
+    /// Returns the density of the fog (extinction per cube) at the given Y coordinate.
+    pub fn density_at(&self, y: FreeCoordinate) -> f32 {
+        let above = (y - self.height.into_inner()).max(0.0) as f32;
+        let falloff = self.height_falloff.into_inner().max(0.0);
+        (self.density.into_inner() * (-falloff * above).exp()).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn density_at() {
+        let fog = Fog {
+            color: Rgb::ONE,
+            density: notnan!(0.5),
+            height: notnan!(10.),
+            height_falloff: notnan!(1.0),
+        };
+        assert_eq!(fog.density_at(-100.0), 0.5);
+        assert_eq!(fog.density_at(10.0), 0.5);
+        assert_eq!(fog.density_at(11.0), 0.5 * (-1.0f32).exp());
+
+        let uniform = Fog::uniform(Rgb::ONE, notnan!(0.25));
+        assert_eq!(uniform.density_at(1000.0), 0.25);
+
+        let negative = Fog::uniform(Rgb::ONE, notnan!(-1.0));
+        assert_eq!(negative.density_at(0.0), 0.0);
+    }
+}
@@ -7,8 +7,8 @@ mod debug;
 pub use debug::{LightUpdateCubeInfo, LightUpdateRayInfo};
 
 mod updater;
-pub(crate) use updater::opaque_for_light_computation;
 pub use updater::LightUpdatesInfo;
+pub(crate) use updater::{opaque_for_light_computation, transmission_tint};
 
 #[cfg(test)]
 mod tests;
@@ -1,13 +1,15 @@
 //! Tests for the behavior of light in a [`Space`].
 
+use cgmath::Vector3;
 use pretty_assertions::assert_eq;
 
 use super::{data::LightStatus, LightUpdatesInfo, PackedLight};
-use crate::block::{AnimationHint, Block, AIR};
+use crate::block::{AnimationHint, Block, Resolution::*, AIR};
 use crate::listen::{Listen as _, Listener, Sink};
-use crate::math::{FaceMap, GridPoint, Rgb, Rgba};
-use crate::space::{GridAab, LightPhysics, Space, SpaceChange, SpacePhysics};
+use crate::math::{FaceMap, GridPoint, NotNan, Rgb, Rgba};
+use crate::space::{Fog, GridAab, LightPhysics, RayPattern, Sky, Space, SpaceChange, SpacePhysics};
 use crate::time::Tick;
+use crate::universe::Universe;
 
 #[test]
 fn initial_lighting_value() {
@@ -195,6 +197,277 @@ fn reflectance_is_clamped() {
     assert!(light.red() <= sky_color.red());
 }
 
+/// Returns the light in a cube whose only opening to the sky is covered by `cover`.
+fn light_under_cover(cover: Block) -> Rgb {
+    // A white box with one opening at the top, covered by `cover`, and open to the sky above.
+    let mut space = Space::builder(GridAab::from_lower_size([0, 0, 0], [3, 4, 3]))
+        .sky_color(Rgb::ONE)
+        .build();
+    space
+        .fill_uniform(
+            GridAab::from_lower_size([0, 0, 0], [3, 3, 3]),
+            Block::from(Rgb::ONE),
+        )
+        .unwrap();
+    space.set([1, 1, 1], AIR).unwrap();
+    space.set([1, 2, 1], cover).unwrap();
+    space.evaluate_light(0, |_| {});
+    space.get_lighting([1, 1, 1]).value()
+}
+
+/// Light passing through a tinted transparent block should pick up its color,
+/// whereas an untinted one should only reduce the amount of light.
+#[test]
+fn transparent_block_tints_light() {
+    let neutral = light_under_cover(Block::from(rgba_const!(1.0, 1.0, 1.0, 0.5)));
+    let red = light_under_cover(Block::from(rgba_const!(1.0, 0.0, 0.0, 0.5)));
+    dbg!(neutral, red);
+
+    assert!(neutral.red().into_inner() > 0.0);
+    assert_eq!(neutral.red(), neutral.green());
+    assert_eq!(neutral.green(), neutral.blue());
+
+    // Red glass passes as much red light as neutral glass does, but less of the other colors.
+    assert_eq!(red.red(), neutral.red());
+    assert!(red.green() < red.red() * 0.75, "{red:?}");
+    assert_eq!(red.green(), red.blue());
+}
+
+/// Light passing through the gaps in a block made of opaque colored voxels did not touch
+/// them, so it should be dimmed but not tinted.
+#[test]
+fn gaps_between_opaque_voxels_do_not_tint_light() {
+    let mut universe = Universe::new();
+    let red = Block::from(Rgb::new(1.0, 0.0, 0.0));
+    let grating = Block::builder()
+        .voxels_fn(&mut universe, R2, |cube| {
+            if (cube.x + cube.z) % 2 == 0 {
+                &red
+            } else {
+                &AIR
+            }
+        })
+        .unwrap()
+        .build();
+
+    let light = light_under_cover(grating);
+    dbg!(light);
+    assert!(light.red().into_inner() > 0.0);
+    assert_eq!(light.red(), light.green());
+    assert_eq!(light.green(), light.blue());
+}
+
+#[test]
+fn sun_direction_affects_light() {
+    fn light_with_sun_from(x: f64) -> Rgb {
+        // The lit cube has a wall on its -X side.
+        let mut space = Space::empty_positive(2, 1, 1);
+        space.set_physics(SpacePhysics {
+            sky_color: Rgb::ZERO,
+            sky: Some(Sky {
+                sun_direction: Vector3::new(x, 0.0, 0.0).map(|c| NotNan::new(c).unwrap()),
+                sun_color: Rgb::ONE,
+                ..Sky::uniform(Rgb::ZERO)
+            }),
+            ..SpacePhysics::default()
+        });
+        space.set([0, 0, 0], Block::from(Rgb::ONE)).unwrap();
+        space.evaluate_light(0, |_| {});
+        space.get_lighting([1, 0, 0]).value()
+    }
+
+    let facing_sun = light_with_sun_from(1.0);
+    let shadowed = light_with_sun_from(-1.0);
+    dbg!(facing_sun, shadowed);
+    assert!(facing_sun.luminance() > shadowed.luminance() * 2.0);
+}
+
+#[test]
+fn sky_change_queues_light_updates() {
+    let mut space = Space::empty_positive(2, 1, 1);
+    space.set([0, 0, 0], Block::from(Rgb::ONE)).unwrap();
+    space.evaluate_light(0, |_| {});
+    let old_light = space.get_lighting([1, 0, 0]);
+    let sink = Sink::new();
+    space.listen(sink.listener());
+
+    space.set_physics(SpacePhysics {
+        sky: Some(Sky::uniform(Rgb::new(1.0, 0.0, 0.0))),
+        ..space.physics().clone()
+    });
+    assert_eq!(sink.drain(), vec![SpaceChange::Physics]);
+    // Light is not changed immediately, but is updated when the queue is processed.
+    assert_eq!(space.get_lighting([1, 0, 0]), old_light);
+    assert_eq!(space.light_update_queue.len(), 1);
+    space.evaluate_light(0, |_| {});
+    let new_light = space.get_lighting([1, 0, 0]).value();
+    assert!(new_light.red() > notnan!(0.5), "{new_light:?}");
+    assert_eq!(new_light.green(), notnan!(0.0));
+}
+
+#[test]
+fn sky_change_skips_enclosed_cubes() {
+    let mut space = Space::empty_positive(3, 3, 3);
+    space
+        .fill_uniform(space.bounds(), &Block::from(Rgb::ONE))
+        .unwrap();
+    space.set([1, 1, 1], AIR).unwrap();
+    space.evaluate_light(0, |_| {});
+    assert_eq!(space.light_update_queue.len(), 0);
+
+    space.set_physics(SpacePhysics {
+        sky: Some(Sky::uniform(Rgb::new(1.0, 0.0, 0.0))),
+        ..space.physics().clone()
+    });
+    // The enclosed cube cannot see the sky, so it does not need updating.
+    assert_eq!(space.light_update_queue.len(), 0);
+}
+
+/// Fog scatters light from every cube it fills, so empty air needs light values too.
+#[test]
+fn fog_lights_empty_air() {
+    let mut space = Space::empty_positive(3, 3, 3);
+    space.evaluate_light(0, |_| {});
+    assert_eq!(space.get_lighting([1, 1, 1]), PackedLight::NO_RAYS);
+
+    space.set_physics(SpacePhysics {
+        fog: Some(Fog::uniform(Rgb::ONE, notnan!(0.1))),
+        ..space.physics().clone()
+    });
+    space.evaluate_light(0, |_| {});
+    assert_eq!(
+        space.get_lighting([1, 1, 1]),
+        PackedLight::from(space.physics().sky_color)
+    );
+}
+
+#[test]
+fn ray_patterns_see_uniform_sky() {
+    for ray_pattern in [
+        RayPattern::Cube { divisions: 2 },
+        RayPattern::Octahedron { divisions: 3 },
+        RayPattern::Spiral { count: 50 },
+    ] {
+        for cosine_weighting in [false, true] {
+            let mut light = LightPhysics::rays(10);
+            if let LightPhysics::Rays {
+                ray_pattern: p,
+                cosine_weighting: c,
+                ..
+            } = &mut light
+            {
+                *p = ray_pattern;
+                *c = cosine_weighting;
+            }
+            let mut space = Space::empty_positive(2, 1, 1);
+            space.set_physics(SpacePhysics {
+                sky_color: Rgb::new(1.0, 0.5, 0.0),
+                light,
+                ..SpacePhysics::default()
+            });
+            space.set([0, 0, 0], Block::from(Rgb::ZERO)).unwrap();
+            space.evaluate_light(0, |_| {});
+
+            // Every ray either escapes to the sky, or hits the black block and is
+            // not counted.
+            assert_eq!(
+                space.get_lighting([1, 0, 0]),
+                PackedLight::from(space.physics().sky_color),
+                "{ray_pattern:?} {cosine_weighting:?}"
+            );
+        }
+    }
+}
+
+/// Each [`RayPattern`] should give results close to those of a high-quality reference
+/// pattern, in a scene with both direct sky light and shadows.
+#[test]
+fn ray_patterns_approximate_reference() {
+    fn lit_space(ray_pattern: RayPattern) -> Space {
+        // A floor, partly covered by a roof, with a pillar holding it up.
+        let mut space = Space::empty_positive(6, 4, 6);
+        let mut light = LightPhysics::rays(10);
+        if let LightPhysics::Rays { ray_pattern: p, .. } = &mut light {
+            *p = ray_pattern;
+        }
+        space.set_physics(SpacePhysics {
+            light,
+            ..SpacePhysics::default()
+        });
+        let wall = Block::from(Rgb::new(0.5, 0.5, 0.5));
+        space
+            .fill_uniform(GridAab::from_lower_size([0, 0, 0], [6, 1, 6]), &wall)
+            .unwrap();
+        space
+            .fill_uniform(GridAab::from_lower_size([0, 3, 0], [4, 1, 4]), &wall)
+            .unwrap();
+        space
+            .fill_uniform(GridAab::from_lower_size([1, 1, 1], [1, 2, 1]), &wall)
+            .unwrap();
+        space.evaluate_light(0, |_| {});
+        space
+    }
+
+    fn mean_luminance_error(space: &Space, reference: &Space) -> f32 {
+        let mut sum = 0.0;
+        let mut count = 0;
+        for cube in space.bounds().interior_iter() {
+            let light = space.get_lighting(cube).value().luminance();
+            let reference_light = reference.get_lighting(cube).value().luminance();
+            sum += (light - reference_light).abs();
+            count += 1;
+        }
+        sum / count as f32
+    }
+
+    let reference = lit_space(RayPattern::Spiral { count: 2000 });
+    for pattern in [
+        RayPattern::Cube { divisions: 2 },
+        RayPattern::Cube { divisions: 5 },
+        RayPattern::Octahedron { divisions: 5 },
+        RayPattern::Octahedron { divisions: 10 },
+        RayPattern::Spiral { count: 100 },
+        RayPattern::Spiral { count: 600 },
+    ] {
+        let error = mean_luminance_error(&lit_space(pattern), &reference);
+        assert!(error < 0.02, "{pattern:?}: mean luminance error {error}");
+    }
+}
+
+#[test]
+fn update_budget_limits_step() {
+    let mut light = LightPhysics::rays(10);
+    if let LightPhysics::Rays { update_budget, .. } = &mut light {
+        *update_budget = 1;
+    }
+    let mut space = Space::empty_positive(3, 1, 1);
+    space.set_physics(SpacePhysics {
+        light,
+        ..SpacePhysics::default()
+    });
+    space.set([1, 0, 0], Rgb::ONE).unwrap();
+    assert_eq!(space.light_update_queue.len(), 2);
+
+    let (info, _) = space.step(None, Tick::arbitrary());
+    assert_eq!(info.light.update_count, 1);
+    assert_eq!(info.light.queue_count, 1);
+}
+
+#[test]
+fn bake_lighting_converges() {
+    let mut space = Space::empty_positive(5, 3, 5);
+    space
+        .fill_uniform(GridAab::from_lower_size([0, 0, 0], [5, 1, 5]), Rgb::ONE)
+        .unwrap();
+    space.set([2, 1, 2], Rgb::new(1.0, 0.0, 0.0)).unwrap();
+    space.set([1, 2, 3], Rgba::new(0.0, 1.0, 0.0, 0.5)).unwrap();
+    let passes = space.bake_lighting(10, |_| {});
+    assert!(passes < 10, "did not converge");
+
+    // Having converged, nothing changes on another full pass.
+    assert_eq!(space.bake_lighting(1, |_| {}), 1);
+}
+
 /// Helper to construct a space with `LightPhysics` set to None
 fn space_with_disabled_light() -> Space {
     let mut space = Space::empty_positive(1, 1, 1);
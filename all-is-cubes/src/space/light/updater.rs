@@ -1,8 +1,9 @@
 //! Lighting algorithms for `Space`. This module is closely tied to `Space`
 //! and separated out for readability, not modularity.
 
-use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::{Arc, Mutex};
 
 use cgmath::{EuclideanSpace as _, InnerSpace as _, Point3, Vector3};
 use once_cell::sync::Lazy;
@@ -10,10 +11,15 @@ use once_cell::sync::Lazy;
 use super::debug::LightComputeOutput;
 use super::LightUpdateRequest;
 use crate::block::EvaluatedBlock;
-use crate::math::{Face6, FaceMap, FreeCoordinate, Geometry, GridPoint, NotNan, Rgb};
+use crate::math::{
+    Face6, FaceMap, FreeCoordinate, Geometry, GridPoint, NotNan, OpacityCategory, Rgb,
+};
 use crate::raycast::{Ray, RaycastStep};
 use crate::space::light::LightUpdateRayInfo;
-use crate::space::{GridAab, LightPhysics, PackedLight, PackedLightScalar, Space, SpaceChange};
+use crate::space::{
+    GridAab, LightPhysics, PackedLight, PackedLightScalar, RayPattern, Space, SpaceBlockData,
+    SpaceChange, SpacePhysics,
+};
 use crate::util::{CustomFormat, StatusText};
 
 /// This parameter determines to what degree absorption of light due to a block surface's
@@ -23,57 +29,76 @@ use crate::util::{CustomFormat, StatusText};
 /// model.
 const SURFACE_ABSORPTION: f32 = 0.75;
 
-const RAY_DIRECTION_STEP: isize = 5;
-const RAY_CUBE_EDGE: usize = (RAY_DIRECTION_STEP as usize) * 2 + 1;
-const ALL_RAYS_COUNT: usize = RAY_CUBE_EDGE.pow(3) - (RAY_CUBE_EDGE - 2).pow(3);
-
-/// Limit on light computation per one [`Space::update_lighting_from_queue`] call.
-///
-/// The unit of measure is one raycast step; other operations are arbitrarily assigned
-/// higher cost values. (TODO: Profile to assign more consistent cost values.)
-///
-/// TODO: This should be an option configured for the Universe/Space, so that it is
-/// both adjustable and deterministic/platform-independent.
-/// For now, tweaked in a "works okay on my machine" way.
-const MAXIMUM_LIGHT_COMPUTATION_COST: usize = 100_000;
-
 #[derive(Debug)]
 struct LightRayData {
     ray: Ray,
     face_cosines: FaceMap<f32>,
 }
 
-// TODO: Make multiple ray patterns that suit the maximum_distance parameter.
-static LIGHT_RAYS: Lazy<[LightRayData; ALL_RAYS_COUNT]> = Lazy::new(|| {
-    let mut rays: Vec<LightRayData> = Vec::new();
-    let origin = Point3::new(0.5, 0.5, 0.5);
-
-    // TODO: octahedron instead of cube
-    for x in -RAY_DIRECTION_STEP..=RAY_DIRECTION_STEP {
-        for y in -RAY_DIRECTION_STEP..=RAY_DIRECTION_STEP {
-            for z in -RAY_DIRECTION_STEP..=RAY_DIRECTION_STEP {
-                if x.abs() == RAY_DIRECTION_STEP
-                    || y.abs() == RAY_DIRECTION_STEP
-                    || z.abs() == RAY_DIRECTION_STEP
-                {
-                    let direction = Vector3::new(
-                        x as FreeCoordinate,
-                        y as FreeCoordinate,
-                        z as FreeCoordinate,
-                    )
-                    .normalize();
-                    rays.push(LightRayData {
-                        ray: Ray { origin, direction },
-                        face_cosines: FaceMap::from_fn(|face| {
-                            face.dot(direction.map(|s| s as f32)).max(0.0)
-                        }),
-                    });
+/// Cache of the rays for each [`RayPattern`] which has been used.
+static LIGHT_RAYS: Lazy<Mutex<HashMap<RayPattern, Arc<[LightRayData]>>>> =
+    Lazy::new(Default::default);
+
+/// Returns the rays to cast from each cube for the given pattern, computing them if
+/// they are not already cached.
+///
+/// This locks a global cache, so callers processing many cubes should call it once
+/// and reuse the result.
+fn light_rays(pattern: RayPattern) -> Arc<[LightRayData]> {
+    let mut cache = LIGHT_RAYS.lock().unwrap();
+    Arc::clone(cache.entry(pattern).or_insert_with(|| {
+        let origin = Point3::new(0.5, 0.5, 0.5);
+        ray_directions(pattern)
+            .into_iter()
+            .map(|direction| {
+                let direction = direction.normalize();
+                LightRayData {
+                    ray: Ray { origin, direction },
+                    face_cosines: FaceMap::from_fn(|face| {
+                        face.dot(direction.map(|s| s as f32)).max(0.0)
+                    }),
+                }
+            })
+            .collect()
+    }))
+}
+
+/// Returns the (non-normalized) directions of the rays of the given pattern.
+fn ray_directions(pattern: RayPattern) -> Vec<Vector3<FreeCoordinate>> {
+    let mut directions = Vec::new();
+    match pattern {
+        RayPattern::Cube { divisions } | RayPattern::Octahedron { divisions } => {
+            let d = i32::from(divisions);
+            for x in -d..=d {
+                for y in -d..=d {
+                    for z in -d..=d {
+                        let on_surface = match pattern {
+                            RayPattern::Cube { .. } => x.abs() == d || y.abs() == d || z.abs() == d,
+                            _ => x.abs() + y.abs() + z.abs() == d,
+                        };
+                        if on_surface && d > 0 {
+                            directions.push(Vector3::new(x, y, z).map(FreeCoordinate::from));
+                        }
+                    }
                 }
             }
         }
+        RayPattern::Spiral { count } => {
+            // Fibonacci lattice: evenly spaced heights, with the angle around the axis
+            // advancing by the golden angle.
+            let golden_angle = std::f64::consts::PI * (3.0 - 5f64.sqrt());
+            let count = f64::from(count);
+            for i in 0..count as u16 {
+                let i = f64::from(i);
+                let y = 1.0 - (2.0 * i + 1.0) / count;
+                let radius = (1.0 - y * y).sqrt();
+                let (sin, cos) = (golden_angle * i).sin_cos();
+                directions.push(Vector3::new(radius * cos, y, radius * sin));
+            }
+        }
     }
-    rays.try_into().unwrap()
-});
+    directions
+}
 
 /// Methods on Space that specifically implement the lighting algorithm.
 impl Space {
@@ -96,17 +121,24 @@ impl Space {
         let mut max_difference: PackedLightScalar = 0;
         let mut cost = 0;
 
-        if self.physics.light != LightPhysics::None {
+        if let LightPhysics::Rays {
+            update_budget,
+            ray_pattern,
+            ..
+        } = self.physics.light
+        {
+            let update_budget = update_budget as usize;
+            let rays = light_rays(ray_pattern);
             while let Some(LightUpdateRequest { cube, .. }) = self.light_update_queue.pop() {
                 if false {
                     // Log cubes that were updated for debug visualization.
                     self.last_light_updates.push(cube);
                 }
                 light_update_count += 1;
-                let (difference, cube_cost) = self.update_lighting_now_on(cube);
+                let (difference, cube_cost) = self.update_lighting_now_on(cube, &rays);
                 max_difference = max_difference.max(difference);
                 cost += cube_cost;
-                if cost >= MAXIMUM_LIGHT_COMPUTATION_COST {
+                if cost >= update_budget {
                     break;
                 }
             }
@@ -121,8 +153,24 @@ impl Space {
     }
 
     #[inline]
-    fn update_lighting_now_on(&mut self, cube: GridPoint) -> (PackedLightScalar, usize) {
-        let (new_light_value, dependencies, mut cost, ()) = self.compute_lighting(cube);
+    fn update_lighting_now_on(
+        &mut self,
+        cube: GridPoint,
+        rays: &[LightRayData],
+    ) -> (PackedLightScalar, usize) {
+        let (new_light_value, buffer, ()) = self.compute_lighting_impl(cube, rays);
+        let LightBuffer {
+            dependencies,
+            mut cost,
+            sky_exposed,
+            ..
+        } = buffer;
+        if sky_exposed {
+            self.sky_exposed_cubes.insert(cube);
+        } else {
+            self.sky_exposed_cubes.remove(&cube);
+        }
+
         let old_light_value: PackedLight = self.get_lighting(cube);
         // Compare and set new value. Note that we MUST compare only the packed value so
         // that changes are detected in terms of that rounding, not float values.
@@ -159,11 +207,37 @@ impl Space {
     where
         D: LightComputeOutput,
     {
-        let maximum_distance = match self.physics.light {
+        let rays = match self.physics.light {
             LightPhysics::None => {
                 panic!("Light is disabled; should not reach here");
             }
-            LightPhysics::Rays { maximum_distance } => FreeCoordinate::from(maximum_distance),
+            LightPhysics::Rays { ray_pattern, .. } => light_rays(ray_pattern),
+        };
+        let (new_light_value, buffer, info) = self.compute_lighting_impl(cube, &rays);
+        (new_light_value, buffer.dependencies, buffer.cost, info)
+    }
+
+    /// Implementation of [`Self::compute_lighting()`] which also returns the rest of the
+    /// [`LightBuffer`] state.
+    ///
+    /// `rays` must be the result of [`light_rays()`] for the current ray pattern.
+    fn compute_lighting_impl<D>(
+        &self,
+        cube: GridPoint,
+        rays: &[LightRayData],
+    ) -> (PackedLight, LightBuffer, D)
+    where
+        D: LightComputeOutput,
+    {
+        let (maximum_distance, cosine_weighting) = match self.physics.light {
+            LightPhysics::None => {
+                panic!("Light is disabled; should not reach here");
+            }
+            LightPhysics::Rays {
+                maximum_distance,
+                cosine_weighting,
+                ..
+            } => (FreeCoordinate::from(maximum_distance), cosine_weighting),
         };
 
         let mut cube_buffer = LightBuffer::new();
@@ -179,17 +253,19 @@ impl Space {
         } else {
             let ev_neighbors =
                 FaceMap::from_fn(|face| self.get_evaluated(cube + face.normal_vector()));
-            let direction_weights = directions_to_seek_light(ev_origin, ev_neighbors);
+            let direction_weights =
+                directions_to_seek_light(ev_origin, ev_neighbors, self.physics.fog.is_some());
 
-            // TODO: Choose a ray pattern that suits the maximum_distance.
-            for &LightRayData { ray, face_cosines } in &LIGHT_RAYS[..] {
-                // TODO: Theoretically we should weight light rays by the cosine but that has caused poor behavior in the past.
-                let ray_weight_by_faces = face_cosines
+            for &LightRayData { ray, face_cosines } in rays {
+                let mut ray_weight_by_faces = face_cosines
                     .zip(direction_weights, |_face, ray_cosine, reflects| {
                         ray_cosine * reflects
                     })
                     .into_values_iter()
                     .sum::<f32>();
+                if !cosine_weighting && ray_weight_by_faces > 0.0 {
+                    ray_weight_by_faces = 1.0;
+                }
                 if ray_weight_by_faces <= 0.0 {
                     continue;
                 }
@@ -205,7 +281,7 @@ impl Space {
                         break 'raycast;
                     }
                     cube_buffer.traverse::<D>(&mut ray_state, &mut info_rays, self, hit);
-                    if ray_state.alpha.partial_cmp(&0.0) != Some(Ordering::Greater) {
+                    if ray_state.is_exhausted() {
                         break;
                     }
                 }
@@ -214,13 +290,9 @@ impl Space {
         }
 
         let new_light_value = cube_buffer.finish(origin_is_opaque);
+        let info = D::new(cube, new_light_value, info_rays);
 
-        (
-            new_light_value,
-            cube_buffer.dependencies,
-            cube_buffer.cost,
-            D::new(cube, new_light_value, info_rays),
-        )
+        (new_light_value, cube_buffer, info)
     }
 
     /// Clear and recompute light data and update queue, in a way which gets fast approximate
@@ -234,6 +306,9 @@ impl Space {
             return;
         }
 
+        // Fog scatters the light in every cube it fills, so every cube needs a value.
+        // Those are only seen through the fog, so they are queued after the surfaces.
+        let fogged = self.physics.fog.is_some();
         let bounds = self.bounds();
         for x in bounds.x_range() {
             for z in bounds.z_range() {
@@ -244,21 +319,25 @@ impl Space {
 
                     let this_cube_evaluated =
                         &self.block_data[self.contents[index] as usize].evaluated;
-                    self.lighting[index] = if opaque_for_light_computation(this_cube_evaluated) {
+                    let new_light = if opaque_for_light_computation(this_cube_evaluated) {
                         covered = true;
                         PackedLight::OPAQUE
                     } else {
-                        if this_cube_evaluated.visible_or_animated()
+                        let near_surface = this_cube_evaluated.visible_or_animated()
                             || Face6::ALL.into_iter().any(|face| {
                                 self.get_evaluated(cube + face.normal_vector())
                                     .visible_or_animated()
-                            })
-                        {
+                            });
+                        if near_surface || fogged {
                             // In this case (and only this case), we are guessing rather than being certain,
                             // so we need to schedule a proper update.
                             // (Bypassing `self.light_needs_update()` to skip bounds checks).
                             self.light_update_queue.insert(LightUpdateRequest {
-                                priority: PackedLightScalar::MAX,
+                                priority: if near_surface {
+                                    PackedLightScalar::MAX
+                                } else {
+                                    1
+                                },
                                 cube,
                             });
 
@@ -271,10 +350,53 @@ impl Space {
                             PackedLight::NO_RAYS
                         }
                     };
+                    if self.lighting[index] != new_light {
+                        self.lighting[index] = new_light;
+                        self.notifier.notify(SpaceChange::Lighting(cube));
+                    }
                 }
             }
         }
     }
+
+    /// Schedule light updates for every cube whose light may be affected by a change of
+    /// the sky from `old_physics`'s to the current one.
+    ///
+    /// Unlike [`Self::fast_evaluate_light()`], this keeps the existing light values, so
+    /// the change takes effect gradually as the queue is processed.
+    ///
+    /// Only cubes which directly received light from the sky are queued; cubes lit only
+    /// indirectly are updated as their neighbors' changes propagate.
+    pub(crate) fn queue_light_updates_for_sky_change(&mut self, old_physics: &SpacePhysics) {
+        if self.physics.light == LightPhysics::None {
+            return;
+        }
+
+        // Estimate how large the change is by comparing the sky in the axis directions
+        // and towards the old and new suns.
+        let sun_directions = [&old_physics.sky, &self.physics.sky]
+            .into_iter()
+            .flatten()
+            .map(|sky| sky.sun_direction.map(NotNan::into_inner));
+        let priority = Face6::ALL
+            .into_iter()
+            .map(|face| face.normal_vector())
+            .chain(sun_directions)
+            .map(|direction| {
+                PackedLight::from(self.physics.sky_light(direction))
+                    .difference_priority(PackedLight::from(old_physics.sky_light(direction)))
+            })
+            .max()
+            .unwrap_or(0)
+            // Even a change too small to estimate may add up to a visible difference.
+            .max(1);
+
+        for &cube in &self.sky_exposed_cubes {
+            // (Bypassing `self.light_needs_update()` to skip bounds checks).
+            self.light_update_queue
+                .insert(LightUpdateRequest { priority, cube });
+        }
+    }
 }
 
 impl LightPhysics {
@@ -291,11 +413,14 @@ impl LightPhysics {
 
 /// Given a block and its neighbors, which directions should we cast rays to find light
 /// falling on it?
+///
+/// If `fogged`, the cube is filled with fog, which is lit from every direction.
 fn directions_to_seek_light(
     origin: &EvaluatedBlock,
     neighborhood: FaceMap<&EvaluatedBlock>,
+    fogged: bool,
 ) -> FaceMap<f32> {
-    if origin.visible_or_animated() {
+    if origin.visible_or_animated() || fogged {
         // Non-opaque blocks should work the same as blocks which have all six adjacent faces present.
         FaceMap::repeat(1.0)
     } else {
@@ -329,14 +454,17 @@ struct LightBuffer {
     /// Approximation of CPU cost of doing the calculation, with one unit defined as
     /// one raycast step.
     cost: usize,
+    /// Whether any ray reached the sky without being entirely blocked.
+    sky_exposed: bool,
 }
 
 /// Companion to [`LightBuffer`] that tracks state for a single ray that makes part of
 /// the sum.
 struct LightRayState {
-    /// Fraction of the light value that is to be determined by future, rather than past,
-    /// tracing; starts at 1.0 and decreases as opaque surfaces are encountered.
-    alpha: f32,
+    /// Fraction of the light value, per color component, that is to be determined by
+    /// future, rather than past, tracing; starts at 1.0 and decreases as partially
+    /// transparent blocks filter the light and opaque surfaces are encountered.
+    transmittance: Rgb,
     /// Weighting factor for how much this ray contributes to the total light.
     /// If zero, this will not be counted as a ray at all.
     ray_weight_by_faces: f32,
@@ -355,12 +483,18 @@ impl LightRayState {
         let translated_ray =
             abstract_ray.translate(origin_cube.cast::<FreeCoordinate>().unwrap().to_vec());
         LightRayState {
-            alpha: 1.0,
+            transmittance: Rgb::ONE,
             ray_weight_by_faces,
             origin_cube,
             translated_ray,
         }
     }
+
+    /// Returns whether no further light can arrive along this ray, so tracing it
+    /// further would have no effect.
+    fn is_exhausted(&self) -> bool {
+        self.transmittance == Rgb::ZERO
+    }
 }
 
 impl LightBuffer {
@@ -371,12 +505,14 @@ impl LightBuffer {
             total_ray_weight: 0.0,
             dependencies: Vec::new(),
             cost: 0,
+            sky_exposed: false,
         }
     }
 
     /// Process a ray intersecting a single cube.
     ///
-    /// The caller should check `ray_state.alpha` to decide when to stop calling this.
+    /// The caller should check [`LightRayState::is_exhausted()`] to decide when to stop
+    /// calling this.
     #[inline]
     #[allow(clippy::too_many_arguments)]
     fn traverse<D>(
@@ -388,7 +524,11 @@ impl LightBuffer {
     ) where
         D: LightComputeOutput,
     {
-        let ev_hit = space.get_evaluated(hit.cube_ahead());
+        let SpaceBlockData {
+            evaluated: ev_hit,
+            light_tint,
+            ..
+        } = space.get_block_data(hit.cube_ahead());
         if !ev_hit.visible_or_animated() {
             // Completely transparent block is passed through.
             return;
@@ -415,7 +555,7 @@ impl LightBuffer {
                 // Setting the weight to 0 cancels its future effect,
                 // and there were no past effects.
                 ray_state.ray_weight_by_faces = 0.0;
-                ray_state.alpha = 0.0;
+                ray_state.transmittance = Rgb::ZERO;
                 return;
             }
             let stored_light = space.get_lighting(light_cube);
@@ -425,12 +565,12 @@ impl LightBuffer {
             let light_from_struck_face =
                 ev_hit.attributes.light_emission + stored_light.value() * surface_color;
             self.incoming_light +=
-                light_from_struck_face * ray_state.alpha * ray_state.ray_weight_by_faces;
+                light_from_struck_face * ray_state.transmittance * ray_state.ray_weight_by_faces;
             self.dependencies.push(light_cube);
             self.cost += 10;
             // This terminates the raycast; we don't bounce rays
             // (diffuse reflections, not specular/mirror).
-            ray_state.alpha = 0.0;
+            ray_state.transmittance = Rgb::ZERO;
 
             // Diagnostics. TODO: Track transparency too.
             D::push_ray(
@@ -462,11 +602,12 @@ impl LightBuffer {
             // approximation as an alpha value.
             let coverage = ev_hit.color.alpha().into_inner().clamp(0.0, 1.0);
             self.incoming_light += (ev_hit.attributes.light_emission + stored_light)
+                * ray_state.transmittance
                 * coverage
-                * ray_state.alpha
                 * ray_state.ray_weight_by_faces;
             self.cost += 10;
-            ray_state.alpha *= 1.0 - coverage;
+            // The light which is not covered passes through, picking up the block's tint.
+            ray_state.transmittance = ray_state.transmittance * *light_tint * (1.0 - coverage);
 
             self.dependencies.push(hit.cube_ahead());
             // We did not read hit.cube_behind(), but we want to trigger its updates
@@ -485,9 +626,11 @@ impl LightBuffer {
         // not count this as a successful ray.
         // TODO: clarify signaling flow?
         if ray_state.ray_weight_by_faces > 0. {
-            // Note that if ray_state.alpha has reached zero, the sky color has no effect.
+            self.sky_exposed |= ray_state.transmittance != Rgb::ZERO;
+            // Note that if ray_state.transmittance has reached zero, the sky color has no effect.
             self.add_weighted_light(
-                space.physics.sky_color * ray_state.alpha,
+                space.physics.sky_light(ray_state.translated_ray.direction)
+                    * ray_state.transmittance,
                 ray_state.ray_weight_by_faces,
             );
         }
@@ -554,6 +697,35 @@ impl CustomFormat<StatusText> for LightUpdatesInfo {
     }
 }
 
+/// Returns the color by which light passing through a partially transparent block is
+/// filtered.
+///
+/// Only the block's translucent voxels contribute, weighted by their alpha; its opaque
+/// voxels already block light according to its coverage, and light passing through its
+/// gaps does not touch them. This is normalized so that its brightest component is 1,
+/// because the amount of light blocked is determined separately by the coverage; only
+/// the hue and saturation of the translucent voxels affect the transmitted light. Gray
+/// and white voxels therefore do not tint light at all.
+///
+/// This scans every voxel, so it is cached in [`SpaceBlockData`].
+pub(crate) fn transmission_tint(evaluated: &EvaluatedBlock) -> Rgb {
+    let voxels = &evaluated.voxels;
+    let mut sum = Rgb::ZERO;
+    for position in voxels.bounds().interior_iter() {
+        let color = voxels[position].color;
+        if color.opacity_category() == OpacityCategory::Partial {
+            sum += color.to_rgb().clamp() * color.alpha().into_inner();
+        }
+    }
+    let brightest = sum.red().max(sum.green()).max(sum.blue()).into_inner();
+    if brightest > 0.0 {
+        sum * brightest.recip()
+    } else {
+        // Black has no hue to speak of, and there may be no translucent voxels at all.
+        Rgb::ONE
+    }
+}
+
 /// A special definition of opacity for the lighting algorithm:
 /// we want to treat opaque light-emitting blocks similarly to transparent blocks
 /// *when deciding to compute light for them*, because this produces better results
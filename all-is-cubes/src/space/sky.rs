@@ -0,0 +1,235 @@
+use std::f64::consts::TAU;
+
+use cgmath::{InnerSpace as _, Vector3, Zero as _};
+use ordered_float::NotNan;
+
+use crate::behavior::{Behavior, BehaviorContext};
+use crate::math::{FreeCoordinate, Rgb};
+#[cfg(doc)]
+use crate::space::SpacePhysics;
+use crate::space::{Space, SpaceTransaction};
+use crate::time::{Duration, Tick};
+use crate::transaction::Merge as _;
+use crate::universe::{RefVisitor, UniverseTransaction, VisitRefs};
+
+/// Exponent of the cosine falloff of the sun's glow; higher values make the sun smaller.
+const SUN_GLOW_EXPONENT: i32 = 8;
+
+/// Describes the light arriving from outside a [`Space`] as a function of direction:
+/// a vertical gradient of ambient sky color plus a directional sun.
+///
+/// Used as [`SpacePhysics::sky`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[allow(clippy::exhaustive_structs)]
+pub struct Sky {
+    /// Color of the sky directly overhead (in the +Y direction).
+    pub zenith_color: Rgb,
+    /// Color of the sky at and below the horizon.
+    pub horizon_color: Rgb,
+    /// Direction from which sunlight arrives (that is, pointing towards the sun).
+    /// Need not be normalized; if zero, there is no sun.
+    pub sun_direction: Vector3<NotNan<FreeCoordinate>>,
+    /// Color and intensity of the sun. This is scaled so that it is approximately the
+    /// amount of sunlight falling on a surface facing the sun.
+    pub sun_color: Rgb,
+}
+
+impl Sky {
+    /// Constructs a [`Sky`] which is the same color in every direction and has no sun.
+    pub const fn uniform(color: Rgb) -> Self {
+        Self {
+            zenith_color: color,
+            horizon_color: color,
+            sun_direction: Vector3::new(notnan!(0.), notnan!(0.), notnan!(0.)),
+            sun_color: Rgb::ZERO,
+        }
+    }
+
+    /// Returns the light arriving from the given direction (pointing away from the
+    /// viewer, towards the sky).
+    pub fn sample(&self, direction: Vector3<FreeCoordinate>) -> Rgb {
+        if direction.is_zero() {
+            return self.horizon_color;
+        }
+        let direction = direction.normalize();
+
+        let elevation = direction.y.clamp(0.0, 1.0) as f32;
+        let mut color = self.horizon_color * (1.0 - elevation) + self.zenith_color * elevation;
+
+        if let Some(sun_direction) = self.visible_sun_direction() {
+            let cosine = direction.dot(sun_direction).max(0.0) as f32;
+            // Scaled so that the average over a hemisphere centered on the sun is 1.
+            let glow = cosine.powi(SUN_GLOW_EXPONENT) * (SUN_GLOW_EXPONENT + 1) as f32;
+            color += self.sun_color * glow;
+        }
+        color
+    }
+
+    /// Returns a single color which approximates the overall illumination from this sky,
+    /// for use where direction is not known (such as [`SpacePhysics::sky_color`]).
+    pub fn average(&self) -> Rgb {
+        // Average of the gradient over the upper hemisphere, with equal weight given to
+        // the lower hemisphere's horizon color, plus the sun's hemisphere average.
+        let ambient = (self.horizon_color * 3.0 + self.zenith_color) * 0.25;
+        match self.visible_sun_direction() {
+            Some(_) => ambient + self.sun_color * 0.5,
+            None => ambient,
+        }
+    }
+
+    /// Returns the normalized direction towards the sun, or [`None`] if there is no sun
+    /// or it is below the horizon (and so contributes no light).
+    pub fn visible_sun_direction(&self) -> Option<Vector3<FreeCoordinate>> {
+        let sun_direction = self.sun_direction.map(NotNan::into_inner);
+        if sun_direction.is_zero() || sun_direction.y < 0.0 {
+            None
+        } else {
+            Some(sun_direction.normalize())
+        }
+    }
+
+    /// Linearly interpolates between `self` (at 0) and `other` (at 1), including the
+    /// sun direction.
+    ///
+    /// If a component of an infinite sun direction would become NaN (as infinity times
+    /// zero does), it is replaced with zero.
+    #[must_use]
+    pub fn lerp(&self, other: &Sky, t: f32) -> Sky {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: Rgb, b: Rgb| a * (1.0 - t) + b * t;
+        Sky {
+            zenith_color: mix(self.zenith_color, other.zenith_color),
+            horizon_color: mix(self.horizon_color, other.horizon_color),
+            sun_direction: (self.sun_direction.map(NotNan::into_inner) * f64::from(1.0 - t)
+                + other.sun_direction.map(NotNan::into_inner) * f64::from(t))
+            .map(|c| NotNan::new(c).unwrap_or_default()),
+            sun_color: mix(self.sun_color, other.sun_color),
+        }
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+#[mutants::skip]
+impl<'a> arbitrary::Arbitrary<'a> for Sky {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self {
+            zenith_color: u.arbitrary()?,
+            horizon_color: u.arbitrary()?,
+            sun_direction: Vector3::new(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?),
+            sun_color: u.arbitrary()?,
+        })
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        use arbitrary::{size_hint::and_all, Arbitrary};
+        and_all(&[
+            <Rgb as Arbitrary>::size_hint(depth),
+            <Rgb as Arbitrary>::size_hint(depth),
+            <[f64; 3] as Arbitrary>::size_hint(depth),
+            <Rgb as Arbitrary>::size_hint(depth),
+        ])
+    }
+}
+
+/// A [`Behavior`] which moves the sun of its [`Space`] across the sky over time,
+/// changing [`SpacePhysics::sky`] (and [`SpacePhysics::sky_color`]) to match.
+///
+/// The sun rises in the +X direction, is overhead at noon, and sets in the −X direction.
+/// The sky fades between `day_sky` and `night_sky` around sunrise and sunset.
+///
+/// In order to avoid constantly recomputing lighting, the sky is only changed
+/// [`Self::UPDATES_PER_DAY`] times per day. Each change queues light updates for the
+/// space, which are then processed incrementally in the usual way.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub struct DayNightCycle {
+    /// The length of one full day.
+    pub day_length: Duration,
+    /// The current time of day, as a fraction of `day_length`:
+    /// 0 is midnight, 0.25 is sunrise, 0.5 is noon, and 0.75 is sunset.
+    pub time_of_day: NotNan<f64>,
+    /// Sky colors used during the day. The sun direction is ignored and replaced.
+    pub day_sky: Sky,
+    /// Sky colors used at night. The sun direction is ignored and replaced.
+    pub night_sky: Sky,
+}
+
+impl DayNightCycle {
+    /// Number of times per day the sky is updated.
+    pub const UPDATES_PER_DAY: u32 = 360;
+
+    /// Constructs a [`DayNightCycle`] starting at the given time of day, which is a
+    /// fraction of a day as described in [`Self::time_of_day`].
+    ///
+    /// Panics if `time_of_day` is NaN.
+    pub fn new(day_length: Duration, time_of_day: f64, day_sky: Sky, night_sky: Sky) -> Self {
+        Self {
+            day_length,
+            time_of_day: NotNan::new(time_of_day.rem_euclid(1.0)).expect("time_of_day is NaN"),
+            day_sky,
+            night_sky,
+        }
+    }
+
+    /// Returns the sky at the current time of day.
+    pub fn sky(&self) -> Sky {
+        Self::sky_at(&self.day_sky, &self.night_sky, self.quantized_time())
+    }
+
+    fn sky_at(day_sky: &Sky, night_sky: &Sky, time_of_day: f64) -> Sky {
+        let angle = TAU * (time_of_day - 0.25);
+        let (sin, cos) = angle.sin_cos();
+        // Fade over the period when the sun is near the horizon.
+        let daylight = ((sin + 0.1) / 0.3).clamp(0.0, 1.0) as f32;
+        Sky {
+            sun_direction: Vector3::new(cos, sin, 0.0).map(|c| NotNan::new(c).unwrap()),
+            ..night_sky.lerp(day_sky, daylight)
+        }
+    }
+
+    fn update_index(&self) -> u32 {
+        (self.time_of_day.into_inner() * f64::from(Self::UPDATES_PER_DAY)) as u32
+    }
+
+    fn quantized_time(&self) -> f64 {
+        f64::from(self.update_index()) / f64::from(Self::UPDATES_PER_DAY)
+    }
+}
+
+impl Behavior<Space> for DayNightCycle {
+    fn step(&self, context: &BehaviorContext<'_, Space>, tick: Tick) -> UniverseTransaction {
+        if tick.paused() || self.day_length.is_zero() {
+            return UniverseTransaction::default();
+        }
+        let advance = tick.delta_t().as_secs_f64() / self.day_length.as_secs_f64();
+        let next = Self {
+            time_of_day: NotNan::new((self.time_of_day.into_inner() + advance).rem_euclid(1.0))
+                .unwrap(),
+            ..self.clone()
+        };
+
+        let sky = next.sky();
+        let mut txn = context.replace_self(next);
+        if context.host.physics().sky.as_ref() != Some(&sky) {
+            let mut physics = context.host.physics().clone();
+            physics.sky_color = sky.average();
+            physics.sky = Some(sky);
+            txn = txn
+                .merge(context.bind_host(SpaceTransaction::set_physics(physics)))
+                .expect("replacing the behavior and setting physics cannot conflict");
+        }
+        txn
+    }
+
+    fn alive(&self, _: &BehaviorContext<'_, Space>) -> bool {
+        true
+    }
+
+    fn ephemeral(&self) -> bool {
+        false
+    }
+}
+
+impl VisitRefs for DayNightCycle {
+    fn visit_refs(&self, _: &mut dyn RefVisitor) {}
+}
@@ -1,15 +1,20 @@
 //! TODO: Maybe this file is too small
 
+use std::borrow::Cow;
 use std::collections::btree_map::Entry::*;
 use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::{fmt, mem};
 
-use crate::behavior::{self, BehaviorSet, BehaviorSetTransaction};
-use crate::block::Block;
+use crate::behavior::{self, Behavior, BehaviorSet, BehaviorSetTransaction};
+use crate::block::{Block, Primitive, PropertyValue};
 use crate::drawing::DrawingPlane;
+use crate::inv::{Inventory, InventoryTransaction, Slot};
 use crate::math::{GridCoordinate, GridMatrix, GridPoint};
-use crate::space::{ActivatableRegion, GridAab, SetCubeError, Space};
+use crate::space::{
+    ActivatableRegion, CubeData, GridAab, SetCubeError, Space, SpaceChange, SpacePhysics,
+    TickSchedule,
+};
 use crate::transaction::{
     no_outputs, CommitError, Merge, NoOutput, PreconditionFailed, Transaction, TransactionConflict,
     Transactional,
@@ -26,6 +31,8 @@ impl Transactional for Space {
 pub struct SpaceTransaction {
     cubes: BTreeMap<[GridCoordinate; 3], CubeTransaction>,
     behaviors: BehaviorSetTransaction<Space>,
+    /// If not [`None`], replaces the space's [`SpacePhysics`].
+    physics: Option<SpacePhysics>,
 }
 
 impl SpaceTransaction {
@@ -145,6 +152,16 @@ impl SpaceTransaction {
         }
     }
 
+    /// Replace the space's [`SpacePhysics`], as by [`Space::set_physics()`].
+    ///
+    /// Two such transactions conflict unless they set equal values.
+    pub fn set_physics(physics: SpacePhysics) -> Self {
+        Self {
+            physics: Some(physics),
+            ..Default::default()
+        }
+    }
+
     /// Add a behavior to the [`Space`].
     /// This is a shortcut for creating a [`BehaviorSetTransaction`].
     pub fn add_behavior<B>(bounds: GridAab, behavior: B) -> Self
@@ -157,6 +174,99 @@ impl SpaceTransaction {
         ))
     }
 
+    /// Construct a [`SpaceTransaction`] which changes the value of one
+    /// [property](crate::block::BlockProperties) of the block in `cube`, as by
+    /// [`Block::with_property()`], without needing to know the rest of the block.
+    ///
+    /// The transaction will fail if the block does not have the property (which is the
+    /// case unless it is a [`Primitive::Indirect`] block whose definition declares it),
+    /// or if the property does not allow `value`.
+    pub fn set_property(
+        cube: impl Into<GridPoint>,
+        name: impl Into<Cow<'static, str>>,
+        value: impl Into<PropertyValue>,
+    ) -> Self {
+        Self::single(
+            cube,
+            CubeTransaction {
+                property: Some(PropertyChange {
+                    name: name.into(),
+                    value: Some(value.into()),
+                }),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Construct a [`SpaceTransaction`] which changes the value of one
+    /// [property](crate::block::BlockProperties) of the block in `cube` to the
+    /// [next](crate::block::PropertyKind::next) value; a boolean property is toggled.
+    ///
+    /// The transaction will fail if the block does not have the property, as with
+    /// [`SpaceTransaction::set_property()`].
+    pub fn cycle_property(cube: impl Into<GridPoint>, name: impl Into<Cow<'static, str>>) -> Self {
+        Self::single(
+            cube,
+            CubeTransaction {
+                property: Some(PropertyChange {
+                    name: name.into(),
+                    value: None,
+                }),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Construct a [`SpaceTransaction`] which modifies the [`Inventory`] held in `cube`.
+    ///
+    /// The transaction will fail if the cube has no inventory, i.e. its block is not a
+    /// container (see [`CubeData`](crate::space::CubeData)).
+    ///
+    /// To move items between a container and a [`Character`](crate::character::Character),
+    /// merge the bound form of this transaction with a
+    /// [`CharacterTransaction::inventory()`](crate::character::CharacterTransaction::inventory).
+    pub fn inventory(cube: impl Into<GridPoint>, transaction: InventoryTransaction) -> Self {
+        Self::single(
+            cube,
+            CubeTransaction {
+                inventory: Some(transaction),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Construct a [`SpaceTransaction`] which changes when the block entity in `cube`
+    /// (see [`BlockEntityTemplate`](crate::space::BlockEntityTemplate)) is next stepped;
+    /// the time until the next step is counted from when the transaction is committed.
+    ///
+    /// If there is no block entity in the cube after the rest of the transaction is
+    /// committed, this has no effect.
+    pub fn schedule_block_entity(cube: impl Into<GridPoint>, schedule: TickSchedule) -> Self {
+        Self::single(
+            cube,
+            CubeTransaction {
+                schedule: Some(schedule),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Replaces the block entity in `cube`, if it is `old`, with `new`.
+    /// Used by [`behavior::BehaviorContext::replace_self()`] for block entities.
+    pub(crate) fn replace_block_entity(
+        cube: GridPoint,
+        old: Arc<dyn Behavior<Space>>,
+        new: Arc<dyn Behavior<Space>>,
+    ) -> Self {
+        Self::single(
+            cube,
+            CubeTransaction {
+                replace_entity: Some(EntityReplacement { old, new }),
+                ..Default::default()
+            },
+        )
+    }
+
     pub(crate) fn activate_block(cube: GridPoint) -> Self {
         Self::single(cube, CubeTransaction::ACTIVATE)
     }
@@ -173,6 +283,7 @@ impl SpaceTransaction {
         let Self {
             cubes,
             behaviors: _,
+            physics: _,
         } = self;
         let mut bounds: Option<GridAab> = None;
 
@@ -191,11 +302,13 @@ impl SpaceTransaction {
     /// Computes the region affected by this transaction.
     ///
     /// Returns [`None`] if no specific regions of the space are affected.
+    /// (Changes to the [`SpacePhysics`] affect the entire space and are not counted.)
     pub fn bounds(&self) -> Option<GridAab> {
         // Destructuring to statically check that we consider all fields.
         let Self {
             cubes: _,
             behaviors,
+            physics: _,
         } = self;
         let mut bounds: Option<GridAab> = self.bounds_only_cubes();
 
@@ -211,35 +324,84 @@ impl SpaceTransaction {
     }
 }
 
+#[allow(clippy::type_complexity)]
 impl Transaction<Space> for SpaceTransaction {
-    type CommitCheck =
-        <BehaviorSetTransaction<Space> as Transaction<BehaviorSet<Space>>>::CommitCheck;
+    type CommitCheck = (
+        BTreeMap<
+            [GridCoordinate; 3],
+            <InventoryTransaction as Transaction<Inventory>>::CommitCheck,
+        >,
+        <BehaviorSetTransaction<Space> as Transaction<BehaviorSet<Space>>>::CommitCheck,
+    );
     type Output = NoOutput;
 
     fn check(&self, space: &Space) -> Result<Self::CommitCheck, PreconditionFailed> {
+        let mut inventory_checks = BTreeMap::new();
         for (
             &cube,
             CubeTransaction {
                 old,
-                new: _,
+                new,
                 conserved,
                 activate: _,
+                property,
+                inventory,
+                replace_entity,
+                schedule: _,
             },
         ) in &self.cubes
         {
             if let Some(cube_index) = space.bounds().index(cube) {
+                // Raw lookup because we already computed the index for a bounds check
+                // (TODO: Put this in a function, like get_block_index)
+                let existing = &space.block_data[space.contents[cube_index] as usize].block;
                 if let Some(old) = old {
-                    // Raw lookup because we already computed the index for a bounds check
-                    // (TODO: Put this in a function, like get_block_index)
-                    if space.block_data[space.contents[cube_index] as usize].block != *old {
+                    if existing != old {
                         return Err(PreconditionFailed {
                             location: "Space",
                             problem: "existing block not as expected",
                         });
                     }
                 }
+                let with_property = match property {
+                    Some(property) => Some(property.apply(existing)?),
+                    None => None,
+                };
+                if let Some(inventory) = inventory {
+                    let data = space
+                        .cube_data
+                        .get(&cube.into())
+                        .ok_or(PreconditionFailed {
+                            location: "Space",
+                            problem: "cube has no inventory",
+                        })?;
+                    inventory_checks.insert(cube, inventory.check(&data.inventory)?);
+                }
+                if let (Some(replacement), Some(data)) = (
+                    new.as_ref().or(with_property.as_ref()),
+                    space.cube_data.get(&cube.into()),
+                ) {
+                    check_container_replacement(space, data, inventory.as_ref(), replacement)?;
+                }
+                if let Some(replacement) = replace_entity {
+                    if !space
+                        .block_entities
+                        .get(cube.into())
+                        .map_or(false, |existing| replacement.is_of(existing))
+                    {
+                        return Err(PreconditionFailed {
+                            location: "Space",
+                            problem: "block entity not as expected",
+                        });
+                    }
+                }
             } else {
-                if *conserved || old.is_some() {
+                if *conserved
+                    || old.is_some()
+                    || property.is_some()
+                    || inventory.is_some()
+                    || replace_entity.is_some()
+                {
                     // It is an error for conserved cube txns to be out of bounds,
                     // or for a precondition to be not meetable because it is out of bounds.
                     // TODO: Should we allow `old: Some(AIR), new: None`, since we treat
@@ -252,13 +414,13 @@ impl Transaction<Space> for SpaceTransaction {
                 }
             }
         }
-        self.behaviors.check(&space.behaviors)
+        Ok((inventory_checks, self.behaviors.check(&space.behaviors)?))
     }
 
     fn commit(
         &self,
         space: &mut Space,
-        check: Self::CommitCheck,
+        (mut inventory_checks, behaviors_check): Self::CommitCheck,
         _outputs: &mut dyn FnMut(Self::Output),
     ) -> Result<(), CommitError> {
         let mut to_activate = Vec::new();
@@ -269,9 +431,41 @@ impl Transaction<Space> for SpaceTransaction {
                 new,
                 conserved,
                 activate,
+                property,
+                inventory,
+                replace_entity,
+                schedule,
             },
         ) in &self.cubes
         {
+            // The inventory is modified before the block, since the check was of the
+            // inventory as it exists before any change to the block.
+            if let Some(inventory) = inventory {
+                let check = inventory_checks
+                    .remove(&cube)
+                    .expect("missing inventory check");
+                let Space {
+                    cube_data,
+                    notifier,
+                    ..
+                } = &mut *space;
+                let data = cube_data
+                    .get_mut(&cube.into())
+                    .ok_or_else(|| CommitError::message::<Self>("cube has no inventory".into()))?;
+                inventory
+                    .commit(&mut data.inventory, check, &mut |_change| {
+                        notifier.notify(SpaceChange::CubeData(cube.into()));
+                    })
+                    .map_err(|e| e.context("inventory".into()))?;
+            }
+            if let Some(property) = property {
+                let new = property
+                    .apply(&space[cube])
+                    .map_err(|e| CommitError::message::<Self>(e.problem.to_string()))?;
+                space
+                    .set(cube, new)
+                    .map_err(CommitError::catch::<Self, _>)?;
+            }
             if let Some(new) = new {
                 match space.set(cube, new) {
                     Ok(_) => Ok(()),
@@ -282,14 +476,26 @@ impl Transaction<Space> for SpaceTransaction {
                     Err(other) => Err(CommitError::catch::<Self, _>(other)),
                 }?;
             }
+            // If the block was changed such that the entity was replaced, this does nothing.
+            if let Some(EntityReplacement { old, new }) = replace_entity {
+                space
+                    .block_entities
+                    .replace_behavior(cube.into(), old, new.clone());
+            }
+            if let Some(schedule) = schedule {
+                space.block_entities.set_schedule(cube.into(), *schedule);
+            }
             if *activate {
                 // Deferred for slightly more consistency
                 to_activate.push(cube);
             }
         }
         self.behaviors
-            .commit(&mut space.behaviors, check, &mut no_outputs)
+            .commit(&mut space.behaviors, behaviors_check, &mut no_outputs)
             .map_err(|e| e.context("behaviors".into()))?;
+        if let Some(physics) = &self.physics {
+            space.set_physics(physics.clone());
+        }
         if !to_activate.is_empty() {
             'b: for query_item in space.behaviors.query::<ActivatableRegion>() {
                 // TODO: error return from the function? error report for nonexistence?
@@ -306,6 +512,54 @@ impl Transaction<Space> for SpaceTransaction {
     }
 }
 
+/// Fails if replacing the block of a container cube, whose data is `data`, with
+/// `replacement` would discard items, after `inventory` is applied to them.
+/// (Items must be taken out of a container before it is replaced.)
+fn check_container_replacement(
+    space: &Space,
+    data: &CubeData,
+    inventory: Option<&InventoryTransaction>,
+    replacement: &Block,
+) -> Result<(), PreconditionFailed> {
+    let remaining = match inventory {
+        Some(inventory) => {
+            let mut after = data.inventory.clone();
+            let check = inventory.check(&after)?;
+            inventory
+                .commit(&mut after, check, &mut |_| {})
+                .map_err(|_| PreconditionFailed {
+                    location: "Space",
+                    problem: "inventory transaction failed",
+                })?;
+            Cow::Owned(after)
+        }
+        None => Cow::Borrowed(&data.inventory),
+    };
+    let new_slots = match space.block_to_index.get(replacement) {
+        Some(&index) => {
+            space.block_data[index as usize]
+                .evaluated
+                .attributes
+                .container_slots
+        }
+        None => replacement
+            .evaluate()
+            .map_or(0, |ev| ev.attributes.container_slots),
+    };
+    if remaining
+        .slots
+        .iter()
+        .skip(usize::from(new_slots))
+        .any(|slot| *slot != Slot::Empty)
+    {
+        return Err(PreconditionFailed {
+            location: "Space",
+            problem: "replacing the block would discard the container's items",
+        });
+    }
+    Ok(())
+}
+
 impl Merge for SpaceTransaction {
     type MergeCheck = <BehaviorSetTransaction<Space> as Merge>::MergeCheck;
 
@@ -326,6 +580,9 @@ impl Merge for SpaceTransaction {
                 let CubeMergeCheck {} = t1.check_merge(t2)?;
             }
         }
+        if matches!((&self.physics, &other.physics), (Some(a), Some(b)) if a != b) {
+            return Err(TransactionConflict {});
+        }
         self.behaviors.check_merge(&other.behaviors)
     }
 
@@ -345,6 +602,7 @@ impl Merge for SpaceTransaction {
             }
         }
         self.behaviors = self.behaviors.commit_merge(other.behaviors, check);
+        self.physics = self.physics.or(other.physics);
         self
     }
 }
@@ -363,6 +621,9 @@ impl fmt::Debug for SpaceTransaction {
         if !self.behaviors.is_empty() {
             ds.field("behaviors", &self.behaviors);
         }
+        if let Some(physics) = &self.physics {
+            ds.field("physics", physics);
+        }
         ds.finish()
     }
 }
@@ -385,6 +646,19 @@ struct CubeTransaction {
     /// The cube was “activated” (clicked on, more or less) and should
     /// respond to that.
     activate: bool,
+
+    /// Change to one property of the block in this cube, computed from whatever block
+    /// is there when the transaction is committed. Exclusive with `new`.
+    property: Option<PropertyChange>,
+
+    /// Modification of the cube's inventory, which must exist.
+    inventory: Option<InventoryTransaction>,
+
+    /// Replacement of the cube's block entity, which must exist.
+    replace_entity: Option<EntityReplacement>,
+
+    /// New schedule for the cube's block entity, if it has one.
+    schedule: Option<TickSchedule>,
 }
 
 impl CubeTransaction {
@@ -393,9 +667,84 @@ impl CubeTransaction {
         new: None,
         conserved: false,
         activate: true,
+        property: None,
+        inventory: None,
+        replace_entity: None,
+        schedule: None,
     };
 }
 
+/// Data for [`SpaceTransaction::replace_block_entity()`].
+#[derive(Clone)]
+struct EntityReplacement {
+    old: Arc<dyn Behavior<Space>>,
+    new: Arc<dyn Behavior<Space>>,
+}
+
+impl EntityReplacement {
+    #[allow(clippy::vtable_address_comparisons)] // The hazards should be okay for this use case
+    fn is_of(&self, existing: &Arc<dyn Behavior<Space>>) -> bool {
+        Arc::ptr_eq(&self.old, existing)
+    }
+}
+
+impl fmt::Debug for EntityReplacement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { old: _, new } = self;
+        f.debug_struct("EntityReplacement")
+            .field("new", new)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for EntityReplacement {
+    #[allow(clippy::vtable_address_comparisons)] // The hazards should be okay for this use case
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.old, &other.old) && Arc::ptr_eq(&self.new, &other.new)
+    }
+}
+impl Eq for EntityReplacement {}
+
+/// Data for [`SpaceTransaction::set_property()`] and
+/// [`SpaceTransaction::cycle_property()`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct PropertyChange {
+    name: Cow<'static, str>,
+    /// New value of the property, or [`None`] to change to the next value.
+    value: Option<PropertyValue>,
+}
+
+impl PropertyChange {
+    /// Returns `block` with this change made, or an error if it cannot be.
+    fn apply(&self, block: &Block) -> Result<Block, PreconditionFailed> {
+        let failure = |problem| PreconditionFailed {
+            location: "Space",
+            problem,
+        };
+        let Primitive::Indirect(def_ref) = block.primitive() else {
+            return Err(failure("block does not have the property"));
+        };
+        let def = def_ref
+            .read()
+            .map_err(|_| failure("block definition could not be read"))?;
+        let properties = def.properties();
+        let declaration = properties
+            .get(&self.name)
+            .ok_or_else(|| failure("block does not have the property"))?;
+
+        let value = match &self.value {
+            Some(value) if declaration.kind().allows(value) => value.clone(),
+            Some(_) => return Err(failure("property does not allow the value")),
+            None => declaration.kind().next(
+                &properties
+                    .value(block.state(), &self.name)
+                    .expect("property should exist"),
+            ),
+        };
+        Ok(block.clone().with_property(self.name.clone(), value))
+    }
+}
+
 impl Merge for CubeTransaction {
     type MergeCheck = CubeMergeCheck;
 
@@ -409,6 +758,22 @@ impl Merge for CubeTransaction {
             // equal, doing so could violate an intended conservation law.
             return Err(TransactionConflict {});
         }
+        if (self.property.is_some() && (other.property.is_some() || other.new.is_some()))
+            || (other.property.is_some() && self.new.is_some())
+        {
+            // A property change is relative to the existing block, so it cannot be
+            // combined with any other change to the block.
+            return Err(TransactionConflict {});
+        }
+        if let (Some(a), Some(b)) = (&self.inventory, &other.inventory) {
+            a.check_merge(b)?;
+        }
+        if self.replace_entity.is_some() && other.replace_entity.is_some() {
+            return Err(TransactionConflict {});
+        }
+        if matches!((&self.schedule, &other.schedule), (Some(a), Some(b)) if a != b) {
+            return Err(TransactionConflict {});
+        }
         Ok(CubeMergeCheck {})
     }
 
@@ -422,6 +787,13 @@ where {
             old: self.old.or(other.old),
             new: self.new.or(other.new),
             activate: self.activate || other.activate,
+            property: self.property.or(other.property),
+            inventory: match (self.inventory, other.inventory) {
+                (Some(a), Some(b)) => Some(a.commit_merge(b, ())),
+                (a, b) => a.or(b),
+            },
+            replace_entity: self.replace_entity.or(other.replace_entity),
+            schedule: self.schedule.or(other.schedule),
         }
     }
 }
@@ -440,11 +812,13 @@ mod tests {
     use pretty_assertions::assert_eq;
 
     use crate::behavior::NoopBehavior;
-    use crate::block::AIR;
+    use crate::block::{BlockDef, BlockProperties, Property, AIR};
+    use crate::character::{Character, CharacterTransaction};
     use crate::content::make_some_blocks;
-    use crate::inv::EphemeralOpaque;
-    use crate::math::GridAab;
+    use crate::inv::{EphemeralOpaque, Tool};
+    use crate::math::{GridAab, Rgba};
     use crate::transaction::{no_outputs, TransactionTester};
+    use crate::universe::Universe;
 
     use super::*;
 
@@ -533,6 +907,10 @@ mod tests {
                         new: Some(b2.clone()),
                         conserved: true,
                         activate: false,
+                        property: None,
+                        inventory: None,
+                        replace_entity: None,
+                        schedule: None,
                     }
                 ),
                 (
@@ -542,6 +920,10 @@ mod tests {
                         new: Some(b3.clone()),
                         conserved: true,
                         activate: false,
+                        property: None,
+                        inventory: None,
+                        replace_entity: None,
+                        schedule: None,
                     }
                 ),
             ]
@@ -615,6 +997,190 @@ mod tests {
         assert_eq!(signal.load(Ordering::Relaxed), 1);
     }
 
+    fn space_with_door(universe: &mut Universe) -> (Space, Block) {
+        let [base] = make_some_blocks();
+        let def = BlockDef::new(base).with_properties(
+            BlockProperties::new()
+                .property(Property::bool("open", false))
+                .property(Property::int("level", 2, 0)),
+        );
+        let door = Block::from(Primitive::Indirect(universe.insert_anonymous(def)));
+        let mut space = Space::empty_positive(1, 1, 1);
+        space.set([0, 0, 0], &door).unwrap();
+        (space, door)
+    }
+
+    #[test]
+    fn set_property() {
+        let mut universe = Universe::new();
+        let (mut space, door) = space_with_door(&mut universe);
+        SpaceTransaction::set_property([0, 0, 0], "level", 2u8)
+            .execute(&mut space, &mut no_outputs)
+            .unwrap();
+        assert_eq!(space[[0, 0, 0]], door.with_property("level", 2u8));
+    }
+
+    #[test]
+    fn cycle_property() {
+        let mut universe = Universe::new();
+        let (mut space, door) = space_with_door(&mut universe);
+        for expected in [true, false] {
+            SpaceTransaction::cycle_property([0, 0, 0], "open")
+                .execute(&mut space, &mut no_outputs)
+                .unwrap();
+            assert_eq!(space[[0, 0, 0]].property("open"), Ok(Some(expected.into())));
+        }
+        assert_eq!(space[[0, 0, 0]], door.with_property("open", false));
+    }
+
+    #[test]
+    fn set_property_fails_if_not_allowed() {
+        let mut universe = Universe::new();
+        let (space, _) = space_with_door(&mut universe);
+        SpaceTransaction::set_property([0, 0, 0], "color", "red")
+            .check(&space)
+            .unwrap_err();
+        SpaceTransaction::set_property([0, 0, 0], "level", 3u8)
+            .check(&space)
+            .unwrap_err();
+        SpaceTransaction::set_property([0, 0, 0], "open", 1u8)
+            .check(&space)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn merge_rejects_property_and_other_change() {
+        let [block] = make_some_blocks();
+        let t1 = SpaceTransaction::cycle_property([0, 0, 0], "open");
+        t1.clone()
+            .merge(SpaceTransaction::cycle_property([0, 0, 0], "open"))
+            .unwrap_err();
+        t1.clone()
+            .merge(SpaceTransaction::set_cube([0, 0, 0], None, Some(block)))
+            .unwrap_err();
+        let _ = t1
+            .merge(SpaceTransaction::cycle_property([1, 0, 0], "open"))
+            .unwrap();
+    }
+
+    fn space_with_chest() -> Space {
+        let chest = Block::builder()
+            .color(Rgba::WHITE)
+            .container_slots(2)
+            .build();
+        let mut space = Space::empty_positive(2, 1, 1);
+        space.set([0, 0, 0], &chest).unwrap();
+        space
+    }
+
+    #[test]
+    fn inventory_insert() {
+        let mut space = space_with_chest();
+        SpaceTransaction::inventory([0, 0, 0], InventoryTransaction::insert([Tool::Activate]))
+            .execute(&mut space, &mut no_outputs)
+            .unwrap();
+        assert_eq!(
+            space.cube_data([0, 0, 0]).unwrap().inventory.slots,
+            vec![Slot::from(Tool::Activate), Slot::Empty]
+        );
+    }
+
+    #[test]
+    fn inventory_fails_if_not_container() {
+        let space = space_with_chest();
+        SpaceTransaction::inventory([1, 0, 0], InventoryTransaction::insert([Tool::Activate]))
+            .check(&space)
+            .unwrap_err();
+    }
+
+    #[test]
+    fn inventory_transfer_to_character() {
+        let mut space = space_with_chest();
+        space.insert_cube_data(
+            GridPoint::new(0, 0, 0),
+            CubeData {
+                inventory: Inventory::from_slots(vec![Tool::PushPull.into(), Slot::Empty]),
+            },
+        );
+        let mut universe = Universe::new();
+        let space_ref = universe.insert_anonymous(space);
+        let character = universe.insert_anonymous(Character::spawn_default(space_ref.clone()));
+
+        SpaceTransaction::inventory(
+            [0, 0, 0],
+            InventoryTransaction::replace(0, Tool::PushPull.into(), Slot::Empty),
+        )
+        .bind(space_ref.clone())
+        .merge(
+            CharacterTransaction::inventory(InventoryTransaction::insert([Tool::PushPull]))
+                .bind(character.clone()),
+        )
+        .unwrap()
+        .execute(&mut universe, &mut no_outputs)
+        .unwrap();
+
+        assert_eq!(
+            space_ref
+                .read()
+                .unwrap()
+                .cube_data([0, 0, 0])
+                .unwrap()
+                .inventory
+                .slots[0],
+            Slot::Empty
+        );
+        assert!(character
+            .read()
+            .unwrap()
+            .inventory()
+            .slots
+            .contains(&Slot::from(Tool::PushPull)));
+    }
+
+    #[test]
+    fn replacing_container_with_items_fails() {
+        let mut space = space_with_chest();
+        SpaceTransaction::inventory([0, 0, 0], InventoryTransaction::insert([Tool::Activate]))
+            .execute(&mut space, &mut no_outputs)
+            .unwrap();
+
+        let error = SpaceTransaction::set_cube([0, 0, 0], None, Some(AIR))
+            .check(&space)
+            .unwrap_err();
+        assert_eq!(
+            error.problem,
+            "replacing the block would discard the container's items"
+        );
+    }
+
+    #[test]
+    fn replacing_container_after_taking_items() {
+        let mut space = space_with_chest();
+        SpaceTransaction::inventory([0, 0, 0], InventoryTransaction::insert([Tool::Activate]))
+            .execute(&mut space, &mut no_outputs)
+            .unwrap();
+
+        SpaceTransaction::inventory(
+            [0, 0, 0],
+            InventoryTransaction::replace(0, Tool::Activate.into(), Slot::Empty),
+        )
+        .merge(SpaceTransaction::set_cube([0, 0, 0], None, Some(AIR)))
+        .unwrap()
+        .execute(&mut space, &mut no_outputs)
+        .unwrap();
+        assert_eq!(space[[0, 0, 0]], AIR);
+        assert_eq!(space.cube_data([0, 0, 0]), None);
+    }
+
+    #[test]
+    fn replacing_empty_container() {
+        let mut space = space_with_chest();
+        SpaceTransaction::set_cube([0, 0, 0], None, Some(AIR))
+            .execute(&mut space, &mut no_outputs)
+            .unwrap();
+        assert_eq!(space.cube_data([0, 0, 0]), None);
+    }
+
     #[test]
     fn systematic() {
         let [b1, b2, b3] = make_some_blocks();
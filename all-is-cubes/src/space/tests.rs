@@ -2,22 +2,27 @@
 //!
 //! Note that some sub-modules have their own test modules.
 
-use cgmath::EuclideanSpace as _;
+use cgmath::{EuclideanSpace as _, Vector3};
 use indoc::indoc;
 
+use crate::behavior::{Behavior, BehaviorContext};
 use crate::block::{
-    Block, BlockDef, BlockDefTransaction, EvalBlockError, Primitive, Resolution::*, AIR,
+    Block, BlockDef, BlockDefTransaction, EvalBlockError, Primitive, Quote, Resolution::*, AIR,
 };
 use crate::content::make_some_blocks;
 use crate::drawing::VoxelBrush;
+use crate::inv::{Inventory, Tool};
 use crate::listen::{Listen as _, Sink};
-use crate::math::{GridCoordinate, GridPoint, Rgba};
+use crate::math::{GridCoordinate, GridPoint, NotNan, Rgb, Rgba};
 use crate::space::{
-    GridAab, LightPhysics, PackedLight, SetCubeError, Space, SpaceChange, SpacePhysics,
+    BlockEntityTemplate, CubeData, DayNightCycle, GridAab, LightPhysics, PackedLight, SetCubeError,
+    Sky, Space, SpaceChange, SpacePhysics, SpaceTransaction, TickSchedule,
+};
+use crate::time::{Duration, Tick};
+use crate::transaction::{self, Transaction as _};
+use crate::universe::{
+    Name, RefError, RefVisitor, Universe, UniverseIndex as _, UniverseTransaction, VisitRefs,
 };
-use crate::time::Tick;
-use crate::transaction;
-use crate::universe::{Name, RefError, Universe, UniverseIndex as _, UniverseTransaction};
 
 // TODO: test consistency between the index and get_* methods
 // TODO: test fill() equivalence and error handling
@@ -422,9 +427,13 @@ fn space_debug() {
                 physics: SpacePhysics {
                     gravity: (+0.000, -20.000, +0.000),
                     sky_color: Rgb(0.8962694, 0.8962694, 1.0),
+                    sky: None,
+                    fog: None,
                     light: None,
                 },
                 behaviors: BehaviorSet([]),
+                cube_data: {},
+                block_entities: {},
                 cubes_wanting_ticks: {},
                 ..
             }
@@ -465,9 +474,7 @@ fn set_physics_light_rays() {
 
     // This is the set_physics we're actually testing
     space.set_physics(SpacePhysics {
-        light: LightPhysics::Rays {
-            maximum_distance: 10,
-        },
+        light: LightPhysics::rays(10),
         ..SpacePhysics::default()
     });
 
@@ -478,6 +485,80 @@ fn set_physics_light_rays() {
     // TODO: test what change notifications are sent
 }
 
+#[test]
+fn day_night_cycle() {
+    let mut universe = Universe::new();
+    let mut space = Space::empty_positive(1, 1, 1);
+    let day_sky = Sky {
+        sun_color: Rgb::ONE,
+        ..Sky::uniform(Rgb::new(0.5, 0.5, 1.0))
+    };
+    let night_sky = Sky::uniform(Rgb::ZERO);
+    SpaceTransaction::add_behavior(
+        space.bounds(),
+        DayNightCycle::new(Duration::from_secs(100), 0.5, day_sky, night_sky),
+    )
+    .execute(&mut space, &mut transaction::no_outputs)
+    .unwrap();
+    let space = universe.insert_anonymous(space);
+
+    // First step sets the sky to noon.
+    universe.step(Tick::from_seconds(1.0));
+    let noon_sky = space.read().unwrap().physics().sky.clone().unwrap();
+    assert_eq!(noon_sky.sun_color, Rgb::ONE);
+    assert!(noon_sky.sun_direction.y > notnan!(0.99));
+
+    // A quarter of a day later, it is sunset.
+    for _ in 0..25 {
+        universe.step(Tick::from_seconds(1.0));
+    }
+    let sunset_sky = space.read().unwrap().physics().sky.clone().unwrap();
+    assert!(sunset_sky.sun_direction.x < notnan!(-0.99));
+    assert!(sunset_sky.sun_color.luminance() < 1.0);
+    assert_eq!(
+        space.read().unwrap().physics().sky_color,
+        sunset_sky.average()
+    );
+}
+
+#[test]
+fn sky_sun_below_horizon_has_no_glow() {
+    let sky = Sky {
+        sun_direction: Vector3::new(notnan!(1.0), notnan!(-0.5), notnan!(0.0)),
+        sun_color: Rgb::ONE,
+        ..Sky::uniform(Rgb::new(0.0, 0.0, 0.5))
+    };
+    assert_eq!(sky.visible_sun_direction(), None);
+    assert_eq!(sky.sample(Vector3::new(1.0, -0.5, 0.0)), sky.horizon_color);
+    assert_eq!(sky.average(), Rgb::new(0.0, 0.0, 0.5));
+}
+
+#[test]
+fn sky_lerp_infinite_sun_direction() {
+    let sunny = Sky {
+        sun_direction: Vector3::new(
+            NotNan::new(f64::INFINITY).unwrap(),
+            notnan!(1.0),
+            notnan!(0.0),
+        ),
+        sun_color: Rgb::ONE,
+        ..Sky::uniform(Rgb::ZERO)
+    };
+    let sunless = Sky::uniform(Rgb::ZERO);
+
+    // Infinity times zero is NaN, which must not be produced.
+    let sky = sunless.lerp(&sunny, 0.0);
+    assert_eq!(
+        sky.sun_direction,
+        Vector3::new(notnan!(0.0), notnan!(0.0), notnan!(0.0))
+    );
+    assert_eq!(sky.visible_sun_direction(), None);
+
+    let sky = sunless.lerp(&sunny, 0.5);
+    assert_eq!(sky.sun_direction.x.into_inner(), f64::INFINITY);
+    assert_eq!(sky.sun_direction.y, notnan!(0.5));
+}
+
 #[test]
 fn block_tick_action() {
     let [mut block1, block2] = make_some_blocks();
@@ -496,3 +577,292 @@ fn block_tick_action() {
 
     assert_eq!(&space[[0, 0, 0]], &block2);
 }
+
+#[test]
+fn container_cube_data() {
+    let small = Block::builder()
+        .color(Rgba::WHITE)
+        .container_slots(2)
+        .build();
+    let large = Block::builder()
+        .color(Rgba::BLACK)
+        .container_slots(5)
+        .build();
+    let mut space = Space::empty_positive(2, 1, 1);
+    let sink = Sink::new();
+    space.listen(sink.listener());
+
+    assert_eq!(space.cube_data([0, 0, 0]), None);
+    space.set([0, 0, 0], &small).unwrap();
+    assert_eq!(
+        space.cube_data([0, 0, 0]),
+        Some(&CubeData {
+            inventory: Inventory::new(2)
+        })
+    );
+    assert!(sink
+        .drain()
+        .contains(&SpaceChange::CubeData(GridPoint::new(0, 0, 0))));
+
+    space.set([0, 0, 0], &large).unwrap();
+    assert_eq!(space.cube_data([0, 0, 0]).unwrap().inventory.slots.len(), 5);
+
+    // Shrinking discards the items in the excess slots.
+    let mut inventory = Inventory::new(5);
+    inventory.slots[4] = Tool::Activate.into();
+    space.insert_cube_data(GridPoint::new(0, 0, 0), CubeData { inventory });
+    space.set([0, 0, 0], &small).unwrap();
+    assert_eq!(
+        space.cube_data([0, 0, 0]),
+        Some(&CubeData {
+            inventory: Inventory::new(2)
+        })
+    );
+
+    space.set([0, 0, 0], AIR).unwrap();
+    assert_eq!(space.cube_data([0, 0, 0]), None);
+    assert_eq!(space.cube_data([1, 0, 0]), None);
+    assert!(sink
+        .drain()
+        .contains(&SpaceChange::CubeData(GridPoint::new(0, 0, 0))));
+
+    space.fill_uniform(space.bounds(), &small).unwrap();
+    assert!(space.cube_data([1, 0, 0]).is_some());
+    space.consistency_check();
+}
+
+/// Block entity which counts how many times it has been stepped.
+#[derive(Debug)]
+struct CountingEntity(u32);
+
+impl Behavior<Space> for CountingEntity {
+    fn step(&self, context: &BehaviorContext<'_, Space>, _tick: Tick) -> UniverseTransaction {
+        context.replace_self(CountingEntity(self.0 + 1))
+    }
+    fn alive(&self, _context: &BehaviorContext<'_, Space>) -> bool {
+        true
+    }
+    fn ephemeral(&self) -> bool {
+        false
+    }
+}
+
+impl VisitRefs for CountingEntity {
+    fn visit_refs(&self, _visitor: &mut dyn RefVisitor) {}
+}
+
+fn counting_block(schedule: TickSchedule) -> Block {
+    Block::builder()
+        .color(Rgba::WHITE)
+        .block_entity(Some(BlockEntityTemplate::new(schedule, || {
+            CountingEntity(0)
+        })))
+        .build()
+}
+
+fn entity_count(space: &Space, cube: [GridCoordinate; 3]) -> Option<u32> {
+    space
+        .block_entity(cube)
+        .map(|entity| entity.downcast_ref::<CountingEntity>().unwrap().0)
+}
+
+#[test]
+fn block_entity_created_and_removed() {
+    let block = counting_block(TickSchedule::Never);
+    let mut space = Space::empty_positive(2, 1, 1);
+    assert_eq!(entity_count(&space, [0, 0, 0]), None);
+
+    space.set([0, 0, 0], &block).unwrap();
+    assert_eq!(entity_count(&space, [0, 0, 0]), Some(0));
+    assert_eq!(entity_count(&space, [1, 0, 0]), None);
+
+    // Replacing the block with one with the same template keeps the entity.
+    let entity = space.block_entity([0, 0, 0]).unwrap() as *const dyn Behavior<Space>;
+    space.set([0, 0, 0], block.clone()).unwrap();
+    assert!(std::ptr::eq(space.block_entity([0, 0, 0]).unwrap(), entity));
+
+    // Quoting removes the template and hence the entity.
+    space
+        .set([0, 0, 0], block.clone().with_modifier(Quote::new()))
+        .unwrap();
+    assert_eq!(entity_count(&space, [0, 0, 0]), None);
+
+    space.fill_uniform(space.bounds(), &block).unwrap();
+    assert_eq!(entity_count(&space, [0, 0, 0]), Some(0));
+    assert_eq!(entity_count(&space, [1, 0, 0]), Some(0));
+
+    space.set([1, 0, 0], AIR).unwrap();
+    assert_eq!(entity_count(&space, [1, 0, 0]), None);
+    assert!(
+        format!("{space:?}").contains("block_entities: {[0, 0, 0]: (CountingEntity(0), Never)}")
+    );
+}
+
+#[test]
+fn block_entity_schedules() {
+    let mut universe = Universe::new();
+    let mut space = Space::empty_positive(3, 1, 1);
+    space
+        .set(
+            [0, 0, 0],
+            counting_block(TickSchedule::Every(Duration::from_secs(1))),
+        )
+        .unwrap();
+    space
+        .set(
+            [1, 0, 0],
+            counting_block(TickSchedule::Once(Duration::from_secs(2))),
+        )
+        .unwrap();
+    space
+        .set(
+            [2, 0, 0],
+            counting_block(TickSchedule::Random {
+                min: Duration::from_secs(2),
+                max: Duration::from_secs(4),
+            }),
+        )
+        .unwrap();
+    let space = universe.insert_anonymous(space);
+
+    for _ in 0..12 {
+        universe.step(Tick::from_seconds(1.0));
+    }
+    {
+        let space = space.read().unwrap();
+        assert_eq!(entity_count(&space, [0, 0, 0]), Some(12));
+        assert_eq!(entity_count(&space, [1, 0, 0]), Some(1));
+        let random_count = entity_count(&space, [2, 0, 0]).unwrap();
+        assert!((3..=6).contains(&random_count), "{random_count}");
+    }
+
+    // Paused ticks do not advance the schedule.
+    universe.step(Tick::from_seconds(1.0).pause());
+    assert_eq!(entity_count(&space.read().unwrap(), [0, 0, 0]), Some(12));
+
+    // Rescheduling the one-shot entity steps it again.
+    space
+        .execute(
+            &SpaceTransaction::schedule_block_entity(
+                [1, 0, 0],
+                TickSchedule::Once(Duration::from_secs(1)),
+            ),
+            &mut transaction::no_outputs,
+        )
+        .unwrap();
+    universe.step(Tick::from_seconds(1.0));
+    universe.step(Tick::from_seconds(1.0));
+    assert_eq!(entity_count(&space.read().unwrap(), [1, 0, 0]), Some(2));
+}
+
+/// Block entity which, when stepped, paints the cube `[2, 0, 0]` with its color.
+#[derive(Debug)]
+struct PaintingEntity(Rgba);
+
+impl Behavior<Space> for PaintingEntity {
+    fn step(&self, context: &BehaviorContext<'_, Space>, _tick: Tick) -> UniverseTransaction {
+        context.bind_host(SpaceTransaction::set_cube(
+            [2, 0, 0],
+            None,
+            Some(Block::from(self.0)),
+        ))
+    }
+    fn alive(&self, _context: &BehaviorContext<'_, Space>) -> bool {
+        true
+    }
+    fn ephemeral(&self) -> bool {
+        false
+    }
+}
+
+impl VisitRefs for PaintingEntity {
+    fn visit_refs(&self, _visitor: &mut dyn RefVisitor) {}
+}
+
+#[test]
+fn block_entity_conflict_is_retried() {
+    let painting_block = |color: Rgba| {
+        Block::builder()
+            .color(color)
+            .block_entity(Some(BlockEntityTemplate::new(
+                TickSchedule::Once(Duration::from_secs(1)),
+                move || PaintingEntity(color),
+            )))
+            .build()
+    };
+    let mut universe = Universe::new();
+    let mut space = Space::empty_positive(3, 1, 1);
+    space.set([0, 0, 0], painting_block(Rgba::WHITE)).unwrap();
+    space.set([1, 0, 0], painting_block(Rgba::BLACK)).unwrap();
+    let space = universe.insert_anonymous(space);
+    let mut step = |universe: &mut Universe| {
+        let (info, transaction) = space
+            .try_modify(|s| s.step(Some(&space), Tick::from_seconds(1.0)))
+            .unwrap();
+        transaction
+            .execute(universe, &mut transaction::no_outputs)
+            .unwrap();
+        info
+    };
+
+    // Both entities step, but they conflict, so only the first one's effect happens.
+    let info = step(&mut universe);
+    assert_eq!(
+        (info.block_entity_ticks, info.block_entity_conflicts),
+        (2, 1)
+    );
+    assert_eq!(space.read().unwrap()[[2, 0, 0]], Block::from(Rgba::WHITE));
+
+    // The second entity is retried on the next step, even though its schedule is done.
+    let info = step(&mut universe);
+    assert_eq!(
+        (info.block_entity_ticks, info.block_entity_conflicts),
+        (1, 0)
+    );
+    assert_eq!(space.read().unwrap()[[2, 0, 0]], Block::from(Rgba::BLACK));
+}
+
+/// When a [`BlockDef`] changes, the cubes containing it get cube data and block entities
+/// matching its new definition.
+#[test]
+fn block_def_change_updates_cube_data_and_entities() {
+    let mut universe = Universe::new();
+    let def = universe.insert_anonymous(BlockDef::new(Block::from(Rgba::WHITE)));
+    let block = Block::from(Primitive::Indirect(def.clone()));
+    let mut space = Space::empty_positive(2, 1, 1);
+    space.set([0, 0, 0], &block).unwrap();
+    assert_eq!(space.cube_data([0, 0, 0]), None);
+    assert_eq!(entity_count(&space, [0, 0, 0]), None);
+
+    let container = Block::builder()
+        .color(Rgba::WHITE)
+        .container_slots(3)
+        .block_entity(Some(BlockEntityTemplate::new(TickSchedule::Never, || {
+            CountingEntity(0)
+        })))
+        .build();
+    def.execute(
+        &BlockDefTransaction::overwrite(container),
+        &mut transaction::no_outputs,
+    )
+    .unwrap();
+    let (_, _) = space.step(None, Tick::arbitrary());
+    assert_eq!(
+        space.cube_data([0, 0, 0]),
+        Some(&CubeData {
+            inventory: Inventory::new(3)
+        })
+    );
+    assert_eq!(entity_count(&space, [0, 0, 0]), Some(0));
+    assert_eq!(space.cube_data([1, 0, 0]), None);
+
+    def.execute(
+        &BlockDefTransaction::overwrite(Block::from(Rgba::WHITE)),
+        &mut transaction::no_outputs,
+    )
+    .unwrap();
+    let (_, _) = space.step(None, Tick::arbitrary());
+    assert_eq!(space.cube_data([0, 0, 0]), None);
+    assert_eq!(entity_count(&space, [0, 0, 0]), None);
+    space.consistency_check();
+}
@@ -75,3 +75,119 @@ impl Tick {
         self.paused
     }
 }
+
+/// Converts real elapsed time, in whatever amounts it is reported, into a sequence of
+/// [`Tick`]s which all have the same length.
+///
+/// Stepping a [`Universe`](crate::universe::Universe) only with such ticks makes the
+/// outcome independent of the frame rate, and reproducible; see
+/// [`Universe::step_fixed()`](crate::universe::Universe::step_fixed).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FixedStepClock {
+    step_length: Duration,
+    accumulated: Duration,
+    /// Maximum value of `accumulated`, if any.
+    accumulation_limit: Option<Duration>,
+}
+
+impl FixedStepClock {
+    /// Constructs a [`FixedStepClock`] which produces ticks of length `step_length`,
+    /// and has not yet accumulated any time.
+    ///
+    /// By default, all elapsed time is eventually turned into ticks, however many are
+    /// needed; use [`FixedStepClock::with_catch_up_limit()`] to discard time instead
+    /// when stepping cannot keep up.
+    ///
+    /// Panics if `step_length` is zero.
+    pub fn new(step_length: Duration) -> Self {
+        assert!(
+            step_length > Duration::ZERO,
+            "FixedStepClock step length must be nonzero"
+        );
+        Self {
+            step_length,
+            accumulated: Duration::ZERO,
+            accumulation_limit: None,
+        }
+    }
+
+    /// Limits the time accumulated and not yet stepped to `steps` steps' worth;
+    /// any more elapsed time is discarded, so that game time runs slower than real time
+    /// rather than requiring ever more steps to catch up.
+    #[must_use]
+    pub fn with_catch_up_limit(mut self, steps: u32) -> Self {
+        self.accumulation_limit = Some(self.step_length * steps);
+        self.cap();
+        self
+    }
+
+    /// Returns the length of every tick this clock produces.
+    pub fn step_length(&self) -> Duration {
+        self.step_length
+    }
+
+    /// Returns the [`Tick`] that this clock produces.
+    #[must_use] // avoid confusion with side-effecting methods
+    pub fn tick(&self) -> Tick {
+        Tick::from_duration(self.step_length)
+    }
+
+    /// Informs the clock that `elapsed` real time has passed.
+    pub fn advance_by(&mut self, elapsed: Duration) {
+        self.accumulated += elapsed;
+        self.cap();
+    }
+
+    /// Returns whether enough time has accumulated that [`FixedStepClock::next_tick()`]
+    /// will return a tick.
+    pub fn should_step(&self) -> bool {
+        self.accumulated >= self.step_length
+    }
+
+    /// If enough time has accumulated, consumes one step's worth of it and returns the
+    /// tick with which to step.
+    pub fn next_tick(&mut self) -> Option<Tick> {
+        if self.should_step() {
+            self.accumulated -= self.step_length;
+            Some(self.tick())
+        } else {
+            None
+        }
+    }
+
+    fn cap(&mut self) {
+        if let Some(limit) = self.accumulation_limit {
+            self.accumulated = self.accumulated.min(limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_step_clock_accumulates() {
+        let mut clock = FixedStepClock::new(Duration::from_millis(10));
+        clock.advance_by(Duration::from_millis(25));
+        assert_eq!(
+            clock.next_tick(),
+            Some(Tick::from_duration(Duration::from_millis(10)))
+        );
+        assert!(clock.next_tick().is_some());
+        assert_eq!(clock.next_tick(), None);
+        // The remaining 5 ms is kept.
+        clock.advance_by(Duration::from_millis(5));
+        assert!(clock.next_tick().is_some());
+        assert_eq!(clock.next_tick(), None);
+    }
+
+    #[test]
+    fn fixed_step_clock_catch_up_limit() {
+        let mut clock = FixedStepClock::new(Duration::from_millis(10)).with_catch_up_limit(2);
+        clock.advance_by(Duration::from_secs(1));
+        assert!(clock.next_tick().is_some());
+        assert!(clock.next_tick().is_some());
+        assert_eq!(clock.next_tick(), None);
+    }
+}
@@ -24,7 +24,7 @@ use instant::Instant;
 use crate::block::BlockDef;
 use crate::character::Character;
 use crate::space::{Space, SpaceStepInfo};
-use crate::time::Tick;
+use crate::time::{FixedStepClock, Tick};
 use crate::transaction::Transaction as _;
 use crate::util::{CustomFormat, StatusText, TypeName};
 
@@ -177,6 +177,17 @@ impl Universe {
     }
 
     /// Advance time for all members.
+    ///
+    /// Stepping is deterministic: two universes in identical states, stepped with the same
+    /// sequence of [`Tick`]s and given the same inputs between steps, will remain in
+    /// identical states. (This does not apply to the timing information in the returned
+    /// [`UniverseStepInfo`], nor to [`Behavior`]s which consult outside information such
+    /// as the system clock.) To take advantage of this, use fixed-length ticks
+    /// rather than ones derived from real elapsed time (see [`Universe::step_fixed()`]),
+    /// and consider recording inputs with [`InputRecording`].
+    ///
+    /// [`Behavior`]: crate::behavior::Behavior
+    /// [`InputRecording`]: crate::character::InputRecording
     pub fn step(&mut self, tick: Tick) -> UniverseStepInfo {
         let mut info = UniverseStepInfo::default();
         let start_time = Instant::now();
@@ -218,6 +229,25 @@ impl Universe {
         info
     }
 
+    /// Informs `clock` that `elapsed` real time has passed, then advances time for all
+    /// members by as many steps as `clock` produces, all of the same length.
+    ///
+    /// This is the fixed-step alternative to calling [`Universe::step()`] with ticks
+    /// derived from real elapsed time; the resulting state depends only on the number of
+    /// steps, not on how the elapsed time was divided up.
+    pub fn step_fixed(
+        &mut self,
+        clock: &mut FixedStepClock,
+        elapsed: Duration,
+    ) -> UniverseStepInfo {
+        clock.advance_by(elapsed);
+        let mut info = UniverseStepInfo::default();
+        while let Some(tick) = clock.next_tick() {
+            info += self.step(tick);
+        }
+        info
+    }
+
     /// Inserts a new object without giving it a specific name, and returns
     /// a reference to it.
     pub fn insert_anonymous<T>(&mut self, value: T) -> URef<T>
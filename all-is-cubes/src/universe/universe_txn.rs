@@ -509,6 +509,7 @@ impl Debug for UniverseTransaction {
 /// _entry_ in a Universe. We could kludge around that by having it take the Universe
 /// and embed the Name, but that's unnecessary.
 #[derive(Clone, Debug, Default, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 enum MemberTxn {
     /// Mergeable types are required to have a no-operation [`Default`] value,
     /// though this shouldn't come up much.
@@ -763,6 +764,10 @@ mod tests {
                         ),
                         conserved: true,
                         activate: false,
+                        property: None,
+                        inventory: None,
+                        replace_entity: None,
+                        schedule: None,
                     },
                 },
             }
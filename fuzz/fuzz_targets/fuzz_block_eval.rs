@@ -2,7 +2,7 @@
 use libfuzzer_sys::fuzz_target;
 extern crate all_is_cubes;
 
-use all_is_cubes::block::Block;
+use all_is_cubes::block::{Block, Cost, EvalBlockError};
 
 fuzz_target!(|block: Block| {
     // TODO: The `Block` will have pending URefs (not inserted in a Universe).
@@ -13,6 +13,16 @@ fuzz_target!(|block: Block| {
     match (block.evaluate(), block.listen(sink.listener())) {
         (Ok(evaluated), Ok(())) => {
             evaluated.consistency_check();
+            assert!(evaluated.voxels.bounds().volume() <= Cost::DEFAULT_BUDGET.voxels);
+        }
+        (Err(EvalBlockError::BudgetExceeded { budget, used }), Err(_) | Ok(())) => {
+            // The error must be reported only for an actual excess of the budget.
+            assert_eq!(budget, Cost::DEFAULT_BUDGET);
+            assert!(used.recursion <= budget.recursion);
+            assert!(
+                used.components > budget.components || used.voxels > budget.voxels,
+                "{used} does not exceed {budget}"
+            );
         }
         (Err(_), Err(_) | Ok(())) => {
             // Errors are an expected possibility; this fuzz test is looking for no panic.
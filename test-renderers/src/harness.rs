@@ -161,7 +161,10 @@ where
 
     // Gather tests (don't run them yet).
     let mut test_table: BTreeMap<String, TestCase> = BTreeMap::new();
-    test_suite(&mut TestCaseCollector(&mut test_table));
+    test_suite(&mut TestCaseCollector {
+        table: &mut test_table,
+        renderer_id,
+    });
 
     // Filter tests, synchronously so we can count them simply.
     let mut count_filtered = 0;
@@ -309,9 +312,18 @@ where
 }
 
 #[allow(missing_debug_implementations)]
-pub struct TestCaseCollector<'a>(&'a mut BTreeMap<String, TestCase>);
+pub struct TestCaseCollector<'a> {
+    table: &'a mut BTreeMap<String, TestCase>,
+    renderer_id: RendererId,
+}
 
 impl<'a> TestCaseCollector<'a> {
+    /// Returns which renderer the tests being collected will be run with,
+    /// so that tests of features it does not implement can be omitted.
+    pub fn renderer_id(&self) -> RendererId {
+        self.renderer_id
+    }
+
     #[track_caller]
     pub fn insert<F>(
         &mut self,
@@ -322,7 +334,7 @@ impl<'a> TestCaseCollector<'a> {
         F: AsyncFn1<RenderTestContext, Output = ()> + Send + Sync + Clone + 'static,
         F::OutputFuture: Send,
     {
-        match self.0.entry(name.to_owned()) {
+        match self.table.entry(name.to_owned()) {
             btree_map::Entry::Vacant(e) => {
                 let boxed_test_function = Box::new(move |context| {
                     let test_function = test_function.clone(); // usually a fn pointer
@@ -5,11 +5,13 @@ use std::sync::Arc;
 
 use futures_core::future::BoxFuture;
 use futures_util::FutureExt;
+use image::RgbaImage;
 
-use all_is_cubes::block::{Block, Resolution::R2};
+use all_is_cubes::block::{Block, BlockCollision, Resolution::R2};
 use all_is_cubes::camera::{
-    AntialiasingOption, ExposureOption, FogOption, GraphicsOptions, LightingOption, RenderError,
-    StandardCameras, ToneMappingOperator, TransparencyOption, UiViewState, ViewTransform, Viewport,
+    AntialiasingOption, Camera, ExposureOption, Flaws, FogOption, GraphicsOptions, LightingOption,
+    RenderError, StandardCameras, ToneMappingOperator, TransparencyOption, UiViewState,
+    ViewTransform, Viewport,
 };
 use all_is_cubes::cgmath::{EuclideanSpace as _, One, Point2, Point3, Vector2, Vector3};
 use all_is_cubes::character::{Character, Spawn};
@@ -18,7 +20,7 @@ use all_is_cubes::math::{
     Face6, FreeCoordinate, GridAab, GridCoordinate, GridPoint, GridRotation, GridVector, NotNan,
     Rgb,
 };
-use all_is_cubes::space::{LightPhysics, Space, SpaceBuilder};
+use all_is_cubes::space::{DynamicLight, LightPhysics, Space, SpaceBuilder};
 use all_is_cubes::transaction::{self, Transaction as _};
 use all_is_cubes::universe::{RefError, URef, Universe, UniverseIndex, UniverseTransaction};
 use all_is_cubes::util::YieldProgress;
@@ -26,8 +28,8 @@ use all_is_cubes::{notnan, rgb_const, rgba_const};
 use all_is_cubes_content::{make_some_voxel_blocks, palette};
 
 use crate::{
-    finish_universe_from_space, Overlays, RenderTestContext, TestCaseCollector, Threshold,
-    UniverseFuture, COMMON_VIEWPORT,
+    finish_universe_from_space, Overlays, RenderTestContext, RendererId, TestCaseCollector,
+    Threshold, UniverseFuture, COMMON_VIEWPORT,
 };
 
 /// Function to be called by the custom test harness to find all tests.
@@ -40,6 +42,11 @@ pub fn all_tests(c: &mut TestCaseCollector<'_>) {
         });
     }
 
+    c.insert(
+        "ambient_occlusion",
+        light_test_universe.clone(),
+        ambient_occlusion,
+    );
     c.insert_variants(
         "antialias",
         u(antialias_test_universe()),
@@ -51,6 +58,10 @@ pub fn all_tests(c: &mut TestCaseCollector<'_>) {
     c.insert_variants("bloom", light_test_universe.clone(), bloom, [0.0, 0.25]);
     c.insert("color_srgb_ramp", None, color_srgb_ramp);
     c.insert("cursor_basic", None, cursor_basic);
+    // The raytracer does not implement dynamic lights, so there is nothing to test.
+    if c.renderer_id() != RendererId::Raytracer {
+        c.insert_variants("dynamic_light", None, dynamic_light, ["point", "spot"]);
+    }
     c.insert("error_character_gone", None, error_character_gone);
     c.insert(
         "error_character_unavailable",
@@ -110,6 +121,49 @@ fn u(f: impl Future<Output = Arc<Universe>> + Send + Sync + 'static) -> Option<U
 // --- Test cases ---------------------------------------------------------------------------------
 // Listed in alphabetical order.
 
+/// Test that ambient occlusion darkens the inside corner between the wall and a pillar,
+/// but not the open wall.
+///
+/// The GPU renderer computes ambient occlusion in screen space and the raytracer from
+/// voxels, so rather than comparing against an expected image, this compares the
+/// brightness of points with and without ambient occlusion.
+async fn ambient_occlusion(context: RenderTestContext) {
+    let render = |ambient_occlusion: bool| {
+        let mut options = light_test_options();
+        options.ambient_occlusion = ambient_occlusion;
+        let mut cameras =
+            StandardCameras::from_constant_for_test(options, COMMON_VIEWPORT, context.universe());
+        cameras.update();
+        let camera = cameras.cameras().world.clone();
+        let mut renderer = context.renderer(cameras);
+        async move {
+            renderer.update(None).await.unwrap();
+            let (image, _flaws) = renderer.draw("").await.unwrap();
+            move |point: Point3<FreeCoordinate>| f32::from(brightness_at(&camera, &image, point))
+        }
+    };
+    let without = render(false).await;
+    let with = render(true).await;
+
+    // On the wall, beside the -Y face of the pillar at [1, 1, 0].
+    let corner = Point3::new(1.5, 0.85, 0.0);
+    // On the wall, far from any pillar.
+    let open = Point3::new(3.5, -1.5, 0.0);
+
+    assert!(
+        without(corner) > 16.0 && with(corner) < without(corner) * 0.9,
+        "expected corner to be darkened; without = {}, with = {}",
+        without(corner),
+        with(corner),
+    );
+    assert!(
+        with(open) >= without(open) * 0.95,
+        "expected open wall not to be darkened; without = {}, with = {}",
+        without(open),
+        with(open),
+    );
+}
+
 async fn antialias(mut context: RenderTestContext, antialias_option: AntialiasingOption) {
     let mut options = GraphicsOptions::UNALTERED_COLORS;
     options.antialiasing = antialias_option;
@@ -216,6 +270,81 @@ async fn cursor_basic(mut context: RenderTestContext) {
         .await;
 }
 
+/// Test that a [`DynamicLight`] lights a wall and that an obstacle casts a shadow.
+///
+/// Rather than comparing against an expected image, this checks the relative brightness
+/// of two points on the wall, so as to be insensitive to details of the light falloff.
+async fn dynamic_light(context: RenderTestContext, shape: &str) {
+    let light = match shape {
+        "point" => DynamicLight::point(rgb_const!(20.0, 20.0, 20.0), notnan!(16.0)),
+        "spot" => DynamicLight::spot(
+            rgb_const!(20.0, 20.0, 20.0),
+            notnan!(16.0),
+            Vector3::new(0.0, 0.0, -1.0),
+            60.0,
+        ),
+        _ => unreachable!(),
+    };
+
+    let bounds = GridAab::from_lower_size([-8, -8, -1], [16, 16, 6]);
+    let mut space = Space::builder(bounds)
+        .sky_color(Rgb::ZERO)
+        .spawn_position(Point3::new(0.5, 0.5, 12.))
+        .build();
+    // Wall whose surface is at z = 0.
+    space
+        .fill_uniform(
+            bounds.abut(Face6::NZ, -1).unwrap(),
+            Block::from(rgba_const!(0.8, 0.8, 0.8, 1.0)),
+        )
+        .unwrap();
+    // Invisible light source in front of the wall.
+    space
+        .set(
+            [0, 0, 4],
+            Block::builder()
+                .color(rgba_const!(0.0, 0.0, 0.0, 0.0))
+                .collision(BlockCollision::None)
+                .dynamic_light(Some(light))
+                .build(),
+        )
+        .unwrap();
+    // Obstacle which shadows the wall around (5.5, 0.5, 0).
+    space
+        .set([2, 0, 2], Block::from(palette::ALMOST_BLACK))
+        .unwrap();
+    space.evaluate_light(1, |_| {});
+    let mut universe = Universe::new();
+    finish_universe_from_space(&mut universe, space);
+
+    let mut options = GraphicsOptions::UNALTERED_COLORS;
+    options.lighting_display = LightingOption::Smooth;
+    let mut cameras =
+        StandardCameras::from_constant_for_test(options.clone(), COMMON_VIEWPORT, &universe);
+    cameras.update();
+    let camera = cameras.cameras().world.clone();
+
+    let mut renderer = context.renderer(StandardCameras::from_constant_for_test(
+        options,
+        COMMON_VIEWPORT,
+        &universe,
+    ));
+    renderer.update(None).await.unwrap();
+    let (image, flaws) = renderer.draw("").await.unwrap();
+    assert!(
+        !flaws.contains(Flaws::NO_DYNAMIC_LIGHTS),
+        "renderer unexpectedly does not support dynamic lights"
+    );
+
+    let lit = brightness_at(&camera, &image, Point3::new(-4.5, 0.5, 0.0));
+    let shadowed = brightness_at(&camera, &image, Point3::new(5.5, 0.5, 0.0));
+    assert!(
+        lit > 64 && shadowed < lit / 4,
+        "expected lit point to be bright and shadowed point to be dark; \
+        lit = {lit}, shadowed = {shadowed}, flaws = {flaws}"
+    );
+}
+
 /// Test what happens when the renderer's character goes away *after* the first frame.
 ///
 /// TODO: also test case of space gone but character not gone
@@ -766,6 +895,16 @@ const TEXT_MAX_DIFF: u8 = 20;
 /// rendering.
 const COLOR_ROUNDING_MAX_DIFF: u8 = 2;
 
+/// Returns the green component of the pixel of `image`, as rendered by `camera`, which
+/// shows `point`.
+fn brightness_at(camera: &Camera, image: &RgbaImage, point: Point3<FreeCoordinate>) -> u8 {
+    let clip = camera.projection() * camera.view_matrix() * point.to_homogeneous();
+    let ndc = clip.truncate() / clip.w;
+    let x = (ndc.x + 1.0) / 2.0 * f64::from(image.width());
+    let y = (1.0 - ndc.y) / 2.0 * f64::from(image.height());
+    image.get_pixel(x as u32, y as u32).0[1]
+}
+
 fn one_cube_space() -> Space {
     let bounds = GridAab::from_lower_size([0, 0, 0], [1, 1, 1]);
 